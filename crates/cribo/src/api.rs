@@ -0,0 +1,498 @@
+//! Stable entry point for Rust tools that embed the bundler
+//!
+//! Everything in this module follows semantic versioning: fields and methods are only
+//! added in minor releases, and the structs are `#[non_exhaustive]` so that adding one
+//! is not a breaking change. Other modules of the crate expose internals that may
+//! change between any two releases.
+//!
+//! ```no_run
+//! use cribo::api::{BundleOptions, BundleRequest, bundle};
+//!
+//! let options = BundleOptions::new()
+//!     .with_config_discovery()
+//!     .with_target_version("py311");
+//! let request = BundleRequest::new("src/main.py").with_options(options);
+//! let result = bundle(&request).expect("bundling failed");
+//! std::fs::write("bundle.py", &result.code).expect("write failed");
+//! ```
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::error::CriboResult;
+use crate::logging::LogSinkHandle;
+use crate::metrics::{MetricsHandle, NoopMetrics};
+pub use crate::module_map::{ModuleMap, ModuleMapEntry};
+use crate::orchestrator::{BundleOrchestrator, BundleOutcome};
+pub use crate::plugin::{BundlePlugin, BundlePluginHandle, PluginModule};
+use crate::resolver::{self, ModuleResolver};
+use crate::type_check;
+
+/// Settings to bundle with, on top of the configuration files they name
+///
+/// Without any settings the bundle is built with the built-in defaults and no
+/// configuration file is read.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct BundleOptions {
+    config_file: Option<PathBuf>,
+    discover_config: bool,
+    target_version: Option<String>,
+    source_dirs: Vec<PathBuf>,
+    respect_pythonpath: Option<bool>,
+    type_checker: Option<String>,
+}
+
+impl BundleOptions {
+    /// Options that bundle with the built-in defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the configuration from `path`, layered over the user and system
+    /// configuration like the CLI's `--config`
+    pub fn with_config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// Look up configuration files (`cribo.toml`, `pyproject.toml`, user and system
+    /// configuration) the way the CLI does when no configuration file is given
+    pub fn with_config_discovery(mut self) -> Self {
+        self.discover_config = true;
+        self
+    }
+
+    /// Target Python version, e.g. `py311`
+    pub fn with_target_version(mut self, version: impl Into<String>) -> Self {
+        self.target_version = Some(version.into());
+        self
+    }
+
+    /// Also search `dir` for first-party modules, after the configured source
+    /// directories
+    pub fn with_source_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.source_dirs.push(dir.into());
+        self
+    }
+
+    /// Whether directories from the `PYTHONPATH` environment variable are searched
+    pub fn with_pythonpath(mut self, respect: bool) -> Self {
+        self.respect_pythonpath = Some(respect);
+        self
+    }
+
+    /// Type-check the bundle with `command` (e.g. `mypy --strict`) and report its
+    /// findings in [`Diagnostics::type_check_findings`]
+    pub fn with_type_checker(mut self, command: impl Into<String>) -> Self {
+        self.type_checker = Some(command.into());
+        self
+    }
+
+    fn to_config(&self) -> CriboResult<Config> {
+        let mut config = if self.config_file.is_some() || self.discover_config {
+            Config::load(self.config_file.as_deref())?
+        } else {
+            Config::default()
+        };
+        if let Some(target_version) = &self.target_version {
+            config.set_target_version(target_version.clone())?;
+        }
+        for dir in &self.source_dirs {
+            if !config.src.contains(dir) {
+                config.src.push(dir.clone());
+            }
+        }
+        if let Some(respect_pythonpath) = self.respect_pythonpath {
+            config.respect_pythonpath = respect_pythonpath;
+        }
+        if let Some(type_checker) = &self.type_checker {
+            config.type_checker = Some(type_checker.clone());
+        }
+        Ok(config)
+    }
+}
+
+/// How the bundler treats an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImportType {
+    /// Found under a source directory and inlined into the bundle
+    FirstParty,
+    /// Installed separately and imported by the bundle at runtime
+    ThirdParty,
+    /// Part of the standard library of the target Python version
+    StandardLibrary,
+    /// Left exactly as written: not bundled, not hoisted and not a requirement
+    Ignored,
+}
+
+/// Policy hook that classifies imports before the built-in rules
+///
+/// Lets embedders encode rules such as "everything under `corp.*` is external".
+/// Relative imports are always first-party and never reach the classifier.
+pub trait ImportClassifier: Send + Sync {
+    /// Classify `module_name`, or return `None` to fall back to the built-in rules
+    fn classify(&self, module_name: &str) -> Option<ImportType>;
+}
+
+impl fmt::Debug for dyn ImportClassifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ImportClassifier")
+    }
+}
+
+/// Shared handle to an import classifier
+pub type ImportClassifierHandle = Arc<dyn ImportClassifier>;
+
+/// Hands the resolver the decisions of a facade classifier
+struct ResolverClassifier(ImportClassifierHandle);
+
+impl resolver::ImportClassifier for ResolverClassifier {
+    fn classify(&self, module_name: &str) -> Option<resolver::ImportType> {
+        self.0
+            .classify(module_name)
+            .map(|import_type| match import_type {
+                ImportType::FirstParty => resolver::ImportType::FirstParty,
+                ImportType::ThirdParty => resolver::ImportType::ThirdParty,
+                ImportType::StandardLibrary => resolver::ImportType::StandardLibrary,
+                ImportType::Ignored => resolver::ImportType::Ignored,
+            })
+    }
+}
+
+/// What to bundle and how
+#[derive(Debug, Clone)]
+pub struct BundleRequest {
+    entry: PathBuf,
+    options: BundleOptions,
+    metrics: MetricsHandle,
+    log_sink: Option<LogSinkHandle>,
+    import_classifier: Option<ImportClassifierHandle>,
+    plugins: Vec<BundlePluginHandle>,
+}
+
+impl BundleRequest {
+    /// Bundle the script at `entry` with the default configuration
+    pub fn new(entry: impl Into<PathBuf>) -> Self {
+        Self {
+            entry: entry.into(),
+            options: BundleOptions::default(),
+            metrics: Arc::new(NoopMetrics),
+            log_sink: None,
+            import_classifier: None,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Bundle with `options` instead of the default configuration
+    pub fn with_options(mut self, options: BundleOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Report bundling progress to `metrics`
+    pub fn with_metrics(mut self, metrics: MetricsHandle) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Deliver log records produced while bundling to `sink`
+    pub fn with_log_sink(mut self, sink: LogSinkHandle) -> Self {
+        self.log_sink = Some(sink);
+        self
+    }
+
+    /// Let `classifier` decide how imports are classified before the built-in rules
+    pub fn with_import_classifier(mut self, classifier: ImportClassifierHandle) -> Self {
+        self.import_classifier = Some(classifier);
+        self
+    }
+
+    /// Run `plugin`'s hooks after those of the plugins added before it
+    pub fn with_plugin(mut self, plugin: BundlePluginHandle) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+
+    /// Entry script of the bundle
+    pub fn entry(&self) -> &Path {
+        &self.entry
+    }
+
+    /// Settings the bundle is built with
+    pub fn options(&self) -> &BundleOptions {
+        &self.options
+    }
+
+    fn resolver_classifier(&self) -> Option<resolver::ImportClassifierHandle> {
+        self.import_classifier
+            .as_ref()
+            .map(|classifier| -> resolver::ImportClassifierHandle {
+                Arc::new(ResolverClassifier(classifier.clone()))
+            })
+    }
+
+    fn orchestrator(&self) -> CriboResult<BundleOrchestrator> {
+        let mut orchestrator =
+            BundleOrchestrator::new(self.options.to_config()?).with_metrics(self.metrics.clone());
+        if let Some(classifier) = self.resolver_classifier() {
+            orchestrator = orchestrator.with_import_classifier(classifier);
+        }
+        for plugin in &self.plugins {
+            orchestrator = orchestrator.with_plugin(plugin.clone());
+        }
+        Ok(match &self.log_sink {
+            Some(sink) => orchestrator.with_log_sink(sink.clone()),
+            None => orchestrator,
+        })
+    }
+}
+
+/// A first-party module inlined into the bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BundledModule {
+    /// Dotted module name
+    pub name: String,
+    /// Source file the module was read from
+    pub path: PathBuf,
+}
+
+/// One message of the configured type checker about the bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TypeCheckFinding {
+    /// Module whose code contains the line, or `None` for the bundle's runtime code
+    pub module: Option<String>,
+    /// 1-based line in the bundle
+    pub line: usize,
+    /// Severity reported by the checker, e.g. `error` or `note`
+    pub severity: String,
+    /// Text of the message as the checker printed it
+    pub message: String,
+}
+
+impl From<type_check::TypeCheckFinding> for TypeCheckFinding {
+    fn from(finding: type_check::TypeCheckFinding) -> Self {
+        Self {
+            module: finding.module,
+            line: finding.line,
+            severity: finding.severity,
+            message: finding.message,
+        }
+    }
+}
+
+/// Facts about a successful run that do not affect the generated code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostics {
+    /// Function-level import cycles that were resolved, as module names per cycle
+    pub resolved_cycles: Vec<Vec<String>>,
+    /// Number of distinct first-party imports encountered
+    pub first_party_imports: usize,
+    /// Number of distinct third-party imports encountered
+    pub third_party_imports: usize,
+    /// Number of distinct standard library imports encountered
+    pub stdlib_imports: usize,
+    /// Number of distinct imports the import classifier chose to ignore
+    pub ignored_imports: usize,
+    /// What the configured type checker reported about the bundle
+    pub type_check_findings: Vec<TypeCheckFinding>,
+}
+
+/// Output of a successful bundling run
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BundleResult {
+    /// The bundled Python source
+    pub code: String,
+    /// Inlined modules in the order they appear in the bundle
+    pub modules: Vec<BundledModule>,
+    /// Sorted top-level third-party packages the bundle imports
+    pub requirements: Vec<String>,
+    /// Facts about the run that did not affect the generated code
+    pub diagnostics: Diagnostics,
+    /// Where each module's code lies in `code`
+    pub module_map: ModuleMap,
+}
+
+impl From<BundleOutcome> for BundleResult {
+    fn from(outcome: BundleOutcome) -> Self {
+        let counts = outcome.classification_counts;
+        Self {
+            code: outcome.code,
+            modules: outcome
+                .modules
+                .into_iter()
+                .map(|(name, path)| BundledModule { name, path })
+                .collect(),
+            requirements: outcome.requirements,
+            diagnostics: Diagnostics {
+                resolved_cycles: outcome.resolved_cycles,
+                first_party_imports: counts.first_party,
+                third_party_imports: counts.third_party,
+                stdlib_imports: counts.standard_library,
+                ignored_imports: counts.ignored,
+                type_check_findings: outcome
+                    .type_check_findings
+                    .into_iter()
+                    .map(TypeCheckFinding::from)
+                    .collect(),
+            },
+            module_map: outcome.module_map,
+        }
+    }
+}
+
+/// A first-party module in the dependency graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GraphModule {
+    /// Dotted module name
+    pub name: String,
+    /// Source file the module was read from, when known
+    pub path: Option<PathBuf>,
+    /// Names of the first-party modules this module imports, sorted
+    pub dependencies: Vec<String>,
+}
+
+/// Kind of module a name resolves to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResolutionKind {
+    /// Part of the standard library of the target Python version
+    Stdlib,
+    /// A module or regular package found under a source directory
+    FirstParty,
+    /// Anything not found locally, expected to be installed at runtime
+    ThirdParty,
+    /// A first-party directory without an `__init__.py`
+    Namespace,
+    /// Left alone by the bundler, as decided by an [`ImportClassifier`]
+    Ignored,
+}
+
+/// Where a module name resolves to and how the bundler treats it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Resolution {
+    /// Source file of a first-party module; `None` for every other kind
+    pub path: Option<PathBuf>,
+    /// Kind of module the name resolves to
+    pub kind: ResolutionKind,
+    /// Source directory the module or namespace package was found in
+    pub origin_root: Option<PathBuf>,
+    /// Directories making up a namespace package, one per source directory holding a
+    /// portion of it; empty for every other kind
+    pub namespace_portions: Vec<PathBuf>,
+}
+
+impl From<resolver::Resolution> for Resolution {
+    fn from(resolution: resolver::Resolution) -> Self {
+        Self {
+            path: resolution.path,
+            kind: match resolution.kind {
+                resolver::ResolutionKind::Stdlib => ResolutionKind::Stdlib,
+                resolver::ResolutionKind::FirstParty => ResolutionKind::FirstParty,
+                resolver::ResolutionKind::ThirdParty => ResolutionKind::ThirdParty,
+                resolver::ResolutionKind::Namespace => ResolutionKind::Namespace,
+                resolver::ResolutionKind::Ignored => ResolutionKind::Ignored,
+            },
+            origin_root: resolution.origin_root,
+            namespace_portions: resolution.namespace_portions,
+        }
+    }
+}
+
+/// Bundle `request.entry()` and everything it imports into a single module
+///
+/// Nothing is written to disk; the caller decides what to do with the result.
+pub fn bundle(request: &BundleRequest) -> CriboResult<BundleResult> {
+    request
+        .orchestrator()?
+        .bundle_outcome(&request.entry)
+        .map(BundleResult::from)
+}
+
+/// Bundle like [`bundle`] and also write the result to `output`, as the CLI's
+/// `--output` does
+///
+/// `requirements.txt` is written next to `output` when `emit_requirements` is set, and
+/// so are the other artifacts the configuration asks for.
+pub fn bundle_to_file(
+    request: &BundleRequest,
+    output: &Path,
+    emit_requirements: bool,
+) -> CriboResult<BundleResult> {
+    request
+        .orchestrator()?
+        .bundle_to_file(&request.entry, output, emit_requirements)
+        .map(BundleResult::from)
+}
+
+/// Analyze the project without generating code
+///
+/// Succeeds exactly when [`bundle`] would get past module discovery, parsing and
+/// cycle analysis, which makes it a cheap pre-flight for build pipelines.
+pub fn check(request: &BundleRequest) -> CriboResult<()> {
+    request.orchestrator()?.build_graph(&request.entry)?;
+    Ok(())
+}
+
+/// First-party modules reachable from `request.entry()` with their dependencies,
+/// sorted by module name
+pub fn graph(request: &BundleRequest) -> CriboResult<Vec<GraphModule>> {
+    let graph = request.orchestrator()?.build_graph(&request.entry)?;
+
+    let mut modules: Vec<GraphModule> = graph
+        .modules
+        .iter()
+        .map(|(&module_id, module)| {
+            let path = graph
+                .module_paths
+                .iter()
+                .find(|(_, id)| **id == module_id)
+                .map(|(path, _)| path.clone());
+            let mut dependencies: Vec<String> = graph
+                .get_dependencies(module_id)
+                .into_iter()
+                .filter_map(|dependency| graph.modules.get(&dependency))
+                .map(|dependency| dependency.module_name.clone())
+                .collect();
+            dependencies.sort();
+            GraphModule {
+                name: module.module_name.clone(),
+                path,
+                dependencies,
+            }
+        })
+        .collect();
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(modules)
+}
+
+/// Resolve `module_name` the way bundling `request` would, reporting its kind, source
+/// file and the source directory it was found in
+///
+/// Like the bundler, the entry script's directory is searched in addition to the
+/// configured `src` directories, and the request's import classifier is consulted first.
+pub fn resolve(request: &BundleRequest, module_name: &str) -> CriboResult<Resolution> {
+    let mut config = request.options.to_config()?;
+    if let Some(entry_dir) = request.entry.parent() {
+        let entry_dir = entry_dir
+            .canonicalize()
+            .unwrap_or_else(|_| entry_dir.to_path_buf());
+        if !config.src.contains(&entry_dir) {
+            config.src.insert(0, entry_dir);
+        }
+    }
+
+    let mut resolver = ModuleResolver::new(config)?;
+    if let Some(classifier) = request.resolver_classifier() {
+        resolver.set_classifier(classifier);
+    }
+    Ok(resolver.resolve(module_name)?.into())
+}
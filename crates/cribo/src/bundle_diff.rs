@@ -0,0 +1,127 @@
+//! Unified diffs between an existing bundle and a freshly generated one
+//!
+//! Every hunk header is followed by the modules whose code the hunk touches, taken from
+//! the module map of the new bundle, so reviewers can tell which source change caused it.
+
+use std::fmt::Write;
+
+use similar::{DiffOp, DiffTag, TextDiff};
+
+use crate::module_map::{ModuleMap, ModuleMapEntry};
+
+/// Lines of context around each change, like `diff -u`
+const CONTEXT_LINES: usize = 3;
+
+/// Unified diff from `old` to `new`, or an empty string when they are identical
+///
+/// `old_name` and `new_name` label the two sides in the `---`/`+++` header lines.
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+    module_map: &ModuleMap,
+) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut output = String::new();
+
+    for hunk in diff
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .iter_hunks()
+    {
+        if output.is_empty() {
+            let _ = writeln!(output, "--- {old_name}\n+++ {new_name}");
+        }
+
+        let modules = changed_modules(hunk.ops(), module_map);
+        let rendered = hunk.to_string();
+        let (header, changes) = rendered.split_once('\n').unwrap_or((&rendered, ""));
+        output.push_str(header);
+        if !modules.is_empty() {
+            let _ = write!(output, " {}", modules.join(", "));
+        }
+        output.push('\n');
+        output.push_str(changes);
+    }
+
+    output
+}
+
+/// The modules whose lines in the new bundle a hunk changes, as `name (strategy)` in
+/// bundle order
+///
+/// Changes outside of any module, e.g. in init calls or the header, are attributed to
+/// none. Pure deletions are attributed to the module at the position they occurred.
+fn changed_modules(ops: &[DiffOp], module_map: &ModuleMap) -> Vec<String> {
+    let mut modules: Vec<&ModuleMapEntry> = Vec::new();
+    for op in ops {
+        let (tag, _, new_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        // Module map lines are 1-based and inclusive
+        let first_line = new_range.start + 1;
+        let last_line = new_range.end.max(first_line);
+        for entry in &module_map.modules {
+            let Some([start, end]) = entry.lines else {
+                continue;
+            };
+            if start <= last_line
+                && first_line <= end
+                && !modules.iter().any(|module| module.name == entry.name)
+            {
+                modules.push(entry);
+            }
+        }
+    }
+    modules
+        .into_iter()
+        .map(|entry| format!("{} ({})", entry.name, entry.strategy))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_generator::ModuleStrategy;
+    use crate::module_map::MODULE_MAP_VERSION;
+
+    fn entry(name: &str, strategy: ModuleStrategy, lines: [usize; 2]) -> ModuleMapEntry {
+        ModuleMapEntry {
+            name: name.to_string(),
+            path: format!("{name}.py"),
+            strategy,
+            synthetic_name: None,
+            bytes: None,
+            lines: Some(lines),
+        }
+    }
+
+    #[test]
+    fn test_identical_bundles_produce_no_diff() {
+        let source = "a = 1\nb = 2\n";
+        assert!(unified_diff(source, source, "old", "new", &ModuleMap::default()).is_empty());
+    }
+
+    #[test]
+    fn test_hunks_name_the_changed_module() {
+        let old = "def helper():\n    return 1\n\nprint(helper())\n";
+        let new = "def helper():\n    return 2\n\nprint(helper())\n";
+        let module_map = ModuleMap {
+            version: MODULE_MAP_VERSION,
+            modules: vec![
+                entry("util", ModuleStrategy::Inlined, [1, 2]),
+                entry("main", ModuleStrategy::Entry, [4, 4]),
+            ],
+        };
+
+        let diff = unified_diff(old, new, "bundle.py", "bundle.py (new)", &module_map);
+        assert!(diff.starts_with("--- bundle.py\n+++ bundle.py (new)\n"));
+        assert!(
+            diff.contains("@@ -1,4 +1,4 @@ util (inlined)\n"),
+            "unexpected diff:\n{diff}"
+        );
+        assert!(diff.contains("-    return 1\n+    return 2\n"));
+    }
+}
@@ -0,0 +1,607 @@
+//! The `cribo` command line
+//!
+//! [`run`] parses the arguments and carries out the command. The `cribo` binary and
+//! the Python package's `python -m cribo` both go through it, so their arguments,
+//! output and exit statuses are the same.
+// Reporting on stdout and stderr is what a command line is for
+#![allow(clippy::print_stdout, clippy::print_stderr)]
+
+use clap::{Parser, Subcommand, ValueEnum};
+use env_logger::Env;
+use log::{Level, Log, Metadata, Record, debug, info};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::Config;
+use crate::cribo_graph::{CircularDependencyGroup, CircularDependencyType};
+use crate::defines::parse_definition;
+#[cfg(feature = "git")]
+use crate::git_source::GitSnapshot;
+use crate::metrics::{MetricsHandle, NoopMetrics};
+use crate::orchestrator::BundleOrchestrator;
+use crate::report::OrphanModule;
+use crate::server::BundleServer;
+use crate::stats::StatsCollector;
+use crate::workspace::Workspace;
+
+/// Exit status of a successful run
+const EXIT_SUCCESS: u8 = 0;
+/// Exit status of a failed run, and of `--diff` when the bundle would change
+const EXIT_FAILURE: u8 = 1;
+/// Exit status of invalid arguments when clap does not provide one
+const EXIT_USAGE: u8 = 2;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Entry point Python script, package directory or dotted module name (`pkg.cli`,
+    /// resolved like `python -m`), or a directory of scripts to bundle behind a command
+    /// dispatcher (defaults to `entry` from the configuration); repeat it to build one
+    /// bundle per entry into the `--output` directory, parsing shared modules once
+    #[arg(short, long, global = true)]
+    entry: Vec<PathBuf>,
+
+    /// Output bundled Python file, or the directory for the bundles of several entries
+    /// (defaults to `output` from the configuration)
+    #[arg(short, long, conflicts_with = "stdout")]
+    output: Option<PathBuf>,
+
+    /// Output bundled code to stdout instead of a file
+    #[arg(long, conflicts_with = "output")]
+    stdout: bool,
+
+    /// Make the bundle check that its third-party dependencies are installed before
+    /// running, listing what to `pip install` when they are not
+    #[arg(long)]
+    preflight_check: bool,
+
+    /// Keep the previous bundle as `<output>.bak` when overwriting it
+    #[arg(long, conflicts_with = "stdout")]
+    backup: bool,
+
+    /// Print a unified diff against the existing output file instead of writing it;
+    /// exits with status 1 when the bundle would change
+    #[arg(long, conflicts_with = "stdout")]
+    diff: bool,
+
+    /// Instead of writing the bundle, print each statement and rule that keeps a module
+    /// from being inlined
+    #[arg(long, conflicts_with_all = ["output", "stdout", "diff", "all", "only"])]
+    why_not_inlined: bool,
+
+    /// Instead of writing the bundle, print its dependency graph with each module
+    /// marked as entry, inlined, namespace, wrapper, stdlib or third-party
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        conflicts_with_all = ["output", "stdout", "diff", "all", "only", "why_not_inlined"]
+    )]
+    emit_graph: Option<GraphFormat>,
+
+    /// Only assign wrapper-module attributes that other bundled modules access
+    #[arg(long)]
+    prune_wrapper_exports: bool,
+
+    /// Drop functions, classes and constants of inlined modules the bundle never uses
+    #[arg(long)]
+    tree_shake: bool,
+
+    /// Build every bundle declared as `[[bundles]]` in the configuration
+    #[arg(long, global = true, conflicts_with_all = ["entry", "output", "stdout", "diff", "only"])]
+    all: bool,
+
+    /// Build only the named `[[bundles]]` entry (can be repeated)
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        conflicts_with_all = ["entry", "output", "stdout", "diff"]
+    )]
+    only: Vec<String>,
+
+    /// Build at most N bundles at the same time with --all or --only (defaults to the
+    /// number of CPU cores)
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Increase verbosity (can be repeated: -v, -vv, -vvv)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Configuration file path
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Apply the `[profile.<NAME>]` section of the configuration (e.g. dev, release)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Emit requirements.txt with third-party dependencies
+    #[arg(long)]
+    emit_requirements: bool,
+
+    /// Write a JSON map of where each module's code lies in the bundle
+    /// (`<output>.map.json`, or `bundle.py.map.json` with --stdout)
+    #[arg(long)]
+    module_map: bool,
+
+    /// Also write the bundle as an executable zip application (`<output stem>.pyz`)
+    #[arg(long)]
+    pyz: bool,
+
+    /// Write the bundle compressed behind a stub that decompresses and runs it
+    #[arg(long)]
+    self_extracting: bool,
+
+    /// Make the written bundle executable (Unix) so it runs through its shebang line
+    #[arg(long)]
+    executable: bool,
+
+    /// Interpreter line at the top of the bundle (default: `#!/usr/bin/env python3`)
+    #[arg(long, value_name = "LINE")]
+    shebang: Option<String>,
+
+    /// Write a JSON report of the bundled modules, requirements and resolved cycles
+    /// (`<output>.report.json`)
+    #[arg(long)]
+    report: bool,
+
+    /// Target Python version (e.g., py38, py39, py310, py311, py312, py313, py314)
+    #[arg(long, alias = "python-version")]
+    target_version: Option<String>,
+
+    /// Additional directory to search for first-party modules (can be repeated)
+    #[arg(long = "path", value_name = "DIR")]
+    paths: Vec<PathBuf>,
+
+    /// Do not search directories from the PYTHONPATH environment variable
+    #[arg(long)]
+    no_pythonpath: bool,
+
+    /// Replace reads of a module-level name with a value at bundle time (e.g.
+    /// `FEATURE_X=false`); can be repeated
+    #[arg(short = 'D', long = "define", value_name = "NAME=VALUE", value_parser = parse_define)]
+    defines: Vec<(String, toml::Value)>,
+
+    /// Type-check the bundle with this command (e.g. "mypy --strict" or "pyright") and
+    /// report its findings as warnings
+    #[arg(long, value_name = "COMMAND")]
+    type_checker: Option<String>,
+
+    /// Read sources from this git revision (branch, tag or commit) instead of the
+    /// working tree, ignoring uncommitted changes
+    #[cfg(feature = "git")]
+    #[arg(long, global = true, value_name = "REV")]
+    git_ref: Option<String>,
+
+    /// Print a summary table to stderr when done: modules per strategy, lines in and
+    /// out, third-party dependencies, warnings and time per phase
+    #[arg(long)]
+    stats: bool,
+}
+
+/// Number of warnings logged so far, for --stats
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Logger that counts the warnings it lets through
+struct CountingLogger(env_logger::Logger);
+
+impl Log for CountingLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if record.level() == Level::Warn && self.0.matches(record) {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+        self.0.log(record);
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+/// Serialization of the dependency graph printed by --emit-graph
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT, e.g. for `dot -Tsvg`
+    Dot,
+    /// JSON that also lists each module's side effects and renamed symbols
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect the project instead of bundling it
+    Analyze {
+        #[command(subcommand)]
+        analysis: Analysis,
+    },
+    /// Print every chain of imports from the entry to MODULE, with the import
+    /// statements and files along each chain
+    Explain {
+        /// Dotted name of the bundled module to explain
+        module: String,
+    },
+    /// Keep parsed modules in memory and answer JSON-RPC bundle requests, one per
+    /// line, on stdin/stdout until `shutdown`
+    Serve {
+        /// Listen on this Unix socket instead of stdin/stdout
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum Analysis {
+    /// List first-party modules under the source roots that no entry imports (all
+    /// `[[bundles]]` entries with --all or --only)
+    Orphans,
+    /// List mutual-import clusters (strongly connected components) with the imports
+    /// that hold them together, largest first
+    Sccs,
+}
+
+/// Run the command line `args`, program name first, and return the exit status
+///
+/// Usage errors, `--help` and `--version` are printed by clap and other failures as
+/// `Error: ...` on stderr, exactly like the `cribo` binary does.
+pub fn run<I, T>(args: I) -> u8
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            let _ = err.print();
+            return u8::try_from(err.exit_code()).unwrap_or(EXIT_USAGE);
+        }
+    };
+    match execute(cli) {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+// Only --git-ref rewrites the parsed entries
+#[cfg_attr(not(feature = "git"), allow(unused_mut))]
+fn execute(mut cli: Cli) -> anyhow::Result<u8> {
+    // Initialize logging based on verbosity level
+    let log_level = match cli.verbose {
+        0 => "warn",  // Default: warnings and errors only
+        1 => "info",  // -v: informational messages
+        2 => "debug", // -vv: debug messages
+        _ => "trace", // -vvv or more: trace messages
+    };
+    let logger = env_logger::Builder::from_env(Env::default().default_filter_or(log_level)).build();
+    let max_level = logger.filter();
+    // A process running the command line more than once keeps its first logger
+    if crate::logging::install(Some(Box::new(CountingLogger(logger))), max_level).is_err() {
+        debug!("Logger already installed by an earlier run");
+    }
+
+    debug!(
+        "Verbosity level: {} (log level: {})",
+        cli.verbose, log_level
+    );
+    info!("Starting Cribo Python bundler");
+
+    // Load configuration
+    let mut config = Config::load_with_profile(cli.config.as_deref(), cli.profile.as_deref())?;
+
+    // Override target-version from CLI if provided
+    if let Some(target_version) = cli.target_version {
+        config.set_target_version(target_version)?;
+    }
+
+    // Extra search roots from the CLI come after the configured source directories
+    for path in cli.paths {
+        if !config.src.contains(&path) {
+            config.src.push(path);
+        }
+    }
+    if cli.no_pythonpath {
+        config.respect_pythonpath = false;
+    }
+    if cli.module_map {
+        config.module_map = true;
+    }
+    if cli.pyz {
+        config.emit_pyz = true;
+    }
+    if cli.self_extracting {
+        config.self_extracting = true;
+    }
+    if cli.executable {
+        config.executable = true;
+    }
+    if let Some(shebang) = cli.shebang {
+        config.shebang = shebang;
+    }
+    if cli.report {
+        config.emit_report = true;
+    }
+    if cli.preflight_check {
+        config.preflight_check = true;
+    }
+    if cli.backup {
+        config.backup_output = true;
+    }
+    if cli.prune_wrapper_exports {
+        config.prune_wrapper_exports = true;
+    }
+    if cli.tree_shake {
+        config.tree_shake = true;
+    }
+    config.defines.extend(cli.defines);
+    if let Some(type_checker) = cli.type_checker {
+        config.type_checker = Some(type_checker);
+    }
+
+    // Dropping the snapshot removes the exported sources, so keep it until the end
+    #[cfg(feature = "git")]
+    let snapshot = cli
+        .git_ref
+        .as_deref()
+        .map(GitSnapshot::export)
+        .transpose()?;
+    #[cfg(feature = "git")]
+    if let Some(snapshot) = &snapshot {
+        snapshot.apply_to(&mut config);
+        cli.entry = cli
+            .entry
+            .iter()
+            .map(|entry| PathBuf::from(snapshot.map_entry(&entry.to_string_lossy())))
+            .collect();
+    }
+
+    debug!("Configuration: {:?}", config);
+
+    // Display target version for troubleshooting
+    info!(
+        "Target Python version: {} (resolved to Python 3.{})",
+        config.target_version,
+        config.python_version().unwrap_or(10)
+    );
+
+    if let Some(Command::Explain { module }) = &cli.command {
+        let entry = entry_path(cli.entry, &config)?;
+        let explanation = BundleOrchestrator::new(config).explain(&entry, module)?;
+        print!("{}", explanation);
+        return Ok(EXIT_SUCCESS);
+    }
+
+    if let Some(Command::Serve { socket }) = &cli.command {
+        let server = BundleServer::new(config);
+        match socket {
+            Some(path) => server.serve_socket(path)?,
+            None => {
+                server.serve(std::io::stdin().lock(), std::io::stdout().lock())?;
+            }
+        }
+        return Ok(EXIT_SUCCESS);
+    }
+
+    if let Some(Command::Analyze { analysis }) = &cli.command {
+        return match analysis {
+            Analysis::Orphans => {
+                let orphans = if cli.all || !cli.only.is_empty() {
+                    Workspace::from_config(&config, &cli.only)?.orphans()?
+                } else if cli.entry.len() > 1 {
+                    // Only the entries matter, not where their bundles would go
+                    Workspace::from_entries(&config, &cli.entry, Path::new("."))?.orphans()?
+                } else {
+                    let entry = entry_path(cli.entry, &config)?;
+                    BundleOrchestrator::new(config).orphans(&entry)?
+                };
+                print_orphans(&orphans);
+                Ok(EXIT_SUCCESS)
+            }
+            Analysis::Sccs => {
+                let entry = entry_path(cli.entry, &config)?;
+                let components =
+                    BundleOrchestrator::new(config).strongly_connected_components(&entry)?;
+                print_components(&components);
+                Ok(EXIT_SUCCESS)
+            }
+        };
+    }
+
+    let stats = cli.stats.then(|| Arc::new(StatsCollector::new()));
+    let metrics: MetricsHandle = match &stats {
+        Some(stats) => stats.clone(),
+        None => Arc::new(NoopMetrics),
+    };
+
+    if cli.all || !cli.only.is_empty() {
+        let mut workspace = Workspace::from_config(&config, &cli.only)?.with_metrics(metrics);
+        if let Some(jobs) = cli.jobs {
+            workspace = workspace.with_jobs(jobs);
+        }
+        workspace.bundle_all(cli.emit_requirements)?;
+        info!("Built {} bundle(s)", workspace.bundles().len());
+        print_stats(stats.as_deref());
+        return Ok(EXIT_SUCCESS);
+    }
+
+    if cli.entry.len() > 1 {
+        if cli.stdout || cli.diff || cli.why_not_inlined || cli.emit_graph.is_some() {
+            return Err(anyhow::anyhow!(
+                "Several entries can only be bundled to files: pass --output <DIR>"
+            ));
+        }
+        let output_dir = cli.output.ok_or_else(|| {
+            anyhow::anyhow!("Several entries need --output <DIR> for their bundles")
+        })?;
+        std::fs::create_dir_all(&output_dir)?;
+        let mut workspace =
+            Workspace::from_entries(&config, &cli.entry, &output_dir)?.with_metrics(metrics);
+        if let Some(jobs) = cli.jobs {
+            workspace = workspace.with_jobs(jobs);
+        }
+        workspace.bundle_all(cli.emit_requirements)?;
+        info!(
+            "Built {} bundle(s) in {:?}",
+            workspace.bundles().len(),
+            output_dir
+        );
+        print_stats(stats.as_deref());
+        return Ok(EXIT_SUCCESS);
+    }
+
+    // Command-line arguments take precedence over the configured entry and output
+    let entry = entry_path(cli.entry, &config)?;
+
+    if cli.why_not_inlined {
+        let mut bundler = BundleOrchestrator::new(config);
+        let explanation = bundler.why_not_inlined(&entry)?;
+        if explanation.is_empty() {
+            println!("Every module is inlined");
+        } else {
+            print!("{}", explanation);
+        }
+        return Ok(EXIT_SUCCESS);
+    }
+
+    if let Some(format) = cli.emit_graph {
+        let graph = BundleOrchestrator::new(config).dependency_graph(&entry)?;
+        match format {
+            GraphFormat::Dot => print!("{}", graph.to_dot()),
+            GraphFormat::Json => println!("{}", graph.to_json()?),
+        }
+        return Ok(EXIT_SUCCESS);
+    }
+
+    let output = if cli.stdout {
+        None
+    } else {
+        cli.output.or_else(|| config.output.clone())
+    };
+
+    debug!("Entry point: {:?}", entry);
+    if cli.stdout {
+        debug!("Output mode: stdout");
+    } else {
+        debug!("Output: {:?}", output);
+    }
+
+    // Validate arguments
+    if !cli.stdout && output.is_none() {
+        return Err(anyhow::anyhow!(
+            "Either --output or --stdout must be specified (or `output` set in cribo.toml)"
+        ));
+    }
+
+    let mut bundler = BundleOrchestrator::new(config).with_metrics(metrics);
+
+    if cli.diff {
+        let output_path = output
+            .as_ref()
+            .expect("Output path should be present when using --diff");
+        let diff = bundler.diff(&entry, output_path)?;
+        if diff.is_empty() {
+            info!("Bundle at {:?} is up to date", output_path);
+        } else {
+            print!("{}", diff);
+            print_stats(stats.as_deref());
+            return Ok(EXIT_FAILURE);
+        }
+    } else if cli.stdout {
+        // Output to stdout
+        let bundled_code = bundler.bundle_to_string(&entry, cli.emit_requirements)?;
+        print!("{}", bundled_code);
+        info!("Bundle output to stdout");
+    } else {
+        // Output to file
+        let output_path = output
+            .as_ref()
+            .expect("Output path should be present when not using stdout");
+        bundler.bundle(&entry, output_path, cli.emit_requirements)?;
+        info!("Bundle created successfully at {:?}", output_path);
+    }
+
+    print_stats(stats.as_deref());
+    Ok(EXIT_SUCCESS)
+}
+
+/// Print the --stats table to stderr, which keeps stdout clean for --stdout and --diff
+fn print_stats(stats: Option<&StatsCollector>) {
+    if let Some(stats) = stats {
+        eprint!("{}", stats.stats(WARNINGS.load(Ordering::Relaxed)));
+    }
+}
+
+/// Parse a `--define NAME=VALUE` argument
+fn parse_define(definition: &str) -> Result<(String, toml::Value), String> {
+    parse_definition(definition).ok_or_else(|| {
+        format!(
+            "expected NAME=VALUE with NAME a Python identifier, got '{}'",
+            definition
+        )
+    })
+}
+
+/// The single entry from the command line, or the configured one
+fn entry_path(mut entries: Vec<PathBuf>, config: &Config) -> anyhow::Result<PathBuf> {
+    if entries.len() > 1 {
+        return Err(anyhow::anyhow!("This command takes a single --entry"));
+    }
+    match entries.pop() {
+        Some(entry) => Ok(config.resolve_entry(&entry.to_string_lossy())?),
+        None => config.entry_path()?.ok_or_else(|| {
+            anyhow::anyhow!("No entry point: pass --entry or set `entry` in cribo.toml")
+        }),
+    }
+}
+
+/// Print one orphaned module per line, with its path relative to the working directory
+fn print_orphans(orphans: &[OrphanModule]) {
+    if orphans.is_empty() {
+        println!("No orphaned modules");
+        return;
+    }
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    for orphan in orphans {
+        let path = orphan
+            .path
+            .strip_prefix(&current_dir)
+            .unwrap_or(&orphan.path);
+        println!("{} ({})", orphan.name, path.display());
+    }
+}
+
+/// Print each cluster with its size, whether the bundler can handle it and its edges
+fn print_components(components: &[CircularDependencyGroup]) {
+    if components.is_empty() {
+        println!("No circular imports");
+        return;
+    }
+    for component in components {
+        let kind = match component.cycle_type {
+            CircularDependencyType::FunctionLevel => "function-level, bundleable",
+            CircularDependencyType::ClassLevel => "class-level, not bundleable",
+            CircularDependencyType::ModuleConstants => "module constants, not bundleable",
+            CircularDependencyType::ImportTime => "import-time, bundleable",
+        };
+        println!(
+            "{} modules ({}): {}",
+            component.modules.len(),
+            kind,
+            component.modules.join(", ")
+        );
+        for edge in &component.import_chain {
+            println!("  {} -> {}", edge.from_module, edge.to_module);
+        }
+    }
+}
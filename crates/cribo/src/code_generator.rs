@@ -5,25 +5,215 @@ use indexmap::{IndexMap, IndexSet};
 use log::debug;
 use ruff_python_ast::{
     Arguments, CmpOp, Comprehension, ExceptHandler, Expr, ExprAttribute, ExprCall, ExprCompare,
-    ExprContext, ExprFString, ExprIf, ExprList, ExprName, ExprNoneLiteral, ExprStringLiteral,
-    FString, FStringFlags, FStringValue, Identifier, InterpolatedElement,
-    InterpolatedStringElement, InterpolatedStringElements, Keyword, ModModule, Stmt, StmtAssign,
-    StmtClassDef, StmtFunctionDef, StmtIf, StmtImport, StmtImportFrom, StringLiteral,
-    StringLiteralFlags, StringLiteralValue,
+    ExprContext, ExprIf, ExprList, ExprName, ExprNoneLiteral, ExprStringLiteral, Identifier,
+    Keyword, ModModule, Stmt, StmtAssign, StmtAugAssign, StmtClassDef, StmtFunctionDef, StmtIf,
+    StmtImport, StmtImportFrom, StmtTry, StmtWith, StringLiteral, StringLiteralFlags,
+    StringLiteralValue,
 };
-use ruff_text_size::TextRange;
+use ruff_text_size::{Ranged, TextRange};
 use rustc_hash::FxHasher;
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
 use std::hash::BuildHasherDefault;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
-use crate::cribo_graph::CriboGraph as DependencyGraph;
-use crate::semantic_bundler::{ModuleGlobalInfo, SemanticBundler, SymbolRegistry};
+use crate::config::DEFAULT_RUNTIME_PREFIX;
+use crate::cribo_graph::{
+    CircularDependencyGroup, CircularDependencyType, CriboGraph as DependencyGraph,
+    UnusedImportInfo,
+};
+use crate::defines::nested_bodies;
+use crate::distributions::distribution_name;
+use crate::semantic_bundler::{ClassScopeInfo, ModuleGlobalInfo, SemanticBundler, SymbolRegistry};
+use crate::tree_shaking;
+use crate::visitors::{
+    DynamicExportVisitor, FindSpecProbeVisitor, IdentifierVisitor, ModuleAttributeVisitor,
+    ModuleNameRewriter, handles_import_error, interpolated_expressions_mut,
+};
 
 /// Type alias for IndexMap with FxHasher for better performance
 type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
 /// Type alias for IndexSet with FxHasher for better performance
 type FxIndexSet<T> = IndexSet<T, BuildHasherDefault<FxHasher>>;
 
+/// How a module's code is placed in the bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleStrategy {
+    /// Statements are inlined into the bundle's top level
+    Inlined,
+    /// Inlined, with a namespace object exposing the module's symbols
+    Namespace,
+    /// Wrapped in an init function that builds a module object
+    Wrapper,
+    /// The entry script, emitted last
+    Entry,
+}
+
+impl std::fmt::Display for ModuleStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Inlined => "inlined",
+            Self::Namespace => "namespace",
+            Self::Wrapper => "wrapper",
+            Self::Entry => "entry",
+        })
+    }
+}
+
+/// Rule that keeps a module from being inlined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapperRule {
+    /// The module creates names at runtime, e.g. with `globals().update()`
+    DynamicNamespace,
+    /// A top-level statement may have side effects when run
+    SideEffect,
+    /// Another module imports it with `import module` or as a submodule
+    DirectImport,
+    /// A function or class of the module imports inside its body
+    FunctionScopedImport,
+    /// The module is a script run on demand by the dispatcher of a multi-script bundle
+    DispatchedScript,
+    /// The module uses a module that imports it back while it initializes
+    ImportCycle,
+    /// The configuration lists the module in `force_wrapper`
+    Configured,
+}
+
+impl std::fmt::Display for WrapperRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::DynamicNamespace => "builds its namespace dynamically",
+            Self::SideEffect => "top-level statement with side effects",
+            Self::DirectImport => "imported directly",
+            Self::FunctionScopedImport => "function-scoped import",
+            Self::DispatchedScript => "script run by the dispatcher",
+            Self::ImportCycle => "initializes inside an import cycle",
+            Self::Configured => "listed in force_wrapper",
+        })
+    }
+}
+
+/// One reason a module is bundled as a wrapper module instead of being inlined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrapperReason {
+    pub rule: WrapperRule,
+    /// Module whose source contains `range`: the wrapper module itself, or the
+    /// importing module for direct imports
+    pub source_module: String,
+    /// Statement responsible, when the rule applies to one
+    pub range: Option<TextRange>,
+    /// What was detected when there is no statement, e.g. the dynamic pattern used
+    pub detail: Option<String>,
+}
+
+/// A contiguous run of statements in the bundled body that came from one module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleSection {
+    pub module_name: String,
+    pub strategy: ModuleStrategy,
+    /// Synthetic module name of wrapped modules
+    pub synthetic_name: Option<String>,
+    /// Statement indices in the body returned by `bundle_modules`
+    pub statements: Range<usize>,
+}
+
+/// Identifiers the bundle's runtime support code binds
+///
+/// Each one is renamed when bundled code already uses the name, since the runtime
+/// binding would otherwise shadow the user's or be shadowed by it.
+#[derive(Debug, Clone)]
+struct RuntimeNames {
+    /// Prefix of the bundle's own names (see `Config::runtime_prefix`)
+    prefix: String,
+    /// Module object that each wrapper module's init function builds and returns
+    module_var: String,
+    /// Registry mapping original module names to synthetic ones
+    modules_registry: String,
+    /// Registry mapping synthetic module names to their init functions
+    init_functions_registry: String,
+    /// Meta path finder class that imports wrapper modules on demand
+    finder_class: String,
+    /// Stand-in for a wrapper module of an import cycle that initializes it on first
+    /// attribute access
+    lazy_module_class: String,
+    /// Meta path finder class reporting the bundled modules that code probes for with
+    /// `importlib.util.find_spec`
+    bundled_module_finder_class: String,
+    /// Lock held while a wrapper module initializes, so threads importing it at the
+    /// same time wait for the first one instead of seeing a half-built module
+    init_lock: String,
+    /// Synthetic names of wrapper modules whose init function has completed
+    initialized_modules: String,
+    /// Module in sys.modules holding the lock, the initialized set and the installed
+    /// finder for every copy of the bundle in the process
+    runtime_state: String,
+}
+
+impl Default for RuntimeNames {
+    fn default() -> Self {
+        Self::new(DEFAULT_RUNTIME_PREFIX)
+    }
+}
+
+impl RuntimeNames {
+    fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            module_var: "module".to_owned(),
+            modules_registry: format!("{}modules", prefix),
+            init_functions_registry: format!("{}init_functions", prefix),
+            finder_class: "CriboBundledFinder".to_owned(),
+            lazy_module_class: format!("{}LazyModule", prefix),
+            bundled_module_finder_class: format!("{}BundledModuleFinder", prefix),
+            init_lock: format!("{}init_lock", prefix),
+            initialized_modules: format!("{}initialized_modules", prefix),
+            runtime_state: format!("{}runtime", prefix),
+        }
+    }
+
+    /// `name` with the runtime prefix
+    fn prefixed(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Rename each runtime identifier that appears in `user_names` by appending the
+    /// first free numeric suffix, warning about every collision
+    fn avoid_collisions(&mut self, user_names: &FxIndexSet<String>) {
+        for name in [
+            &mut self.module_var,
+            &mut self.modules_registry,
+            &mut self.init_functions_registry,
+            &mut self.finder_class,
+            &mut self.lazy_module_class,
+            &mut self.bundled_module_finder_class,
+            &mut self.init_lock,
+            &mut self.initialized_modules,
+            &mut self.runtime_state,
+        ] {
+            if !user_names.contains(name.as_str()) {
+                continue;
+            }
+            let mut suffix = 1;
+            let renamed = loop {
+                let candidate = format!("{}_{}", name, suffix);
+                if !user_names.contains(&candidate) {
+                    break candidate;
+                }
+                suffix += 1;
+            };
+            log::warn!(
+                "Bundled code uses the name '{}', which the bundle runtime also defines; \
+                 the runtime uses '{}' instead",
+                name,
+                renamed
+            );
+            *name = renamed;
+        }
+    }
+}
+
 /// Context for module transformation operations
 struct ModuleTransformContext<'a> {
     module_name: &'a str,
@@ -32,6 +222,13 @@ struct ModuleTransformContext<'a> {
     global_info: Option<ModuleGlobalInfo>,
 }
 
+/// Modules after the preparation pass of the bundling pipeline
+struct PreparedModules {
+    modules: Vec<(String, ModModule, PathBuf, String)>,
+    /// Modules that contain imports inside function or method bodies
+    modules_with_function_imports: FxIndexSet<String>,
+}
+
 /// Context for inlining operations
 struct InlineContext<'a> {
     module_exports_map: &'a FxIndexMap<String, Option<Vec<String>>>,
@@ -61,6 +258,7 @@ struct NamespaceImportParams<'a> {
 struct ProcessGlobalsParams<'a> {
     module_name: &'a str,
     ast: &'a ModModule,
+    module_path: &'a Path,
     semantic_ctx: &'a SemanticContext<'a>,
 }
 
@@ -107,6 +305,118 @@ pub struct BundleParams<'a> {
     pub entry_module_name: &'a str,
     pub graph: &'a DependencyGraph, // Dependency graph for unused import detection
     pub semantic_bundler: &'a SemanticBundler, // Semantic analysis results
+    /// Emit a bundle that can be both executed and imported (see `Config::dual_mode`)
+    pub dual_mode: bool,
+    /// Register inlined modules in `sys.modules` (see `Config::register_inlined_modules`)
+    pub register_inlined_modules: bool,
+    /// Third-party packages the bundle checks for before running any module code;
+    /// empty to skip the check (see `Config::preflight_check`)
+    pub preflight_requirements: &'a [String],
+    /// Distributions the preflight check tells users to install, keyed by import name
+    /// (see `Config::distribution_names`)
+    pub distribution_names: &'a IndexMap<String, String>,
+    /// Only export the wrapper-module attributes the bundle accesses (see
+    /// `Config::prune_wrapper_exports`)
+    pub prune_wrapper_exports: bool,
+    /// Prefix of the names the bundle's runtime support code defines (see
+    /// `Config::runtime_prefix`)
+    pub runtime_prefix: &'a str,
+    /// Context managers whose top-level `with` blocks may be inlined (see
+    /// `Config::inline_with_managers`)
+    pub inline_with_managers: &'a [String],
+    /// External packages that relative imports climbing above the source roots resolve
+    /// into (see `Config::relative_import_fallbacks`)
+    pub relative_import_fallbacks: &'a IndexMap<String, String>,
+    /// Scripts the entry dispatches to when the entry is a directory of scripts (see
+    /// `dispatcher::ScriptDispatcher`)
+    pub dispatched_scripts: &'a [String],
+    /// Drop top-level definitions of inlined modules the bundle never uses (see
+    /// `Config::tree_shake`)
+    pub tree_shake: bool,
+    /// Remove `assert` statements from every module (see `Config::strip_asserts`)
+    pub strip_asserts: bool,
+    /// Remove docstrings from every module (see `Config::strip_docstrings`)
+    pub strip_docstrings: bool,
+    /// Leave the entry module's docstrings in place when stripping them (see
+    /// `Config::keep_entry_docstrings`)
+    pub keep_entry_docstrings: bool,
+    /// Remove annotations outside class bodies (see `Config::strip_annotations`)
+    pub strip_annotations: bool,
+    /// Circular dependencies the bundle has to resolve; wrapper modules of a cycle
+    /// import each other lazily
+    pub import_cycles: &'a [CircularDependencyGroup],
+    /// Modules inlined despite side effects (see `Config::force_inline`)
+    pub force_inline: &'a IndexSet<String>,
+    /// Modules always bundled as wrapper modules (see `Config::force_wrapper`)
+    pub force_wrapper: &'a IndexSet<String>,
+    /// Python 3 minor version whose standard library imports may be hoisted (see
+    /// `Config::target_version`)
+    pub python_version: u8,
+}
+
+/// Number of leading statements of `body` that Python requires to stay first: the
+/// module docstring and every `from __future__` import following it
+fn future_preamble_len(body: &[Stmt]) -> usize {
+    let docstring = usize::from(matches!(
+        body.first(),
+        Some(Stmt::Expr(expr_stmt)) if matches!(expr_stmt.value.as_ref(), Expr::StringLiteral(_))
+    ));
+    docstring
+        + body[docstring..]
+            .iter()
+            .take_while(|stmt| {
+                matches!(
+                    stmt,
+                    Stmt::ImportFrom(import_from)
+                        if import_from.module.as_ref().map(|m| m.as_str()) == Some("__future__")
+                )
+            })
+            .count()
+}
+
+/// Check that every package in `requirements` is importable, exiting with a message
+/// listing the distributions to install otherwise
+fn create_preflight_check(
+    requirements: &[String],
+    distribution_names: &IndexMap<String, String>,
+    runtime_prefix: &str,
+) -> Result<Vec<Stmt>> {
+    let packages = requirements
+        .iter()
+        .map(|name| {
+            format!(
+                "(\"{}\", \"{}\")",
+                name,
+                distribution_name(name, distribution_names)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    parse_statements(&format!(
+        r#"def {runtime_prefix}preflight():
+    import importlib.util
+    import sys
+
+    missing = [
+        distribution
+        for name, distribution in [{packages}]
+        if importlib.util.find_spec(name) is None
+    ]
+    if missing:
+        sys.stderr.write(
+            "This program requires packages that are not installed: "
+            + ", ".join(missing)
+            + "\nInstall them with: pip install "
+            + " ".join(missing)
+            + "\n"
+        )
+        raise SystemExit(1)
+
+
+{runtime_prefix}preflight()
+del {runtime_prefix}preflight
+"#
+    ))
 }
 
 /// Transformer that lifts module-level globals to true global scope
@@ -118,7 +428,7 @@ struct GlobalsLifter {
 }
 
 /// Transform globals() calls to module.__dict__ when inside module functions
-fn transform_globals_in_expr(expr: &mut Expr) {
+fn transform_globals_in_expr(expr: &mut Expr, module_var: &str) {
     match expr {
         Expr::Call(call_expr) => {
             // Check if this is a globals() call
@@ -127,7 +437,7 @@ fn transform_globals_in_expr(expr: &mut Expr) {
                     // Replace the entire expression with module.__dict__
                     *expr = Expr::Attribute(ExprAttribute {
                         value: Box::new(Expr::Name(ExprName {
-                            id: "module".into(),
+                            id: module_var.into(),
                             ctx: ExprContext::Load,
                             range: TextRange::default(),
                         })),
@@ -140,79 +450,84 @@ fn transform_globals_in_expr(expr: &mut Expr) {
             }
 
             // Recursively transform in function and arguments
-            transform_globals_in_expr(&mut call_expr.func);
+            transform_globals_in_expr(&mut call_expr.func, module_var);
             for arg in &mut call_expr.arguments.args {
-                transform_globals_in_expr(arg);
+                transform_globals_in_expr(arg, module_var);
             }
             for keyword in &mut call_expr.arguments.keywords {
-                transform_globals_in_expr(&mut keyword.value);
+                transform_globals_in_expr(&mut keyword.value, module_var);
             }
         }
         Expr::Attribute(attr_expr) => {
-            transform_globals_in_expr(&mut attr_expr.value);
+            transform_globals_in_expr(&mut attr_expr.value, module_var);
         }
         Expr::Subscript(subscript_expr) => {
-            transform_globals_in_expr(&mut subscript_expr.value);
-            transform_globals_in_expr(&mut subscript_expr.slice);
+            transform_globals_in_expr(&mut subscript_expr.value, module_var);
+            transform_globals_in_expr(&mut subscript_expr.slice, module_var);
         }
         Expr::List(list_expr) => {
             for elem in &mut list_expr.elts {
-                transform_globals_in_expr(elem);
+                transform_globals_in_expr(elem, module_var);
             }
         }
         Expr::Dict(dict_expr) => {
             for item in &mut dict_expr.items {
                 if let Some(ref mut key) = item.key {
-                    transform_globals_in_expr(key);
+                    transform_globals_in_expr(key, module_var);
                 }
-                transform_globals_in_expr(&mut item.value);
+                transform_globals_in_expr(&mut item.value, module_var);
             }
         }
         Expr::If(if_expr) => {
-            transform_globals_in_expr(&mut if_expr.test);
-            transform_globals_in_expr(&mut if_expr.body);
-            transform_globals_in_expr(&mut if_expr.orelse);
+            transform_globals_in_expr(&mut if_expr.test, module_var);
+            transform_globals_in_expr(&mut if_expr.body, module_var);
+            transform_globals_in_expr(&mut if_expr.orelse, module_var);
         }
         // Add more expression types as needed
         _ => {}
     }
 }
 
+/// Parse generated Python source into statements
+fn parse_statements(source: &str) -> Result<Vec<Stmt>> {
+    Ok(ruff_python_parser::parse_module(source)?.into_syntax().body)
+}
+
 /// Transform globals() calls in a statement
-fn transform_globals_in_stmt(stmt: &mut Stmt) {
+fn transform_globals_in_stmt(stmt: &mut Stmt, module_var: &str) {
     match stmt {
         Stmt::Expr(expr_stmt) => {
-            transform_globals_in_expr(&mut expr_stmt.value);
+            transform_globals_in_expr(&mut expr_stmt.value, module_var);
         }
         Stmt::Assign(assign_stmt) => {
-            transform_globals_in_expr(&mut assign_stmt.value);
+            transform_globals_in_expr(&mut assign_stmt.value, module_var);
             for target in &mut assign_stmt.targets {
-                transform_globals_in_expr(target);
+                transform_globals_in_expr(target, module_var);
             }
         }
         Stmt::Return(return_stmt) => {
             if let Some(ref mut value) = return_stmt.value {
-                transform_globals_in_expr(value);
+                transform_globals_in_expr(value, module_var);
             }
         }
         Stmt::If(if_stmt) => {
-            transform_globals_in_expr(&mut if_stmt.test);
+            transform_globals_in_expr(&mut if_stmt.test, module_var);
             for stmt in &mut if_stmt.body {
-                transform_globals_in_stmt(stmt);
+                transform_globals_in_stmt(stmt, module_var);
             }
             for clause in &mut if_stmt.elif_else_clauses {
                 if let Some(ref mut test_expr) = clause.test {
-                    transform_globals_in_expr(test_expr);
+                    transform_globals_in_expr(test_expr, module_var);
                 }
                 for stmt in &mut clause.body {
-                    transform_globals_in_stmt(stmt);
+                    transform_globals_in_stmt(stmt, module_var);
                 }
             }
         }
         Stmt::FunctionDef(func_def) => {
             // Transform globals() calls in function body
             for stmt in &mut func_def.body {
-                transform_globals_in_stmt(stmt);
+                transform_globals_in_stmt(stmt, module_var);
             }
         }
         // Add more statement types as needed
@@ -221,7 +536,7 @@ fn transform_globals_in_stmt(stmt: &mut Stmt) {
 }
 
 impl GlobalsLifter {
-    fn new(global_info: &ModuleGlobalInfo) -> Self {
+    fn new(global_info: &ModuleGlobalInfo, runtime_prefix: &str) -> Self {
         let mut lifted_names = FxIndexMap::default();
         let mut lifted_declarations = Vec::new();
 
@@ -239,7 +554,8 @@ impl GlobalsLifter {
             if global_info.global_declarations.contains_key(var_name) {
                 let module_name_sanitized = global_info.module_name.cow_replace(".", "_");
                 let module_name_sanitized = module_name_sanitized.cow_replace("-", "_");
-                let lifted_name = format!("__cribo_{}_{}", module_name_sanitized, var_name);
+                let lifted_name =
+                    format!("{}{}_{}", runtime_prefix, module_name_sanitized, var_name);
 
                 debug!(
                     "Creating lifted declaration for {} -> {}",
@@ -304,6 +620,10 @@ pub struct HybridStaticBundler {
     entry_path: Option<String>,
     /// Module export information (for __all__ handling)
     module_exports: FxIndexMap<String, Option<Vec<String>>>,
+    /// Names each module deletes at module level without rebinding them afterwards
+    module_deleted_names: FxIndexMap<String, FxIndexSet<String>>,
+    /// Bundled modules that some module probes with `importlib.util.find_spec`
+    find_spec_probed_modules: FxIndexSet<String>,
     /// Lifted global declarations to add at module top level
     lifted_global_declarations: Vec<Stmt>,
     /// Modules that are imported as namespaces (e.g., from package import module)
@@ -312,6 +632,28 @@ pub struct HybridStaticBundler {
     /// Tracks namespace modules already created in the entry module to avoid duplicates
     /// Used when processing multiple dotted imports
     created_namespace_modules: FxIndexSet<String>,
+    /// Memoized results of `is_safe_stdlib_module`, shared across all bundling passes
+    stdlib_safety_cache: RefCell<FxIndexMap<String, bool>>,
+    /// Python 3 minor version the bundle targets, for standard library checks
+    python_version: u8,
+    /// Statements of the bundled body that each module's code occupies, in emission order
+    sections: Vec<BundleSection>,
+    /// Symbol tables of each module's class bodies, keyed by dotted class name
+    class_scopes: FxIndexMap<String, FxIndexMap<String, ClassScopeInfo>>,
+    /// Attributes the bundle accesses on each wrapper module whose exports are pruned
+    /// to them (see `Config::prune_wrapper_exports`)
+    accessed_wrapper_attributes: FxIndexMap<String, IndexSet<String>>,
+    /// Identifiers of the runtime support code, renamed away from user names
+    runtime_names: RuntimeNames,
+    /// External packages for relative imports that climb above the source roots
+    relative_import_fallbacks: IndexMap<String, String>,
+    /// Why each wrapper module could not be inlined
+    wrapper_reasons: FxIndexMap<String, Vec<WrapperReason>>,
+    /// Modules of each circular dependency in the bundle
+    import_cycles: Vec<FxIndexSet<String>>,
+    /// Whether an init function binds a cycle peer to a lazy module, so the bundle
+    /// needs the lazy module class
+    uses_lazy_modules: Cell<bool>,
 }
 
 impl Default for HybridStaticBundler {
@@ -332,9 +674,55 @@ impl HybridStaticBundler {
             inlined_modules: FxIndexSet::default(),
             entry_path: None,
             module_exports: FxIndexMap::default(),
+            module_deleted_names: FxIndexMap::default(),
+            find_spec_probed_modules: FxIndexSet::default(),
             lifted_global_declarations: Vec::new(),
             namespace_imported_modules: FxIndexMap::default(),
             created_namespace_modules: FxIndexSet::default(),
+            stdlib_safety_cache: RefCell::new(FxIndexMap::default()),
+            python_version: 10,
+            sections: Vec::new(),
+            class_scopes: FxIndexMap::default(),
+            accessed_wrapper_attributes: FxIndexMap::default(),
+            runtime_names: RuntimeNames::default(),
+            relative_import_fallbacks: IndexMap::new(),
+            wrapper_reasons: FxIndexMap::default(),
+            import_cycles: Vec::new(),
+            uses_lazy_modules: Cell::new(false),
+        }
+    }
+
+    /// Where each module's code lies in the body returned by `bundle_modules`
+    ///
+    /// Modules that produced no statements have an empty range at the index of the next
+    /// section. Init calls, registries and other glue between sections belong to none.
+    pub fn sections(&self) -> &[BundleSection] {
+        &self.sections
+    }
+
+    /// Why each wrapper module of the last `bundle_modules` call was not inlined, in
+    /// module order
+    pub fn wrapper_reasons(&self) -> impl Iterator<Item = (&str, &[WrapperReason])> {
+        self.wrapper_reasons
+            .iter()
+            .map(|(module_name, reasons)| (module_name.as_str(), reasons.as_slice()))
+    }
+
+    fn begin_section(&mut self, start: usize, module_name: &str, strategy: ModuleStrategy) {
+        let synthetic_name = (strategy == ModuleStrategy::Wrapper)
+            .then(|| self.module_registry.get(module_name).cloned())
+            .flatten();
+        self.sections.push(BundleSection {
+            module_name: module_name.to_string(),
+            strategy,
+            synthetic_name,
+            statements: start..start,
+        });
+    }
+
+    fn end_section(&mut self, end: usize) {
+        if let Some(section) = self.sections.last_mut() {
+            section.statements.end = end;
         }
     }
 
@@ -353,85 +741,508 @@ impl HybridStaticBundler {
     /// Check if a module AST has side effects (executable code at top level)
     /// Returns true if the module has side effects beyond simple definitions
     pub fn has_side_effects(ast: &ModModule) -> bool {
-        // First, collect all imported names
+        Self::has_side_effects_with_managers(ast, &[])
+    }
+
+    /// Like `has_side_effects`, but top-level `with` blocks whose context managers are all
+    /// in `inline_with_managers` count as definitions (see `Config::inline_with_managers`)
+    pub fn has_side_effects_with_managers(
+        ast: &ModModule,
+        inline_with_managers: &[String],
+    ) -> bool {
+        let imported_names = Self::collect_imported_names(ast);
+        ast.body.iter().any(|stmt| {
+            Self::statement_has_side_effects(stmt, inline_with_managers, &imported_names)
+        })
+    }
+
+    /// Whether a top-level statement keeps its module from being inlined, given the
+    /// names bound by the module's top-level imports
+    fn statement_has_side_effects(
+        stmt: &Stmt,
+        inline_with_managers: &[String],
+        imported_names: &FxIndexSet<String>,
+    ) -> bool {
+        match stmt {
+            // These statements are pure definitions, no side effects
+            Stmt::FunctionDef(_) | Stmt::ClassDef(_) | Stmt::AnnAssign(_) => return false,
+
+            // Simple variable assignments are generally safe
+            Stmt::Assign(assign) => {
+                // Special case: __all__ assignments are metadata, not side effects
+                if Self::is_all_assignment(assign) {
+                    return false;
+                }
+                // Storing into attributes or subscripts runs the target object's code
+                if !assign.targets.iter().all(Self::is_name_binding_target) {
+                    return true;
+                }
+                // Check if the assignment has function calls or other complex expressions
+                if Self::expression_has_side_effects(&assign.value) {
+                    return true;
+                }
+                // Check if the assignment uses imported names
+                if Self::expression_uses_imported_names(&assign.value, imported_names) {
+                    return true;
+                }
+            }
+
+            // Augmented assignments like `items += [...]` update one of the module's
+            // own names, which is safe as long as the operand is
+            Stmt::AugAssign(aug_assign) => {
+                let Expr::Name(target) = aug_assign.target.as_ref() else {
+                    return true;
+                };
+                // Updating an imported object in place changes another module
+                if imported_names.contains(target.id.as_str()) {
+                    return true;
+                }
+                if Self::expression_has_side_effects(&aug_assign.value)
+                    || Self::expression_uses_imported_names(&aug_assign.value, imported_names)
+                {
+                    return true;
+                }
+            }
+
+            Stmt::With(with_stmt) => {
+                if !Self::is_inlinable_with(with_stmt, inline_with_managers, imported_names) {
+                    return true;
+                }
+            }
+
+            // `try: import ujson as json` / `except ImportError: import json` only binds
+            // whichever module is available
+            Stmt::Try(try_stmt) => {
+                if !Self::is_import_fallback(try_stmt, imported_names) {
+                    return true;
+                }
+            }
+
+            // Import statements are handled separately by the bundler
+            Stmt::Import(_) | Stmt::ImportFrom(_) => return false,
+
+            // Type alias statements are safe
+            Stmt::TypeAlias(_) => return false,
+
+            // Pass statements are no-ops and safe
+            Stmt::Pass(_) => return false,
+
+            // Docstrings are safe; other expression statements have side effects
+            Stmt::Expr(expr_stmt) => {
+                return !matches!(expr_stmt.value.as_ref(), Expr::StringLiteral(_));
+            }
+
+            // These are definitely side effects
+            Stmt::If(_)
+            | Stmt::While(_)
+            | Stmt::For(_)
+            | Stmt::Match(_)
+            | Stmt::Raise(_)
+            | Stmt::Assert(_)
+            | Stmt::Global(_)
+            | Stmt::Nonlocal(_)
+            | Stmt::Delete(_) => return true,
+
+            // Any other statement type is considered a side effect
+            _ => return true,
+        }
+        false
+    }
+
+    /// Every rule, with the statements triggering it, that keeps a module from being
+    /// inlined; `import_sites` are the importing module and statement of each direct import
+    fn collect_wrapper_reasons(
+        module_name: &str,
+        ast: &ModModule,
+        dynamic_exports: &[&str],
+        import_sites: &[(String, TextRange)],
+        inline_with_managers: &[String],
+    ) -> Vec<WrapperReason> {
+        let reason = |rule, source_module: &str, range: Option<TextRange>| WrapperReason {
+            rule,
+            source_module: source_module.to_owned(),
+            range,
+            detail: None,
+        };
+        let mut reasons: Vec<WrapperReason> = dynamic_exports
+            .iter()
+            .map(|pattern| WrapperReason {
+                detail: Some((*pattern).to_owned()),
+                ..reason(WrapperRule::DynamicNamespace, module_name, None)
+            })
+            .collect();
+
+        let imported_names = Self::collect_imported_names(ast);
+        for stmt in &ast.body {
+            if Self::statement_has_side_effects(stmt, inline_with_managers, &imported_names) {
+                reasons.push(reason(
+                    WrapperRule::SideEffect,
+                    module_name,
+                    Some(stmt.range()),
+                ));
+            }
+        }
+
+        for (importer, range) in import_sites {
+            reasons.push(reason(WrapperRule::DirectImport, importer, Some(*range)));
+        }
+
+        for stmt in &ast.body {
+            let body = match stmt {
+                Stmt::FunctionDef(func_def) => &func_def.body,
+                Stmt::ClassDef(class_def) => &class_def.body,
+                _ => continue,
+            };
+            if Self::function_has_imports(body) {
+                reasons.push(reason(
+                    WrapperRule::FunctionScopedImport,
+                    module_name,
+                    Some(stmt.range()),
+                ));
+            }
+        }
+        reasons
+    }
+
+    /// Local names bound by a module's top-level imports, including those in top-level
+    /// `try` blocks
+    fn collect_imported_names(ast: &ModModule) -> FxIndexSet<String> {
         let mut imported_names = FxIndexSet::default();
         for stmt in &ast.body {
+            let statements: Vec<&Stmt> = match stmt {
+                Stmt::Try(try_stmt) => Self::try_block_statements(try_stmt).collect(),
+                other => vec![other],
+            };
+            for stmt in statements {
+                match stmt {
+                    Stmt::Import(import_stmt) => {
+                        for alias in &import_stmt.names {
+                            let name = alias.asname.as_ref().unwrap_or(&alias.name).as_str();
+                            imported_names.insert(name.to_string());
+                        }
+                    }
+                    Stmt::ImportFrom(import_from) => {
+                        for alias in &import_from.names {
+                            let name = alias.asname.as_ref().unwrap_or(&alias.name).as_str();
+                            imported_names.insert(name.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        imported_names
+    }
+
+    /// Whether a top-level `try` block only picks between alternative imports, as in
+    /// `try: import ujson as json` / `except ImportError: import json`
+    ///
+    /// Every handler must catch the failed import, and the blocks may only import
+    /// absolute modules or bind names to values such as `json = None` or a flag recording
+    /// which import succeeded.
+    fn is_import_fallback(try_stmt: &StmtTry, imported_names: &FxIndexSet<String>) -> bool {
+        if try_stmt.is_star
+            || try_stmt.handlers.is_empty()
+            || !try_stmt.finalbody.is_empty()
+            || !try_stmt.handlers.iter().all(handles_import_error)
+        {
+            return false;
+        }
+        Self::try_block_statements(try_stmt).all(|stmt| match stmt {
+            Stmt::Import(_) | Stmt::Pass(_) => true,
+            Stmt::ImportFrom(import_from) => {
+                import_from.level == 0
+                    && import_from
+                        .names
+                        .iter()
+                        .all(|alias| alias.name.as_str() != "*")
+            }
+            Stmt::Assign(assign) => {
+                assign.targets.iter().all(Self::is_name_binding_target)
+                    && !Self::expression_has_side_effects(&assign.value)
+                    && !Self::expression_uses_imported_names(&assign.value, imported_names)
+            }
+            _ => false,
+        })
+    }
+
+    /// Statements of a `try` block's body, `except` handlers and `else` clause
+    fn try_block_statements(try_stmt: &StmtTry) -> impl Iterator<Item = &Stmt> {
+        let handler_bodies = try_stmt.handlers.iter().flat_map(|handler| {
+            let ExceptHandler::ExceptHandler(handler) = handler;
+            &handler.body
+        });
+        try_stmt
+            .body
+            .iter()
+            .chain(handler_bodies)
+            .chain(&try_stmt.orelse)
+    }
+
+    /// Names an import fallback `try` block binds in its module
+    fn import_fallback_bound_names(try_stmt: &StmtTry) -> FxIndexSet<String> {
+        let mut names = FxIndexSet::default();
+        for stmt in Self::try_block_statements(try_stmt) {
             match stmt {
                 Stmt::Import(import_stmt) => {
                     for alias in &import_stmt.names {
-                        let name = alias.asname.as_ref().unwrap_or(&alias.name).as_str();
-                        imported_names.insert(name.to_string());
+                        // `import a.b` binds `a`
+                        let name = match &alias.asname {
+                            Some(asname) => asname.as_str(),
+                            None => alias.name.as_str().split('.').next().unwrap_or_default(),
+                        };
+                        names.insert(name.to_owned());
                     }
                 }
                 Stmt::ImportFrom(import_from) => {
                     for alias in &import_from.names {
-                        let name = alias.asname.as_ref().unwrap_or(&alias.name).as_str();
-                        imported_names.insert(name.to_string());
+                        names.insert(alias.asname.as_ref().unwrap_or(&alias.name).to_string());
                     }
                 }
+                Stmt::Assign(assign) => names.extend(Self::assign_target_names(assign)),
                 _ => {}
             }
         }
+        names
+    }
 
-        for stmt in &ast.body {
-            match stmt {
-                // These statements are pure definitions, no side effects
-                Stmt::FunctionDef(_) | Stmt::ClassDef(_) | Stmt::AnnAssign(_) => continue,
+    /// Whether a top-level `try` block of the module imports a module of the bundle,
+    /// which the inlined block could no longer import
+    fn has_bundled_fallback_import(&self, ast: &ModModule) -> bool {
+        ast.body.iter().any(|stmt| {
+            let Stmt::Try(try_stmt) = stmt else {
+                return false;
+            };
+            Self::try_block_statements(try_stmt).any(|stmt| match stmt {
+                Stmt::Import(import_stmt) => import_stmt
+                    .names
+                    .iter()
+                    .any(|alias| self.bundled_modules.contains(alias.name.as_str())),
+                Stmt::ImportFrom(import_from) => import_from
+                    .module
+                    .as_ref()
+                    .is_some_and(|module| self.bundled_modules.contains(module.as_str())),
+                _ => false,
+            })
+        })
+    }
 
-                // Simple variable assignments are generally safe
-                Stmt::Assign(assign) => {
-                    // Special case: __all__ assignments are metadata, not side effects
-                    if Self::is_all_assignment(assign) {
-                        continue;
-                    }
-                    // Check if the assignment has function calls or other complex expressions
-                    if Self::expression_has_side_effects(&assign.value) {
-                        return true;
-                    }
-                    // Check if the assignment uses imported names
-                    if Self::expression_uses_imported_names(&assign.value, &imported_names) {
-                        return true;
-                    }
+    /// Whether a top-level `with` block may run at the bundle's top level like other
+    /// inlined code: each context manager is a call to one of `inline_with_managers` with
+    /// side-effect-free arguments, and the body only binds names
+    fn is_inlinable_with(
+        with_stmt: &StmtWith,
+        inline_with_managers: &[String],
+        imported_names: &FxIndexSet<String>,
+    ) -> bool {
+        if with_stmt.is_async {
+            return false;
+        }
+        let managers_allowed = with_stmt.items.iter().all(|item| {
+            let Expr::Call(call) = &item.context_expr else {
+                return false;
+            };
+            let is_listed = Self::dotted_name(&call.func)
+                .is_some_and(|name| inline_with_managers.contains(&name));
+            let arguments = call
+                .arguments
+                .args
+                .iter()
+                .chain(call.arguments.keywords.iter().map(|keyword| &keyword.value));
+            is_listed
+                && arguments.all(|argument| {
+                    !Self::expression_has_side_effects(argument)
+                        && !Self::expression_uses_imported_names(argument, imported_names)
+                })
+                && item
+                    .optional_vars
+                    .as_deref()
+                    .is_none_or(Self::is_name_binding_target)
+        });
+        managers_allowed
+            && with_stmt.body.iter().all(|stmt| match stmt {
+                Stmt::Assign(assign) => assign.targets.iter().all(Self::is_name_binding_target),
+                Stmt::AnnAssign(ann_assign) => ann_assign.target.is_name_expr(),
+                Stmt::AugAssign(aug_assign) => aug_assign.target.is_name_expr(),
+                Stmt::Pass(_) => true,
+                _ => false,
+            })
+    }
+
+    /// Names a `with` block binds with `as` and with assignments directly in its body
+    fn with_bound_names(with_stmt: &StmtWith) -> Vec<String> {
+        let mut names = Vec::new();
+        for item in &with_stmt.items {
+            if let Some(optional_vars) = &item.optional_vars {
+                Self::collect_target_names(optional_vars, &mut names);
+            }
+        }
+        for stmt in &with_stmt.body {
+            match stmt {
+                Stmt::Assign(assign) => names.extend(Self::assign_target_names(assign)),
+                Stmt::AnnAssign(ann_assign) => {
+                    Self::collect_target_names(&ann_assign.target, &mut names);
                 }
+                _ => {}
+            }
+        }
+        names
+    }
 
-                // Import statements are handled separately by the bundler
-                Stmt::Import(_) | Stmt::ImportFrom(_) => continue,
+    /// Dotted name of a name or attribute chain such as `contextlib.suppress`
+    fn dotted_name(expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Name(name) => Some(name.id.to_string()),
+            Expr::Attribute(attribute) => Some(format!(
+                "{}.{}",
+                Self::dotted_name(&attribute.value)?,
+                attribute.attr
+            )),
+            _ => None,
+        }
+    }
 
-                // Type alias statements are safe
-                Stmt::TypeAlias(_) => continue,
+    /// Log how each top-level `with` block of a module affects its bundling strategy
+    fn report_with_statements(module_name: &str, ast: &ModModule, inline_with_managers: &[String]) {
+        let with_stmts: Vec<&StmtWith> = ast
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::With(with_stmt) => Some(with_stmt),
+                _ => None,
+            })
+            .collect();
+        if with_stmts.is_empty() {
+            return;
+        }
 
-                // Pass statements are no-ops and safe
-                Stmt::Pass(_) => continue,
+        let imported_names = Self::collect_imported_names(ast);
 
-                // Expression statements - check if they're docstrings
-                Stmt::Expr(expr_stmt) => {
-                    if matches!(expr_stmt.value.as_ref(), Expr::StringLiteral(_)) {
-                        // Docstring - safe
-                        continue;
-                    } else {
-                        // Other expression statements have side effects
-                        return true;
-                    }
+        for with_stmt in with_stmts {
+            let managers = with_stmt
+                .items
+                .iter()
+                .map(|item| {
+                    let func = match &item.context_expr {
+                        Expr::Call(call) => call.func.as_ref(),
+                        other => other,
+                    };
+                    Self::dotted_name(func).unwrap_or_else(|| "<expression>".to_owned())
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            if Self::is_inlinable_with(with_stmt, inline_with_managers, &imported_names) {
+                log::info!(
+                    "Module '{}': top-level `with` using {} is allowed by inline_with_managers \
+                     and does not keep the module from being inlined",
+                    module_name,
+                    managers
+                );
+            } else {
+                log::info!(
+                    "Module '{}': top-level `with` using {} runs in the module's init function; \
+                     list its context managers in inline_with_managers to allow inlining",
+                    module_name,
+                    managers
+                );
+            }
+        }
+    }
+
+    /// Warn about `from X import *` statements whose imported names cannot be determined
+    /// statically, saying what the bundle does instead
+    fn report_unresolved_star_imports(&self, modules: &[(String, ModModule, PathBuf, String)]) {
+        for (module_name, ast, module_path, _) in modules {
+            for stmt in &ast.body {
+                let Stmt::ImportFrom(import_from) = stmt else {
+                    continue;
+                };
+                if !import_from
+                    .names
+                    .iter()
+                    .any(|alias| alias.name.as_str() == "*")
+                {
+                    continue;
                 }
+                let Some(target) = self.resolve_relative_import_with_context(
+                    import_from,
+                    module_name,
+                    Some(module_path),
+                ) else {
+                    continue;
+                };
 
-                // These are definitely side effects
-                Stmt::If(_)
-                | Stmt::While(_)
-                | Stmt::For(_)
-                | Stmt::With(_)
-                | Stmt::Match(_)
-                | Stmt::Raise(_)
-                | Stmt::Try(_)
-                | Stmt::Assert(_)
-                | Stmt::Global(_)
-                | Stmt::Nonlocal(_)
-                | Stmt::Delete(_) => return true,
+                let Some((_, target_ast, _, _)) = modules.iter().find(|(name, ..)| *name == target)
+                else {
+                    log::warn!(
+                        "Module '{}': `from {} import *` targets a module outside the bundle, whose \
+                         exports cannot be determined statically; the import is kept and runs at \
+                         runtime, and the names it binds are not considered when resolving name \
+                         conflicts between modules",
+                        module_name,
+                        target
+                    );
+                    continue;
+                };
 
-                // Any other statement type is considered a side effect
-                _ => return true,
+                let mut reasons = Vec::new();
+                if Self::has_dynamic_all(target_ast) {
+                    reasons.push("its `__all__` is computed at runtime".to_owned());
+                }
+                let dynamic_exports = DynamicExportVisitor::find_patterns(target_ast);
+                if !dynamic_exports.is_empty() {
+                    reasons.push(format!(
+                        "it builds its namespace dynamically ({})",
+                        dynamic_exports.join(", ")
+                    ));
+                }
+                if reasons.is_empty() {
+                    continue;
+                }
+                log::warn!(
+                    "Module '{}': the names exported by `from {} import *` cannot be determined \
+                     statically because {}; only the names visible statically (the literal entries \
+                     of `__all__`, or public module-level definitions) are imported, and names \
+                     created at runtime are missing",
+                    module_name,
+                    target,
+                    reasons.join(" and ")
+                );
             }
         }
-        false
+    }
+
+    /// Whether a module computes `__all__` at runtime instead of assigning a literal list
+    /// or tuple of strings
+    fn has_dynamic_all(ast: &ModModule) -> bool {
+        let is_all =
+            |expr: &Expr| matches!(expr, Expr::Name(name) if name.id.as_str() == "__all__");
+        let is_string_sequence = |expr: &Expr| match expr {
+            Expr::List(list) => list
+                .elts
+                .iter()
+                .all(|element| matches!(element, Expr::StringLiteral(_))),
+            Expr::Tuple(tuple) => tuple
+                .elts
+                .iter()
+                .all(|element| matches!(element, Expr::StringLiteral(_))),
+            _ => false,
+        };
+        ast.body.iter().any(|stmt| match stmt {
+            Stmt::Assign(assign) => {
+                assign.targets.iter().any(is_all) && !is_string_sequence(&assign.value)
+            }
+            Stmt::AugAssign(aug_assign) => is_all(&aug_assign.target),
+            // `__all__.extend(...)`, `__all__.append(...)`
+            Stmt::Expr(expr_stmt) => matches!(
+                expr_stmt.value.as_ref(),
+                Expr::Call(call) if matches!(
+                    call.func.as_ref(),
+                    Expr::Attribute(attribute) if is_all(&attribute.value)
+                )
+            ),
+            _ => false,
+        })
     }
 
     /// Check if an expression uses any imported names
@@ -594,41 +1405,55 @@ impl HybridStaticBundler {
             .collect::<String>();
         // Use first 6 characters of content hash for readability
         let short_hash = &content_hash[..6];
-        format!("__cribo_{}_{}", short_hash, module_name_escaped)
+        self.runtime_names
+            .prefixed(&format!("{}_{}", short_hash, module_name_escaped))
     }
 
-    /// Trim unused imports from all modules before bundling using graph information
-    fn trim_unused_imports_from_modules(
+    /// Run the per-module preparation pipeline in a single traversal of the module list
+    ///
+    /// For each module this collects `__future__` imports (before trimming, so late future
+    /// imports are still hoisted), trims unused imports using graph information, and records
+    /// whether the trimmed module has function-scoped imports.
+    fn prepare_modules(
         &mut self,
         modules: Vec<(String, ModModule, PathBuf, String)>,
         graph: &DependencyGraph,
-    ) -> Result<Vec<(String, ModModule, PathBuf, String)>> {
+    ) -> Result<PreparedModules> {
         let mut trimmed_modules = Vec::new();
+        let mut modules_with_function_imports = FxIndexSet::default();
+
+        // Unused import analysis is read-only over the graph, so run it for all modules
+        // up front and concurrently; results come back in module order
+        let all_unused_imports = find_unused_imports_parallel(&modules, graph);
+
+        for ((module_name, mut ast, module_path, content_hash), unused_imports) in
+            modules.into_iter().zip(all_unused_imports)
+        {
+            self.collect_future_imports_from_ast(&ast);
 
-        for (module_name, mut ast, module_path, content_hash) in modules {
             log::debug!("Trimming unused imports from module: {}", module_name);
 
-            // Check if this is an __init__.py file
-            let is_init_py =
-                module_path.file_name().and_then(|name| name.to_str()) == Some("__init__.py");
+            if !unused_imports.is_empty() {
+                log::debug!(
+                    "Found {} unused imports in {}",
+                    unused_imports.len(),
+                    module_name
+                );
+                // Log unused imports details
+                Self::log_unused_imports_details(&unused_imports);
 
-            // Get unused imports from the graph
-            if let Some(module_dep_graph) = graph.get_module_by_name(&module_name) {
-                let unused_imports = module_dep_graph.find_unused_imports(is_init_py);
+                // Filter out unused imports from the AST
+                ast.body
+                    .retain(|stmt| !self.should_remove_import_stmt(stmt, &unused_imports));
 
-                if !unused_imports.is_empty() {
-                    log::debug!(
-                        "Found {} unused imports in {}",
-                        unused_imports.len(),
-                        module_name
-                    );
-                    // Log unused imports details
-                    Self::log_unused_imports_details(&unused_imports);
+                // `del name` does not count as a use, so an import may have been removed
+                // while a `del` of it remains
+                Self::remove_deleted_unbound_names(&mut ast, &unused_imports);
+            }
 
-                    // Filter out unused imports from the AST
-                    ast.body
-                        .retain(|stmt| !self.should_remove_import_stmt(stmt, &unused_imports));
-                }
+            if self.module_has_function_scoped_imports(&ast) {
+                log::info!("Module '{}' has function-scoped imports", module_name);
+                modules_with_function_imports.insert(module_name.clone());
             }
 
             trimmed_modules.push((module_name, ast, module_path, content_hash));
@@ -638,15 +1463,14 @@ impl HybridStaticBundler {
             "Successfully trimmed unused imports from {} modules",
             trimmed_modules.len()
         );
-        Ok(trimmed_modules)
+        Ok(PreparedModules {
+            modules: trimmed_modules,
+            modules_with_function_imports,
+        })
     }
 
     /// Check if an import statement should be removed based on unused imports
-    fn should_remove_import_stmt(
-        &self,
-        stmt: &Stmt,
-        unused_imports: &[crate::cribo_graph::UnusedImportInfo],
-    ) -> bool {
+    fn should_remove_import_stmt(&self, stmt: &Stmt, unused_imports: &[UnusedImportInfo]) -> bool {
         match stmt {
             Stmt::Import(import_stmt) => {
                 // Check if all names in this import are unused
@@ -703,6 +1527,152 @@ impl HybridStaticBundler {
         }
     }
 
+    /// Drop targets of module-level `del` statements that name a removed import
+    ///
+    /// A statement left without targets is removed altogether.
+    fn remove_deleted_unbound_names(ast: &mut ModModule, unused_imports: &[UnusedImportInfo]) {
+        let bound_by_imports: FxIndexSet<&str> = ast
+            .body
+            .iter()
+            .flat_map(|stmt| match stmt {
+                Stmt::Import(import_stmt) => import_stmt.names.iter().collect::<Vec<_>>(),
+                Stmt::ImportFrom(import_from) => import_from.names.iter().collect(),
+                _ => Vec::new(),
+            })
+            .map(|alias| alias.asname.as_ref().unwrap_or(&alias.name).as_str())
+            .collect();
+        let is_removed = |name: &str| {
+            !bound_by_imports.contains(name)
+                && unused_imports.iter().any(|unused| unused.name == name)
+        };
+
+        ast.body.retain_mut(|stmt| {
+            let Stmt::Delete(delete_stmt) = stmt else {
+                return true;
+            };
+            delete_stmt.targets.retain(|target| match target {
+                Expr::Name(name) => !is_removed(name.id.as_str()),
+                _ => true,
+            });
+            !delete_stmt.targets.is_empty()
+        });
+    }
+
+    /// Remove the `assert` statements of `body` and of the blocks nested in it, as
+    /// `python -O` skips them; blocks that must not be empty keep a `pass`
+    fn strip_assert_statements(body: &mut Vec<Stmt>, needs_statement: bool) {
+        let had_statements = !body.is_empty();
+        body.retain(|stmt| !matches!(stmt, Stmt::Assert(_)));
+        for stmt in body.iter_mut() {
+            for nested in nested_bodies(stmt) {
+                Self::strip_assert_statements(nested, true);
+            }
+        }
+        if had_statements && needs_statement && body.is_empty() {
+            body.push(Stmt::Pass(ruff_python_ast::StmtPass {
+                range: TextRange::default(),
+            }));
+        }
+    }
+
+    /// Remove the docstring of the module, class or function with statements `body`, and
+    /// those of the classes and functions defined in it; bodies that must not be empty
+    /// keep a `pass`
+    fn strip_docstrings(body: &mut Vec<Stmt>, needs_statement: bool) {
+        let has_docstring = matches!(
+            body.first(),
+            Some(Stmt::Expr(expr_stmt)) if matches!(expr_stmt.value.as_ref(), Expr::StringLiteral(_))
+        );
+        if has_docstring {
+            body.remove(0);
+            if needs_statement && body.is_empty() {
+                body.push(Stmt::Pass(ruff_python_ast::StmtPass {
+                    range: TextRange::default(),
+                }));
+            }
+        }
+        Self::strip_nested_docstrings(body);
+    }
+
+    /// Remove the docstrings of the classes and functions defined anywhere in `body`
+    fn strip_nested_docstrings(body: &mut [Stmt]) {
+        for stmt in body {
+            match stmt {
+                Stmt::FunctionDef(func_def) => Self::strip_docstrings(&mut func_def.body, true),
+                Stmt::ClassDef(class_def) => Self::strip_docstrings(&mut class_def.body, true),
+                other => {
+                    for nested in nested_bodies(other) {
+                        Self::strip_nested_docstrings(nested);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove the annotations of the functions defined in `body` and turn its annotated
+    /// assignments into plain ones, dropping those without a value; annotations in class
+    /// bodies stay, as `dataclasses`, `NamedTuple` and the like define fields with them
+    fn strip_annotations(body: &mut Vec<Stmt>, in_class: bool, needs_statement: bool) {
+        let had_statements = !body.is_empty();
+        if !in_class && body.iter().any(|stmt| matches!(stmt, Stmt::AnnAssign(_))) {
+            *body = std::mem::take(body)
+                .into_iter()
+                .filter_map(|stmt| match stmt {
+                    Stmt::AnnAssign(ruff_python_ast::StmtAnnAssign {
+                        target,
+                        value,
+                        range,
+                        ..
+                    }) => value.map(|value| {
+                        Stmt::Assign(StmtAssign {
+                            targets: vec![*target],
+                            value,
+                            range,
+                        })
+                    }),
+                    other => Some(other),
+                })
+                .collect();
+        }
+        for stmt in body.iter_mut() {
+            match stmt {
+                Stmt::FunctionDef(func_def) => {
+                    Self::strip_parameter_annotations(&mut func_def.parameters);
+                    func_def.returns = None;
+                    Self::strip_annotations(&mut func_def.body, false, true);
+                }
+                Stmt::ClassDef(class_def) => {
+                    Self::strip_annotations(&mut class_def.body, true, true);
+                }
+                other => {
+                    for nested in nested_bodies(other) {
+                        Self::strip_annotations(nested, in_class, true);
+                    }
+                }
+            }
+        }
+        if had_statements && needs_statement && body.is_empty() {
+            body.push(Stmt::Pass(ruff_python_ast::StmtPass {
+                range: TextRange::default(),
+            }));
+        }
+    }
+
+    /// Remove the annotations of a function's parameters
+    fn strip_parameter_annotations(parameters: &mut ruff_python_ast::Parameters) {
+        for parameter in parameters
+            .posonlyargs
+            .iter_mut()
+            .chain(&mut parameters.args)
+            .chain(&mut parameters.kwonlyargs)
+        {
+            parameter.parameter.annotation = None;
+        }
+        for parameter in parameters.vararg.iter_mut().chain(&mut parameters.kwarg) {
+            parameter.annotation = None;
+        }
+    }
+
     /// Collect future imports from an AST
     fn collect_future_imports_from_ast(&mut self, ast: &ModModule) {
         for stmt in &ast.body {
@@ -725,6 +1695,10 @@ impl HybridStaticBundler {
     /// Bundle multiple modules using the hybrid approach
     pub fn bundle_modules(&mut self, params: BundleParams<'_>) -> Result<ModModule> {
         let mut final_body = Vec::new();
+        if self.python_version != params.python_version {
+            self.python_version = params.python_version;
+            self.stdlib_safety_cache.borrow_mut().clear();
+        }
 
         log::debug!("Entry module name: {}", params.entry_module_name);
         log::debug!(
@@ -736,17 +1710,38 @@ impl HybridStaticBundler {
                 .collect::<Vec<_>>()
         );
 
-        // First pass: collect future imports from ALL modules before trimming
-        // This ensures future imports are hoisted even if they appear late in the file
-        for (_module_name, ast, _, _) in &params.modules {
-            self.collect_future_imports_from_ast(ast);
+        let mut source_modules = params.modules;
+        for (module_name, ast, _, _) in &mut source_modules {
+            if params.strip_asserts {
+                Self::strip_assert_statements(&mut ast.body, false);
+            }
+            let keeps_docstrings =
+                params.keep_entry_docstrings && module_name == params.entry_module_name;
+            if params.strip_docstrings && !keeps_docstrings {
+                Self::strip_docstrings(&mut ast.body, false);
+            }
+            if params.strip_annotations {
+                Self::strip_annotations(&mut ast.body, false, false);
+            }
         }
 
-        // Second pass: trim unused imports from all modules
-        let modules = self.trim_unused_imports_from_modules(params.modules, params.graph)?;
+        // Preparation pass: collect future imports, trim unused imports and detect
+        // function-scoped imports (from import rewriting) in one walk over the modules
+        let PreparedModules {
+            mut modules,
+            modules_with_function_imports,
+        } = self.prepare_modules(source_modules, params.graph)?;
 
-        // Check which modules have function-scoped imports (from import rewriting)
-        let modules_with_function_imports = self.find_modules_with_function_imports(&modules);
+        // Outside the entry module, `__name__` no longer holds the module's own name.
+        // Dispatched scripts only run when their command is invoked, so they run as
+        // `__main__` just like the standalone script would.
+        for (module_name, ast, _, _) in &mut modules {
+            if params.dispatched_scripts.contains(module_name) {
+                ModuleNameRewriter::new("__main__").rewrite_module(ast);
+            } else if module_name != params.entry_module_name {
+                ModuleNameRewriter::new(module_name).rewrite_module(ast);
+            }
+        }
 
         // Store entry path for relative path calculation
         if let Some((_, entry_path, _)) = params.sorted_modules.last() {
@@ -758,10 +1753,29 @@ impl HybridStaticBundler {
             self.bundled_modules.insert(module_name.clone());
         }
 
+        // Keep the runtime's own identifiers clear of every name the modules use
+        self.runtime_names = RuntimeNames::new(params.runtime_prefix);
+        let mut user_names = FxIndexSet::default();
+        for (_, ast, _, _) in &modules {
+            user_names.extend(IdentifierVisitor::collect_names(ast));
+        }
+        self.runtime_names.avoid_collisions(&user_names);
+        self.relative_import_fallbacks = params.relative_import_fallbacks.clone();
+        self.import_cycles = params
+            .import_cycles
+            .iter()
+            .map(|cycle| cycle.modules.iter().cloned().collect())
+            .collect();
+
+        self.report_unresolved_star_imports(&modules);
+
         // Check which modules are imported directly (e.g., import module_name)
         let directly_imported_modules =
             self.find_directly_imported_modules(&modules, params.entry_module_name);
-        log::debug!("Directly imported modules: {:?}", directly_imported_modules);
+        log::debug!(
+            "Directly imported modules: {:?}",
+            directly_imported_modules.keys()
+        );
 
         // Find modules that are imported as namespaces (e.g., from models import base)
         self.find_namespace_imported_modules(&modules);
@@ -782,6 +1796,12 @@ impl HybridStaticBundler {
             let module_exports = self.extract_all_exports(ast);
             module_exports_map.insert(module_name.clone(), module_exports.clone());
 
+            let deleted_names = Self::collect_deleted_module_names(ast);
+            if !deleted_names.is_empty() {
+                self.module_deleted_names
+                    .insert(module_name.clone(), deleted_names);
+            }
+
             // Check if module is imported as a namespace
             let is_namespace_imported = self.namespace_imported_modules.contains_key(module_name);
 
@@ -793,17 +1813,49 @@ impl HybridStaticBundler {
                 );
             }
 
+            // Names created at runtime are invisible to static analysis, so inlining such a
+            // module (or exposing it as a namespace of its static symbols) would lose them
+            let dynamic_exports = DynamicExportVisitor::find_patterns(ast);
+            let has_dynamic_exports = !dynamic_exports.is_empty();
+            if has_dynamic_exports {
+                log::warn!(
+                    "Module '{}' builds its namespace dynamically ({}); bundling it as a wrapper \
+                     module so names created at runtime remain available",
+                    module_name,
+                    dynamic_exports.join(", ")
+                );
+            }
+
             // Check if module can be inlined
             // A module can only be inlined if:
             // 1. It has no side effects
             // 2. It's never imported directly (only from X import Y style)
             // 3. It's not imported as a namespace
             // 4. It doesn't have function-scoped imports (from import rewriting)
-            let has_side_effects = Self::has_side_effects(ast);
-            let is_directly_imported = directly_imported_modules.contains(module_name);
+            // 5. It doesn't build its namespace dynamically
+            Self::report_with_statements(module_name, ast, params.inline_with_managers);
+            // Configured policies take precedence over the side-effect heuristic
+            let is_forced_inline = params.force_inline.contains(module_name);
+            let is_forced_wrapper = params.force_wrapper.contains(module_name);
+            let has_side_effects = !is_forced_inline
+                && (Self::has_side_effects_with_managers(ast, params.inline_with_managers)
+                    || self.has_bundled_fallback_import(ast));
+            let is_directly_imported = directly_imported_modules.contains_key(module_name);
             let has_function_imports = modules_with_function_imports.contains(module_name);
+            let is_dispatched = params.dispatched_scripts.contains(module_name);
+            // Members of a cycle that use each other while they initialize can't be
+            // inlined in any order, but their init functions can call each other
+            let import_time_cycle = params.import_cycles.iter().find(|cycle| {
+                cycle.cycle_type == CircularDependencyType::ImportTime
+                    && cycle.modules.contains(module_name)
+            });
 
-            if is_namespace_imported {
+            if is_namespace_imported
+                && !has_dynamic_exports
+                && !is_dispatched
+                && !is_forced_wrapper
+                && import_time_cycle.is_none()
+            {
                 // Module is imported as namespace - use hybrid approach
                 log::debug!(
                     "Module '{}' is imported as namespace - using hybrid inlining approach",
@@ -815,8 +1867,23 @@ impl HybridStaticBundler {
                     module_path.clone(),
                     content_hash.clone(),
                 ));
-            } else if has_side_effects || is_directly_imported || has_function_imports {
-                let reason = if has_side_effects {
+            } else if has_side_effects
+                || is_directly_imported
+                || has_function_imports
+                || has_dynamic_exports
+                || is_dispatched
+                || is_forced_wrapper
+                || import_time_cycle.is_some()
+            {
+                let reason = if is_dispatched {
+                    "is a dispatched script"
+                } else if is_forced_wrapper {
+                    "is listed in force_wrapper"
+                } else if import_time_cycle.is_some() {
+                    "initializes inside an import cycle"
+                } else if has_dynamic_exports {
+                    "builds its namespace dynamically"
+                } else if has_side_effects {
                     "has side effects"
                 } else if is_directly_imported {
                     "is imported directly"
@@ -828,6 +1895,55 @@ impl HybridStaticBundler {
                     module_name,
                     reason
                 );
+                if is_forced_inline {
+                    log::warn!(
+                        "Module '{}' is listed in force_inline but {}; bundling it as a \
+                         wrapper module",
+                        module_name,
+                        reason
+                    );
+                }
+                let mut reasons = Self::collect_wrapper_reasons(
+                    module_name,
+                    ast,
+                    &dynamic_exports,
+                    directly_imported_modules
+                        .get(module_name)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default(),
+                    params.inline_with_managers,
+                );
+                if is_dispatched {
+                    reasons.insert(
+                        0,
+                        WrapperReason {
+                            rule: WrapperRule::DispatchedScript,
+                            source_module: params.entry_module_name.to_owned(),
+                            range: None,
+                            detail: None,
+                        },
+                    );
+                }
+                if is_forced_wrapper {
+                    reasons.insert(
+                        0,
+                        WrapperReason {
+                            rule: WrapperRule::Configured,
+                            source_module: module_name.clone(),
+                            range: None,
+                            detail: None,
+                        },
+                    );
+                }
+                if let Some(cycle) = import_time_cycle {
+                    reasons.push(WrapperReason {
+                        rule: WrapperRule::ImportCycle,
+                        source_module: module_name.clone(),
+                        range: None,
+                        detail: Some(cycle.modules.join(" → ")),
+                    });
+                }
+                self.wrapper_reasons.insert(module_name.clone(), reasons);
                 wrapper_modules.push((
                     module_name.clone(),
                     ast.clone(),
@@ -860,20 +1976,20 @@ impl HybridStaticBundler {
             self.inlined_modules.insert(module_name.clone());
         }
 
-        // First pass: normalize stdlib import aliases in ALL modules before collecting imports
+        // Import pass: normalize stdlib import aliases and collect imports for hoisting.
+        // Normalization only touches the module itself, so both steps run back to back
+        // on each module instead of as two separate sweeps over the whole bundle.
         let mut modules_normalized = modules;
         for (_module_name, ast, _, _) in &mut modules_normalized {
             self.normalize_stdlib_import_aliases(ast);
-        }
-
-        // Second pass: collect imports from ALL modules (for hoisting)
-        for (_module_name, ast, _, _) in &modules_normalized {
             self.collect_imports_from_module(ast);
         }
 
-        // If we have wrapper modules, inject sys and types as stdlib dependencies
+        // If we have wrapper modules, inject sys and types as stdlib dependencies, and
+        // threading for the lock guarding their init functions
         if !wrapper_modules.is_empty() {
             self.add_stdlib_import("sys");
+            self.add_stdlib_import("threading");
             self.add_stdlib_import("types");
         }
 
@@ -882,6 +1998,37 @@ impl HybridStaticBundler {
             self.add_stdlib_import("types");
         }
 
+        // Registering inlined modules creates module objects in sys.modules
+        if params.register_inlined_modules && !self.inlined_modules.is_empty() {
+            self.add_stdlib_import("sys");
+            self.add_stdlib_import("types");
+        }
+
+        // Bundled modules are not on disk anymore, so `find_spec` probes for them need a
+        // finder that knows about them
+        for (_, ast, _, _) in &modules_normalized {
+            for probed in FindSpecProbeVisitor::find_probed_names(ast) {
+                if modules_normalized
+                    .iter()
+                    .any(|(name, _, _, _)| name == &probed)
+                {
+                    self.find_spec_probed_modules.insert(probed);
+                }
+            }
+        }
+        if !self.find_spec_probed_modules.is_empty() {
+            log::debug!(
+                "Registering find_spec-probed modules: {:?}",
+                self.find_spec_probed_modules
+            );
+            self.add_stdlib_import("importlib.machinery");
+            self.add_stdlib_import("sys");
+        }
+
+        if params.prune_wrapper_exports && !wrapper_modules.is_empty() {
+            self.collect_accessed_wrapper_attributes(&modules_normalized, &wrapper_modules);
+        }
+
         // Register wrapper modules
         for (module_name, _ast, _module_path, content_hash) in &wrapper_modules {
             self.module_exports.insert(
@@ -895,7 +2042,9 @@ impl HybridStaticBundler {
                 .insert(module_name.clone(), synthetic_name.clone());
 
             // Register init function
-            let init_func_name = format!("__cribo_init_{}", synthetic_name);
+            let init_func_name = self
+                .runtime_names
+                .prefixed(&format!("init_{}", synthetic_name));
             self.init_functions.insert(synthetic_name, init_func_name);
         }
 
@@ -911,6 +2060,7 @@ impl HybridStaticBundler {
                 for stmt in &ast.body {
                     self.collect_module_symbol(stmt, &mut symbols);
                 }
+                symbols.retain(|symbol| !self.is_deleted_module_name(symbol, module_name));
                 if symbols.is_empty() {
                     None
                 } else {
@@ -926,6 +2076,24 @@ impl HybridStaticBundler {
         // Add imports first
         self.add_hoisted_imports(&mut final_body);
 
+        // The dependency check must run before any hoisted third-party import can fail,
+        // and only the module docstring and `from __future__` imports may precede it
+        if !params.preflight_requirements.is_empty() {
+            let position = future_preamble_len(&final_body);
+            final_body.splice(
+                position..position,
+                create_preflight_check(
+                    params.preflight_requirements,
+                    params.distribution_names,
+                    params.runtime_prefix,
+                )?,
+            );
+        }
+
+        if !self.find_spec_probed_modules.is_empty() {
+            final_body.extend(self.create_bundled_module_finder()?);
+        }
+
         // Check if we need sys import (for wrapper modules)
         let need_sys_import = !wrapper_modules.is_empty();
 
@@ -958,6 +2126,7 @@ impl HybridStaticBundler {
         // Process namespace hybrid modules - inline their symbols with module-qualified names
         for (module_name, ast, _module_path, _content_hash) in &namespace_hybrid_modules {
             log::debug!("Processing namespace hybrid module '{}'", module_name);
+            self.begin_section(final_body.len(), module_name, ModuleStrategy::Namespace);
             let mut inlined_stmts = Vec::new();
             let mut inline_ctx = InlineContext {
                 module_exports_map: &module_exports_map,
@@ -978,12 +2147,14 @@ impl HybridStaticBundler {
                 module_name
             );
             final_body.extend(inlined_stmts);
+            self.end_section(final_body.len());
         }
 
         // Inline the inlinable modules FIRST to populate symbol_renames
         // This ensures we know what symbols have been renamed before processing wrapper modules
         for (module_name, ast, _module_path, _content_hash) in &inlinable_modules {
             log::debug!("Inlining module '{}'", module_name);
+            self.begin_section(final_body.len(), module_name, ModuleStrategy::Inlined);
             let mut inlined_stmts = Vec::new();
             let mut inline_ctx = InlineContext {
                 module_exports_map: &module_exports_map,
@@ -999,6 +2170,7 @@ impl HybridStaticBundler {
                 module_name
             );
             final_body.extend(inlined_stmts);
+            self.end_section(final_body.len());
         }
 
         // Now transform wrapper modules into init functions AFTER inlining
@@ -1008,10 +2180,11 @@ impl HybridStaticBundler {
             let mut module_globals = FxIndexMap::default();
             let mut all_lifted_declarations = Vec::new();
 
-            for (module_name, ast, _, _) in &wrapper_modules_saved {
+            for (module_name, ast, module_path, _) in &wrapper_modules_saved {
                 let params = ProcessGlobalsParams {
                     module_name,
                     ast,
+                    module_path,
                     semantic_ctx: &semantic_ctx,
                 };
                 self.process_wrapper_module_globals(
@@ -1049,17 +2222,22 @@ impl HybridStaticBundler {
                 };
                 let init_function =
                     self.transform_module_to_init_function(ctx, ast.clone(), &symbol_renames)?;
+                self.begin_section(final_body.len(), module_name, ModuleStrategy::Wrapper);
                 final_body.push(init_function);
+                self.end_section(final_body.len());
             }
 
             // Now add the registries after init functions are defined
-            final_body.extend(self.generate_registries_and_hook());
+            final_body.extend(self.generate_registries_and_hook()?);
         }
 
         // Initialize wrapper modules in dependency order AFTER inlined modules are defined
         if need_sys_import {
             for (module_name, _, _) in params.sorted_modules {
-                if module_name == params.entry_module_name {
+                // Dispatched scripts run when the dispatcher imports them
+                if module_name == params.entry_module_name
+                    || params.dispatched_scripts.contains(module_name)
+                {
                     continue;
                 }
 
@@ -1074,13 +2252,19 @@ impl HybridStaticBundler {
             self.generate_submodule_attributes(params.sorted_modules, &mut final_body);
         }
 
+        if params.register_inlined_modules {
+            self.register_inlined_modules(params.sorted_modules, &symbol_renames, &mut final_body)?;
+        }
+
         // Finally, add entry module code (it's always last in topological order)
+        let entry_start = final_body.len();
         for (module_name, ast, _, _) in &modules_normalized {
             if module_name != params.entry_module_name {
                 continue;
             }
 
             // Entry module - add its code directly at the end
+            self.begin_section(final_body.len(), module_name, ModuleStrategy::Entry);
             // The entry module needs special handling for symbol conflicts
             let entry_module_renames = symbol_renames.get(module_name).cloned().unwrap_or_default();
 
@@ -1114,6 +2298,7 @@ impl HybridStaticBundler {
                     _ => {
                         self.process_entry_module_statement(
                             &mut stmt,
+                            module_name,
                             &entry_module_renames,
                             &mut final_body,
                         );
@@ -1122,16 +2307,182 @@ impl HybridStaticBundler {
             }
         }
 
+        if params.dual_mode {
+            let entry_stmts = final_body.split_off(entry_start);
+            final_body.extend(self.make_entry_dual_mode(entry_stmts));
+        }
+        if self
+            .sections
+            .last()
+            .is_some_and(|section| section.strategy == ModuleStrategy::Entry)
+        {
+            self.end_section(final_body.len());
+        }
+
+        if params.tree_shake {
+            self.remove_unused_definitions(&mut final_body);
+        }
+
         Ok(ModModule {
             range: TextRange::default(),
             body: final_body,
         })
     }
 
+    /// Drop the top-level definitions of inlined modules that nothing in the bundle
+    /// refers to, moving the sections to the statements that remain
+    fn remove_unused_definitions(&mut self, body: &mut Vec<Stmt>) {
+        let shakeable: Vec<Range<usize>> = self
+            .sections
+            .iter()
+            .filter(|section| section.strategy == ModuleStrategy::Inlined)
+            .map(|section| section.statements.clone())
+            .collect();
+        let unused = tree_shaking::unused_definitions(body, &shakeable);
+        if unused.is_empty() {
+            return;
+        }
+        log::info!(
+            "Tree shaking removed {} unused definitions from inlined modules",
+            unused.len()
+        );
+
+        // Index of `index` once the unused statements before it are gone
+        let shifted = |index: usize| index - unused.partition_point(|&removed| removed < index);
+        for section in &mut self.sections {
+            section.statements = shifted(section.statements.start)..shifted(section.statements.end);
+        }
+        let mut index = 0;
+        body.retain(|_| {
+            let keep = unused.binary_search(&index).is_err();
+            index += 1;
+            keep
+        });
+    }
+
+    /// Make the entry module's code safe to import as well as to run
+    ///
+    /// Bare top-level expression statements (typically `main()`) only run when the bundle
+    /// is executed as a script, and unless the entry module declares `__all__` itself, its
+    /// public top-level names become the bundle's `__all__` so that star-imports expose
+    /// the entry's API rather than the inlined modules' internals.
+    fn make_entry_dual_mode(&self, entry_stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut result = Vec::with_capacity(entry_stmts.len() + 1);
+        let mut guarded = Vec::new();
+        let mut public_names = FxIndexSet::default();
+        let mut declares_all = false;
+
+        for stmt in entry_stmts {
+            let is_script_only = matches!(
+                &stmt,
+                Stmt::Expr(expr_stmt) if !matches!(expr_stmt.value.as_ref(), Expr::StringLiteral(_))
+            );
+            if is_script_only {
+                guarded.push(stmt);
+                continue;
+            }
+            if !guarded.is_empty() {
+                result.push(self.create_main_guard(std::mem::take(&mut guarded)));
+            }
+
+            for name in Self::top_level_bindings(&stmt) {
+                if name == "__all__" {
+                    declares_all = true;
+                } else if !name.starts_with('_') {
+                    public_names.insert(name);
+                }
+            }
+            result.push(stmt);
+        }
+        if !guarded.is_empty() {
+            result.push(self.create_main_guard(guarded));
+        }
+
+        if !declares_all {
+            result.push(Stmt::Assign(StmtAssign {
+                targets: vec![Expr::Name(ExprName {
+                    id: "__all__".into(),
+                    ctx: ExprContext::Store,
+                    range: TextRange::default(),
+                })],
+                value: Box::new(Expr::List(ExprList {
+                    elts: public_names
+                        .iter()
+                        .map(|name| self.create_string_literal(name))
+                        .collect(),
+                    ctx: ExprContext::Load,
+                    range: TextRange::default(),
+                })),
+                range: TextRange::default(),
+            }));
+        }
+        result
+    }
+
+    /// Names bound at module level by a top-level statement
+    fn top_level_bindings(stmt: &Stmt) -> Vec<String> {
+        match stmt {
+            Stmt::FunctionDef(func_def) => vec![func_def.name.to_string()],
+            Stmt::ClassDef(class_def) => vec![class_def.name.to_string()],
+            Stmt::Assign(assign) => assign
+                .targets
+                .iter()
+                .filter_map(|target| match target {
+                    Expr::Name(name) => Some(name.id.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            Stmt::AnnAssign(ann_assign) => match ann_assign.target.as_ref() {
+                Expr::Name(name) if ann_assign.value.is_some() => vec![name.id.to_string()],
+                _ => vec![],
+            },
+            Stmt::Import(import) => import
+                .names
+                .iter()
+                .map(|alias| match &alias.asname {
+                    Some(asname) => asname.to_string(),
+                    None => alias
+                        .name
+                        .split('.')
+                        .next()
+                        .unwrap_or(&alias.name)
+                        .to_string(),
+                })
+                .collect(),
+            Stmt::ImportFrom(import_from) => import_from
+                .names
+                .iter()
+                .filter(|alias| alias.name.as_str() != "*")
+                .map(|alias| alias.asname.as_ref().unwrap_or(&alias.name).to_string())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Create `if __name__ == "__main__":` around `body`
+    fn create_main_guard(&self, body: Vec<Stmt>) -> Stmt {
+        Stmt::If(StmtIf {
+            test: Box::new(Expr::Compare(ExprCompare {
+                left: Box::new(Expr::Name(ExprName {
+                    id: "__name__".into(),
+                    ctx: ExprContext::Load,
+                    range: TextRange::default(),
+                })),
+                ops: Box::from([CmpOp::Eq]),
+                comparators: Box::from([self.create_string_literal("__main__")]),
+                range: TextRange::default(),
+            })),
+            body,
+            elif_else_clauses: vec![],
+            range: TextRange::default(),
+        })
+    }
+
     /// Process a statement in the entry module, handling renames and reassignments
     fn process_entry_module_statement(
         &mut self,
         stmt: &mut Stmt,
+        module_name: &str,
         entry_module_renames: &FxIndexMap<String, String>,
         final_body: &mut Vec<Stmt>,
     ) {
@@ -1146,8 +2497,11 @@ impl HybridStaticBundler {
                         self.process_entry_module_function(func_def, entry_module_renames);
                 }
                 Stmt::ClassDef(class_def) => {
-                    pending_reassignment =
-                        self.process_entry_module_class(class_def, entry_module_renames);
+                    pending_reassignment = self.process_entry_module_class(
+                        class_def,
+                        module_name,
+                        entry_module_renames,
+                    );
                 }
                 _ => {
                     // For other statements, use the existing rewrite method
@@ -1208,6 +2562,7 @@ impl HybridStaticBundler {
     fn process_entry_module_class(
         &self,
         class_def: &mut StmtClassDef,
+        module_name: &str,
         entry_module_renames: &FxIndexMap<String, String>,
     ) -> Option<(String, String)> {
         let class_name = class_def.name.to_string();
@@ -1223,13 +2578,18 @@ impl HybridStaticBundler {
             false
         };
 
-        // Apply renames to class body - classes don't create new scopes for globals
-        // We need to create a temporary Stmt to pass to rewrite_aliases_in_stmt
-        let mut temp_stmt = Stmt::ClassDef(class_def.clone());
-        self.rewrite_aliases_in_stmt(&mut temp_stmt, entry_module_renames);
-        if let Stmt::ClassDef(updated_class) = temp_stmt {
-            *class_def = updated_class;
+        // Apply renames to base classes and the class body
+        if let Some(ref mut arguments) = class_def.arguments {
+            for arg in &mut arguments.args {
+                self.rewrite_aliases_in_expr(arg, entry_module_renames);
+            }
         }
+        self.rewrite_class_body_for_renames(
+            &mut class_def.body,
+            module_name,
+            &class_name,
+            entry_module_renames,
+        );
 
         if needs_reassignment {
             Some((
@@ -1241,6 +2601,60 @@ impl HybridStaticBundler {
         }
     }
 
+    /// Apply module-level renames to the body of class `class_name` of `module_name`
+    ///
+    /// Names the class body binds itself (attributes, methods, nested classes) refer to
+    /// the class namespace there and keep their names. Method bodies do not see the
+    /// class namespace, so they get the module-level renames unchanged.
+    fn rewrite_class_body_for_renames(
+        &self,
+        body: &mut [Stmt],
+        module_name: &str,
+        class_name: &str,
+        module_renames: &FxIndexMap<String, String>,
+    ) {
+        let class_scope = self
+            .class_scopes
+            .get(module_name)
+            .and_then(|scopes| scopes.get(class_name));
+        let class_renames: FxIndexMap<String, String> = module_renames
+            .iter()
+            .filter(|(name, _)| !class_scope.is_some_and(|scope| scope.binds(name)))
+            .map(|(name, renamed)| (name.clone(), renamed.clone()))
+            .collect();
+
+        for stmt in body {
+            match stmt {
+                Stmt::FunctionDef(method) => {
+                    // Defaults are evaluated in the class body
+                    for param in &mut method.parameters.args {
+                        if let Some(ref mut default) = param.default {
+                            self.rewrite_aliases_in_expr(default, &class_renames);
+                        }
+                    }
+                    for body_stmt in &mut method.body {
+                        self.rewrite_aliases_in_stmt(body_stmt, module_renames);
+                    }
+                }
+                Stmt::ClassDef(nested_class) => {
+                    if let Some(ref mut arguments) = nested_class.arguments {
+                        for arg in &mut arguments.args {
+                            self.rewrite_aliases_in_expr(arg, &class_renames);
+                        }
+                    }
+                    let nested_name = format!("{}.{}", class_name, nested_class.name);
+                    self.rewrite_class_body_for_renames(
+                        &mut nested_class.body,
+                        module_name,
+                        &nested_name,
+                        module_renames,
+                    );
+                }
+                _ => self.rewrite_aliases_in_stmt(stmt, &class_renames),
+            }
+        }
+    }
+
     /// Check if an assignment statement has been renamed
     fn check_renamed_assignment(
         &self,
@@ -1276,7 +2690,9 @@ impl HybridStaticBundler {
         let init_func_name = &self.init_functions[ctx.synthetic_name];
         let mut body = Vec::new();
 
-        // Check if module already exists in sys.modules
+        // Check if module already exists in sys.modules: initialized by another thread
+        // while this one waited for the lock, or being initialized by this thread
+        // further up a circular import
         body.push(self.create_module_exists_check(ctx.synthetic_name));
 
         // Create module object (returns multiple statements)
@@ -1286,10 +2702,19 @@ impl HybridStaticBundler {
         body.push(self.create_sys_modules_registration(ctx.synthetic_name));
         body.push(self.create_sys_modules_registration_alias(ctx.synthetic_name, ctx.module_name));
 
+        // `find_spec` returns the `__spec__` of modules already in sys.modules and rejects
+        // a missing one
+        if self.find_spec_probed_modules.contains(ctx.module_name) {
+            body.extend(parse_statements(&format!(
+                "{}.__spec__ = importlib.machinery.ModuleSpec(\"{}\", None)\n",
+                self.runtime_names.module_var, ctx.module_name
+            ))?);
+        }
+
         // Apply globals lifting if needed
         let lifted_names = if let Some(ref global_info) = ctx.global_info {
             if !global_info.global_declarations.is_empty() {
-                let globals_lifter = GlobalsLifter::new(global_info);
+                let globals_lifter = GlobalsLifter::new(global_info, &self.runtime_names.prefix);
                 let lifted_names = globals_lifter.get_lifted_names().clone();
 
                 // Transform the AST to use lifted globals
@@ -1321,7 +2746,10 @@ impl HybridStaticBundler {
                     // Set as module attribute only if it should be exported
                     let symbol_name = class_def.name.to_string();
                     if self.should_export_symbol(&symbol_name, ctx.module_name) {
-                        body.push(self.create_module_attr_assignment("module", &symbol_name));
+                        body.push(self.create_module_attr_assignment(
+                            &self.runtime_names.module_var,
+                            &symbol_name,
+                        ));
                     }
                 }
                 Stmt::FunctionDef(func_def) => {
@@ -1330,7 +2758,10 @@ impl HybridStaticBundler {
                     // Set as module attribute only if it should be exported
                     let symbol_name = func_def.name.to_string();
                     if self.should_export_symbol(&symbol_name, ctx.module_name) {
-                        body.push(self.create_module_attr_assignment("module", &symbol_name));
+                        body.push(self.create_module_attr_assignment(
+                            &self.runtime_names.module_var,
+                            &symbol_name,
+                        ));
                     }
                 }
                 Stmt::Assign(assign) => {
@@ -1351,6 +2782,31 @@ impl HybridStaticBundler {
                         );
                     }
                 }
+                Stmt::AugAssign(aug_assign) => {
+                    body.push(stmt.clone());
+                    // `x += ...` rebinds `x` for immutable values, so the module attribute
+                    // set after its first assignment is stale
+                    if let Expr::Name(name) = aug_assign.target.as_ref() {
+                        if self.should_export_symbol(name.id.as_str(), ctx.module_name) {
+                            body.push(self.create_module_attr_assignment(
+                                &self.runtime_names.module_var,
+                                name.id.as_str(),
+                            ));
+                        }
+                    }
+                }
+                Stmt::With(with_stmt) => {
+                    body.push(stmt.clone());
+                    // Names bound inside the block are module attributes too
+                    for name in Self::with_bound_names(with_stmt) {
+                        if self.should_export_symbol(&name, ctx.module_name) {
+                            body.push(self.create_module_attr_assignment(
+                                &self.runtime_names.module_var,
+                                &name,
+                            ));
+                        }
+                    }
+                }
                 _ => {
                     // Other statements execute normally
                     body.push(stmt.clone());
@@ -1389,20 +2845,31 @@ impl HybridStaticBundler {
             body.push(self.create_all_assignment_for_module(ctx.module_name));
         }
 
-        // Return the module object
-        body.push(Stmt::Return(ruff_python_ast::StmtReturn {
-            value: Some(Box::new(Expr::Name(ExprName {
-                id: "module".into(),
-                ctx: ExprContext::Load,
-                range: TextRange::default(),
-            }))),
-            range: TextRange::default(),
-        }));
+        // Mark the module as complete and return it
+        body.extend(parse_statements(&format!(
+            "{}.add('{}')\nreturn {}\n",
+            self.runtime_names.initialized_modules,
+            ctx.synthetic_name,
+            self.runtime_names.module_var
+        ))?);
 
         // Transform globals() calls to module.__dict__ in the entire body
         for stmt in &mut body {
-            transform_globals_in_stmt(stmt);
+            transform_globals_in_stmt(stmt, &self.runtime_names.module_var);
+        }
+
+        // Double-checked locking: completed modules are returned without taking the
+        // lock, everything else initializes (or waits for another thread to) under it
+        let mut guarded = parse_statements(&format!(
+            "if '{name}' in {initialized}:\n    return sys.modules['{name}']\nwith {lock}:\n    pass\n",
+            name = ctx.synthetic_name,
+            initialized = self.runtime_names.initialized_modules,
+            lock = self.runtime_names.init_lock,
+        ))?;
+        if let Some(Stmt::With(with_stmt)) = guarded.last_mut() {
+            with_stmt.body = body;
         }
+        let body = guarded;
 
         // Create the init function
         Ok(Stmt::FunctionDef(StmtFunctionDef {
@@ -1425,9 +2892,25 @@ impl HybridStaticBundler {
     }
 
     /// Generate registries and import hook after init functions are defined
-    fn generate_registries_and_hook(&self) -> Vec<Stmt> {
+    fn generate_registries_and_hook(&self) -> Result<Vec<Stmt>> {
         let mut stmts = Vec::new();
 
+        // Initialization state shared by the init functions. It lives in sys.modules
+        // under a name derived from the wrapper modules' content, so a bundle executed
+        // as `__main__` and imported under its own name (or copied under two names)
+        // shares one lock and one set of initialized modules with its other copies.
+        let runtime_module = self
+            .runtime_names
+            .prefixed(&format!("runtime_{}", self.bundle_runtime_hash()));
+        stmts.extend(parse_statements(&format!(
+            "{runtime} = sys.modules.setdefault('{runtime_module}', types.ModuleType('{runtime_module}'))\n\
+             {lock} = vars({runtime}).setdefault('init_lock', threading.RLock())\n\
+             {initialized} = vars({runtime}).setdefault('initialized_modules', set())\n",
+            runtime = self.runtime_names.runtime_state,
+            lock = self.runtime_names.init_lock,
+            initialized = self.runtime_names.initialized_modules,
+        ))?);
+
         // Create module registry
         stmts.push(self.create_module_registry());
 
@@ -1435,9 +2918,58 @@ impl HybridStaticBundler {
         stmts.push(self.create_init_functions_registry());
 
         // Create and install import hook
-        stmts.extend(self.create_import_hook());
+        stmts.extend(self.create_import_hook()?);
+
+        if self.uses_lazy_modules.get() {
+            stmts.extend(self.create_lazy_module_class()?);
+        }
+
+        Ok(stmts)
+    }
+
+    /// Class of the lazy modules that cycle peers are bound to in init functions
+    ///
+    /// Attribute access runs the module's init function, which returns right away once
+    /// the module is initialized (or while it is initializing).
+    fn create_lazy_module_class(&self) -> Result<Vec<Stmt>> {
+        parse_statements(&format!(
+            r#"class {class}:
+    __slots__ = ("_cribo_name", "_cribo_init")
+
+    def __init__(self, name, init):
+        object.__setattr__(self, "_cribo_name", name)
+        object.__setattr__(self, "_cribo_init", init)
+
+    def __getattr__(self, attr):
+        return getattr(self._cribo_init(), attr)
+
+    def __setattr__(self, attr, value):
+        setattr(self._cribo_init(), attr, value)
+
+    def __delattr__(self, attr):
+        delattr(self._cribo_init(), attr)
+
+    def __dir__(self):
+        return dir(self._cribo_init())
+
+    def __repr__(self):
+        return "<lazy module %r>" % self._cribo_name
+"#,
+            class = self.runtime_names.lazy_module_class,
+        ))
+    }
 
-        stmts
+    /// Identity of the bundle's wrapper modules: a hash of their synthetic names, which
+    /// already carry each module's content hash
+    fn bundle_runtime_hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        for synthetic_name in self.init_functions.keys() {
+            hasher.update(synthetic_name.as_bytes());
+            hasher.update(b"\n");
+        }
+        let hash = format!("{:x}", hasher.finalize());
+        hash[..12].to_owned()
     }
 
     /// Create the __cribo_modules registry
@@ -1453,7 +2985,7 @@ impl HybridStaticBundler {
 
         Stmt::Assign(StmtAssign {
             targets: vec![Expr::Name(ExprName {
-                id: "__cribo_modules".into(),
+                id: self.runtime_names.modules_registry.as_str().into(),
                 ctx: ExprContext::Store,
                 range: TextRange::default(),
             })],
@@ -1482,7 +3014,7 @@ impl HybridStaticBundler {
 
         Stmt::Assign(StmtAssign {
             targets: vec![Expr::Name(ExprName {
-                id: "__cribo_init_functions".into(),
+                id: self.runtime_names.init_functions_registry.as_str().into(),
                 ctx: ExprContext::Store,
                 range: TextRange::default(),
             })],
@@ -1495,69 +3027,20 @@ impl HybridStaticBundler {
     }
 
     /// Create the import hook class and install it
-    fn create_import_hook(&self) -> Vec<Stmt> {
-        let mut stmts = Vec::new();
-
+    fn create_import_hook(&self) -> Result<Vec<Stmt>> {
         // Define CriboBundledFinder class
-        let finder_class = self.create_finder_class();
-        stmts.push(finder_class);
-
-        // Install the hook: sys.meta_path.insert(0, CriboBundledFinder(__cribo_modules, __cribo_init_functions))
-        let install_stmt = Stmt::Expr(ruff_python_ast::StmtExpr {
-            value: Box::new(Expr::Call(ExprCall {
-                func: Box::new(Expr::Attribute(ExprAttribute {
-                    value: Box::new(Expr::Attribute(ExprAttribute {
-                        value: Box::new(Expr::Name(ExprName {
-                            id: "sys".into(),
-                            ctx: ExprContext::Load,
-                            range: TextRange::default(),
-                        })),
-                        attr: Identifier::new("meta_path", TextRange::default()),
-                        ctx: ExprContext::Load,
-                        range: TextRange::default(),
-                    })),
-                    attr: Identifier::new("insert", TextRange::default()),
-                    ctx: ExprContext::Load,
-                    range: TextRange::default(),
-                })),
-                arguments: ruff_python_ast::Arguments {
-                    args: Box::from([
-                        self.create_number_literal(0),
-                        Expr::Call(ExprCall {
-                            func: Box::new(Expr::Name(ExprName {
-                                id: "CriboBundledFinder".into(),
-                                ctx: ExprContext::Load,
-                                range: TextRange::default(),
-                            })),
-                            arguments: ruff_python_ast::Arguments {
-                                args: Box::from([
-                                    Expr::Name(ExprName {
-                                        id: "__cribo_modules".into(),
-                                        ctx: ExprContext::Load,
-                                        range: TextRange::default(),
-                                    }),
-                                    Expr::Name(ExprName {
-                                        id: "__cribo_init_functions".into(),
-                                        ctx: ExprContext::Load,
-                                        range: TextRange::default(),
-                                    }),
-                                ]),
-                                keywords: Box::from([]),
-                                range: TextRange::default(),
-                            },
-                            range: TextRange::default(),
-                        }),
-                    ]),
-                    keywords: Box::from([]),
-                    range: TextRange::default(),
-                },
-                range: TextRange::default(),
-            })),
-            range: TextRange::default(),
-        });
+        let mut stmts = vec![self.create_finder_class()];
 
-        stmts.push(install_stmt);
-        stmts
+        // Install the hook once per process: another copy of this bundle may have
+        // installed it already
+        stmts.extend(parse_statements(&format!(
+            "if 'finder' not in vars({runtime}):\n    {runtime}.finder = {finder}({modules}, {init_functions})\n    sys.meta_path.insert(0, {runtime}.finder)\n",
+            runtime = self.runtime_names.runtime_state,
+            finder = self.runtime_names.finder_class,
+            modules = self.runtime_names.modules_registry,
+            init_functions = self.runtime_names.init_functions_registry,
+        ))?);
+        Ok(stmts)
     }
 
     /// Create the CriboBundledFinder class
@@ -1758,27 +3241,9 @@ impl HybridStaticBundler {
             range: TextRange::default(),
         }));
 
-        // if synthetic_name not in sys.modules:
-        let inner_condition = Expr::Compare(ruff_python_ast::ExprCompare {
-            left: Box::new(Expr::Name(ExprName {
-                id: "synthetic_name".into(),
-                ctx: ExprContext::Load,
-                range: TextRange::default(),
-            })),
-            ops: Box::from([ruff_python_ast::CmpOp::NotIn]),
-            comparators: Box::from([Expr::Attribute(ExprAttribute {
-                value: Box::new(Expr::Name(ExprName {
-                    id: "sys".into(),
-                    ctx: ExprContext::Load,
-                    range: TextRange::default(),
-                })),
-                attr: Identifier::new("modules", TextRange::default()),
-                ctx: ExprContext::Load,
-                range: TextRange::default(),
-            })]),
-            range: TextRange::default(),
-        });
-
+        // Init functions return completed modules right away and wait for one being
+        // initialized by another thread, so they are called even when the module is
+        // already in sys.modules
         let mut inner_if_body = Vec::new();
 
         // init_func = self.init_functions.get(synthetic_name)
@@ -1852,12 +3317,7 @@ impl HybridStaticBundler {
             range: TextRange::default(),
         }));
 
-        if_body.push(Stmt::If(StmtIf {
-            test: Box::new(inner_condition),
-            body: inner_if_body,
-            elif_else_clauses: vec![],
-            range: TextRange::default(),
-        }));
+        if_body.extend(inner_if_body);
 
         // import importlib.util
         if_body.push(Stmt::Import(StmtImport {
@@ -1940,7 +3400,10 @@ impl HybridStaticBundler {
 
         // Create the class definition
         Stmt::ClassDef(StmtClassDef {
-            name: Identifier::new("CriboBundledFinder", TextRange::default()),
+            name: Identifier::new(
+                self.runtime_names.finder_class.as_str(),
+                TextRange::default(),
+            ),
             type_params: None,
             arguments: None,
             body: class_body,
@@ -1961,14 +3424,6 @@ impl HybridStaticBundler {
         })
     }
 
-    /// Create a number literal expression
-    fn create_number_literal(&self, value: i32) -> Expr {
-        Expr::NumberLiteral(ruff_python_ast::ExprNumberLiteral {
-            value: ruff_python_ast::Number::Int(ruff_python_ast::Int::from(value as u32)),
-            range: TextRange::default(),
-        })
-    }
-
     /// Check if module exists in sys.modules
     fn create_module_exists_check(&self, synthetic_name: &str) -> Stmt {
         let condition = Expr::Compare(ruff_python_ast::ExprCompare {
@@ -2037,7 +3492,7 @@ impl HybridStaticBundler {
             // module = types.ModuleType(synthetic_name)
             Stmt::Assign(StmtAssign {
                 targets: vec![Expr::Name(ExprName {
-                    id: "module".into(),
+                    id: self.runtime_names.module_var.as_str().into(),
                     ctx: ExprContext::Store,
                     range: TextRange::default(),
                 })],
@@ -2048,7 +3503,7 @@ impl HybridStaticBundler {
             Stmt::Assign(StmtAssign {
                 targets: vec![Expr::Attribute(ExprAttribute {
                     value: Box::new(Expr::Name(ExprName {
-                        id: "module".into(),
+                        id: self.runtime_names.module_var.as_str().into(),
                         ctx: ExprContext::Load,
                         range: TextRange::default(),
                     })),
@@ -2116,7 +3571,7 @@ impl HybridStaticBundler {
                 range: TextRange::default(),
             })],
             value: Box::new(Expr::Name(ExprName {
-                id: "module".into(),
+                id: self.runtime_names.module_var.as_str().into(),
                 ctx: ExprContext::Load,
                 range: TextRange::default(),
             })),
@@ -2147,7 +3602,7 @@ impl HybridStaticBundler {
                 range: TextRange::default(),
             })],
             value: Box::new(Expr::Name(ExprName {
-                id: "module".into(),
+                id: self.runtime_names.module_var.as_str().into(),
                 ctx: ExprContext::Load,
                 range: TextRange::default(),
             })),
@@ -2177,14 +3632,68 @@ impl HybridStaticBundler {
         })
     }
 
-    /// Extract simple assignment target
-    fn extract_simple_assign_target(&self, assign: &StmtAssign) -> Option<String> {
-        if assign.targets.len() == 1 {
-            if let Expr::Name(name) = &assign.targets[0] {
-                return Some(name.id.to_string());
+    /// Names an assignment binds, including those unpacked by tuple, list and starred
+    /// targets such as `a, *rest = ...`, in source order
+    fn assign_target_names(assign: &StmtAssign) -> Vec<String> {
+        let mut names = Vec::new();
+        for target in &assign.targets {
+            Self::collect_target_names(target, &mut names);
+        }
+        names
+    }
+
+    /// Collect the names bound by an assignment target
+    fn collect_target_names(target: &Expr, names: &mut Vec<String>) {
+        match target {
+            Expr::Name(name) => names.push(name.id.to_string()),
+            Expr::Tuple(tuple) => {
+                for element in &tuple.elts {
+                    Self::collect_target_names(element, names);
+                }
+            }
+            Expr::List(list) => {
+                for element in &list.elts {
+                    Self::collect_target_names(element, names);
+                }
             }
+            Expr::Starred(starred) => Self::collect_target_names(&starred.value, names),
+            _ => {}
+        }
+    }
+
+    /// Whether an assignment target only binds names, as opposed to storing into an
+    /// attribute or subscript, which runs code of the object stored into
+    fn is_name_binding_target(target: &Expr) -> bool {
+        match target {
+            Expr::Name(_) => true,
+            Expr::Tuple(tuple) => tuple.elts.iter().all(Self::is_name_binding_target),
+            Expr::List(list) => list.elts.iter().all(Self::is_name_binding_target),
+            Expr::Starred(starred) => Self::is_name_binding_target(&starred.value),
+            _ => false,
+        }
+    }
+
+    /// Rename the names bound by an assignment target according to `renames`
+    fn rename_target_names(target: &mut Expr, renames: &FxIndexMap<String, String>) {
+        match target {
+            Expr::Name(name) => {
+                if let Some(renamed) = renames.get(name.id.as_str()) {
+                    name.id = renamed.as_str().into();
+                }
+            }
+            Expr::Tuple(tuple) => {
+                for element in &mut tuple.elts {
+                    Self::rename_target_names(element, renames);
+                }
+            }
+            Expr::List(list) => {
+                for element in &mut list.elts {
+                    Self::rename_target_names(element, renames);
+                }
+            }
+            Expr::Starred(starred) => Self::rename_target_names(&mut starred.value, renames),
+            _ => {}
         }
-        None
     }
 
     /// Add module attribute assignment if the symbol should be exported
@@ -2194,11 +3703,110 @@ impl HybridStaticBundler {
         module_name: &str,
         body: &mut Vec<Stmt>,
     ) {
-        if let Some(name) = self.extract_simple_assign_target(assign) {
+        for name in Self::assign_target_names(assign) {
             if self.should_export_symbol(&name, module_name) {
-                body.push(self.create_module_attr_assignment("module", &name));
+                body.push(
+                    self.create_module_attr_assignment(&self.runtime_names.module_var, &name),
+                );
+            }
+        }
+    }
+
+    /// Register a module object for each inlined module in `sys.modules`
+    ///
+    /// Each object carries the module's public names, bound to their (possibly renamed)
+    /// bundle-level definitions, and is attached to its parent package as an attribute.
+    /// Parent packages that are not part of the bundle get an empty module object.
+    fn register_inlined_modules(
+        &self,
+        sorted_modules: &[(String, PathBuf, Vec<String>)],
+        symbol_renames: &FxIndexMap<String, FxIndexMap<String, String>>,
+        final_body: &mut Vec<Stmt>,
+    ) -> Result<()> {
+        let registered: Vec<&String> = sorted_modules
+            .iter()
+            .map(|(module_name, _, _)| module_name)
+            .filter(|module_name| {
+                self.inlined_modules.contains(*module_name)
+                    && !self.module_registry.contains_key(*module_name)
+            })
+            .collect();
+        if registered.is_empty() {
+            return Ok(());
+        }
+
+        let module_var = self.runtime_names.prefixed("module");
+        for module_name in &registered {
+            log::debug!(
+                "Registering inlined module '{}' in sys.modules",
+                module_name
+            );
+            final_body.extend(parse_statements(&format!(
+                "{module_var} = types.ModuleType(\"{module_name}\")\n"
+            ))?);
+            if let Some(module_renames) = symbol_renames.get(*module_name) {
+                for (original_name, renamed_name) in module_renames {
+                    if self.should_export_symbol(original_name, module_name) {
+                        self.add_symbol_to_namespace(
+                            &module_var,
+                            original_name,
+                            renamed_name,
+                            final_body,
+                        );
+                    }
+                }
+            }
+            final_body.extend(parse_statements(&format!(
+                "sys.modules.setdefault(\"{module_name}\", {module_var})\n"
+            ))?);
+        }
+
+        // Link submodules to their packages, creating packages the bundle lacks
+        let mut linked = FxIndexSet::default();
+        for module_name in &registered {
+            let parts: Vec<&str> = module_name.split('.').collect();
+            for depth in 1..parts.len() {
+                let parent = parts[..depth].join(".");
+                let child = parts[..=depth].join(".");
+                if !linked.insert(child.clone()) {
+                    continue;
+                }
+                let attr_name = parts[depth];
+                final_body.extend(parse_statements(&format!(
+                    "setattr(sys.modules.setdefault(\"{parent}\", types.ModuleType(\"{parent}\")), \"{attr_name}\", sys.modules.setdefault(\"{child}\", types.ModuleType(\"{child}\")))\n"
+                ))?);
             }
         }
+        final_body.extend(parse_statements(&format!("del {module_var}\n"))?);
+        Ok(())
+    }
+
+    /// Create a meta path finder that reports the `find_spec`-probed bundled modules
+    ///
+    /// The finder is appended to `sys.meta_path`, so modules that really exist on the
+    /// import path still take precedence.
+    fn create_bundled_module_finder(&self) -> Result<Vec<Stmt>> {
+        let names = self
+            .find_spec_probed_modules
+            .iter()
+            .map(|name| format!("\"{}\"", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let finder = &self.runtime_names.bundled_module_finder_class;
+        parse_statements(&format!(
+            r#"class {finder}:
+    names = frozenset([{names}])
+
+    @classmethod
+    def find_spec(cls, fullname, path=None, target=None):
+        if fullname in cls.names:
+            return importlib.machinery.ModuleSpec(fullname, None)
+        return None
+
+
+sys.meta_path.append({finder})
+"#
+        ))
     }
 
     /// Generate a call to initialize a module
@@ -2830,6 +4438,58 @@ impl HybridStaticBundler {
     }
 }
 
+/// Find unused imports for every module, spreading the work across available cores
+///
+/// Returns one entry per module, in the same order as `modules`. Modules that are not
+/// present in the graph get an empty list.
+fn find_unused_imports_parallel(
+    modules: &[(String, ModModule, PathBuf, String)],
+    graph: &DependencyGraph,
+) -> Vec<Vec<UnusedImportInfo>> {
+    let find_for_module =
+        |(module_name, _, module_path, _): &(String, ModModule, PathBuf, String)| {
+            // Check if this is an __init__.py file
+            let is_init_py =
+                module_path.file_name().and_then(|name| name.to_str()) == Some("__init__.py");
+            graph
+                .get_module_by_name(module_name)
+                .map(|module_dep_graph| module_dep_graph.find_unused_imports(is_init_py))
+                .unwrap_or_default()
+        };
+
+    let worker_count = std::thread::available_parallelism()
+        .map(std::num::NonZeroUsize::get)
+        .unwrap_or(1)
+        .min(modules.len());
+    if worker_count <= 1 {
+        return modules.iter().map(find_for_module).collect();
+    }
+
+    let chunk_size = modules.len().div_ceil(worker_count);
+    // Keep routing worker thread logs to the caller's log sink
+    let log_sink = crate::logging::current_sink();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = modules
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let log_sink = log_sink.clone();
+                scope.spawn(move || {
+                    let _log_scope = crate::logging::SinkScope::enter(log_sink);
+                    chunk.iter().map(find_for_module).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .expect("unused import analysis thread panicked")
+            })
+            .collect()
+    })
+}
+
 /// Helper function to recursively rewrite aliases in an expression
 fn rewrite_aliases_in_expr_impl(expr: &mut Expr, alias_to_canonical: &FxIndexMap<String, String>) {
     match expr {
@@ -2965,10 +4625,10 @@ fn rewrite_aliases_in_expr_impl(expr: &mut Expr, alias_to_canonical: &FxIndexMap
         Expr::Starred(starred_expr) => {
             rewrite_aliases_in_expr_impl(&mut starred_expr.value, alias_to_canonical);
         }
-        Expr::FString(_fstring_expr) => {
-            // FString handling is complex due to its structure
-            // For now, skip FString rewriting as it's rarely used with module aliases
-            log::debug!("FString expression rewriting not yet implemented");
+        Expr::FString(_) | Expr::TString(_) => {
+            for value in interpolated_expressions_mut(expr) {
+                rewrite_aliases_in_expr_impl(value, alias_to_canonical);
+            }
         }
         // Constant values and other literals don't need rewriting
         Expr::StringLiteral(_)
@@ -3187,13 +4847,15 @@ impl HybridStaticBundler {
                     in_function_with_globals,
                 );
             }
-            Expr::FString(_) => {
-                self.transform_fstring_for_lifted_globals(
-                    expr,
-                    lifted_names,
-                    global_info,
-                    in_function_with_globals,
-                );
+            Expr::FString(_) | Expr::TString(_) => {
+                for value in interpolated_expressions_mut(expr) {
+                    self.transform_expr_for_lifted_globals(
+                        value,
+                        lifted_names,
+                        global_info,
+                        in_function_with_globals,
+                    );
+                }
             }
             Expr::BinOp(binop) => {
                 self.transform_expr_for_lifted_globals(
@@ -3295,7 +4957,7 @@ impl HybridStaticBundler {
 impl HybridStaticBundler {
     /// Collect module renames from semantic analysis
     fn collect_module_renames(
-        &self,
+        &mut self,
         module_name: &str,
         semantic_ctx: &SemanticContext,
         symbol_renames: &mut FxIndexMap<String, FxIndexMap<String, String>>,
@@ -3328,11 +4990,20 @@ impl HybridStaticBundler {
                 module_info.exported_symbols.iter().collect::<Vec<_>>()
             );
 
+            self.class_scopes.insert(
+                module_name.to_string(),
+                module_info
+                    .class_scopes
+                    .iter()
+                    .map(|(class_name, scope)| (class_name.clone(), scope.clone()))
+                    .collect(),
+            );
+
             // Process all exported symbols from the module
             for symbol in &module_info.exported_symbols {
-                if let Some(new_name) = semantic_ctx.symbol_registry.get_rename(&module_id, symbol)
+                if let Some(new_name) = semantic_ctx.symbol_registry.get_rename(&module_id, *symbol)
                 {
-                    module_renames.insert(symbol.to_string(), new_name.to_string());
+                    module_renames.insert(symbol.as_str().to_owned(), new_name.as_str().to_owned());
                     log::debug!(
                         "Module '{}': symbol '{}' renamed to '{}'",
                         module_name,
@@ -3341,7 +5012,8 @@ impl HybridStaticBundler {
                     );
                 } else {
                     // Include non-renamed symbols too - they still need to be in the namespace
-                    module_renames.insert(symbol.to_string(), symbol.to_string());
+                    let symbol = symbol.as_str().to_owned();
+                    module_renames.insert(symbol.clone(), symbol);
                     log::debug!(
                         "Module '{}': symbol '{}' has no rename, using original name",
                         module_name,
@@ -3389,20 +5061,63 @@ impl HybridStaticBundler {
         };
 
         let module_id = module.module_id;
-        let global_info = params.semantic_ctx.semantic_bundler.analyze_module_globals(
+        let mut global_info = params.semantic_ctx.semantic_bundler.analyze_module_globals(
             module_id,
             params.ast,
             params.module_name,
         );
 
+        // Names imported from inlined modules become plain locals of the init function,
+        // so a `global` declaration of one must be lifted like a module-level variable
+        for name in self.names_imported_from_inlined_modules(params) {
+            if global_info.global_declarations.contains_key(&name) {
+                debug!(
+                    "Lifting global '{}' of module '{}' that is imported from an inlined module",
+                    name, params.module_name
+                );
+                global_info.module_level_vars.insert(name);
+            }
+        }
+
         // Create GlobalsLifter and collect declarations
         if !global_info.global_declarations.is_empty() {
-            let globals_lifter = GlobalsLifter::new(&global_info);
+            let globals_lifter = GlobalsLifter::new(&global_info, &self.runtime_names.prefix);
             all_lifted_declarations.extend(globals_lifter.get_lifted_declarations());
         }
 
         module_globals.insert(params.module_name.to_string(), global_info);
     }
+
+    /// Local names a wrapper module binds with module-level `from ... import` statements
+    /// that resolve to inlined modules
+    fn names_imported_from_inlined_modules(
+        &self,
+        params: &ProcessGlobalsParams,
+    ) -> FxIndexSet<String> {
+        let mut names = FxIndexSet::default();
+        for stmt in &params.ast.body {
+            let Stmt::ImportFrom(import_from) = stmt else {
+                continue;
+            };
+            let Some(resolved) = self.resolve_relative_import_with_context(
+                import_from,
+                params.module_name,
+                Some(params.module_path),
+            ) else {
+                continue;
+            };
+            if !self.inlined_modules.contains(&resolved) {
+                continue;
+            }
+            for alias in &import_from.names {
+                let local_name = alias.asname.as_ref().unwrap_or(&alias.name);
+                if local_name.as_str() != "*" {
+                    names.insert(local_name.to_string());
+                }
+            }
+        }
+        names
+    }
 }
 
 impl HybridStaticBundler {
@@ -3439,15 +5154,69 @@ impl HybridStaticBundler {
             Stmt::ClassDef(class) => {
                 symbols.push(class.name.to_string());
             }
-            Stmt::Assign(assign) if assign.targets.len() == 1 => {
-                if let Expr::Name(name) = &assign.targets[0] {
-                    symbols.push(name.id.to_string());
-                }
+            Stmt::Assign(assign) => {
+                symbols.extend(Self::assign_target_names(assign));
             }
             _ => {}
         }
     }
 
+    /// Collect names that a module deletes at module level and does not rebind later
+    ///
+    /// Such names are gone once the module finishes executing, so they are not module
+    /// attributes.
+    fn collect_deleted_module_names(ast: &ModModule) -> FxIndexSet<String> {
+        let mut deleted = FxIndexSet::default();
+        for stmt in &ast.body {
+            match stmt {
+                Stmt::Delete(delete_stmt) => {
+                    for target in &delete_stmt.targets {
+                        if let Expr::Name(name) = target {
+                            deleted.insert(name.id.to_string());
+                        }
+                    }
+                }
+                Stmt::FunctionDef(func_def) => {
+                    deleted.shift_remove(func_def.name.as_str());
+                }
+                Stmt::ClassDef(class_def) => {
+                    deleted.shift_remove(class_def.name.as_str());
+                }
+                Stmt::Assign(assign) => {
+                    for name in Self::assign_target_names(assign) {
+                        deleted.shift_remove(&name);
+                    }
+                }
+                Stmt::AnnAssign(ann_assign) if ann_assign.value.is_some() => {
+                    if let Expr::Name(name) = ann_assign.target.as_ref() {
+                        deleted.shift_remove(name.id.as_str());
+                    }
+                }
+                Stmt::Import(import_stmt) => {
+                    for alias in &import_stmt.names {
+                        let local_name = alias.asname.as_ref().unwrap_or(&alias.name);
+                        deleted.shift_remove(local_name.as_str());
+                    }
+                }
+                Stmt::ImportFrom(import_from) => {
+                    for alias in &import_from.names {
+                        let local_name = alias.asname.as_ref().unwrap_or(&alias.name);
+                        deleted.shift_remove(local_name.as_str());
+                    }
+                }
+                _ => {}
+            }
+        }
+        deleted
+    }
+
+    /// Check whether `module_name` deletes `symbol_name` at module level
+    fn is_deleted_module_name(&self, symbol_name: &str, module_name: &str) -> bool {
+        self.module_deleted_names
+            .get(module_name)
+            .is_some_and(|names| names.contains(symbol_name))
+    }
+
     /// Extract __all__ exports from a module
     /// Returns Some(vec) if __all__ is defined, None if not defined
     fn extract_all_exports(&self, ast: &ModModule) -> Option<Vec<String>> {
@@ -3521,6 +5290,64 @@ impl HybridStaticBundler {
         false
     }
 
+    /// Record which attributes the bundle accesses on each wrapper module
+    ///
+    /// Wrapper modules that are used as objects, star-imported, looked up by name or
+    /// probed with `find_spec` are left out and keep all their exports, as are all of
+    /// them when any module looks modules up by a computed name.
+    fn collect_accessed_wrapper_attributes(
+        &mut self,
+        modules: &[(String, ModModule, PathBuf, String)],
+        wrapper_modules: &[(String, ModModule, PathBuf, String)],
+    ) {
+        let bundled: IndexSet<String> = modules
+            .iter()
+            .map(|(module_name, _, _, _)| module_name.clone())
+            .collect();
+        let mut accessed: FxIndexMap<String, IndexSet<String>> = FxIndexMap::default();
+        let mut escaped = FxIndexSet::default();
+
+        for (module_name, ast, module_path, _) in modules {
+            let is_package =
+                module_path.file_name().and_then(|name| name.to_str()) == Some("__init__.py");
+            let accesses =
+                ModuleAttributeVisitor::new(module_name, is_package, &bundled).find_accesses(ast);
+            if accesses.dynamic_lookup {
+                log::debug!(
+                    "Module '{}' looks up modules by a computed name; keeping all wrapper exports",
+                    module_name
+                );
+                return;
+            }
+            escaped.extend(accesses.escaped);
+            for (accessed_module, attributes) in accesses.accessed {
+                accessed
+                    .entry(accessed_module)
+                    .or_default()
+                    .extend(attributes);
+            }
+        }
+
+        for (module_name, _, _, _) in wrapper_modules {
+            if escaped.contains(module_name) || self.find_spec_probed_modules.contains(module_name)
+            {
+                log::debug!(
+                    "Wrapper module '{}' is used as an object; keeping all its exports",
+                    module_name
+                );
+                continue;
+            }
+            let attributes = accessed.swap_remove(module_name).unwrap_or_default();
+            log::debug!(
+                "Wrapper module '{}' only needs attributes {:?}",
+                module_name,
+                attributes
+            );
+            self.accessed_wrapper_attributes
+                .insert(module_name.clone(), attributes);
+        }
+    }
+
     /// Determine if a symbol should be exported based on __all__ or default visibility rules
     fn should_export_symbol(&self, symbol_name: &str, module_name: &str) -> bool {
         // Don't export __all__ itself as a module attribute
@@ -3528,6 +5355,18 @@ impl HybridStaticBundler {
             return false;
         }
 
+        // Names deleted at module level are not attributes of the original module
+        if self.is_deleted_module_name(symbol_name, module_name) {
+            return false;
+        }
+
+        // Wrapper modules with pruned exports only need the attributes the bundle uses
+        if let Some(accessed) = self.accessed_wrapper_attributes.get(module_name) {
+            if !accessed.contains(symbol_name) {
+                return false;
+            }
+        }
+
         // Check if the module has explicit __all__ exports
         if let Some(Some(exports)) = self.module_exports.get(module_name) {
             // Module defines __all__, only export symbols listed there
@@ -3573,7 +5412,10 @@ impl HybridStaticBundler {
 
                     // Check if this imported symbol should be exported
                     if self.should_export_symbol(local_name, module_name) {
-                        body.push(self.create_module_attr_assignment("module", local_name));
+                        body.push(self.create_module_attr_assignment(
+                            &self.runtime_names.module_var,
+                            local_name,
+                        ));
                     }
                 }
             }
@@ -3593,7 +5435,10 @@ impl HybridStaticBundler {
 
                     // Check if this imported module should be exported
                     if self.should_export_symbol(local_name, module_name) {
-                        body.push(self.create_module_attr_assignment("module", local_name));
+                        body.push(self.create_module_attr_assignment(
+                            &self.runtime_names.module_var,
+                            local_name,
+                        ));
                     }
                 }
             }
@@ -3621,7 +5466,7 @@ impl HybridStaticBundler {
         Stmt::Assign(StmtAssign {
             targets: vec![Expr::Attribute(ExprAttribute {
                 value: Box::new(Expr::Name(ExprName {
-                    id: "module".into(),
+                    id: self.runtime_names.module_var.as_str().into(),
                     ctx: ExprContext::Load,
                     range: TextRange::default(),
                 })),
@@ -3787,6 +5632,22 @@ impl HybridStaticBundler {
 
     /// Check if a module is safe to hoist
     fn is_safe_stdlib_module(&self, module_name: &str) -> bool {
+        if let Some(&cached) = self.stdlib_safety_cache.borrow().get(module_name) {
+            return cached;
+        }
+
+        let is_safe = Self::is_safe_stdlib_module_uncached(module_name, self.python_version);
+        self.stdlib_safety_cache
+            .borrow_mut()
+            .insert(module_name.to_string(), is_safe);
+        is_safe
+    }
+
+    /// Classify a module as safe to hoist without consulting the cache
+    ///
+    /// Only modules in the standard library of the target `python_version` are hoisted;
+    /// for older targets, newer modules such as `tomllib` are imported where written.
+    fn is_safe_stdlib_module_uncached(module_name: &str, python_version: u8) -> bool {
         match module_name {
             // Modules that modify global state - DO NOT HOIST
             "antigravity" | "this" | "__hello__" | "__phello__" => false,
@@ -3798,7 +5659,7 @@ impl HybridStaticBundler {
 
             _ => {
                 let root_module = module_name.split('.').next().unwrap_or(module_name);
-                ruff_python_stdlib::sys::is_known_standard_library(10, root_module)
+                ruff_python_stdlib::sys::is_known_standard_library(python_version, root_module)
             }
         }
     }
@@ -4119,7 +5980,9 @@ impl HybridStaticBundler {
         }
 
         // Always set as module attribute
-        body.push(self.create_module_attr_assignment("module", params.local_name));
+        body.push(
+            self.create_module_attr_assignment(&self.runtime_names.module_var, params.local_name),
+        );
 
         log::debug!(
             "Import '{}' as '{}' from inlined module '{}' resolved to '{}' in wrapper '{}'",
@@ -4223,7 +6086,12 @@ impl HybridStaticBundler {
                 for _ in 0..levels_to_remove {
                     if parts.is_empty() {
                         log::debug!("Invalid relative import - ran out of parent levels");
-                        return None; // Invalid relative import
+                        // Climbing above the source roots may map to an external package
+                        return crate::util::relative_import_fallback(
+                            &self.relative_import_fallbacks,
+                            current_module,
+                            import_from.module.as_ref().map(|m| m.as_str()),
+                        );
                     }
                     parts.pop();
                 }
@@ -4247,32 +6115,14 @@ impl HybridStaticBundler {
             log::debug!("Resolved relative import to: {}", resolved);
             Some(resolved)
         } else {
-            // Not a relative import
-            let resolved = import_from.module.as_ref().map(|m| m.as_str().to_string());
-            log::debug!("Not a relative import, resolved to: {:?}", resolved);
-            resolved
-        }
-    }
-
-    /// Find modules that have function-scoped imports (from import rewriting)
-    fn find_modules_with_function_imports(
-        &self,
-        modules: &[(String, ModModule, PathBuf, String)],
-    ) -> FxIndexSet<String> {
-        let mut modules_with_function_imports = FxIndexSet::default();
-
-        // Check each module for imports inside function bodies
-        for (module_name, ast, _, _) in modules {
-            if self.module_has_function_scoped_imports(ast) {
-                log::info!("Module '{}' has function-scoped imports", module_name);
-                modules_with_function_imports.insert(module_name.clone());
-            }
+            // Not a relative import
+            let resolved = import_from.module.as_ref().map(|m| m.as_str().to_string());
+            log::debug!("Not a relative import, resolved to: {:?}", resolved);
+            resolved
         }
-
-        modules_with_function_imports
     }
 
-    /// Check if a module has imports inside function bodies or class methods
+    /// Check if a module has imports inside function bodies, class bodies or class methods
     fn module_has_function_scoped_imports(&self, ast: &ModModule) -> bool {
         for stmt in &ast.body {
             match stmt {
@@ -4283,8 +6133,9 @@ impl HybridStaticBundler {
                     }
                 }
                 Stmt::ClassDef(class_def) => {
-                    // Check methods inside the class (including async methods)
-                    if self.class_has_method_imports(class_def) {
+                    // Check the class body itself and the methods inside the class
+                    // (including async methods)
+                    if Self::function_has_imports(&class_def.body) {
                         return true;
                     }
                 }
@@ -4294,13 +6145,6 @@ impl HybridStaticBundler {
         false
     }
 
-    /// Check if a class has methods with imports
-    fn class_has_method_imports(&self, class_def: &StmtClassDef) -> bool {
-        class_def.body.iter().any(|class_stmt| {
-            matches!(class_stmt, Stmt::FunctionDef(method_def) if Self::function_has_imports(&method_def.body))
-        })
-    }
-
     /// Check if a function body contains import statements
     fn function_has_imports(body: &[Stmt]) -> bool {
         for stmt in body {
@@ -4353,6 +6197,11 @@ impl HybridStaticBundler {
                         return true;
                     }
                 }
+                Stmt::ClassDef(nested_class) => {
+                    if Self::function_has_imports(&nested_class.body) {
+                        return true;
+                    }
+                }
                 _ => {}
             }
         }
@@ -4360,12 +6209,16 @@ impl HybridStaticBundler {
     }
 
     /// Find which modules are imported directly in all modules
+    ///
+    /// Each module maps to the importing module and top-level statement of every place
+    /// that imports it directly.
     fn find_directly_imported_modules(
         &self,
         modules: &[(String, ModModule, PathBuf, String)],
         _entry_module_name: &str,
-    ) -> FxIndexSet<String> {
-        let mut directly_imported = FxIndexSet::default();
+    ) -> FxIndexMap<String, Vec<(String, TextRange)>> {
+        let mut directly_imported: FxIndexMap<String, Vec<(String, TextRange)>> =
+            FxIndexMap::default();
 
         // Check all modules for direct imports
         for (module_name, ast, module_path, _) in modules {
@@ -4376,14 +6229,21 @@ impl HybridStaticBundler {
                 modules,
             };
             for stmt in &ast.body {
-                self.collect_direct_imports(stmt, &ctx, &mut directly_imported);
+                let mut imported_by_stmt = FxIndexSet::default();
+                self.collect_direct_imports(stmt, &ctx, &mut imported_by_stmt);
+                for imported in imported_by_stmt {
+                    directly_imported
+                        .entry(imported)
+                        .or_default()
+                        .push((module_name.clone(), stmt.range()));
+                }
             }
         }
 
         log::info!(
             "Found {} directly imported modules: {:?}",
             directly_imported.len(),
-            directly_imported
+            directly_imported.keys()
         );
         directly_imported
     }
@@ -4459,10 +6319,49 @@ impl HybridStaticBundler {
                     self.collect_direct_relative_imports(import_from, ctx, directly_imported);
                 }
             }
+            Stmt::ClassDef(class_def) => {
+                // Imports in a class body go through the import system when the class is
+                // created, so the modules they name must exist in sys.modules
+                for class_stmt in &class_def.body {
+                    self.collect_class_body_import(class_stmt, ctx, directly_imported);
+                    self.collect_direct_imports(class_stmt, ctx, directly_imported);
+                }
+            }
             _ => {}
         }
     }
 
+    /// Mark the module a class-level `from ... import` reads from as directly imported
+    fn collect_class_body_import(
+        &self,
+        stmt: &Stmt,
+        ctx: &DirectImportContext<'_>,
+        directly_imported: &mut FxIndexSet<String>,
+    ) {
+        let Stmt::ImportFrom(import_from) = stmt else {
+            return;
+        };
+        let Some(resolved_module) = self.resolve_relative_import_with_context(
+            import_from,
+            ctx.current_module,
+            Some(ctx.module_path),
+        ) else {
+            return;
+        };
+        if ctx
+            .modules
+            .iter()
+            .any(|(name, _, _, _)| name == &resolved_module)
+        {
+            log::debug!(
+                "Found class-level import from '{}' in '{}'",
+                resolved_module,
+                ctx.current_module
+            );
+            directly_imported.insert(resolved_module);
+        }
+    }
+
     /// Collect direct imports from relative import statements
     fn collect_direct_relative_imports(
         &self,
@@ -4643,9 +6542,7 @@ impl HybridStaticBundler {
                 global_symbols.insert(class_def.name.to_string());
             }
             Stmt::Assign(assign) => {
-                if let Some(name) = self.extract_simple_assign_target(assign) {
-                    global_symbols.insert(name);
-                }
+                global_symbols.extend(Self::assign_target_names(assign));
             }
             Stmt::AnnAssign(ann_assign) => {
                 if let Expr::Name(name) = ann_assign.target.as_ref() {
@@ -4675,7 +6572,8 @@ impl HybridStaticBundler {
         }
 
         // Fallback with module prefix
-        format!("__cribo_renamed_{}", base_name)
+        self.runtime_names
+            .prefixed(&format!("renamed_{}", base_name))
     }
 
     /// Get a unique name for a symbol, using the same pattern as generate_unique_name
@@ -4780,6 +6678,15 @@ impl HybridStaticBundler {
                 Stmt::AnnAssign(ann_assign) => {
                     self.inline_ann_assignment(ann_assign, module_name, &mut module_renames, ctx);
                 }
+                Stmt::AugAssign(aug_assign) => {
+                    self.inline_aug_assignment(aug_assign, module_name, &module_renames, ctx);
+                }
+                Stmt::With(with_stmt) => {
+                    self.inline_with_statement(with_stmt, module_name, &mut module_renames, ctx);
+                }
+                Stmt::Try(try_stmt) => {
+                    self.inline_import_fallback(try_stmt, module_name, &mut module_renames, ctx);
+                }
                 // TypeAlias statements are safe metadata definitions
                 Stmt::TypeAlias(_) => {
                     // Type aliases don't need renaming in Python, they're just metadata
@@ -4877,51 +6784,52 @@ impl HybridStaticBundler {
                     module_renames.insert(class_name.clone(), renamed);
                 }
                 Stmt::Assign(assign) => {
-                    // Handle module-level assignments
-                    if assign.targets.len() != 1 {
-                        continue;
-                    }
-
-                    let Expr::Name(name_expr) = &assign.targets[0] else {
-                        continue;
+                    // Handle module-level assignments, including tuple and starred unpacking
+                    let is_self_ref = match (assign.targets.as_slice(), assign.value.as_ref()) {
+                        ([Expr::Name(target)], Expr::Name(value)) => target.id == value.id,
+                        _ => false,
                     };
 
-                    let var_name = name_expr.id.to_string();
-                    if !self.should_inline_symbol(&var_name, module_name, ctx.module_exports_map) {
-                        continue;
-                    }
+                    for var_name in Self::assign_target_names(assign) {
+                        if !self.should_inline_symbol(
+                            &var_name,
+                            module_name,
+                            ctx.module_exports_map,
+                        ) {
+                            continue;
+                        }
+
+                        // Check for self-referential assignment and existing rename
+                        if is_self_ref && module_renames.contains_key(&var_name) {
+                            let existing_renamed = module_renames.get(&var_name).expect(
+                                "module_renames should contain var_name after contains_key check",
+                            );
+                            log::debug!(
+                                "Handling self-referential assignment '{}' in namespace module '{}' -> '{}' = '{}'",
+                                var_name,
+                                module_name,
+                                existing_renamed,
+                                existing_renamed
+                            );
+                            // Skip this - it's redundant after renaming
+                            continue;
+                        }
+
+                        // Generate module-qualified name
+                        let module_suffix = module_name.cow_replace('.', "_").into_owned();
+                        let base_name = format!("{}_{}", var_name, module_suffix);
+                        let renamed = self.get_unique_name(&base_name, ctx.global_symbols);
 
-                    // Check for self-referential assignment and existing rename
-                    let is_self_ref = matches!(&*assign.value, Expr::Name(value_name) if value_name.id.as_str() == var_name);
-                    if is_self_ref && module_renames.contains_key(&var_name) {
-                        let existing_renamed = module_renames.get(&var_name).expect(
-                            "module_renames should contain var_name after contains_key check",
-                        );
                         log::debug!(
-                            "Handling self-referential assignment '{}' in namespace module '{}' -> '{}' = '{}'",
+                            "Collecting rename for variable '{}' from namespace module '{}' as '{}'",
                             var_name,
                             module_name,
-                            existing_renamed,
-                            existing_renamed
+                            renamed
                         );
-                        // Skip this - it's redundant after renaming
-                        continue;
-                    }
-
-                    // Generate module-qualified name
-                    let module_suffix = module_name.cow_replace('.', "_").into_owned();
-                    let base_name = format!("{}_{}", var_name, module_suffix);
-                    let renamed = self.get_unique_name(&base_name, ctx.global_symbols);
-
-                    log::debug!(
-                        "Collecting rename for variable '{}' from namespace module '{}' as '{}'",
-                        var_name,
-                        module_name,
-                        renamed
-                    );
 
-                    ctx.global_symbols.insert(renamed.clone());
-                    module_renames.insert(var_name.clone(), renamed);
+                        ctx.global_symbols.insert(renamed.clone());
+                        module_renames.insert(var_name, renamed);
+                    }
                 }
                 _ => {}
             }
@@ -4974,50 +6882,46 @@ impl HybridStaticBundler {
                         // Transform the class body to use renamed symbols and resolve imports
                         for body_stmt in &mut renamed_class.body {
                             self.resolve_import_aliases_in_stmt(body_stmt, &ctx.import_aliases);
-                            self.transform_stmt_for_renames(body_stmt, &module_renames);
                         }
+                        self.rewrite_class_body_for_renames(
+                            &mut renamed_class.body,
+                            module_name,
+                            &class_name,
+                            &module_renames,
+                        );
 
                         ctx.inlined_stmts.push(Stmt::ClassDef(renamed_class));
                     }
                 }
                 Stmt::Assign(assign) => {
-                    if assign.targets.len() != 1 {
-                        continue;
-                    }
-
-                    let Expr::Name(name_expr) = &assign.targets[0] else {
-                        continue;
-                    };
-
-                    let var_name = name_expr.id.to_string();
-                    if !self.should_inline_symbol(&var_name, module_name, ctx.module_exports_map) {
-                        continue;
+                    // Skip self-referential assignments, redundant after renaming
+                    if let ([Expr::Name(target)], Expr::Name(value)) =
+                        (assign.targets.as_slice(), assign.value.as_ref())
+                    {
+                        if target.id == value.id {
+                            continue;
+                        }
                     }
 
-                    // Skip if not in renames
-                    let Some(renamed) = module_renames.get(&var_name) else {
-                        continue;
-                    };
-
-                    // Skip self-referential assignments
-                    let is_self_ref = matches!(&*assign.value, Expr::Name(value_name) if value_name.id.as_str() == var_name);
-                    if is_self_ref {
+                    // Skip unless some name it binds is inlined under a new name
+                    let names = Self::assign_target_names(assign);
+                    if !names.iter().any(|name| {
+                        module_renames.contains_key(name)
+                            && self.should_inline_symbol(name, module_name, ctx.module_exports_map)
+                    }) {
                         continue;
                     }
 
                     log::debug!(
-                        "Inlining variable '{}' from namespace module '{}' as '{}'",
-                        var_name,
-                        module_name,
-                        renamed
+                        "Inlining variables {:?} from namespace module '{}'",
+                        names,
+                        module_name
                     );
 
                     let mut renamed_assign = assign.clone();
-                    renamed_assign.targets = vec![Expr::Name(ExprName {
-                        id: renamed.clone().into(),
-                        ctx: ExprContext::Store,
-                        range: TextRange::default(),
-                    })];
+                    for target in &mut renamed_assign.targets {
+                        Self::rename_target_names(target, &module_renames);
+                    }
 
                     // Transform the value expression to use renamed symbols and resolve imports
                     let mut value = (*assign.value).clone();
@@ -5027,6 +6931,23 @@ impl HybridStaticBundler {
 
                     ctx.inlined_stmts.push(Stmt::Assign(renamed_assign));
                 }
+                Stmt::AugAssign(aug_assign) => {
+                    let Expr::Name(name) = aug_assign.target.as_ref() else {
+                        continue;
+                    };
+                    if !module_renames.contains_key(name.id.as_str()) {
+                        continue;
+                    }
+
+                    let mut renamed_aug_assign = aug_assign.clone();
+                    Self::rename_target_names(&mut renamed_aug_assign.target, &module_renames);
+                    self.resolve_import_aliases_in_expr(
+                        &mut renamed_aug_assign.value,
+                        &ctx.import_aliases,
+                    );
+                    Self::rename_references_in_expr(&mut renamed_aug_assign.value, &module_renames);
+                    ctx.inlined_stmts.push(Stmt::AugAssign(renamed_aug_assign));
+                }
                 _ => {
                     log::debug!(
                         "Skipping statement type in namespace module '{}' during second pass",
@@ -5158,49 +7079,9 @@ impl HybridStaticBundler {
                     Self::rename_references_in_expr(&mut keyword.value, module_renames);
                 }
             }
-            Expr::FString(fstring) => {
-                // Handle FString transformation
-                let fstring_range = fstring.range;
-                let mut transformed_elements = Vec::new();
-                let mut any_transformed = false;
-
-                for element in fstring.value.elements() {
-                    match element {
-                        InterpolatedStringElement::Literal(lit_elem) => {
-                            transformed_elements
-                                .push(InterpolatedStringElement::Literal(lit_elem.clone()));
-                        }
-                        InterpolatedStringElement::Interpolation(expr_elem) => {
-                            let mut new_expr = expr_elem.expression.clone();
-                            Self::rename_references_in_expr(&mut new_expr, module_renames);
-
-                            let new_element = InterpolatedElement {
-                                expression: new_expr,
-                                debug_text: expr_elem.debug_text.clone(),
-                                conversion: expr_elem.conversion,
-                                format_spec: expr_elem.format_spec.clone(),
-                                range: expr_elem.range,
-                            };
-                            transformed_elements
-                                .push(InterpolatedStringElement::Interpolation(new_element));
-                            any_transformed = true;
-                        }
-                    }
-                }
-
-                if any_transformed {
-                    let new_fstring = FString {
-                        elements: InterpolatedStringElements::from(transformed_elements),
-                        range: TextRange::default(),
-                        flags: FStringFlags::empty(),
-                    };
-
-                    let new_value = FStringValue::single(new_fstring);
-
-                    *expr = Expr::FString(ExprFString {
-                        value: new_value,
-                        range: fstring_range,
-                    });
+            Expr::FString(_) | Expr::TString(_) => {
+                for value in interpolated_expressions_mut(expr) {
+                    Self::rename_references_in_expr(value, module_renames);
                 }
             }
             Expr::BinOp(binop) => {
@@ -5310,6 +7191,9 @@ impl HybridStaticBundler {
         if self.is_hoisted_import(&stmt) {
             return;
         }
+        let Some(stmt) = self.defer_cycle_peer_imports(stmt, ctx, body) else {
+            return;
+        };
 
         let mut handled_inlined_import = false;
 
@@ -5368,6 +7252,122 @@ impl HybridStaticBundler {
         }
     }
 
+    /// Whether `imported` is a wrapper module in an import cycle with the wrapper
+    /// module `module_name`
+    fn is_cycle_peer(&self, module_name: &str, imported: &str) -> bool {
+        module_name != imported
+            && self.module_registry.contains_key(module_name)
+            && self.module_registry.contains_key(imported)
+            && self
+                .import_cycles
+                .iter()
+                .any(|cycle| cycle.contains(module_name) && cycle.contains(imported))
+    }
+
+    /// Handle the imports of cycle peers in a wrapper module, returning what remains
+    /// of `stmt` for the standard transformation
+    ///
+    /// The peer's init function may not have run yet, or may be running further up the
+    /// stack, so `sys.modules` can't be read directly. `import peer` binds a lazy module
+    /// that runs the init function on first attribute access. Other imports need the
+    /// peer's attributes right away and call its init function first, which returns
+    /// the partially initialized module while it is running, as Python would.
+    fn defer_cycle_peer_imports(
+        &self,
+        stmt: Stmt,
+        ctx: &ModuleTransformContext,
+        body: &mut Vec<Stmt>,
+    ) -> Option<Stmt> {
+        match stmt {
+            Stmt::Import(mut import_stmt) => {
+                let mut remaining = Vec::new();
+                for alias in import_stmt.names {
+                    let imported = alias.name.as_str();
+                    if !self.is_cycle_peer(ctx.module_name, imported) {
+                        remaining.push(alias);
+                        continue;
+                    }
+                    let synthetic_name = &self.module_registry[imported];
+                    // `import pkg.peer` binds `pkg`, which the standard rewrite builds
+                    if alias.asname.is_none() && imported.contains('.') {
+                        body.push(self.generate_module_init_call(synthetic_name));
+                        remaining.push(alias);
+                        continue;
+                    }
+                    let local_name = alias.asname.as_ref().unwrap_or(&alias.name).as_str();
+                    body.push(self.create_lazy_module_binding(local_name, synthetic_name));
+                    if self.should_export_symbol(local_name, ctx.module_name) {
+                        body.push(self.create_module_attr_assignment(
+                            &self.runtime_names.module_var,
+                            local_name,
+                        ));
+                    }
+                }
+                if remaining.is_empty() {
+                    return None;
+                }
+                import_stmt.names = remaining;
+                Some(Stmt::Import(import_stmt))
+            }
+            Stmt::ImportFrom(import_from) => {
+                if let Some(resolved) = self.resolve_relative_import_with_context(
+                    &import_from,
+                    ctx.module_name,
+                    Some(ctx.module_path),
+                ) {
+                    let mut peers = vec![resolved.clone()];
+                    peers.extend(
+                        import_from
+                            .names
+                            .iter()
+                            .map(|alias| format!("{}.{}", resolved, alias.name.as_str())),
+                    );
+                    for peer in peers {
+                        if self.is_cycle_peer(ctx.module_name, &peer) {
+                            body.push(self.generate_module_init_call(&self.module_registry[&peer]));
+                        }
+                    }
+                }
+                Some(Stmt::ImportFrom(import_from))
+            }
+            _ => Some(stmt),
+        }
+    }
+
+    /// `local_name = LazyModule('synthetic_name', init_function)`
+    fn create_lazy_module_binding(&self, local_name: &str, synthetic_name: &str) -> Stmt {
+        self.uses_lazy_modules.set(true);
+        let call = Expr::Call(ExprCall {
+            func: Box::new(Expr::Name(ExprName {
+                id: self.runtime_names.lazy_module_class.as_str().into(),
+                ctx: ExprContext::Load,
+                range: TextRange::default(),
+            })),
+            arguments: Arguments {
+                args: Box::from([
+                    self.create_string_literal(synthetic_name),
+                    Expr::Name(ExprName {
+                        id: self.init_functions[synthetic_name].as_str().into(),
+                        ctx: ExprContext::Load,
+                        range: TextRange::default(),
+                    }),
+                ]),
+                keywords: Box::from([]),
+                range: TextRange::default(),
+            },
+            range: TextRange::default(),
+        });
+        Stmt::Assign(StmtAssign {
+            targets: vec![Expr::Name(ExprName {
+                id: local_name.into(),
+                ctx: ExprContext::Store,
+                range: TextRange::default(),
+            })],
+            value: Box::new(call),
+            range: TextRange::default(),
+        })
+    }
+
     /// Collect global declarations from a function body
     fn collect_function_globals(&self, body: &[Stmt]) -> FxIndexSet<String> {
         let mut function_globals = FxIndexSet::default();
@@ -5453,99 +7453,6 @@ impl HybridStaticBundler {
         func_def.body = new_body;
     }
 
-    /// Transform f-string expressions for lifted globals
-    fn transform_fstring_for_lifted_globals(
-        &self,
-        expr: &mut Expr,
-        lifted_names: &FxIndexMap<String, String>,
-        global_info: &ModuleGlobalInfo,
-        in_function_with_globals: Option<&FxIndexSet<String>>,
-    ) {
-        if let Expr::FString(fstring) = expr {
-            let fstring_range = fstring.range;
-            let mut transformed_elements = Vec::new();
-            let mut any_transformed = false;
-
-            for element in fstring.value.elements() {
-                match element {
-                    InterpolatedStringElement::Literal(lit_elem) => {
-                        // Literal elements stay the same
-                        transformed_elements
-                            .push(InterpolatedStringElement::Literal(lit_elem.clone()));
-                    }
-                    InterpolatedStringElement::Interpolation(expr_elem) => {
-                        let (new_element, was_transformed) = self.transform_fstring_expression(
-                            expr_elem,
-                            lifted_names,
-                            global_info,
-                            in_function_with_globals,
-                        );
-                        transformed_elements
-                            .push(InterpolatedStringElement::Interpolation(new_element));
-                        if was_transformed {
-                            any_transformed = true;
-                        }
-                    }
-                }
-            }
-
-            // If any expressions were transformed, we need to rebuild the f-string
-            if any_transformed {
-                // Create a new FString with our transformed elements
-                let new_fstring = FString {
-                    elements: InterpolatedStringElements::from(transformed_elements),
-                    range: TextRange::default(),
-                    flags: FStringFlags::empty(),
-                };
-
-                // Create a new FStringValue containing our FString
-                let new_value = FStringValue::single(new_fstring);
-
-                // Replace the entire expression with the new f-string
-                *expr = Expr::FString(ExprFString {
-                    value: new_value,
-                    range: fstring_range,
-                });
-
-                log::debug!("Transformed f-string expressions for lifted globals");
-            }
-        }
-    }
-
-    /// Transform a single f-string expression element
-    fn transform_fstring_expression(
-        &self,
-        expr_elem: &InterpolatedElement,
-        lifted_names: &FxIndexMap<String, String>,
-        global_info: &ModuleGlobalInfo,
-        in_function_with_globals: Option<&FxIndexSet<String>>,
-    ) -> (InterpolatedElement, bool) {
-        // Clone and transform the expression
-        let mut new_expr = (*expr_elem.expression).clone();
-        let old_expr_str = format!("{:?}", new_expr);
-
-        self.transform_expr_for_lifted_globals(
-            &mut new_expr,
-            lifted_names,
-            global_info,
-            in_function_with_globals,
-        );
-
-        let new_expr_str = format!("{:?}", new_expr);
-        let was_transformed = old_expr_str != new_expr_str;
-
-        // Create a new expression element with the transformed expression
-        let new_element = InterpolatedElement {
-            expression: Box::new(new_expr),
-            debug_text: expr_elem.debug_text.clone(),
-            conversion: expr_elem.conversion,
-            format_spec: expr_elem.format_spec.clone(),
-            range: expr_elem.range,
-        };
-
-        (new_element, was_transformed)
-    }
-
     /// Track import aliases from a statement
     fn track_import_aliases(
         &self,
@@ -6423,8 +8330,13 @@ impl HybridStaticBundler {
         // Apply renames and resolve import aliases in class body
         for body_stmt in &mut class_def_clone.body {
             self.resolve_import_aliases_in_stmt(body_stmt, &ctx.import_aliases);
-            self.rewrite_aliases_in_stmt(body_stmt, module_renames);
         }
+        self.rewrite_class_body_for_renames(
+            &mut class_def_clone.body,
+            module_name,
+            &class_name,
+            module_renames,
+        );
 
         ctx.inlined_stmts.push(Stmt::ClassDef(class_def_clone));
     }
@@ -6438,24 +8350,32 @@ impl HybridStaticBundler {
         module_renames: &mut FxIndexMap<String, String>,
         ctx: &mut InlineContext,
     ) {
-        let Some(name) = self.extract_simple_assign_target(assign) else {
-            return;
-        };
-        if !self.should_inline_symbol(&name, module_name, ctx.module_exports_map) {
+        // Unpacking binds several names at once, so the statement is kept whole when any
+        // of them is inlined
+        let names = Self::assign_target_names(assign);
+        if !names
+            .iter()
+            .any(|name| self.should_inline_symbol(name, module_name, ctx.module_exports_map))
+        {
             return;
         }
 
         // Clone the assignment first
         let mut assign_clone = assign.clone();
 
-        // Apply existing renames to the RHS value BEFORE creating new rename for LHS
+        // Apply existing renames to the RHS value BEFORE creating new renames for the LHS
         self.resolve_import_aliases_in_expr(&mut assign_clone.value, &ctx.import_aliases);
         self.rewrite_aliases_in_expr(&mut assign_clone.value, module_renames);
 
-        // Now create a new rename for the LHS
-        // Check if this symbol was renamed by semantic analysis
-        let renamed_name = if let Some(module_rename_map) = ctx.module_renames.get(module_name) {
-            if let Some(new_name) = module_rename_map.get(&name) {
+        // Now create a new rename for each name on the LHS
+        let mut target_renames = FxIndexMap::default();
+        for name in names {
+            // Check if this symbol was renamed by semantic analysis
+            let renamed_name = if let Some(new_name) = ctx
+                .module_renames
+                .get(module_name)
+                .and_then(|module_rename_map| module_rename_map.get(&name))
+            {
                 log::debug!(
                     "Using semantic rename for variable '{}' to '{}' in module '{}'",
                     name,
@@ -6465,22 +8385,143 @@ impl HybridStaticBundler {
                 new_name.clone()
             } else {
                 name.clone()
+            };
+
+            if renamed_name != name {
+                module_renames.insert(name.clone(), renamed_name.clone());
+                target_renames.insert(name, renamed_name.clone());
             }
-        } else {
-            name.clone()
+            ctx.global_symbols.insert(renamed_name);
+        }
+
+        // Apply the renames to the LHS
+        for target in &mut assign_clone.targets {
+            Self::rename_target_names(target, &target_renames);
+        }
+
+        ctx.inlined_stmts.push(Stmt::Assign(assign_clone));
+    }
+
+    /// Inline an augmented assignment such as `items += [...]`
+    ///
+    /// It updates a name the module bound earlier, so it takes that name's rename.
+    fn inline_aug_assignment(
+        &self,
+        aug_assign: &StmtAugAssign,
+        module_name: &str,
+        module_renames: &FxIndexMap<String, String>,
+        ctx: &mut InlineContext,
+    ) {
+        // Other targets are side effects, which keep a module from being inlined
+        let Expr::Name(name) = aug_assign.target.as_ref() else {
+            return;
         };
+        if !self.should_inline_symbol(name.id.as_str(), module_name, ctx.module_exports_map) {
+            return;
+        }
+
+        let mut stmt = Stmt::AugAssign(aug_assign.clone());
+        self.resolve_import_aliases_in_stmt(&mut stmt, &ctx.import_aliases);
+        self.rewrite_aliases_in_stmt(&mut stmt, module_renames);
+        ctx.inlined_stmts.push(stmt);
+    }
 
-        if renamed_name != name {
-            module_renames.insert(name.clone(), renamed_name.clone());
+    /// Inline a top-level `with` block allowed by `Config::inline_with_managers`
+    ///
+    /// The block runs as a whole, so it is kept even when the names it binds are private;
+    /// each of them takes its semantic rename, if any.
+    fn inline_with_statement(
+        &self,
+        with_stmt: &StmtWith,
+        module_name: &str,
+        module_renames: &mut FxIndexMap<String, String>,
+        ctx: &mut InlineContext,
+    ) {
+        for name in Self::with_bound_names(with_stmt) {
+            let renamed_name = ctx
+                .module_renames
+                .get(module_name)
+                .and_then(|module_rename_map| module_rename_map.get(&name))
+                .cloned()
+                .unwrap_or_else(|| name.clone());
+            if renamed_name != name {
+                module_renames.insert(name, renamed_name.clone());
+            }
+            ctx.global_symbols.insert(renamed_name);
         }
-        ctx.global_symbols.insert(renamed_name.clone());
 
-        // Apply the rename to the LHS
-        if let Expr::Name(name_expr) = &mut assign_clone.targets[0] {
-            name_expr.id = renamed_name.into();
+        let mut with_clone = with_stmt.clone();
+        for item in &mut with_clone.items {
+            self.resolve_import_aliases_in_expr(&mut item.context_expr, &ctx.import_aliases);
+            if let Some(optional_vars) = &mut item.optional_vars {
+                Self::rename_target_names(optional_vars, module_renames);
+            }
+        }
+        for body_stmt in &mut with_clone.body {
+            self.resolve_import_aliases_in_stmt(body_stmt, &ctx.import_aliases);
         }
+        let mut stmt = Stmt::With(with_clone);
+        self.rewrite_aliases_in_stmt(&mut stmt, module_renames);
+        ctx.inlined_stmts.push(stmt);
+    }
 
-        ctx.inlined_stmts.push(Stmt::Assign(assign_clone));
+    /// Inline a top-level import fallback `try` block, keeping its structure so the
+    /// fallback import still runs wherever the preferred module is missing
+    fn inline_import_fallback(
+        &self,
+        try_stmt: &StmtTry,
+        module_name: &str,
+        module_renames: &mut FxIndexMap<String, String>,
+        ctx: &mut InlineContext,
+    ) {
+        for name in Self::import_fallback_bound_names(try_stmt) {
+            let renamed_name = ctx
+                .module_renames
+                .get(module_name)
+                .and_then(|module_rename_map| module_rename_map.get(&name))
+                .cloned()
+                .unwrap_or_else(|| name.clone());
+            if renamed_name != name {
+                module_renames.insert(name, renamed_name.clone());
+            }
+            ctx.global_symbols.insert(renamed_name);
+        }
+
+        let mut try_clone = try_stmt.clone();
+        let handler_bodies = try_clone.handlers.iter_mut().map(|handler| {
+            let ExceptHandler::ExceptHandler(handler) = handler;
+            &mut handler.body
+        });
+        for body in std::iter::once(&mut try_clone.body)
+            .chain(handler_bodies)
+            .chain(std::iter::once(&mut try_clone.orelse))
+        {
+            for body_stmt in body {
+                Self::rename_imported_names(body_stmt, module_renames);
+            }
+        }
+        let mut stmt = Stmt::Try(try_clone);
+        self.rewrite_aliases_in_stmt(&mut stmt, module_renames);
+        ctx.inlined_stmts.push(stmt);
+    }
+
+    /// Bind the names an import statement creates under their new names
+    fn rename_imported_names(stmt: &mut Stmt, renames: &FxIndexMap<String, String>) {
+        let aliases = match stmt {
+            Stmt::Import(import_stmt) => &mut import_stmt.names,
+            Stmt::ImportFrom(import_from) => &mut import_from.names,
+            _ => return,
+        };
+        for alias in aliases {
+            // `import a.b` binds `a` and can't take an alias without changing that
+            if alias.asname.is_none() && alias.name.as_str().contains('.') {
+                continue;
+            }
+            let local_name = alias.asname.as_ref().unwrap_or(&alias.name);
+            if let Some(renamed) = renames.get(local_name.as_str()) {
+                alias.asname = Some(Identifier::new(renamed, TextRange::default()));
+            }
+        }
     }
 
     /// Inline an annotated assignment statement
@@ -6522,7 +8563,7 @@ impl HybridStaticBundler {
     }
 
     /// Log unused imports details if debug logging is enabled
-    fn log_unused_imports_details(unused_imports: &[crate::cribo_graph::UnusedImportInfo]) {
+    fn log_unused_imports_details(unused_imports: &[UnusedImportInfo]) {
         if log::log_enabled!(log::Level::Debug) {
             for unused in unused_imports {
                 log::debug!("  - {} from {}", unused.name, unused.module);
@@ -6630,6 +8671,9 @@ impl HybridStaticBundler {
             Stmt::Assign(assign) => {
                 self.resolve_import_aliases_in_expr(&mut assign.value, import_aliases);
             }
+            Stmt::AugAssign(aug_assign) => {
+                self.resolve_import_aliases_in_expr(&mut aug_assign.value, import_aliases);
+            }
             Stmt::AnnAssign(ann_assign) => {
                 if let Some(ref mut value) = ann_assign.value {
                     self.resolve_import_aliases_in_expr(value, import_aliases);
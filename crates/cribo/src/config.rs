@@ -1,46 +1,288 @@
-use anyhow::{Context, Result, anyhow};
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::path::{Path, PathBuf};
 
 use crate::combine::Combine;
+use crate::data_files::validate_targets as validate_data_file_targets;
+use crate::defines::{parse_definition, validate_names as validate_define_names};
 use crate::dirs::{system_config_file, user_cribo_config_dir};
+use crate::error::{CriboError, CriboResult};
+use crate::module_filter::ModuleFilter;
+use crate::resolver::ModuleResolver;
+
+/// Prefix of the names the bundle's runtime support code defines, unless configured
+pub const DEFAULT_RUNTIME_PREFIX: &str = "__cribo_";
+
+/// Interpreter line at the top of the bundle, unless configured
+pub const DEFAULT_SHEBANG: &str = "#!/usr/bin/env python3";
+
+/// Project file whose `[tool.cribo]` table holds project settings
+const PYPROJECT_FILE: &str = "pyproject.toml";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
+    /// Entry point used when none is given on the command line: a script path such as
+    /// `src/app/main.py`, a package directory such as `src/app/` (its `__main__.py`), a
+    /// dotted module name such as `app.main` resolved like `python -m`, or a directory of
+    /// scripts such as `tools/` to bundle behind a command dispatcher
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+
+    /// Output file used when neither `--output` nor `--stdout` is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+
     /// Source directories to scan for first-party modules
     pub src: Vec<PathBuf>,
 
-    /// Known first-party module names
+    /// Glob patterns of the files under the source directories that are first-party;
+    /// when empty, every Python file is. Patterns are relative to the source directory,
+    /// and one without a `/` matches a file or directory name at any depth
+    pub include: Vec<String>,
+
+    /// Glob patterns, like `include`, of files under the source directories that are
+    /// never bundled (e.g. `tests`, `conftest.py` or generated code). Imports of them
+    /// are left as written, even when bundled modules import them
+    pub exclude: Vec<String>,
+
+    /// Known first-party module names; also accepted as `known-first-party`, the
+    /// spelling of isort's and Ruff's setting
+    #[serde(alias = "known-first-party")]
     pub known_first_party: IndexSet<String>,
 
-    /// Known third-party module names
+    /// Known third-party module names; also accepted as `known-third-party`
+    #[serde(alias = "known-third-party")]
     pub known_third_party: IndexSet<String>,
 
+    /// First-party modules, by exact dotted name, inlined even when a top-level
+    /// statement looks like it has side effects. Modules that can't work inlined (e.g.
+    /// imported with `import module` or part of an import-time cycle) stay wrappers
+    pub force_inline: IndexSet<String>,
+
+    /// First-party modules, by exact dotted name, always bundled as wrapper modules
+    /// whose code runs in an init function on first import
+    pub force_wrapper: IndexSet<String>,
+
+    /// Modules or packages, with their submodules, that are never bundled even though
+    /// they are first-party; imports of them are left as written
+    pub keep_external: IndexSet<String>,
+
     /// Whether to preserve comments in output
     pub preserve_comments: bool,
 
     /// Whether to preserve type hints in output
     pub preserve_type_hints: bool,
 
+    /// Whether module-level `if TYPE_CHECKING:` blocks of plain imports become runtime
+    /// imports in modules that evaluate their annotations, instead of being dropped
+    pub runtime_type_checking_imports: bool,
+
     /// Target Python version for standard library and builtin checks
-    /// Supports Ruff-style string values: "py38", "py39", "py310", "py311", "py312", "py313"
+    /// Supports Ruff-style string values: "py38", "py39", "py310", "py311", "py312", "py313", "py314"
     /// Defaults to "py310" (Python 3.10)
     #[serde(rename = "target-version")]
     pub target_version: String,
+
+    /// Emit a bundle that also works when imported as a module: bare top-level calls
+    /// in the entry script only run under `__name__ == "__main__"`, and the entry's
+    /// public names are exported through `__all__`
+    pub dual_mode: bool,
+
+    /// Emit a `# ── module: name (path) ──` comment before each module's code
+    pub section_markers: bool,
+
+    /// Whether directories from the PYTHONPATH environment variable are searched for
+    /// first-party modules, after the `src` directories
+    pub respect_pythonpath: bool,
+
+    /// More directories searched for first-party modules, after PYTHONPATH, for modules
+    /// the application imports from locations the runtime adds to `sys.path` (e.g. a
+    /// `lib/` directory a launcher script inserts)
+    pub extra_paths: Vec<PathBuf>,
+
+    /// Also register inlined modules in `sys.modules`, as lightweight module objects
+    /// holding their public names, so runtime lookups such as a late `import pkg.mod`
+    /// or `sys.modules["pkg.mod"]` succeed
+    pub register_inlined_modules: bool,
+
+    /// Write a JSON map of where each module's code lies in the bundle next to the
+    /// output, as `<output>.map.json`
+    pub module_map: bool,
+
+    /// Also write the bundle as an executable zip application, `<output stem>.pyz`
+    pub emit_pyz: bool,
+
+    /// Write the bundle as a self-extracting script: the code compressed with zlib and
+    /// base64-encoded behind a stub that decompresses and runs it
+    pub self_extracting: bool,
+
+    /// Make the written bundle executable on Unix, so it runs directly through its shebang
+    /// line
+    pub executable: bool,
+
+    /// Interpreter line at the top of the bundle, with or without the leading `#!`
+    pub shebang: String,
+
+    /// Write a JSON report of the bundled modules, requirements and resolved cycles
+    /// next to the output, as `<output>.report.json`
+    pub emit_report: bool,
+
+    /// Keep the previous bundle as `<output>.bak` when overwriting it
+    pub backup_output: bool,
+
+    /// Start the bundle with a check that the third-party packages it imports are
+    /// installed, exiting with a list of what to `pip install` otherwise
+    pub preflight_check: bool,
+
+    /// Only assign the attributes of a wrapper module that other bundled modules access,
+    /// instead of every public name. Modules used as objects, star-imported or looked up
+    /// by name keep all their exports; names looked up at runtime from outside the bundle
+    /// (e.g. by `pickle` or `getattr` with a computed name) may go missing
+    pub prune_wrapper_exports: bool,
+
+    /// Drop the functions, classes and constants of inlined modules that nothing in the
+    /// bundle refers to. Definitions whose creation could have side effects (decorated
+    /// functions, classes with metaclasses, assignments of calls) and wrapper modules,
+    /// whose module objects are visible at runtime, are kept whole
+    pub tree_shake: bool,
+
+    /// Resolve `sys.version_info` comparisons that hold or fail on every Python version from
+    /// `target-version` on, dropping the branches they rule out (and the imports in them)
+    /// before the dependency graph is built
+    pub fold_version_checks: bool,
+
+    /// Remove `assert` statements from the bundled modules, as `python -O` skips them, for
+    /// production builds
+    pub strip_asserts: bool,
+
+    /// Remove module, class and function docstrings from the bundled modules
+    pub strip_docstrings: bool,
+
+    /// With `strip_docstrings`, keep the docstrings of the entry module, e.g. for a script
+    /// using its `__doc__` as `--help` text
+    pub keep_entry_docstrings: bool,
+
+    /// Remove parameter and return annotations and turn annotated assignments into plain
+    /// ones, except in class bodies where libraries such as `dataclasses` read them; also
+    /// applies when `preserve_type_hints` is off
+    pub strip_annotations: bool,
+
+    /// Prefix of the names the bundle's runtime support code defines, such as the module
+    /// registries, init functions and lifted globals; must start an identifier
+    pub runtime_prefix: String,
+
+    /// Context managers, by dotted name as written in the source (e.g. `open` or
+    /// `contextlib.suppress`), whose top-level `with` blocks do not force a module into
+    /// wrapper mode. A block qualifies when every manager is called with side-effect-free
+    /// arguments and its body only binds names
+    pub inline_with_managers: Vec<String>,
+
+    /// External packages standing in for the parent package of first-party code whose
+    /// relative imports climb above every `src` root, keyed by the module or package
+    /// containing them (e.g. vendored code expecting to live inside `upstream`). Such an
+    /// import `from ..x import y` becomes `from upstream.x import y` and is left to
+    /// the runtime environment
+    pub relative_import_fallbacks: IndexMap<String, String>,
+
+    /// Distributions listed in requirements.txt for third-party packages whose
+    /// distribution is named differently than the package they import, keyed by the
+    /// top-level import name (e.g. `cv2 = "opencv-python-headless"`). Entries override
+    /// the built-in table of well-known names such as `yaml` → `PyYAML`
+    pub distribution_names: IndexMap<String, String>,
+
+    /// JSON, TOML or YAML files parsed at bundle time and embedded as Python literals,
+    /// keyed by the `module.NAME` they are assigned to (e.g. `"settings.DEFAULTS"`).
+    /// The assignment is placed at the top of the module, so its code can use `NAME`
+    /// instead of reading the file at runtime
+    pub data_files: IndexMap<String, PathBuf>,
+
+    /// Values of module-level names fixed at bundle time (e.g. `__BUNDLE_VERSION__ =
+    /// "1.4.2"` or `FEATURE_X = false`). Reads of these names become literals, and
+    /// `if` branches they rule out are dropped along with the imports inside them
+    pub defines: IndexMap<String, toml::Value>,
+
+    /// Replacement source files bundled in place of a module, keyed by its exact dotted
+    /// name (e.g. a thin stub of a heavy SDK for size-constrained builds). Submodules of
+    /// a stubbed module are not affected. Also accepted as `[replace]`
+    #[serde(alias = "replace")]
+    pub stub_modules: IndexMap<String, PathBuf>,
+
+    /// Type checker command (e.g. `mypy --strict` or `pyright`) run on the bundle after
+    /// it is generated, with the bundle's path appended. Its findings are reported as
+    /// warnings naming the module whose code they are about
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_checker: Option<String>,
+
+    /// Named bundles of a workspace, built together with `--all` or `--only`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bundles: Vec<BundleTarget>,
+}
+
+/// One bundle of a workspace, declared as a `[[bundles]]` entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleTarget {
+    /// Name used to select the bundle with `--only`
+    pub name: String,
+
+    /// Entry script path or dotted module name, as for the top-level `entry`
+    pub entry: String,
+
+    /// File the bundle is written to
+    pub output: PathBuf,
+
+    /// Any other settings, overriding the top-level ones for this bundle only
+    #[serde(flatten)]
+    pub overrides: toml::Table,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            entry: None,
+            output: None,
             src: vec![PathBuf::from("src"), PathBuf::from(".")],
+            include: Vec::new(),
+            exclude: Vec::new(),
             known_first_party: IndexSet::new(),
             known_third_party: IndexSet::new(),
+            force_inline: IndexSet::new(),
+            force_wrapper: IndexSet::new(),
+            keep_external: IndexSet::new(),
             preserve_comments: true,
             preserve_type_hints: true,
+            runtime_type_checking_imports: false,
             target_version: "py310".to_owned(),
+            dual_mode: false,
+            section_markers: false,
+            respect_pythonpath: true,
+            extra_paths: Vec::new(),
+            register_inlined_modules: false,
+            module_map: false,
+            emit_pyz: false,
+            self_extracting: false,
+            executable: false,
+            shebang: DEFAULT_SHEBANG.to_owned(),
+            emit_report: false,
+            backup_output: false,
+            preflight_check: false,
+            prune_wrapper_exports: false,
+            tree_shake: false,
+            fold_version_checks: false,
+            strip_asserts: false,
+            strip_docstrings: false,
+            keep_entry_docstrings: false,
+            strip_annotations: false,
+            runtime_prefix: DEFAULT_RUNTIME_PREFIX.to_owned(),
+            inline_with_managers: Vec::new(),
+            relative_import_fallbacks: IndexMap::new(),
+            distribution_names: IndexMap::new(),
+            data_files: IndexMap::new(),
+            defines: IndexMap::new(),
+            stub_modules: IndexMap::new(),
+            type_checker: None,
+            bundles: Vec::new(),
         }
     }
 }
@@ -48,6 +290,8 @@ impl Default for Config {
 impl Combine for Config {
     fn combine(self, other: Self) -> Self {
         Self {
+            entry: self.entry.or(other.entry),
+            output: self.output.or(other.output),
             // For collections, higher precedence (self) completely replaces lower precedence (other)
             // if self has non-default values, otherwise use other
             src: if self.src != Config::default().src {
@@ -55,6 +299,16 @@ impl Combine for Config {
             } else {
                 other.src
             },
+            include: if !self.include.is_empty() {
+                self.include
+            } else {
+                other.include
+            },
+            exclude: if !self.exclude.is_empty() {
+                self.exclude
+            } else {
+                other.exclude
+            },
             known_first_party: if !self.known_first_party.is_empty() {
                 self.known_first_party
             } else {
@@ -65,10 +319,87 @@ impl Combine for Config {
             } else {
                 other.known_third_party
             },
+            force_inline: if !self.force_inline.is_empty() {
+                self.force_inline
+            } else {
+                other.force_inline
+            },
+            force_wrapper: if !self.force_wrapper.is_empty() {
+                self.force_wrapper
+            } else {
+                other.force_wrapper
+            },
+            keep_external: if !self.keep_external.is_empty() {
+                self.keep_external
+            } else {
+                other.keep_external
+            },
             // For scalars, self always takes precedence
             preserve_comments: self.preserve_comments,
             preserve_type_hints: self.preserve_type_hints,
+            runtime_type_checking_imports: self.runtime_type_checking_imports,
             target_version: self.target_version,
+            dual_mode: self.dual_mode,
+            section_markers: self.section_markers,
+            respect_pythonpath: self.respect_pythonpath,
+            extra_paths: if !self.extra_paths.is_empty() {
+                self.extra_paths
+            } else {
+                other.extra_paths
+            },
+            register_inlined_modules: self.register_inlined_modules,
+            module_map: self.module_map,
+            emit_pyz: self.emit_pyz,
+            self_extracting: self.self_extracting,
+            executable: self.executable,
+            shebang: self.shebang,
+            emit_report: self.emit_report,
+            backup_output: self.backup_output,
+            preflight_check: self.preflight_check,
+            prune_wrapper_exports: self.prune_wrapper_exports,
+            tree_shake: self.tree_shake,
+            fold_version_checks: self.fold_version_checks,
+            strip_asserts: self.strip_asserts,
+            strip_docstrings: self.strip_docstrings,
+            keep_entry_docstrings: self.keep_entry_docstrings,
+            strip_annotations: self.strip_annotations,
+            runtime_prefix: self.runtime_prefix,
+            inline_with_managers: if !self.inline_with_managers.is_empty() {
+                self.inline_with_managers
+            } else {
+                other.inline_with_managers
+            },
+            relative_import_fallbacks: if !self.relative_import_fallbacks.is_empty() {
+                self.relative_import_fallbacks
+            } else {
+                other.relative_import_fallbacks
+            },
+            distribution_names: if !self.distribution_names.is_empty() {
+                self.distribution_names
+            } else {
+                other.distribution_names
+            },
+            data_files: if !self.data_files.is_empty() {
+                self.data_files
+            } else {
+                other.data_files
+            },
+            defines: if !self.defines.is_empty() {
+                self.defines
+            } else {
+                other.defines
+            },
+            stub_modules: if !self.stub_modules.is_empty() {
+                self.stub_modules
+            } else {
+                other.stub_modules
+            },
+            type_checker: self.type_checker.or(other.type_checker),
+            bundles: if !self.bundles.is_empty() {
+                self.bundles
+            } else {
+                other.bundles
+            },
         }
     }
 }
@@ -76,12 +407,48 @@ impl Combine for Config {
 /// Configuration values from environment variables with CRIBO_ prefix
 #[derive(Debug, Clone, Default)]
 pub struct EnvConfig {
+    pub entry: Option<String>,
+    pub output: Option<PathBuf>,
     pub src: Option<Vec<PathBuf>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
     pub known_first_party: Option<IndexSet<String>>,
     pub known_third_party: Option<IndexSet<String>>,
+    pub force_inline: Option<IndexSet<String>>,
+    pub force_wrapper: Option<IndexSet<String>>,
+    pub keep_external: Option<IndexSet<String>>,
     pub preserve_comments: Option<bool>,
     pub preserve_type_hints: Option<bool>,
+    pub runtime_type_checking_imports: Option<bool>,
     pub target_version: Option<String>,
+    pub dual_mode: Option<bool>,
+    pub section_markers: Option<bool>,
+    pub respect_pythonpath: Option<bool>,
+    pub extra_paths: Option<Vec<PathBuf>>,
+    pub register_inlined_modules: Option<bool>,
+    pub module_map: Option<bool>,
+    pub emit_pyz: Option<bool>,
+    pub self_extracting: Option<bool>,
+    pub executable: Option<bool>,
+    pub shebang: Option<String>,
+    pub emit_report: Option<bool>,
+    pub backup_output: Option<bool>,
+    pub preflight_check: Option<bool>,
+    pub prune_wrapper_exports: Option<bool>,
+    pub tree_shake: Option<bool>,
+    pub fold_version_checks: Option<bool>,
+    pub strip_asserts: Option<bool>,
+    pub strip_docstrings: Option<bool>,
+    pub keep_entry_docstrings: Option<bool>,
+    pub strip_annotations: Option<bool>,
+    pub runtime_prefix: Option<String>,
+    pub inline_with_managers: Option<Vec<String>>,
+    pub relative_import_fallbacks: Option<IndexMap<String, String>>,
+    pub distribution_names: Option<IndexMap<String, String>>,
+    pub data_files: Option<IndexMap<String, PathBuf>>,
+    pub defines: Option<IndexMap<String, toml::Value>>,
+    pub stub_modules: Option<IndexMap<String, PathBuf>>,
+    pub type_checker: Option<String>,
 }
 
 impl EnvConfig {
@@ -89,6 +456,20 @@ impl EnvConfig {
     pub fn from_env() -> Self {
         let mut config = Self::default();
 
+        // CRIBO_ENTRY - entry script path or dotted module name
+        if let Ok(entry) = env::var("CRIBO_ENTRY") {
+            if !entry.trim().is_empty() {
+                config.entry = Some(entry.trim().to_owned());
+            }
+        }
+
+        // CRIBO_OUTPUT - output file path
+        if let Ok(output) = env::var("CRIBO_OUTPUT") {
+            if !output.trim().is_empty() {
+                config.output = Some(PathBuf::from(output.trim()));
+            }
+        }
+
         // CRIBO_SRC - comma-separated list of source directories
         if let Ok(src_str) = env::var("CRIBO_SRC") {
             let paths: Vec<PathBuf> = src_str
@@ -102,6 +483,24 @@ impl EnvConfig {
             }
         }
 
+        // CRIBO_INCLUDE / CRIBO_EXCLUDE - comma-separated lists of glob patterns
+        for (var, patterns) in [
+            ("CRIBO_INCLUDE", &mut config.include),
+            ("CRIBO_EXCLUDE", &mut config.exclude),
+        ] {
+            if let Ok(patterns_str) = env::var(var) {
+                let parsed: Vec<String> = patterns_str
+                    .split(',')
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_owned())
+                    .collect();
+                if !parsed.is_empty() {
+                    *patterns = Some(parsed);
+                }
+            }
+        }
+
         // CRIBO_KNOWN_FIRST_PARTY - comma-separated list of first-party modules
         if let Ok(first_party_str) = env::var("CRIBO_KNOWN_FIRST_PARTY") {
             let modules: IndexSet<String> = first_party_str
@@ -128,6 +527,26 @@ impl EnvConfig {
             }
         }
 
+        // CRIBO_FORCE_INLINE / CRIBO_FORCE_WRAPPER / CRIBO_KEEP_EXTERNAL - comma-separated
+        // lists of module names
+        for (var, modules) in [
+            ("CRIBO_FORCE_INLINE", &mut config.force_inline),
+            ("CRIBO_FORCE_WRAPPER", &mut config.force_wrapper),
+            ("CRIBO_KEEP_EXTERNAL", &mut config.keep_external),
+        ] {
+            if let Ok(modules_str) = env::var(var) {
+                let parsed: IndexSet<String> = modules_str
+                    .split(',')
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_owned())
+                    .collect();
+                if !parsed.is_empty() {
+                    *modules = Some(parsed);
+                }
+            }
+        }
+
         // CRIBO_PRESERVE_COMMENTS - boolean flag
         if let Ok(preserve_comments_str) = env::var("CRIBO_PRESERVE_COMMENTS") {
             config.preserve_comments = parse_bool(&preserve_comments_str);
@@ -138,34 +557,349 @@ impl EnvConfig {
             config.preserve_type_hints = parse_bool(&preserve_type_hints_str);
         }
 
+        // CRIBO_RUNTIME_TYPE_CHECKING_IMPORTS - boolean flag
+        if let Ok(runtime_type_checking_imports_str) =
+            env::var("CRIBO_RUNTIME_TYPE_CHECKING_IMPORTS")
+        {
+            config.runtime_type_checking_imports = parse_bool(&runtime_type_checking_imports_str);
+        }
+
         // CRIBO_TARGET_VERSION - target Python version
         if let Ok(target_version) = env::var("CRIBO_TARGET_VERSION") {
             config.target_version = Some(target_version);
         }
 
+        // CRIBO_DUAL_MODE - boolean flag
+        if let Ok(dual_mode_str) = env::var("CRIBO_DUAL_MODE") {
+            config.dual_mode = parse_bool(&dual_mode_str);
+        }
+
+        // CRIBO_SECTION_MARKERS - boolean flag
+        if let Ok(section_markers_str) = env::var("CRIBO_SECTION_MARKERS") {
+            config.section_markers = parse_bool(&section_markers_str);
+        }
+
+        // CRIBO_RESPECT_PYTHONPATH - boolean flag
+        if let Ok(respect_pythonpath_str) = env::var("CRIBO_RESPECT_PYTHONPATH") {
+            config.respect_pythonpath = parse_bool(&respect_pythonpath_str);
+        }
+
+        // CRIBO_EXTRA_PATHS - comma-separated list of directories
+        if let Ok(extra_paths_str) = env::var("CRIBO_EXTRA_PATHS") {
+            let paths: Vec<PathBuf> = extra_paths_str
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from)
+                .collect();
+            if !paths.is_empty() {
+                config.extra_paths = Some(paths);
+            }
+        }
+
+        // CRIBO_REGISTER_INLINED_MODULES - boolean flag
+        if let Ok(register_inlined_modules_str) = env::var("CRIBO_REGISTER_INLINED_MODULES") {
+            config.register_inlined_modules = parse_bool(&register_inlined_modules_str);
+        }
+
+        // CRIBO_MODULE_MAP - boolean flag
+        if let Ok(module_map_str) = env::var("CRIBO_MODULE_MAP") {
+            config.module_map = parse_bool(&module_map_str);
+        }
+
+        // CRIBO_EMIT_PYZ - boolean flag
+        if let Ok(emit_pyz_str) = env::var("CRIBO_EMIT_PYZ") {
+            config.emit_pyz = parse_bool(&emit_pyz_str);
+        }
+
+        // CRIBO_SELF_EXTRACTING - boolean flag
+        if let Ok(self_extracting_str) = env::var("CRIBO_SELF_EXTRACTING") {
+            config.self_extracting = parse_bool(&self_extracting_str);
+        }
+
+        // CRIBO_EXECUTABLE - boolean flag
+        if let Ok(executable_str) = env::var("CRIBO_EXECUTABLE") {
+            config.executable = parse_bool(&executable_str);
+        }
+
+        // CRIBO_SHEBANG - interpreter line of the bundle
+        if let Ok(shebang) = env::var("CRIBO_SHEBANG") {
+            config.shebang = Some(shebang);
+        }
+
+        // CRIBO_EMIT_REPORT - boolean flag
+        if let Ok(emit_report_str) = env::var("CRIBO_EMIT_REPORT") {
+            config.emit_report = parse_bool(&emit_report_str);
+        }
+
+        // CRIBO_BACKUP_OUTPUT - boolean flag
+        if let Ok(backup_output_str) = env::var("CRIBO_BACKUP_OUTPUT") {
+            config.backup_output = parse_bool(&backup_output_str);
+        }
+
+        // CRIBO_PREFLIGHT_CHECK - boolean flag
+        if let Ok(preflight_check_str) = env::var("CRIBO_PREFLIGHT_CHECK") {
+            config.preflight_check = parse_bool(&preflight_check_str);
+        }
+
+        // CRIBO_PRUNE_WRAPPER_EXPORTS - boolean flag
+        if let Ok(prune_wrapper_exports_str) = env::var("CRIBO_PRUNE_WRAPPER_EXPORTS") {
+            config.prune_wrapper_exports = parse_bool(&prune_wrapper_exports_str);
+        }
+
+        // CRIBO_TREE_SHAKE - boolean flag
+        if let Ok(tree_shake_str) = env::var("CRIBO_TREE_SHAKE") {
+            config.tree_shake = parse_bool(&tree_shake_str);
+        }
+
+        // CRIBO_FOLD_VERSION_CHECKS - boolean flag
+        if let Ok(fold_version_checks_str) = env::var("CRIBO_FOLD_VERSION_CHECKS") {
+            config.fold_version_checks = parse_bool(&fold_version_checks_str);
+        }
+
+        // CRIBO_STRIP_ASSERTS - boolean flag
+        if let Ok(strip_asserts_str) = env::var("CRIBO_STRIP_ASSERTS") {
+            config.strip_asserts = parse_bool(&strip_asserts_str);
+        }
+
+        // CRIBO_STRIP_DOCSTRINGS - boolean flag
+        if let Ok(strip_docstrings_str) = env::var("CRIBO_STRIP_DOCSTRINGS") {
+            config.strip_docstrings = parse_bool(&strip_docstrings_str);
+        }
+
+        // CRIBO_KEEP_ENTRY_DOCSTRINGS - boolean flag
+        if let Ok(keep_entry_docstrings_str) = env::var("CRIBO_KEEP_ENTRY_DOCSTRINGS") {
+            config.keep_entry_docstrings = parse_bool(&keep_entry_docstrings_str);
+        }
+
+        // CRIBO_STRIP_ANNOTATIONS - boolean flag
+        if let Ok(strip_annotations_str) = env::var("CRIBO_STRIP_ANNOTATIONS") {
+            config.strip_annotations = parse_bool(&strip_annotations_str);
+        }
+
+        // CRIBO_RUNTIME_PREFIX - prefix of the bundle's runtime names
+        if let Ok(runtime_prefix) = env::var("CRIBO_RUNTIME_PREFIX") {
+            config.runtime_prefix = Some(runtime_prefix);
+        }
+
+        // CRIBO_INLINE_WITH_MANAGERS - comma-separated list of context managers
+        if let Ok(managers_str) = env::var("CRIBO_INLINE_WITH_MANAGERS") {
+            let managers: Vec<String> = managers_str
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_owned())
+                .collect();
+            if !managers.is_empty() {
+                config.inline_with_managers = Some(managers);
+            }
+        }
+
+        // CRIBO_RELATIVE_IMPORT_FALLBACKS - comma-separated `module=package` pairs
+        if let Ok(fallbacks_str) = env::var("CRIBO_RELATIVE_IMPORT_FALLBACKS") {
+            let fallbacks: IndexMap<String, String> = fallbacks_str
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(module, package)| (module.trim(), package.trim()))
+                .filter(|(module, package)| !module.is_empty() && !package.is_empty())
+                .map(|(module, package)| (module.to_owned(), package.to_owned()))
+                .collect();
+            if !fallbacks.is_empty() {
+                config.relative_import_fallbacks = Some(fallbacks);
+            }
+        }
+
+        // CRIBO_DISTRIBUTION_NAMES - comma-separated `module=distribution` pairs
+        if let Ok(distributions_str) = env::var("CRIBO_DISTRIBUTION_NAMES") {
+            let distributions: IndexMap<String, String> = distributions_str
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(module, distribution)| (module.trim(), distribution.trim()))
+                .filter(|(module, distribution)| !module.is_empty() && !distribution.is_empty())
+                .map(|(module, distribution)| (module.to_owned(), distribution.to_owned()))
+                .collect();
+            if !distributions.is_empty() {
+                config.distribution_names = Some(distributions);
+            }
+        }
+
+        // CRIBO_DATA_FILES - comma-separated `module.NAME=path` pairs
+        if let Ok(data_files_str) = env::var("CRIBO_DATA_FILES") {
+            let data_files: IndexMap<String, PathBuf> = data_files_str
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(target, path)| (target.trim(), path.trim()))
+                .filter(|(target, path)| !target.is_empty() && !path.is_empty())
+                .map(|(target, path)| (target.to_owned(), PathBuf::from(path)))
+                .collect();
+            if !data_files.is_empty() {
+                config.data_files = Some(data_files);
+            }
+        }
+
+        // CRIBO_DEFINES - comma-separated `NAME=VALUE` pairs
+        if let Ok(defines_str) = env::var("CRIBO_DEFINES") {
+            let defines: IndexMap<String, toml::Value> = defines_str
+                .split(',')
+                .filter_map(parse_definition)
+                .collect();
+            if !defines.is_empty() {
+                config.defines = Some(defines);
+            }
+        }
+
+        // CRIBO_STUB_MODULES - comma-separated `module=path` pairs
+        if let Ok(stub_modules_str) = env::var("CRIBO_STUB_MODULES") {
+            let stub_modules: IndexMap<String, PathBuf> = stub_modules_str
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(module, path)| (module.trim(), path.trim()))
+                .filter(|(module, path)| !module.is_empty() && !path.is_empty())
+                .map(|(module, path)| (module.to_owned(), PathBuf::from(path)))
+                .collect();
+            if !stub_modules.is_empty() {
+                config.stub_modules = Some(stub_modules);
+            }
+        }
+
+        // CRIBO_TYPE_CHECKER - type checker command run on the bundle
+        if let Ok(type_checker) = env::var("CRIBO_TYPE_CHECKER") {
+            if !type_checker.trim().is_empty() {
+                config.type_checker = Some(type_checker.trim().to_owned());
+            }
+        }
+
         config
     }
 
     /// Apply environment config to base config
     pub fn apply_to(self, mut config: Config) -> Config {
+        if let Some(entry) = self.entry {
+            config.entry = Some(entry);
+        }
+        if let Some(output) = self.output {
+            config.output = Some(output);
+        }
         if let Some(src) = self.src {
             config.src = src;
         }
+        if let Some(include) = self.include {
+            config.include = include;
+        }
+        if let Some(exclude) = self.exclude {
+            config.exclude = exclude;
+        }
         if let Some(known_first_party) = self.known_first_party {
             config.known_first_party = known_first_party;
         }
         if let Some(known_third_party) = self.known_third_party {
             config.known_third_party = known_third_party;
         }
+        if let Some(force_inline) = self.force_inline {
+            config.force_inline = force_inline;
+        }
+        if let Some(force_wrapper) = self.force_wrapper {
+            config.force_wrapper = force_wrapper;
+        }
+        if let Some(keep_external) = self.keep_external {
+            config.keep_external = keep_external;
+        }
         if let Some(preserve_comments) = self.preserve_comments {
             config.preserve_comments = preserve_comments;
         }
         if let Some(preserve_type_hints) = self.preserve_type_hints {
             config.preserve_type_hints = preserve_type_hints;
         }
+        if let Some(runtime_type_checking_imports) = self.runtime_type_checking_imports {
+            config.runtime_type_checking_imports = runtime_type_checking_imports;
+        }
         if let Some(target_version) = self.target_version {
             config.target_version = target_version;
         }
+        if let Some(dual_mode) = self.dual_mode {
+            config.dual_mode = dual_mode;
+        }
+        if let Some(section_markers) = self.section_markers {
+            config.section_markers = section_markers;
+        }
+        if let Some(respect_pythonpath) = self.respect_pythonpath {
+            config.respect_pythonpath = respect_pythonpath;
+        }
+        if let Some(extra_paths) = self.extra_paths {
+            config.extra_paths = extra_paths;
+        }
+        if let Some(register_inlined_modules) = self.register_inlined_modules {
+            config.register_inlined_modules = register_inlined_modules;
+        }
+        if let Some(module_map) = self.module_map {
+            config.module_map = module_map;
+        }
+        if let Some(emit_pyz) = self.emit_pyz {
+            config.emit_pyz = emit_pyz;
+        }
+        if let Some(self_extracting) = self.self_extracting {
+            config.self_extracting = self_extracting;
+        }
+        if let Some(executable) = self.executable {
+            config.executable = executable;
+        }
+        if let Some(shebang) = self.shebang {
+            config.shebang = shebang;
+        }
+        if let Some(emit_report) = self.emit_report {
+            config.emit_report = emit_report;
+        }
+        if let Some(backup_output) = self.backup_output {
+            config.backup_output = backup_output;
+        }
+        if let Some(preflight_check) = self.preflight_check {
+            config.preflight_check = preflight_check;
+        }
+        if let Some(prune_wrapper_exports) = self.prune_wrapper_exports {
+            config.prune_wrapper_exports = prune_wrapper_exports;
+        }
+        if let Some(tree_shake) = self.tree_shake {
+            config.tree_shake = tree_shake;
+        }
+        if let Some(fold_version_checks) = self.fold_version_checks {
+            config.fold_version_checks = fold_version_checks;
+        }
+        if let Some(strip_asserts) = self.strip_asserts {
+            config.strip_asserts = strip_asserts;
+        }
+        if let Some(strip_docstrings) = self.strip_docstrings {
+            config.strip_docstrings = strip_docstrings;
+        }
+        if let Some(keep_entry_docstrings) = self.keep_entry_docstrings {
+            config.keep_entry_docstrings = keep_entry_docstrings;
+        }
+        if let Some(strip_annotations) = self.strip_annotations {
+            config.strip_annotations = strip_annotations;
+        }
+        if let Some(runtime_prefix) = self.runtime_prefix {
+            config.runtime_prefix = runtime_prefix;
+        }
+        if let Some(inline_with_managers) = self.inline_with_managers {
+            config.inline_with_managers = inline_with_managers;
+        }
+        if let Some(relative_import_fallbacks) = self.relative_import_fallbacks {
+            config.relative_import_fallbacks = relative_import_fallbacks;
+        }
+        if let Some(distribution_names) = self.distribution_names {
+            config.distribution_names = distribution_names;
+        }
+        if let Some(data_files) = self.data_files {
+            config.data_files = data_files;
+        }
+        if let Some(defines) = self.defines {
+            config.defines = defines;
+        }
+        if let Some(stub_modules) = self.stub_modules {
+            config.stub_modules = stub_modules;
+        }
+        if let Some(type_checker) = self.type_checker {
+            config.type_checker = Some(type_checker);
+        }
         config
     }
 }
@@ -180,10 +914,32 @@ fn parse_bool(value: &str) -> Option<bool> {
     }
 }
 
+/// Whether an entry names a module (`pkg.cli`) rather than a path
+pub fn is_module_entry(entry: &str) -> bool {
+    let as_path = Path::new(entry);
+    !(entry.ends_with(".py")
+        || entry.ends_with('/')
+        || as_path.components().count() > 1
+        || as_path.exists())
+}
+
+/// The script a package directory runs as, or `path` itself when it is not a package
+fn package_entry(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        for script in ["__main__.py", "__init__.py"] {
+            let script = path.join(script);
+            if script.is_file() {
+                return script;
+            }
+        }
+    }
+    path.to_path_buf()
+}
+
 impl Config {
     /// Parse a Ruff-style target version string to u8 version number
-    /// Supports: "py38" -> 8, "py39" -> 9, "py310" -> 10, "py311" -> 11, "py312" -> 12, "py313" -> 13
-    pub fn parse_target_version(version_str: &str) -> Result<u8> {
+    /// Supports: "py38" -> 8, "py39" -> 9, "py310" -> 10, "py311" -> 11, "py312" -> 12, "py313" -> 13, "py314" -> 14
+    pub fn parse_target_version(version_str: &str) -> CriboResult<u8> {
         match version_str {
             "py38" => Ok(8),
             "py39" => Ok(9),
@@ -191,84 +947,400 @@ impl Config {
             "py311" => Ok(11),
             "py312" => Ok(12),
             "py313" => Ok(13),
-            _ => Err(anyhow!(
-                "Invalid target version '{}'. Supported versions: py38, py39, py310, py311, py312, py313",
-                version_str
-            )),
+            "py314" => Ok(14),
+            _ => Err(CriboError::Config {
+                path: None,
+                message: format!(
+                    "Invalid target version '{}'. Supported versions: py38, py39, py310, py311, py312, py313, py314",
+                    version_str
+                ),
+            }),
         }
     }
 
     /// Get the Python version as u8 for compatibility with existing code
-    pub fn python_version(&self) -> Result<u8> {
+    pub fn python_version(&self) -> CriboResult<u8> {
         Self::parse_target_version(&self.target_version)
     }
 
     /// Set the target version from a string value
-    pub fn set_target_version(&mut self, version: String) -> Result<()> {
+    pub fn set_target_version(&mut self, version: String) -> CriboResult<()> {
         // Validate the version string
         Self::parse_target_version(&version)?;
         self.target_version = version;
         Ok(())
     }
 
+    /// The configured shebang as the bundle's first line, `#!` included
+    pub fn shebang_line(&self) -> String {
+        let shebang = self.shebang.trim();
+        if shebang.starts_with("#!") {
+            shebang.to_owned()
+        } else {
+            format!("#!{}", shebang)
+        }
+    }
+
+    /// Check that `shebang` is a single line naming an interpreter
+    pub fn validate_shebang(&self) -> CriboResult<()> {
+        let interpreter = self.shebang.trim().trim_start_matches("#!").trim();
+        if !interpreter.is_empty() && !self.shebang.contains(['\n', '\r']) {
+            return Ok(());
+        }
+        Err(CriboError::Config {
+            path: None,
+            message: format!(
+                "Invalid shebang {:?}: expected one line naming an interpreter, such as \
+                 '#!/usr/bin/env python3'",
+                self.shebang
+            ),
+        })
+    }
+
+    /// Check that `runtime_prefix` can start a Python identifier
+    pub fn validate_runtime_prefix(&self) -> CriboResult<()> {
+        let prefix = &self.runtime_prefix;
+        let valid = prefix
+            .chars()
+            .next()
+            .is_some_and(|first| first == '_' || first.is_ascii_alphabetic())
+            && prefix
+                .chars()
+                .all(|c| c == '_' || c.is_ascii_alphanumeric());
+        if valid {
+            return Ok(());
+        }
+        Err(CriboError::Config {
+            path: None,
+            message: format!(
+                "Invalid runtime_prefix '{}': use ASCII letters, digits and underscores, \
+                 not starting with a digit",
+                prefix
+            ),
+        })
+    }
+
+    /// Check that no module is given conflicting bundling policies
+    pub fn validate_module_policies(&self) -> CriboResult<()> {
+        let policies = [
+            ("force_inline", &self.force_inline),
+            ("force_wrapper", &self.force_wrapper),
+            ("keep_external", &self.keep_external),
+        ];
+        for (index, (first, first_modules)) in policies.iter().enumerate() {
+            for (second, second_modules) in &policies[index + 1..] {
+                if let Some(module) = first_modules
+                    .iter()
+                    .find(|module| second_modules.contains(*module))
+                {
+                    return Err(CriboError::Config {
+                        path: None,
+                        message: format!(
+                            "Module '{}' is listed in both {} and {}",
+                            module, first, second
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Script that the configured `entry` refers to, if any (see [`Self::resolve_entry`])
+    pub fn entry_path(&self) -> CriboResult<Option<PathBuf>> {
+        self.entry
+            .as_deref()
+            .map(|entry| self.resolve_entry(entry))
+            .transpose()
+    }
+
+    /// Script to bundle for an entry given as a path or as a dotted module name
+    ///
+    /// Values ending in `.py` or `/`, containing a path separator, or naming an existing
+    /// file or directory are paths; a package directory stands for its `__main__.py`, or
+    /// its `__init__.py` without one, while any other directory is bundled as a directory
+    /// of scripts. Anything else is a module name, resolved among the first-party modules
+    /// like `python -m` does: a package runs as its `__main__` submodule.
+    pub fn resolve_entry(&self, entry: &str) -> CriboResult<PathBuf> {
+        if !is_module_entry(entry) {
+            return Ok(package_entry(Path::new(entry)));
+        }
+
+        let mut resolver = ModuleResolver::new(self.clone())?;
+        for module_name in [format!("{}.__main__", entry), entry.to_owned()] {
+            if let Some(path) = resolver.resolve_module_path(&module_name)? {
+                return Ok(path);
+            }
+        }
+        Err(CriboError::Config {
+            path: None,
+            message: format!(
+                "Entry module '{}' was not found in any source directory ({})",
+                entry,
+                self.src
+                    .iter()
+                    .map(|dir| dir.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        })
+    }
+
+    /// Bundles selected by name, or all of them when `only` is empty
+    pub fn select_bundles(&self, only: &[String]) -> CriboResult<Vec<&BundleTarget>> {
+        if self.bundles.is_empty() {
+            return Err(CriboError::Config {
+                path: None,
+                message: "No bundles are configured; add [[bundles]] entries to cribo.toml"
+                    .to_owned(),
+            });
+        }
+        if let Some(unknown) = only
+            .iter()
+            .find(|name| !self.bundles.iter().any(|target| &target.name == *name))
+        {
+            return Err(CriboError::Config {
+                path: None,
+                message: format!(
+                    "Bundle '{}' is not configured (available: {})",
+                    unknown,
+                    self.bundles
+                        .iter()
+                        .map(|target| target.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+        }
+        Ok(self
+            .bundles
+            .iter()
+            .filter(|target| only.is_empty() || only.contains(&target.name))
+            .collect())
+    }
+
+    /// Configuration for building `target`: this configuration with the target's
+    /// entry, output and overrides applied
+    pub fn for_bundle(&self, target: &BundleTarget) -> CriboResult<Config> {
+        let config_error = |message: String| CriboError::Config {
+            path: None,
+            message: format!("Invalid settings for bundle '{}': {}", target.name, message),
+        };
+
+        let Ok(toml::Value::Table(mut table)) = toml::Value::try_from(self) else {
+            return Err(config_error(
+                "the base configuration could not be serialized".to_owned(),
+            ));
+        };
+        table.remove("bundles");
+        table.extend(target.overrides.clone());
+        table.insert("entry".to_owned(), target.entry.clone().into());
+        table.insert(
+            "output".to_owned(),
+            target.output.to_string_lossy().into_owned().into(),
+        );
+
+        let config = toml::Value::Table(table)
+            .try_into::<Config>()
+            .map_err(|err| config_error(err.to_string()))?;
+        config
+            .python_version()
+            .map_err(|err| config_error(err.to_string()))?;
+        config
+            .validate_runtime_prefix()
+            .map_err(|err| config_error(err.to_string()))?;
+        config
+            .validate_module_policies()
+            .map_err(|err| config_error(err.to_string()))?;
+        validate_data_file_targets(&config.data_files)
+            .map_err(|err| config_error(err.to_string()))?;
+        validate_define_names(&config.defines).map_err(|err| config_error(err.to_string()))?;
+        ModuleFilter::new(&config.include, &config.exclude)
+            .map_err(|err| config_error(err.to_string()))?;
+        Ok(config)
+    }
+
     /// Load a single config file from a path
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Config> {
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> CriboResult<Config> {
+        Self::load_from_file_with_profile(path, None).map(|(config, _)| config)
+    }
+
+    /// Load a single config file, applying the `[profile.<name>]` section for `profile`
+    /// on top of its top-level settings
+    ///
+    /// For a `pyproject.toml`, the settings are read from its `[tool.cribo]` table.
+    /// Also returns whether the file defines the requested profile.
+    pub fn load_from_file_with_profile<P: AsRef<Path>>(
+        path: P,
+        profile: Option<&str>,
+    ) -> CriboResult<(Config, bool)> {
         let path = path.as_ref();
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        let config_error = |message: String| CriboError::Config {
+            path: Some(path.to_path_buf()),
+            message,
+        };
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            config_error(format!("Failed to read config file: {:?}: {}", path, err))
+        })?;
+
+        let mut table: toml::Table = toml::from_str(&content).map_err(|err| {
+            config_error(format!("Failed to parse config file: {:?}: {}", path, err))
+        })?;
+        if path.file_name().is_some_and(|name| name == PYPROJECT_FILE) {
+            table = match table.remove("tool") {
+                Some(toml::Value::Table(mut tool)) => match tool.remove("cribo") {
+                    Some(toml::Value::Table(cribo)) => cribo,
+                    Some(_) => {
+                        return Err(config_error(format!(
+                            "`tool.cribo` in {:?} must be a table",
+                            path
+                        )));
+                    }
+                    None => toml::Table::new(),
+                },
+                _ => toml::Table::new(),
+            };
+        }
+        let profile_found = match (table.remove("profile"), profile) {
+            (Some(toml::Value::Table(mut profiles)), Some(profile)) => {
+                match profiles.remove(profile) {
+                    Some(toml::Value::Table(overrides)) => {
+                        log::debug!("Applying profile '{}' from {:?}", profile, path);
+                        table.extend(overrides);
+                        true
+                    }
+                    Some(_) => {
+                        return Err(config_error(format!(
+                            "Profile '{}' in {:?} must be a table",
+                            profile, path
+                        )));
+                    }
+                    None => false,
+                }
+            }
+            (Some(toml::Value::Table(_)) | None, _) => false,
+            (Some(_), _) => {
+                return Err(config_error(format!(
+                    "`profile` in {:?} must be a table of profiles",
+                    path
+                )));
+            }
+        };
 
-        let config: Config = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+        let config = toml::Value::Table(table)
+            .try_into::<Config>()
+            .map_err(|err| {
+                config_error(format!("Failed to parse config file: {:?}: {}", path, err))
+            })?;
 
         // Validate the target version
-        config.python_version().with_context(|| {
-            format!(
-                "Invalid target-version in config file: {}",
-                config.target_version
-            )
+        config.python_version().map_err(|err| {
+            config_error(format!(
+                "Invalid target-version in config file: {}: {}",
+                config.target_version, err
+            ))
         })?;
+        config
+            .validate_runtime_prefix()
+            .map_err(|err| config_error(err.to_string()))?;
+        config
+            .validate_module_policies()
+            .map_err(|err| config_error(err.to_string()))?;
+        validate_data_file_targets(&config.data_files)
+            .map_err(|err| config_error(err.to_string()))?;
+        validate_define_names(&config.defines).map_err(|err| config_error(err.to_string()))?;
+        ModuleFilter::new(&config.include, &config.exclude)
+            .map_err(|err| config_error(err.to_string()))?;
 
-        Ok(config)
+        Ok((config, profile_found))
     }
 
     fn try_load_and_combine<P: AsRef<Path>>(
         config: &mut Config,
         path: P,
         context: &str,
-    ) -> Result<()> {
+        profile: Option<&str>,
+    ) -> CriboResult<bool> {
+        let mut profile_found = false;
         if path.as_ref().exists() {
             log::debug!("Loading {} from: {:?}", context, path.as_ref());
-            let loaded = Self::load_from_file(&path)
-                .with_context(|| format!("Failed to load {} from {:?}", context, path.as_ref()))?;
+            let (loaded, found) =
+                Self::load_from_file_with_profile(&path, profile).map_err(|err| {
+                    CriboError::Config {
+                        path: Some(path.as_ref().to_path_buf()),
+                        message: format!(
+                            "Failed to load {} from {:?}: {}",
+                            context,
+                            path.as_ref(),
+                            err
+                        ),
+                    }
+                })?;
             *config = loaded.combine(config.clone());
+            profile_found = found;
         }
-        Ok(())
+        Ok(profile_found)
     }
 
     /// Load configuration with hierarchical precedence:
     /// 1. CLI-provided config path (highest precedence)
     /// 2. Environment variables (CRIBO_*)
-    /// 3. Project config (cribo.toml in current directory)
+    /// 3. Project config (cribo.toml, then `[tool.cribo]` in pyproject.toml, in current directory)
     /// 4. User config (~/.config/cribo/cribo.toml)
     /// 5. System config (/etc/cribo/cribo.toml or equivalent)
     /// 6. Default values (lowest precedence)
-    pub fn load(cli_config_path: Option<&Path>) -> Result<Self> {
+    pub fn load(cli_config_path: Option<&Path>) -> CriboResult<Self> {
+        Self::load_with_profile(cli_config_path, None)
+    }
+
+    /// Load configuration like [`Config::load`], applying the `[profile.<name>]` section
+    /// of every config file on top of that file's settings
+    ///
+    /// The profile defaults to the `CRIBO_PROFILE` environment variable. Selecting a
+    /// profile that no config file defines is an error.
+    pub fn load_with_profile(
+        cli_config_path: Option<&Path>,
+        profile: Option<&str>,
+    ) -> CriboResult<Self> {
+        let env_profile = env::var("CRIBO_PROFILE")
+            .ok()
+            .filter(|name| !name.is_empty());
+        let profile = profile.or(env_profile.as_deref());
         let mut config = Config::default();
+        let mut profile_found = false;
 
         // 1. Load system config (lowest precedence)
         if let Some(system_config_path) = system_config_file() {
-            Self::try_load_and_combine(&mut config, &system_config_path, "system config")?;
+            profile_found |= Self::try_load_and_combine(
+                &mut config,
+                &system_config_path,
+                "system config",
+                profile,
+            )?;
         }
 
         // 2. Load user config
         if let Some(user_config_dir) = user_cribo_config_dir() {
             let user_config_path = user_config_dir.join("cribo.toml");
-            Self::try_load_and_combine(&mut config, &user_config_path, "user config")?;
+            profile_found |=
+                Self::try_load_and_combine(&mut config, &user_config_path, "user config", profile)?;
         }
 
-        // 3. Load project config (cribo.toml in current directory)
+        // 3. Load project config ([tool.cribo] in pyproject.toml, then cribo.toml in
+        //    current directory)
+        profile_found |= Self::try_load_and_combine(
+            &mut config,
+            PYPROJECT_FILE,
+            "pyproject.toml config",
+            profile,
+        )?;
         let project_config_path = PathBuf::from("cribo.toml");
-        Self::try_load_and_combine(&mut config, &project_config_path, "project config")?;
+        profile_found |= Self::try_load_and_combine(
+            &mut config,
+            &project_config_path,
+            "project config",
+            profile,
+        )?;
 
         // 4. Apply environment variables
         let env_config = EnvConfig::from_env();
@@ -276,16 +1348,37 @@ impl Config {
 
         // 5. Load CLI-provided config (highest precedence)
         if let Some(cli_config_path) = cli_config_path {
-            Self::try_load_and_combine(&mut config, cli_config_path, "CLI config")?;
+            profile_found |=
+                Self::try_load_and_combine(&mut config, cli_config_path, "CLI config", profile)?;
+        }
+
+        if let Some(profile) = profile {
+            if !profile_found {
+                return Err(CriboError::Config {
+                    path: None,
+                    message: format!(
+                        "Profile '{}' is not defined in any config file; add a [profile.{}] section",
+                        profile, profile
+                    ),
+                });
+            }
+            log::info!("Using configuration profile '{}'", profile);
         }
 
         // Final validation
-        config.python_version().with_context(|| {
-            format!(
-                "Invalid target-version in final config: {}",
-                config.target_version
-            )
+        config.python_version().map_err(|err| CriboError::Config {
+            path: None,
+            message: format!(
+                "Invalid target-version in final config: {}: {}",
+                config.target_version, err
+            ),
         })?;
+        config.validate_runtime_prefix()?;
+        config.validate_shebang()?;
+        config.validate_module_policies()?;
+        validate_data_file_targets(&config.data_files)?;
+        validate_define_names(&config.defines)?;
+        ModuleFilter::new(&config.include, &config.exclude)?;
 
         Ok(config)
     }
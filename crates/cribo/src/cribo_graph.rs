@@ -14,13 +14,14 @@
 use anyhow::{Result, anyhow};
 use indexmap::IndexSet;
 use log::debug;
-use petgraph::algo::{is_cyclic_directed, toposort};
+use petgraph::algo::{condensation, is_cyclic_directed, toposort};
 use petgraph::graph::{DiGraph, NodeIndex};
 use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 /// Unique identifier for a module
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModuleId(u32);
 
 impl ModuleId {
@@ -36,7 +37,7 @@ impl ModuleId {
 }
 
 /// Unique identifier for an item within a module
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ItemId(u32);
 
 impl ItemId {
@@ -46,7 +47,7 @@ impl ItemId {
 }
 
 /// Type of Python item (statement/definition)
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ItemType {
     /// Function definition
     FunctionDef { name: String },
@@ -72,12 +73,14 @@ pub enum ItemType {
     If { condition: String },
     /// Try-except block
     Try,
+    /// Delete statement; `targets` are the plain names it unbinds
+    Delete { targets: Vec<String> },
     /// Other statement types
     Other,
 }
 
 /// Dependency type between items
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DepType {
     /// Always needed (e.g., direct function call)
     Strong,
@@ -86,14 +89,14 @@ pub enum DepType {
 }
 
 /// A single dependency relationship
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dep {
     pub target: ItemId,
     pub dep_type: DepType,
 }
 
 /// Variable state tracking
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VarState {
     /// The item that declares this variable
     pub declarator: Option<ItemId>,
@@ -125,7 +128,7 @@ struct ImportUsageContext<'a> {
 }
 
 /// Data about a Python item (statement/definition)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ItemData {
     /// Type of this item
     pub item_type: ItemType,
@@ -150,7 +153,7 @@ pub struct ItemData {
 }
 
 /// Fine-grained dependency graph for a single module
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleDepGraph {
     /// Module identifier
     pub module_id: ModuleId,
@@ -483,7 +486,7 @@ pub enum ModuleUpdate {
 }
 
 /// Module metadata for optimization
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleMetadata {
     /// Whether module has side effects
     pub has_side_effects: bool,
@@ -748,6 +751,47 @@ impl CriboGraph {
             .map_err(|_| anyhow!("Circular dependency detected"))
     }
 
+    /// Get modules in dependency order, with each circular dependency collapsed into one unit
+    ///
+    /// Unlike [`Self::topological_sort`] this never fails: the modules of a strongly
+    /// connected component form a single unit, which comes after every unit it imports
+    /// and before every unit importing it. Acyclic modules are units of their own.
+    pub fn condensed_topological_sort(&self) -> Vec<Vec<ModuleId>> {
+        let condensed = condensation(self.graph.clone(), true);
+        let order = toposort(&condensed, None)
+            .expect("condensing strongly connected components leaves an acyclic graph");
+        order
+            .into_iter()
+            .map(|node| {
+                let mut unit: Vec<ModuleId> = condensed[node].iter().copied().collect();
+                unit.sort_by(|a, b| {
+                    Self::cycle_member_order(
+                        &self.modules[a].module_name,
+                        &self.modules[b].module_name,
+                    )
+                });
+                unit
+            })
+            .collect()
+    }
+
+    /// Order of the modules inside a circular dependency unit
+    ///
+    /// Imports inside a cycle can't all be honored, so submodules come before their
+    /// packages (a package usually re-exports them), then deeper modules before
+    /// shallower ones, then names sort alphabetically for deterministic output.
+    fn cycle_member_order(a: &str, b: &str) -> std::cmp::Ordering {
+        if a.strip_prefix(b).is_some_and(|rest| rest.starts_with('.')) {
+            return std::cmp::Ordering::Less;
+        }
+        if b.strip_prefix(a).is_some_and(|rest| rest.starts_with('.')) {
+            return std::cmp::Ordering::Greater;
+        }
+        let a_depth = a.matches('.').count();
+        let b_depth = b.matches('.').count();
+        b_depth.cmp(&a_depth).then_with(|| a.cmp(b))
+    }
+
     /// Check if the graph has cycles
     pub fn has_cycles(&self) -> bool {
         is_cyclic_directed(&self.graph)
@@ -836,6 +880,60 @@ impl CriboGraph {
         }
     }
 
+    /// Module-level statements of `module_id` that have side effects, in source order
+    pub fn side_effects(&self, module_id: ModuleId) -> Vec<&ItemType> {
+        self.modules
+            .get(&module_id)
+            .map(|module| {
+                module
+                    .side_effect_items
+                    .iter()
+                    .filter_map(|item_id| module.items.get(item_id))
+                    .map(|item| &item.item_type)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every module with its source file, the first-party modules it imports and its
+    /// side-effecting statements, sorted by module name
+    ///
+    /// The shape is stable for tools built on cribo:
+    /// `{"modules": [{"name", "path", "dependencies", "side_effects"}]}`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut modules: Vec<(&String, ModuleId)> = self
+            .module_names
+            .iter()
+            .map(|(name, &module_id)| (name, module_id))
+            .collect();
+        modules.sort_by(|a, b| a.0.cmp(b.0));
+
+        let modules: Vec<serde_json::Value> = modules
+            .into_iter()
+            .map(|(name, module_id)| {
+                let path = self
+                    .module_paths
+                    .iter()
+                    .find(|(_, id)| **id == module_id)
+                    .map(|(path, _)| path);
+                let mut dependencies: Vec<&str> = self
+                    .get_dependencies(module_id)
+                    .into_iter()
+                    .filter_map(|dependency| self.modules.get(&dependency))
+                    .map(|dependency| dependency.module_name.as_str())
+                    .collect();
+                dependencies.sort_unstable();
+                serde_json::json!({
+                    "name": name,
+                    "path": path,
+                    "dependencies": dependencies,
+                    "side_effects": self.side_effects(module_id),
+                })
+            })
+            .collect();
+        serde_json::json!({ "modules": modules })
+    }
+
     /// Get all modules that a given module depends on
     pub fn get_dependencies(&self, module_id: ModuleId) -> Vec<ModuleId> {
         if let Some(&node_idx) = self.node_indices.get(&module_id) {
@@ -1504,6 +1602,38 @@ mod tests {
         assert_eq!(sorted, vec![utils_id, main_id]);
     }
 
+    #[test]
+    fn test_condensed_topological_sort_collapses_cycles() {
+        let mut graph = CriboGraph::new();
+
+        let main_id = graph.add_module("main".to_string(), PathBuf::from("main.py"));
+        let models_id = graph.add_module("models".to_string(), PathBuf::from("models.py"));
+        let schema_id = graph.add_module("schema".to_string(), PathBuf::from("schema.py"));
+        let utils_id = graph.add_module("utils".to_string(), PathBuf::from("utils.py"));
+
+        // main -> models <-> schema -> utils
+        graph.add_module_dependency(main_id, models_id);
+        graph.add_module_dependency(models_id, schema_id);
+        graph.add_module_dependency(schema_id, models_id);
+        graph.add_module_dependency(schema_id, utils_id);
+
+        assert!(graph.topological_sort().is_err());
+        assert_eq!(
+            graph.condensed_topological_sort(),
+            vec![vec![utils_id], vec![models_id, schema_id], vec![main_id]]
+        );
+    }
+
+    #[test]
+    fn test_cycle_member_order_puts_submodules_first() {
+        let mut names = vec!["pkg", "alpha", "pkg.sub", "pkg.sub.leaf", "beta"];
+        names.sort_by(|a, b| CriboGraph::cycle_member_order(a, b));
+        assert_eq!(
+            names,
+            vec!["pkg.sub.leaf", "pkg.sub", "pkg", "alpha", "beta"]
+        );
+    }
+
     #[test]
     fn test_item_dependencies() {
         let mut module = ModuleDepGraph::new(ModuleId::new(0), "test".to_string());
@@ -1609,4 +1739,23 @@ mod tests {
             panic!("Expected unresolvable strategy for constants cycle");
         }
     }
+
+    #[test]
+    fn test_del_does_not_count_as_import_use() {
+        let source = "import os\nimport sys\n\ndel os\ndel sys.modules['x']\n";
+        let parsed = ruff_python_parser::parse_module(source).expect("valid source");
+
+        let mut module = ModuleDepGraph::new(ModuleId::new(0), "test".to_string());
+        crate::graph_builder::GraphBuilder::new(&mut module)
+            .build_from_ast(parsed.syntax())
+            .expect("graph should build");
+
+        let unused: Vec<String> = module
+            .find_unused_imports(false)
+            .into_iter()
+            .map(|info| info.name)
+            .collect();
+        assert!(unused.contains(&"os".to_string()));
+        assert!(!unused.contains(&"sys".to_string()));
+    }
 }
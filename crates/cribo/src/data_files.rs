@@ -0,0 +1,333 @@
+//! Embedding small data files in the bundle as Python literals
+//!
+//! Each `[data_files]` entry maps a `module.NAME` target to a JSON, TOML or YAML file.
+//! The file is parsed at bundle time and `NAME = <literal>` is placed at the top of the
+//! module, so static configuration no longer has to be read from disk at runtime.
+//!
+//! Files are deserialized straight into [`DataValue`], so mappings keep the order of
+//! their keys and YAML's `.nan` and `.inf` become `float('nan')` and `float('inf')`.
+//! TOML datetimes have no literal and are rejected.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use ruff_python_ast::relocate::relocate_expr;
+use ruff_python_ast::{Expr, ExprContext, ExprName, ModModule, Stmt, StmtAssign};
+use ruff_python_parser::ParseError;
+use ruff_text_size::TextRange;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+
+use crate::error::{CriboError, CriboResult};
+use crate::file_provider::FileProvider;
+
+/// Field the `toml` crate deserializes datetimes as
+const TOML_DATETIME_FIELD: &str = "$__toml_private_datetime";
+
+/// Data file targets of `module_name`, as (assigned name, data file) pairs
+pub(crate) fn data_files_for_module<'a>(
+    data_files: &'a IndexMap<String, PathBuf>,
+    module_name: &'a str,
+) -> impl Iterator<Item = (&'a str, &'a Path)> {
+    data_files.iter().filter_map(move |(target, path)| {
+        let (module, name) = target.rsplit_once('.')?;
+        (module == module_name).then_some((name, path.as_path()))
+    })
+}
+
+/// Check that every target is a `module.NAME` pair with an identifier as the name
+pub(crate) fn validate_targets(data_files: &IndexMap<String, PathBuf>) -> CriboResult<()> {
+    for target in data_files.keys() {
+        let valid = target.rsplit_once('.').is_some_and(|(module, name)| {
+            !module.is_empty() && ruff_python_stdlib::identifiers::is_identifier(name)
+        });
+        if !valid {
+            return Err(CriboError::Config {
+                path: None,
+                message: format!(
+                    "Invalid data file target '{}': expected `module.NAME`",
+                    target
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Assign the parsed contents of each of `module_name`'s data files at the top of
+/// `ast`, after the docstring and `__future__` imports
+///
+/// Returns whether anything was embedded.
+pub(crate) fn embed_data_files(
+    ast: &mut ModModule,
+    module_name: &str,
+    data_files: &IndexMap<String, PathBuf>,
+    files: &dyn FileProvider,
+) -> CriboResult<bool> {
+    let mut assignments = Vec::new();
+    for (name, path) in data_files_for_module(data_files, module_name) {
+        let value = load_data_file(path, files)?;
+        let expr = literal_expr(&value).map_err(|err| data_file_error(path, &err.to_string()))?;
+        log::debug!("Embedding {:?} as {}.{}", path, module_name, name);
+        assignments.push(Stmt::Assign(StmtAssign {
+            targets: vec![Expr::Name(ExprName {
+                id: name.into(),
+                ctx: ExprContext::Store,
+                range: TextRange::default(),
+            })],
+            value: Box::new(expr),
+            range: TextRange::default(),
+        }));
+    }
+    if assignments.is_empty() {
+        return Ok(false);
+    }
+
+    let position = module_prologue_len(&ast.body);
+    ast.body.splice(position..position, assignments);
+    Ok(true)
+}
+
+/// Number of leading statements that must stay first: the docstring and
+/// `from __future__` imports
+fn module_prologue_len(body: &[Stmt]) -> usize {
+    let docstring = usize::from(matches!(
+        body.first(),
+        Some(Stmt::Expr(expr)) if expr.value.is_string_literal_expr()
+    ));
+    docstring
+        + body[docstring..]
+            .iter()
+            .take_while(|stmt| {
+                matches!(
+                    stmt,
+                    Stmt::ImportFrom(import_from)
+                        if import_from.module.as_deref() == Some("__future__")
+                )
+            })
+            .count()
+}
+
+/// Parse a data file according to its extension
+fn load_data_file(path: &Path, files: &dyn FileProvider) -> CriboResult<DataValue> {
+    let contents = files
+        .read_to_string(path)
+        .map_err(|err| data_file_error(path, &format!("Failed to read data file: {}", err)))?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    match extension {
+        "json" => {
+            serde_json::from_str(&contents).map_err(|err| data_file_error(path, &err.to_string()))
+        }
+        "toml" => toml::from_str(&contents).map_err(|err| data_file_error(path, &err.to_string())),
+        "yaml" | "yml" => {
+            serde_yaml::from_str(&contents).map_err(|err| data_file_error(path, &err.to_string()))
+        }
+        _ => Err(data_file_error(
+            path,
+            "Unsupported data file format, expected .json, .toml, .yaml or .yml",
+        )),
+    }
+}
+
+fn data_file_error(path: &Path, message: &str) -> CriboError {
+    CriboError::Config {
+        path: Some(path.to_path_buf()),
+        message: format!("Invalid data file {:?}: {}", path, message),
+    }
+}
+
+/// A deserialized data value, in the shape of the Python literal it becomes
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DataValue {
+    None,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+    List(Vec<DataValue>),
+    /// Entries in the order the file lists them
+    Dict(Vec<(DataValue, DataValue)>),
+}
+
+impl<'de> Deserialize<'de> for DataValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DataValueVisitor)
+    }
+}
+
+struct DataValueVisitor;
+
+impl<'de> Visitor<'de> for DataValueVisitor {
+    type Value = DataValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a null, boolean, number, string, sequence or mapping")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<DataValue, E> {
+        Ok(DataValue::None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<DataValue, E> {
+        Ok(DataValue::None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<DataValue, D::Error> {
+        DataValue::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<DataValue, E> {
+        Ok(DataValue::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<DataValue, E> {
+        Ok(DataValue::Int(value.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<DataValue, E> {
+        Ok(DataValue::Int(value.into()))
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<DataValue, E> {
+        Ok(DataValue::Int(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<DataValue, E> {
+        Ok(DataValue::Float(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<DataValue, E> {
+        Ok(DataValue::Str(value.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<DataValue, E> {
+        Ok(DataValue::Str(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DataValue, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(DataValue::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DataValue, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(key) = map.next_key::<DataValue>()? {
+            match &key {
+                DataValue::Str(field) if field == TOML_DATETIME_FIELD => {
+                    let datetime: String = map.next_value()?;
+                    return Err(de::Error::custom(format!(
+                        "TOML datetime {} has no Python literal; quote it to embed a string",
+                        datetime
+                    )));
+                }
+                DataValue::List(_) | DataValue::Dict(_) => {
+                    return Err(de::Error::custom(
+                        "mapping keys must be null, booleans, numbers or strings",
+                    ));
+                }
+                _ => {}
+            }
+            entries.push((key, map.next_value()?));
+        }
+        Ok(DataValue::Dict(entries))
+    }
+}
+
+/// The Python literal expression equivalent to `value`
+pub(crate) fn literal_expr(value: &DataValue) -> Result<Expr, ParseError> {
+    let mut literal = String::new();
+    python_literal(value, &mut literal);
+    let mut expr = ruff_python_parser::parse_expression(&literal)?.into_expr();
+    relocate_expr(&mut expr, TextRange::default());
+    Ok(expr)
+}
+
+/// Write `value` as the equivalent Python literal
+fn python_literal(value: &DataValue, out: &mut String) {
+    match value {
+        DataValue::None => out.push_str("None"),
+        DataValue::Bool(true) => out.push_str("True"),
+        DataValue::Bool(false) => out.push_str("False"),
+        DataValue::Int(number) => out.push_str(&number.to_string()),
+        DataValue::Float(number) if number.is_nan() => out.push_str("float('nan')"),
+        DataValue::Float(number) if number.is_infinite() => out.push_str(if *number > 0.0 {
+            "float('inf')"
+        } else {
+            "float('-inf')"
+        }),
+        // Debug formatting always has a decimal point or an exponent, like Python's
+        DataValue::Float(number) => out.push_str(&format!("{:?}", number)),
+        // JSON string escapes are valid in Python string literals
+        DataValue::Str(string) => out.push_str(&Value::String(string.clone()).to_string()),
+        DataValue::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                python_literal(item, out);
+            }
+            out.push(']');
+        }
+        DataValue::Dict(entries) => {
+            out.push('{');
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                python_literal(key, out);
+                out.push_str(": ");
+                python_literal(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(value: &DataValue) -> String {
+        let mut literal = String::new();
+        python_literal(value, &mut literal);
+        literal
+    }
+
+    #[test]
+    fn test_python_literal() {
+        let value: DataValue = serde_json::from_str(
+            r#"{"name": "app \"x\"\n", "debug": false, "ratio": 0.5, "tags": [1, null, true]}"#,
+        )
+        .expect("valid JSON");
+        assert_eq!(
+            literal(&value),
+            r#"{"name": "app \"x\"\n", "debug": False, "ratio": 0.5, "tags": [1, None, True]}"#
+        );
+    }
+
+    #[test]
+    fn test_python_literal_from_yaml_keeps_special_floats() {
+        let value: DataValue =
+            serde_yaml::from_str("zero: 1.0\nmissing: .nan\nceiling: .inf\nfloor: -.inf\n1: one\n")
+                .expect("valid YAML");
+        assert_eq!(
+            literal(&value),
+            r#"{"zero": 1.0, "missing": float('nan'), "ceiling": float('inf'), "floor": float('-inf'), 1: "one"}"#
+        );
+    }
+
+    #[test]
+    fn test_toml_datetimes_are_rejected() {
+        let error = toml::from_str::<DataValue>("released = 1979-05-27T07:32:00Z\n")
+            .expect_err("datetimes have no literal");
+        assert!(error.to_string().contains("TOML datetime"), "{error}");
+    }
+}
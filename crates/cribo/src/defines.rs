@@ -0,0 +1,692 @@
+//! Bundle-time constant substitution
+//!
+//! Each `[defines]` entry names a module-level identifier (e.g. `__BUNDLE_VERSION__` or
+//! `FEATURE_X`) and the value it has in this build. Reads of that name are replaced
+//! with the value's literal, and `if`/`elif` branches and conditional expressions
+//! whose condition thereby becomes constant are resolved at bundle time, so code and
+//! imports behind a disabled feature flag never make it into the bundle.
+//!
+//! The same folding resolves `sys.version_info` checks against the target version when
+//! `fold_version_checks` is set, dropping e.g. the `importlib_metadata` backport import
+//! of code that still supports Python 3.7.
+
+use std::path::Path;
+
+use indexmap::{IndexMap, IndexSet};
+use ruff_python_ast::visitor::transformer::{Transformer, walk_expr, walk_stmt};
+use ruff_python_ast::visitor::{
+    Visitor, walk_except_handler, walk_expr as walk_expr_ref, walk_stmt as walk_stmt_ref,
+};
+use ruff_python_ast::{
+    Alias, BoolOp, CmpOp, ElifElseClause, ExceptHandler, Expr, ExprBooleanLiteral, ExprContext,
+    Int, ModModule, Number, Parameter, Stmt, StmtIf, StmtPass, UnaryOp,
+};
+use ruff_text_size::TextRange;
+use serde::Deserialize;
+use toml::Value;
+
+use crate::data_files::{DataValue, literal_expr};
+use crate::error::{CriboError, CriboResult};
+
+/// Check that every define names a valid Python identifier
+pub(crate) fn validate_names(defines: &IndexMap<String, Value>) -> CriboResult<()> {
+    for name in defines.keys() {
+        if !ruff_python_stdlib::identifiers::is_identifier(name) {
+            return Err(CriboError::Config {
+                path: None,
+                message: format!("Invalid define '{}': expected a Python identifier", name),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Parse a `NAME=VALUE` definition from the command line or environment, where `NAME`
+/// must be an identifier
+///
+/// The value is read as a TOML value (`false`, `3`, `"text"`, `[1, 2]`); Python's
+/// `True` and `False` are accepted too, and anything else is taken as a string.
+pub fn parse_definition(definition: &str) -> Option<(String, Value)> {
+    let (name, value) = definition.split_once('=')?;
+    let name = name.trim();
+    if !ruff_python_stdlib::identifiers::is_identifier(name) {
+        return None;
+    }
+    let value = value.trim();
+    let value = match value {
+        "True" => Value::Boolean(true),
+        "False" => Value::Boolean(false),
+        _ => toml::from_str::<toml::Table>(&format!("value = {}", value))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| Value::String(value.to_owned())),
+    };
+    Some((name.to_owned(), value))
+}
+
+/// Substitute the defines read in `module` and resolve the branches they decide
+///
+/// A define is left alone in a module that binds the same name inside a function,
+/// class, lambda or comprehension, where a read may refer to that binding instead.
+/// Returns whether the module changed.
+pub(crate) fn apply_defines(
+    module: &mut ModModule,
+    path: &Path,
+    defines: &IndexMap<String, Value>,
+) -> CriboResult<bool> {
+    if defines.is_empty() {
+        return Ok(false);
+    }
+    let shadowed = nested_bindings(module);
+    let mut literals = IndexMap::new();
+    for (name, value) in defines {
+        if shadowed.contains(name.as_str()) {
+            log::debug!(
+                "{:?} binds '{}' in a nested scope; not substituting the define",
+                path,
+                name
+            );
+            continue;
+        }
+        let invalid_value = |message: String| CriboError::Config {
+            path: None,
+            message: format!("Invalid value for define '{}': {}", name, message),
+        };
+        let value =
+            DataValue::deserialize(value.clone()).map_err(|err| invalid_value(err.to_string()))?;
+        let expr = literal_expr(&value).map_err(|err| invalid_value(err.to_string()))?;
+        literals.insert(name.as_str(), expr);
+    }
+
+    let substituter = DefineSubstituter {
+        literals,
+        python_version: None,
+        substitutions: std::cell::Cell::new(0),
+    };
+    let substitutions = substituter.substitute(module);
+    if substitutions == 0 {
+        return Ok(false);
+    }
+    log::debug!(
+        "Substituted {} define reference(s) in {:?}",
+        substitutions,
+        path
+    );
+    Ok(true)
+}
+
+/// Replace the `sys.version_info` checks of `module` that every Python version from
+/// 3.`python_version` on agrees about, returning whether there were any
+pub(crate) fn resolve_version_checks(
+    module: &mut ModModule,
+    path: &Path,
+    python_version: u8,
+) -> bool {
+    let imports_sys = module.body.iter().any(|stmt| match stmt {
+        Stmt::Import(import) => import
+            .names
+            .iter()
+            .any(|alias| alias.name.as_str() == "sys" && alias.asname.is_none()),
+        _ => false,
+    });
+    if !imports_sys || nested_bindings(module).contains("sys") {
+        return false;
+    }
+    let substituter = DefineSubstituter {
+        literals: IndexMap::new(),
+        python_version: Some(python_version),
+        substitutions: std::cell::Cell::new(0),
+    };
+    let substitutions = substituter.substitute(module);
+    if substitutions == 0 {
+        return false;
+    }
+    log::debug!(
+        "Resolved {} sys.version_info check(s) in {:?} for Python 3.{}",
+        substitutions,
+        path,
+        python_version
+    );
+    true
+}
+
+/// Replaces reads of defined names with their literals, then resolves constant
+/// conditions in the statements and expressions where something was replaced
+struct DefineSubstituter<'a> {
+    literals: IndexMap<&'a str, Expr>,
+    /// Target minor version to resolve `sys.version_info` checks against, if any
+    python_version: Option<u8>,
+    substitutions: std::cell::Cell<usize>,
+}
+
+impl DefineSubstituter<'_> {
+    /// Rewrite `module`, returning the number of replacements
+    fn substitute(&self, module: &mut ModModule) -> usize {
+        for stmt in &mut module.body {
+            self.visit_stmt(stmt);
+        }
+        let substitutions = self.substitutions.get();
+        if substitutions > 0 {
+            fold_body(&mut module.body, false);
+        }
+        substitutions
+    }
+}
+
+impl Transformer for DefineSubstituter<'_> {
+    fn visit_stmt(&self, stmt: &mut Stmt) {
+        let before = self.substitutions.get();
+        walk_stmt(self, stmt);
+        if self.substitutions.get() > before {
+            for body in nested_bodies(stmt) {
+                fold_body(body, true);
+            }
+        }
+    }
+
+    fn visit_expr(&self, expr: &mut Expr) {
+        if let Some(truth) = self
+            .python_version
+            .and_then(|python_version| version_check_truth(expr, python_version))
+        {
+            *expr = Expr::BooleanLiteral(ExprBooleanLiteral {
+                value: truth,
+                range: TextRange::default(),
+            });
+            self.substitutions.set(self.substitutions.get() + 1);
+            return;
+        }
+        if let Expr::Name(name) = expr {
+            if name.ctx == ExprContext::Load {
+                if let Some(literal) = self.literals.get(name.id.as_str()) {
+                    *expr = literal.clone();
+                    self.substitutions.set(self.substitutions.get() + 1);
+                }
+            }
+            return;
+        }
+        let before = self.substitutions.get();
+        walk_expr(self, expr);
+        if self.substitutions.get() == before {
+            return;
+        }
+        if let Expr::If(if_expr) = expr {
+            if let Some(truth) = constant_truth(&if_expr.test) {
+                *expr = if truth {
+                    (*if_expr.body).clone()
+                } else {
+                    (*if_expr.orelse).clone()
+                };
+            }
+        }
+    }
+}
+
+/// The statement blocks directly inside `stmt`
+pub(crate) fn nested_bodies(stmt: &mut Stmt) -> Vec<&mut Vec<Stmt>> {
+    match stmt {
+        Stmt::FunctionDef(function_def) => vec![&mut function_def.body],
+        Stmt::ClassDef(class_def) => vec![&mut class_def.body],
+        Stmt::For(for_stmt) => vec![&mut for_stmt.body, &mut for_stmt.orelse],
+        Stmt::While(while_stmt) => vec![&mut while_stmt.body, &mut while_stmt.orelse],
+        Stmt::With(with_stmt) => vec![&mut with_stmt.body],
+        Stmt::If(if_stmt) => std::iter::once(&mut if_stmt.body)
+            .chain(
+                if_stmt
+                    .elif_else_clauses
+                    .iter_mut()
+                    .map(|clause| &mut clause.body),
+            )
+            .collect(),
+        Stmt::Try(try_stmt) => {
+            let mut bodies = vec![&mut try_stmt.body];
+            bodies.extend(try_stmt.handlers.iter_mut().map(|handler| {
+                let ExceptHandler::ExceptHandler(handler) = handler;
+                &mut handler.body
+            }));
+            bodies.push(&mut try_stmt.orelse);
+            bodies.push(&mut try_stmt.finalbody);
+            bodies
+        }
+        Stmt::Match(match_stmt) => match_stmt
+            .cases
+            .iter_mut()
+            .map(|case| &mut case.body)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Resolve the `if` statements of `body` whose conditions are constant
+///
+/// Blocks that must not be empty (`needs_statement`) keep a `pass` when nothing is
+/// left of them.
+pub(crate) fn fold_body(body: &mut Vec<Stmt>, needs_statement: bool) {
+    if !body.iter().any(|stmt| matches!(stmt, Stmt::If(_))) {
+        return;
+    }
+    let mut folded = Vec::with_capacity(body.len());
+    for stmt in body.drain(..) {
+        match stmt {
+            Stmt::If(if_stmt) => folded.extend(fold_if(if_stmt)),
+            other => folded.push(other),
+        }
+    }
+    if folded.is_empty() && needs_statement {
+        folded.push(Stmt::Pass(StmtPass {
+            range: TextRange::default(),
+        }));
+    }
+    *body = folded;
+}
+
+/// The statements an `if`/`elif`/`else` chain reduces to once branches with constant
+/// conditions are resolved
+fn fold_if(if_stmt: StmtIf) -> Vec<Stmt> {
+    let StmtIf {
+        test,
+        body,
+        elif_else_clauses,
+        range,
+    } = if_stmt;
+    let branches = std::iter::once((Some(*test), body)).chain(
+        elif_else_clauses
+            .into_iter()
+            .map(|clause| (clause.test, clause.body)),
+    );
+
+    // Branches that may still run, with `None` as the condition of an `else`
+    let mut kept: Vec<(Option<Expr>, Vec<Stmt>)> = Vec::new();
+    for (test, body) in branches {
+        match test.as_ref().map(constant_truth) {
+            Some(Some(false)) => {}
+            Some(Some(true)) | None => {
+                kept.push((None, body));
+                break;
+            }
+            Some(None) => kept.push((test, body)),
+        }
+    }
+
+    let mut kept = kept.into_iter();
+    let Some((first_test, first_body)) = kept.next() else {
+        return Vec::new();
+    };
+    let Some(test) = first_test else {
+        // The first remaining branch always runs
+        return first_body;
+    };
+    vec![Stmt::If(StmtIf {
+        test: Box::new(test),
+        body: first_body,
+        elif_else_clauses: kept
+            .map(|(test, body)| ElifElseClause {
+                test,
+                body,
+                range: TextRange::default(),
+            })
+            .collect(),
+        range,
+    })]
+}
+
+/// The truth value of `expr` if it is known without running anything
+fn constant_truth(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::BooleanLiteral(literal) => Some(literal.value),
+        Expr::NoneLiteral(_) => Some(false),
+        Expr::NumberLiteral(literal) => match &literal.value {
+            Number::Int(value) => Some(*value != Int::ZERO),
+            Number::Float(value) => Some(*value != 0.0),
+            Number::Complex { .. } => None,
+        },
+        Expr::StringLiteral(literal) => Some(!literal.value.is_empty()),
+        Expr::UnaryOp(unary) if unary.op == UnaryOp::Not => {
+            constant_truth(&unary.operand).map(|truth| !truth)
+        }
+        Expr::BoolOp(bool_op) => {
+            // The first operand that decides the result must only follow operands
+            // known not to decide it, or those operands' side effects would be lost
+            let deciding = bool_op.op == BoolOp::Or;
+            for value in &bool_op.values {
+                match constant_truth(value) {
+                    Some(truth) if truth == deciding => return Some(deciding),
+                    Some(_) => {}
+                    None => return None,
+                }
+            }
+            Some(!deciding)
+        }
+        Expr::Compare(compare) if compare.ops.len() == 1 => {
+            let left = Constant::from_expr(&compare.left)?;
+            let right = Constant::from_expr(&compare.comparators[0])?;
+            let equal = match (compare.ops[0], left, right) {
+                (CmpOp::Eq | CmpOp::NotEq, left, right) => left.equals(&right)?,
+                (CmpOp::Is | CmpOp::IsNot, Constant::None, right) => right == Constant::None,
+                (CmpOp::Is | CmpOp::IsNot, left, Constant::None) => left == Constant::None,
+                _ => return None,
+            };
+            Some(equal == matches!(compare.ops[0], CmpOp::Eq | CmpOp::Is))
+        }
+        _ => None,
+    }
+}
+
+/// The result of comparing `sys.version_info` (or `sys.version_info[:2]`) with a
+/// version tuple, when it is the same on every Python version from 3.`python_version` on
+fn version_check_truth(expr: &Expr, python_version: u8) -> Option<bool> {
+    let Expr::Compare(compare) = expr else {
+        return None;
+    };
+    if compare.ops.len() != 1 {
+        return None;
+    }
+    let (op, sliced, bound) = if let Some(sliced) = version_info_operand(&compare.left) {
+        (compare.ops[0], sliced, &compare.comparators[0])
+    } else if let Some(sliced) = version_info_operand(&compare.comparators[0]) {
+        let mirrored = match compare.ops[0] {
+            CmpOp::Lt => CmpOp::Gt,
+            CmpOp::LtE => CmpOp::GtE,
+            CmpOp::Gt => CmpOp::Lt,
+            CmpOp::GtE => CmpOp::LtE,
+            _ => return None,
+        };
+        (mirrored, sliced, &*compare.left)
+    } else {
+        return None;
+    };
+    let Expr::Tuple(bound) = bound else {
+        return None;
+    };
+    let parts = bound
+        .elts
+        .iter()
+        .map(|elt| match elt {
+            Expr::NumberLiteral(literal) => match &literal.value {
+                Number::Int(value) => value.as_u8(),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    let minor = match parts.as_slice() {
+        [3] => 0,
+        [3, minor] => *minor,
+        _ => return None,
+    };
+
+    // Each check reduces to whether the running minor version is at least some value.
+    // The full `sys.version_info` has more parts than the bound, so it sorts after a
+    // bound with its own minor version; its `[:2]` slice equals such a bound.
+    let (at_least, holds) = match op {
+        CmpOp::GtE => (minor, true),
+        CmpOp::Gt if sliced => (minor.checked_add(1)?, true),
+        CmpOp::Gt => (minor, true),
+        CmpOp::Lt => (minor, false),
+        CmpOp::LtE if sliced => (minor.checked_add(1)?, false),
+        CmpOp::LtE => (minor, false),
+        _ => return None,
+    };
+    // Newer versions always run too, so only a check every target version passes is
+    // decided
+    (python_version >= at_least).then_some(holds)
+}
+
+/// Whether `expr` is `sys.version_info`, with `Some(true)` for `sys.version_info[:2]`
+fn version_info_operand(expr: &Expr) -> Option<bool> {
+    let is_version_info = |expr: &Expr| {
+        matches!(expr, Expr::Attribute(attribute)
+            if attribute.attr.as_str() == "version_info"
+                && matches!(attribute.value.as_ref(), Expr::Name(name) if name.id.as_str() == "sys"))
+    };
+    match expr {
+        Expr::Subscript(subscript) if is_version_info(&subscript.value) => {
+            let Expr::Slice(slice) = subscript.slice.as_ref() else {
+                return None;
+            };
+            let upper_is_two = matches!(
+                slice.upper.as_deref(),
+                Some(Expr::NumberLiteral(literal))
+                    if matches!(&literal.value, Number::Int(value) if value.as_u8() == Some(2))
+            );
+            (slice.lower.is_none() && slice.step.is_none() && upper_is_two).then_some(true)
+        }
+        expr if is_version_info(expr) => Some(false),
+        _ => None,
+    }
+}
+
+/// A literal operand of a comparison
+#[derive(Debug, PartialEq)]
+enum Constant<'a> {
+    None,
+    Bool(bool),
+    Int(&'a Int),
+    Str(&'a str),
+}
+
+impl<'a> Constant<'a> {
+    fn from_expr(expr: &'a Expr) -> Option<Self> {
+        match expr {
+            Expr::NoneLiteral(_) => Some(Self::None),
+            Expr::BooleanLiteral(literal) => Some(Self::Bool(literal.value)),
+            Expr::NumberLiteral(literal) => match &literal.value {
+                Number::Int(value) => Some(Self::Int(value)),
+                _ => None,
+            },
+            Expr::StringLiteral(literal) => Some(Self::Str(literal.value.to_str())),
+            _ => None,
+        }
+    }
+
+    /// Python's `==` for constants of the same kind; `True == 1` and the like are not
+    /// resolved
+    fn equals(&self, other: &Self) -> Option<bool> {
+        (std::mem::discriminant(self) == std::mem::discriminant(other)).then(|| self == other)
+    }
+}
+
+/// Names bound anywhere below the module's top-level scope
+fn nested_bindings(module: &ModModule) -> IndexSet<String> {
+    #[derive(Default)]
+    struct BindingCollector {
+        depth: usize,
+        names: IndexSet<String>,
+    }
+
+    impl BindingCollector {
+        fn bind(&mut self, name: &str) {
+            if self.depth > 0 {
+                self.names.insert(name.to_owned());
+            }
+        }
+    }
+
+    impl<'a> Visitor<'a> for BindingCollector {
+        fn visit_stmt(&mut self, stmt: &'a Stmt) {
+            match stmt {
+                Stmt::FunctionDef(function_def) => {
+                    self.bind(&function_def.name);
+                    self.depth += 1;
+                    walk_stmt_ref(self, stmt);
+                    self.depth -= 1;
+                }
+                Stmt::ClassDef(class_def) => {
+                    self.bind(&class_def.name);
+                    self.depth += 1;
+                    walk_stmt_ref(self, stmt);
+                    self.depth -= 1;
+                }
+                _ => walk_stmt_ref(self, stmt),
+            }
+        }
+
+        fn visit_expr(&mut self, expr: &'a Expr) {
+            match expr {
+                Expr::Name(name) if name.ctx != ExprContext::Load => self.bind(&name.id),
+                Expr::Lambda(_)
+                | Expr::ListComp(_)
+                | Expr::SetComp(_)
+                | Expr::DictComp(_)
+                | Expr::Generator(_) => {
+                    self.depth += 1;
+                    walk_expr_ref(self, expr);
+                    self.depth -= 1;
+                }
+                _ => walk_expr_ref(self, expr),
+            }
+        }
+
+        fn visit_parameter(&mut self, parameter: &'a Parameter) {
+            self.names.insert(parameter.name.to_string());
+        }
+
+        fn visit_alias(&mut self, alias: &'a Alias) {
+            let bound = alias.asname.as_ref().unwrap_or(&alias.name);
+            let bound = bound.as_str().split('.').next().unwrap_or_default();
+            self.bind(bound);
+        }
+
+        fn visit_except_handler(&mut self, except_handler: &'a ExceptHandler) {
+            let ExceptHandler::ExceptHandler(handler) = except_handler;
+            if let Some(name) = &handler.name {
+                self.bind(name);
+            }
+            walk_except_handler(self, except_handler);
+        }
+    }
+
+    let mut collector = BindingCollector::default();
+    collector.visit_body(&module.body);
+    collector.names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_codegen::{Generator, Stylist};
+
+    fn apply(source: &str, defines: &[(&str, Value)]) -> String {
+        let mut module = ruff_python_parser::parse_module(source)
+            .expect("valid Python")
+            .into_syntax();
+        let defines = defines
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), value.clone()))
+            .collect();
+        apply_defines(&mut module, Path::new("app.py"), &defines).expect("valid defines");
+        let empty = ruff_python_parser::parse_module("").expect("empty module");
+        let stylist = Stylist::from_tokens(empty.tokens(), "");
+        module
+            .body
+            .iter()
+            .map(|stmt| Generator::from(&stylist).stmt(stmt))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_constant_branches_are_resolved() {
+        let source = "\
+if FEATURE_X:
+    import heavy
+elif DEBUG or verbose():
+    print('debug')
+else:
+    print('plain')
+label = 'x' if not FEATURE_X else 'y'
+if VERSION == '1.4.2':
+    RELEASE = True
+def run():
+    if FEATURE_X:
+        return heavy.run()
+";
+        let applied = apply(
+            source,
+            &[
+                ("FEATURE_X", Value::Boolean(false)),
+                ("DEBUG", Value::Boolean(false)),
+                ("VERSION", Value::String("1.4.2".into())),
+            ],
+        );
+        assert_eq!(
+            applied,
+            "\
+if False or verbose():
+    print(\"debug\")
+else:
+    print(\"plain\")
+label = \"x\"
+RELEASE = True
+def run():
+    pass"
+        );
+    }
+
+    #[test]
+    fn test_version_checks_are_resolved_for_the_target() {
+        let source = "\
+import sys
+if sys.version_info >= (3, 8):
+    from importlib import metadata
+else:
+    import importlib_metadata as metadata
+if sys.version_info[:2] > (3, 10):
+    import tomllib
+if (3, 12) <= sys.version_info:
+    from typing import override
+";
+        let mut module = ruff_python_parser::parse_module(source)
+            .expect("valid Python")
+            .into_syntax();
+        assert!(resolve_version_checks(&mut module, Path::new("app.py"), 10));
+        let empty = ruff_python_parser::parse_module("").expect("empty module");
+        let stylist = Stylist::from_tokens(empty.tokens(), "");
+        let resolved = module
+            .body
+            .iter()
+            .map(|stmt| Generator::from(&stylist).stmt(stmt))
+            .collect::<Vec<_>>()
+            .join("\n");
+        // Checks that newer versions may pass are left alone
+        assert_eq!(
+            resolved,
+            "\
+import sys
+from importlib import metadata
+if sys.version_info[:2] > (3, 10):
+    import tomllib
+if (3, 12) <= sys.version_info:
+    from typing import override"
+        );
+    }
+
+    #[test]
+    fn test_locally_bound_names_are_not_substituted() {
+        let source = "\
+def configure(DEBUG):
+    return DEBUG
+print(DEBUG)
+";
+        let applied = apply(source, &[("DEBUG", Value::Boolean(true))]);
+        assert!(applied.contains("print(DEBUG)"), "{applied}");
+    }
+
+    #[test]
+    fn test_parse_definition() {
+        assert_eq!(
+            parse_definition("FEATURE_X=False"),
+            Some(("FEATURE_X".to_owned(), Value::Boolean(false)))
+        );
+        assert_eq!(
+            parse_definition("RETRIES = 3"),
+            Some(("RETRIES".to_owned(), Value::Integer(3)))
+        );
+        assert_eq!(
+            parse_definition("__BUNDLE_VERSION__=1.4.2"),
+            Some(("__BUNDLE_VERSION__".to_owned(), Value::from("1.4.2")))
+        );
+        assert_eq!(parse_definition("FEATURE_X"), None);
+        assert_eq!(parse_definition("FEATURE-X=1"), None);
+    }
+}
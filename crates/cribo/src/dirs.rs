@@ -29,6 +29,17 @@ pub fn user_cribo_config_dir() -> Option<PathBuf> {
     })
 }
 
+/// Returns the path to the user cache directory for cribo.
+///
+/// On Windows, use, e.g., C:\Users\Alice\AppData\Local\cribo
+/// On Linux and macOS, use `XDG_CACHE_HOME` or $HOME/.cache, e.g., /home/alice/.cache/cribo.
+pub fn user_cribo_cache_dir() -> Option<PathBuf> {
+    match etcetera::choose_base_strategy() {
+        Ok(dirs) => Some(dirs.cache_dir().join(CONFIG_DIR)),
+        Err(_) => None,
+    }
+}
+
 #[cfg(not(windows))]
 fn locate_system_config_xdg(value: Option<&str>) -> Option<PathBuf> {
     // On Linux and macOS, read the `XDG_CONFIG_DIRS` environment variable.
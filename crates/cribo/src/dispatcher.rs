@@ -0,0 +1,188 @@
+//! Bundling a directory of independent scripts into one file
+//!
+//! When the entry is a directory, each script in it becomes a subcommand of the bundle
+//! (`python bundle.py <script> args...`). A generated dispatcher serves as the entry
+//! module: it imports only the script named on the command line, which then runs as
+//! `__main__`, while the modules the scripts share are bundled once.
+
+use std::path::{Path, PathBuf};
+
+use ruff_python_ast::visitor::Visitor;
+use ruff_python_stdlib::identifiers::is_identifier;
+use tempfile::TempDir;
+
+use crate::error::{CriboError, CriboResult};
+use crate::file_provider::FileProvider;
+use crate::module_cache::{parse_module_source, read_module_source};
+use crate::visitors::ImportDiscoveryVisitor;
+
+/// The generated entry of a multi-script bundle, written to a temporary directory that
+/// is removed when the dispatcher is dropped
+#[derive(Debug)]
+pub struct ScriptDispatcher {
+    dir: TempDir,
+    entry: PathBuf,
+    scripts_dir: PathBuf,
+    commands: Vec<String>,
+}
+
+impl ScriptDispatcher {
+    /// Generate the dispatcher for the scripts in `scripts_dir`
+    ///
+    /// Every top-level `.py` file is a command, except private (`_`-prefixed) modules
+    /// and modules another script imports, which are helpers the scripts share. The
+    /// scripts are found and read through `files`; the dispatcher itself is written to
+    /// the filesystem.
+    pub fn generate(
+        scripts_dir: &Path,
+        runtime_prefix: &str,
+        files: &dyn FileProvider,
+    ) -> CriboResult<Self> {
+        let io_error = |message: String| CriboError::Resolution {
+            module: None,
+            path: Some(scripts_dir.to_path_buf()),
+            message,
+        };
+        if !files.is_dir(scripts_dir) {
+            return Err(io_error("The scripts directory does not exist".into()));
+        }
+        let scripts_dir = files.canonicalize(scripts_dir);
+        let commands = find_commands(&scripts_dir, files)?;
+        if commands.is_empty() {
+            return Err(io_error(
+                "The scripts directory contains no scripts to dispatch to".into(),
+            ));
+        }
+        log::info!(
+            "Bundling {} scripts behind a dispatcher: {}",
+            commands.len(),
+            commands.join(", ")
+        );
+
+        let dir = TempDir::with_prefix("cribo-dispatch-").map_err(|err| {
+            io_error(format!(
+                "Failed to create a directory for the dispatcher: {}",
+                err
+            ))
+        })?;
+        let entry = dir.path().join(format!("{}dispatch.py", runtime_prefix));
+        std::fs::write(&entry, dispatcher_source(&commands, runtime_prefix))
+            .map_err(|err| io_error(format!("Failed to write the dispatcher: {}", err)))?;
+        Ok(Self {
+            dir,
+            entry,
+            scripts_dir,
+            commands,
+        })
+    }
+
+    /// The dispatcher script, to be bundled as the entry
+    pub fn entry_path(&self) -> &Path {
+        &self.entry
+    }
+
+    /// Directory the dispatcher was written to
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Directory of the scripts, which must be a source root for the dispatcher to
+    /// import them
+    pub fn scripts_dir(&self) -> &Path {
+        &self.scripts_dir
+    }
+
+    /// Module names of the scripts, which are also their command names
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+}
+
+/// Scripts of `scripts_dir` that are commands, sorted by name
+fn find_commands(scripts_dir: &Path, files: &dyn FileProvider) -> CriboResult<Vec<String>> {
+    let entries = files
+        .list_dir(scripts_dir)
+        .map_err(|err| CriboError::Resolution {
+            module: None,
+            path: Some(scripts_dir.to_path_buf()),
+            message: format!("Failed to read the scripts directory: {}", err),
+        })?;
+    let mut scripts = Vec::new();
+    for path in entries {
+        if !files.is_file(&path) || path.extension().is_none_or(|ext| ext != "py") {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if stem.starts_with('_') {
+            continue;
+        }
+        if !is_identifier(stem) {
+            log::warn!(
+                "Skipping script {:?}: '{}' is not a valid module name",
+                path,
+                stem
+            );
+            continue;
+        }
+        scripts.push((stem.to_owned(), path));
+    }
+    scripts.sort();
+
+    // A script that other scripts import is a shared helper, not a command
+    let mut helpers = Vec::new();
+    for (_, path) in &scripts {
+        let source = read_module_source(files, path, None)?;
+        let parsed = parse_module_source(path, &source)?;
+        let mut visitor = ImportDiscoveryVisitor::new();
+        visitor.visit_module(parsed.syntax());
+        for import in visitor.into_imports() {
+            if import.level > 0 {
+                continue;
+            }
+            if let Some(module) = import.module_name {
+                let top_level = module.split('.').next().unwrap_or(&module);
+                helpers.push(top_level.to_owned());
+            }
+        }
+    }
+    Ok(scripts
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| !helpers.contains(name))
+        .collect())
+}
+
+/// Source of the dispatcher entry: it picks the script named by the first argument,
+/// removes that argument so the script sees the command line it expects, and imports
+/// the script, which runs it
+fn dispatcher_source(commands: &[String], runtime_prefix: &str) -> String {
+    let choices = commands.join(",");
+    let mut branches = String::new();
+    for (index, command) in commands.iter().enumerate() {
+        let keyword = if index == 0 { "if" } else { "elif" };
+        branches.push_str(&format!(
+            "    {keyword} command == \"{command}\":\n        import {command}\n"
+        ));
+    }
+    format!(
+        r#"import sys
+
+
+def {runtime_prefix}dispatch():
+    usage = "usage: " + sys.argv[0] + " {{{choices}}} [args...]"
+    if len(sys.argv) > 1 and sys.argv[1] in ("-h", "--help"):
+        print(usage)
+        sys.exit(0)
+    if len(sys.argv) < 2 or sys.argv[1] not in {commands:?}:
+        print(usage, file=sys.stderr)
+        sys.exit(2)
+    command = sys.argv.pop(1)
+    sys.argv[0] = sys.argv[0] + " " + command
+{branches}
+
+{runtime_prefix}dispatch()
+"#
+    )
+}
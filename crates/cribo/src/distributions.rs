@@ -0,0 +1,324 @@
+//! Distribution names of third-party packages, for requirements files
+//!
+//! Bundles know the top-level packages they import, but `pip` installs distributions,
+//! which are often named differently: `import yaml` needs `PyYAML`, `import cv2` needs
+//! `opencv-python`. A built-in table covers well-known mismatches and the
+//! `distribution_names` setting overrides or extends it, and the metadata of the
+//! distributions installed in the virtual environment takes precedence over the table.
+use std::path::Path;
+
+use cow_utils::CowUtils;
+use indexmap::{IndexMap, IndexSet};
+
+use crate::file_provider::FileProvider;
+use crate::markers::RequirementCondition;
+
+/// Distribution providing a top-level package whose name doesn't match it
+///
+/// Includes the backports of modules removed from the standard library, which are
+/// third-party when the target version no longer has them.
+fn known_distribution(import_name: &str) -> Option<&'static str> {
+    Some(match import_name {
+        "Crypto" => "pycryptodome",
+        "MySQLdb" => "mysqlclient",
+        "OpenSSL" => "pyOpenSSL",
+        "PIL" => "Pillow",
+        "asynchat" => "pyasynchat",
+        "asyncore" => "pyasyncore",
+        "attr" => "attrs",
+        "audioop" => "audioop-lts",
+        "bs4" => "beautifulsoup4",
+        "cgi" | "cgitb" => "legacy-cgi",
+        "cv2" => "opencv-python",
+        "dateutil" => "python-dateutil",
+        "distutils" => "setuptools",
+        "dns" => "dnspython",
+        "docx" => "python-docx",
+        "dotenv" => "python-dotenv",
+        "faiss" => "faiss-cpu",
+        "fitz" => "PyMuPDF",
+        "git" => "GitPython",
+        "imghdr" => "standard-imghdr",
+        "jose" => "python-jose",
+        "jwt" => "PyJWT",
+        "kafka" => "kafka-python",
+        "ldap" => "python-ldap",
+        "magic" => "python-magic",
+        "multipart" => "python-multipart",
+        "pkg_resources" => "setuptools",
+        "pptx" => "python-pptx",
+        "pythoncom" | "pywintypes" | "win32api" | "win32com" | "win32con" | "win32gui" => "pywin32",
+        "serial" => "pyserial",
+        "skimage" => "scikit-image",
+        "sklearn" => "scikit-learn",
+        "slugify" => "python-slugify",
+        "telegram" => "python-telegram-bot",
+        "telnetlib" => "standard-telnetlib",
+        "usb" => "pyusb",
+        "websocket" => "websocket-client",
+        "yaml" => "PyYAML",
+        "zmq" => "pyzmq",
+        _ => return None,
+    })
+}
+
+/// Distribution to install for the top-level package `import_name`
+///
+/// Configured names take precedence over the built-in table; packages in neither are
+/// assumed to be distributed under their import name.
+pub fn distribution_name<'a>(
+    import_name: &'a str,
+    overrides: &'a IndexMap<String, String>,
+) -> &'a str {
+    overrides
+        .get(import_name)
+        .map(String::as_str)
+        .or_else(|| known_distribution(import_name))
+        .unwrap_or(import_name)
+}
+
+/// Distributions installed in `site_packages`, by the top-level packages they provide
+///
+/// Read from each `*.dist-info` (or legacy `*.egg-info`) directory: the name from its
+/// metadata, the packages from `top_level.txt`, or else from the files its `RECORD`
+/// lists.
+pub fn installed_distributions(
+    site_packages: &Path,
+    files: &dyn FileProvider,
+) -> IndexMap<String, String> {
+    let mut installed = IndexMap::new();
+    let Ok(entries) = files.list_dir(site_packages) else {
+        return installed;
+    };
+
+    let mut metadata_dirs: Vec<_> = entries
+        .into_iter()
+        .filter(|path| {
+            files.is_dir(path)
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == "dist-info" || ext == "egg-info")
+        })
+        .collect();
+    metadata_dirs.sort();
+
+    for metadata_dir in metadata_dirs {
+        let Some(distribution) = metadata_name(&metadata_dir, files) else {
+            continue;
+        };
+        for import_name in provided_packages(&metadata_dir, files) {
+            installed
+                .entry(import_name)
+                .or_insert_with(|| distribution.clone());
+        }
+    }
+    installed
+}
+
+/// Name of the distribution described by a metadata directory
+fn metadata_name(metadata_dir: &Path, files: &dyn FileProvider) -> Option<String> {
+    let metadata = ["METADATA", "PKG-INFO"]
+        .iter()
+        .find_map(|file| files.read_to_string(&metadata_dir.join(file)).ok());
+    let from_headers = metadata.as_deref().and_then(|metadata| {
+        metadata
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .find_map(|line| line.strip_prefix("Name:"))
+            .map(|name| name.trim().to_owned())
+    });
+    // `<name>-<version>.dist-info`, with the name normalized by the installer
+    from_headers.or_else(|| {
+        let stem = metadata_dir.file_stem()?.to_str()?;
+        Some(stem.split('-').next().unwrap_or(stem).to_owned())
+    })
+}
+
+/// Top-level packages and modules a distribution installs
+fn provided_packages(metadata_dir: &Path, files: &dyn FileProvider) -> IndexSet<String> {
+    if let Ok(top_level) = files.read_to_string(&metadata_dir.join("top_level.txt")) {
+        return top_level
+            .lines()
+            .filter_map(|line| line.trim().split('/').next())
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect();
+    }
+
+    let Ok(record) = files.read_to_string(&metadata_dir.join("RECORD")) else {
+        return IndexSet::new();
+    };
+    record
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .filter_map(|path| match path.split_once('/') {
+            // Modules, including extension modules such as `_cffi_backend.cpython-312.so`
+            None => [".py", ".so", ".pyd"]
+                .iter()
+                .any(|suffix| path.ends_with(suffix))
+                .then(|| path.split('.').next().unwrap_or(path)),
+            Some((directory, _)) => Some(directory),
+        })
+        .filter(|name| {
+            !name.is_empty()
+                && !name.starts_with('.')
+                && *name != "__pycache__"
+                && !name.ends_with(".dist-info")
+                && !name.ends_with(".data")
+        })
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Lines of a requirements file for the top-level packages a bundle imports
+///
+/// Packages provided by the same distribution (e.g. `win32api` and `win32con`) share
+/// one line. Packages in `conditions` get an environment marker, or are listed as
+/// comments after the other lines when every import of them is optional. Lines are
+/// sorted case-insensitively, like `pip freeze` output.
+pub fn requirement_lines(
+    import_names: &[String],
+    conditions: &IndexMap<String, RequirementCondition>,
+    overrides: &IndexMap<String, String>,
+) -> Vec<String> {
+    let mut distributions: Vec<(String, Option<RequirementCondition>)> = Vec::new();
+    for import_name in import_names {
+        let distribution = distribution_name(import_name, overrides);
+        let condition = conditions.get(import_name).cloned();
+        let listed = distributions
+            .iter_mut()
+            .find(|(listed, _)| listed.eq_ignore_ascii_case(distribution));
+        match listed {
+            // A distribution is needed wherever any of its packages is
+            Some((_, listed_condition)) => {
+                *listed_condition = match (listed_condition.take(), condition) {
+                    (Some(left), Some(right)) => Some(RequirementCondition::either(left, right)),
+                    _ => None,
+                };
+            }
+            None => distributions.push((distribution.to_owned(), condition)),
+        }
+    }
+    distributions
+        .sort_by(|(a, _), (b, _)| a.cow_to_ascii_lowercase().cmp(&b.cow_to_ascii_lowercase()));
+
+    let mut lines = Vec::with_capacity(distributions.len());
+    let mut optional = Vec::new();
+    for (distribution, condition) in distributions {
+        match condition {
+            None => lines.push(distribution),
+            Some(RequirementCondition::Marker(marker)) => {
+                lines.push(format!("{}; {}", distribution, marker));
+            }
+            Some(RequirementCondition::Optional) => {
+                optional.push(format!(
+                    "# {}  (optional, imported with a fallback)",
+                    distribution
+                ));
+            }
+        }
+    }
+    lines.extend(optional);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_provider::OsFileProvider;
+    use crate::markers::Marker;
+
+    #[test]
+    fn test_known_import_names_map_to_distributions() {
+        let overrides = IndexMap::new();
+        assert_eq!(distribution_name("PIL", &overrides), "Pillow");
+        assert_eq!(distribution_name("yaml", &overrides), "PyYAML");
+        assert_eq!(distribution_name("cv2", &overrides), "opencv-python");
+        assert_eq!(distribution_name("distutils", &overrides), "setuptools");
+        assert_eq!(distribution_name("requests", &overrides), "requests");
+    }
+
+    #[test]
+    fn test_configured_names_take_precedence() {
+        let overrides = IndexMap::from([
+            ("cv2".to_owned(), "opencv-python-headless".to_owned()),
+            ("acme".to_owned(), "acme-sdk".to_owned()),
+        ]);
+        assert_eq!(
+            distribution_name("cv2", &overrides),
+            "opencv-python-headless"
+        );
+        assert_eq!(distribution_name("acme", &overrides), "acme-sdk");
+    }
+
+    #[test]
+    fn test_installed_distributions_come_from_metadata() {
+        let site_packages = tempfile::TempDir::new().expect("create site-packages");
+        let yaml_info = site_packages.path().join("PyYAML-6.0.1.dist-info");
+        std::fs::create_dir(&yaml_info).expect("create dist-info");
+        std::fs::write(
+            yaml_info.join("METADATA"),
+            "Metadata-Version: 2.1\nName: PyYAML\nVersion: 6.0.1\n\nName: not a header\n",
+        )
+        .expect("write metadata");
+        std::fs::write(yaml_info.join("top_level.txt"), "_yaml\nyaml\n").expect("write top level");
+        let dateutil_info = site_packages.path().join("python_dateutil-2.9.0.dist-info");
+        std::fs::create_dir(&dateutil_info).expect("create dist-info");
+        std::fs::write(
+            dateutil_info.join("RECORD"),
+            "dateutil/__init__.py,sha256=abc,10\n\
+             dateutil/tz/tz.py,sha256=def,20\n\
+             python_dateutil-2.9.0.dist-info/RECORD,,\n\
+             ../../../bin/dateutil-cli,sha256=ghi,30\n\
+             six.py,sha256=jkl,40\n",
+        )
+        .expect("write record");
+
+        let installed = installed_distributions(site_packages.path(), &OsFileProvider);
+        assert_eq!(
+            installed,
+            IndexMap::from([
+                ("_yaml".to_owned(), "PyYAML".to_owned()),
+                ("yaml".to_owned(), "PyYAML".to_owned()),
+                ("dateutil".to_owned(), "python_dateutil".to_owned()),
+                ("six".to_owned(), "python_dateutil".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_requirement_lines_merge_and_sort() {
+        let imports = ["yaml", "win32api", "requests", "win32con", "PIL"]
+            .map(str::to_owned)
+            .to_vec();
+        assert_eq!(
+            requirement_lines(&imports, &IndexMap::new(), &IndexMap::new()),
+            vec!["Pillow", "pywin32", "PyYAML", "requests"]
+        );
+    }
+
+    #[test]
+    fn test_conditional_requirements_get_markers() {
+        let imports = ["win32api", "ujson", "requests", "win32con"]
+            .map(str::to_owned)
+            .to_vec();
+        let windows_only = RequirementCondition::Marker(Marker::Compare {
+            variable: "sys_platform",
+            equal: true,
+            value: "win32".to_owned(),
+        });
+        let conditions = IndexMap::from([
+            ("win32api".to_owned(), windows_only.clone()),
+            ("win32con".to_owned(), windows_only),
+            ("ujson".to_owned(), RequirementCondition::Optional),
+        ]);
+        assert_eq!(
+            requirement_lines(&imports, &conditions, &IndexMap::new()),
+            vec![
+                "pywin32; sys_platform == \"win32\"",
+                "requests",
+                "# ujson  (optional, imported with a fallback)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! PEP 660 editable installs found in site-packages
+//!
+//! `pip install -e .` leaves the project's sources where they are and makes them
+//! importable from site-packages in one of two ways: a `.pth` file putting the source
+//! root on `sys.path`, or a setuptools `__editable___*_finder.py` import hook mapping
+//! each top-level package to its directory. Either way the distribution's
+//! `direct_url.json` records the project directory with `dir_info.editable` set.
+//! Sources found this way are the user's own code, so they are bundled like the
+//! configured source directories instead of being left as third-party imports.
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use ruff_python_ast::{Expr, Stmt};
+use serde::Deserialize;
+
+use crate::file_provider::FileProvider;
+
+/// Sources of the editable installs in one site-packages directory
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EditableSources {
+    /// `.pth` path entries inside an editable project, scanned like source directories
+    pub roots: Vec<PathBuf>,
+    /// Top-level packages or modules mapped to their location by an import hook
+    pub packages: Vec<(String, PathBuf)>,
+}
+
+#[derive(Deserialize)]
+struct DirectUrl {
+    url: String,
+    #[serde(default)]
+    dir_info: DirInfo,
+}
+
+#[derive(Default, Deserialize)]
+struct DirInfo {
+    #[serde(default)]
+    editable: bool,
+}
+
+impl EditableSources {
+    /// Find the editable installs in `site_packages`, whose `.pth` files add
+    /// `path_entries` to `sys.path`
+    pub fn find(site_packages: &Path, path_entries: &[PathBuf], files: &dyn FileProvider) -> Self {
+        let Ok(entries) = files.list_dir(site_packages) else {
+            return Self::default();
+        };
+
+        let mut projects = Vec::new();
+        let mut finders = Vec::new();
+        for path in entries {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name.ends_with(".dist-info") {
+                if let Some(project) = editable_project(&path.join("direct_url.json"), files) {
+                    projects.push(project);
+                }
+            } else if name.starts_with("__editable__") && name.ends_with("_finder.py") {
+                finders.push(path);
+            }
+        }
+        finders.sort();
+
+        let roots = path_entries
+            .iter()
+            .filter(|entry| projects.iter().any(|project| entry.starts_with(project)))
+            .cloned()
+            .collect();
+        let packages = finders
+            .iter()
+            .flat_map(|finder| finder_mapping(finder, files))
+            .filter(|(_, location)| files.exists(location))
+            .collect();
+        Self { roots, packages }
+    }
+}
+
+/// Project directory of an editable install, from its `direct_url.json`
+fn editable_project(direct_url_path: &Path, files: &dyn FileProvider) -> Option<PathBuf> {
+    let content = files.read_to_string(direct_url_path).ok()?;
+    let direct_url: DirectUrl = match serde_json::from_str(&content) {
+        Ok(direct_url) => direct_url,
+        Err(err) => {
+            debug!("Ignoring {}: {}", direct_url_path.display(), err);
+            return None;
+        }
+    };
+    if !direct_url.dir_info.editable {
+        return None;
+    }
+    let project = file_url_to_path(&direct_url.url)?;
+    Some(files.canonicalize(&project))
+}
+
+/// Local path of a `file://` URL
+fn file_url_to_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+    // `file:///C:/project` on Windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &path[1..],
+        _ => path,
+    };
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(decoded).ok()?))
+}
+
+/// The `MAPPING` of top-level names to locations in a setuptools editable finder
+fn finder_mapping(finder: &Path, files: &dyn FileProvider) -> Vec<(String, PathBuf)> {
+    let Ok(source) = files.read_to_string(finder) else {
+        return Vec::new();
+    };
+    let Ok(parsed) = ruff_python_parser::parse_module(&source) else {
+        debug!("Failed to parse editable finder {}", finder.display());
+        return Vec::new();
+    };
+
+    let mapping = parsed.syntax().body.iter().find_map(|stmt| {
+        let (target, value) = match stmt {
+            Stmt::Assign(assign) => (assign.targets.first()?, assign.value.as_ref()),
+            Stmt::AnnAssign(ann_assign) => {
+                (ann_assign.target.as_ref(), ann_assign.value.as_deref()?)
+            }
+            _ => return None,
+        };
+        match (target, value) {
+            (Expr::Name(name), Expr::Dict(dict)) if name.id.as_str() == "MAPPING" => Some(dict),
+            _ => None,
+        }
+    });
+    let Some(mapping) = mapping else {
+        return Vec::new();
+    };
+
+    mapping
+        .items
+        .iter()
+        .filter_map(|item| match (item.key.as_ref()?, &item.value) {
+            (Expr::StringLiteral(name), Expr::StringLiteral(location)) => Some((
+                name.value.to_str().to_owned(),
+                PathBuf::from(location.value.to_str()),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_provider::OsFileProvider;
+
+    #[test]
+    fn test_file_urls_are_decoded() {
+        assert_eq!(
+            file_url_to_path("file:///home/me/my%20project"),
+            Some(PathBuf::from("/home/me/my project"))
+        );
+        assert_eq!(
+            file_url_to_path("file:///C:/work/app"),
+            Some(PathBuf::from("C:/work/app"))
+        );
+        assert_eq!(file_url_to_path("https://example.com/app"), None);
+    }
+
+    #[test]
+    fn test_finder_mapping_and_editable_path_entries() {
+        let root = tempfile::TempDir::new().expect("create temp dir");
+        let site_packages = root.path().join("site-packages");
+        let project = root.path().join("project");
+        let other = root.path().join("other");
+        std::fs::create_dir_all(project.join("src").join("app")).expect("create project");
+        std::fs::create_dir_all(&other).expect("create other dir");
+        let dist_info = site_packages.join("app-0.1.dist-info");
+        std::fs::create_dir_all(&dist_info).expect("create dist-info");
+        std::fs::write(
+            dist_info.join("direct_url.json"),
+            format!(
+                r#"{{"url": "file://{}", "dir_info": {{"editable": true}}}}"#,
+                project.display()
+            ),
+        )
+        .expect("write direct_url.json");
+        let app_dir = project.join("src").join("app").display().to_string();
+        std::fs::write(
+            site_packages.join("__editable___app_0_1_finder.py"),
+            format!(
+                "MAPPING: dict[str, str] = {{'app': {:?}, 'gone': '/nowhere/gone'}}\nNAMESPACES = {{}}\n",
+                app_dir
+            ),
+        )
+        .expect("write finder");
+
+        let project_src = project
+            .join("src")
+            .canonicalize()
+            .expect("canonical project src");
+        let sources = EditableSources::find(
+            &site_packages,
+            &[project_src.clone(), other],
+            &OsFileProvider,
+        );
+        assert_eq!(sources.roots, vec![project_src]);
+        assert_eq!(
+            sources.packages,
+            vec![("app".to_owned(), project.join("src").join("app"))]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Typed errors for the public bundling API
+//!
+//! Internals keep using `anyhow` for convenience; errors are classified where they
+//! originate and surface through `BundleOrchestrator` and `Config` as [`CriboError`],
+//! so embedders can react to each failure class programmatically.
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use ruff_text_size::TextRange;
+
+/// Result type returned by the public bundling API
+pub type CriboResult<T> = std::result::Result<T, CriboError>;
+
+/// A failure while configuring, analyzing or emitting a bundle
+#[derive(Debug)]
+pub enum CriboError {
+    /// A module or the entry point could not be resolved or read
+    Resolution {
+        /// Module name, when known
+        module: Option<String>,
+        /// File that was being resolved or read, when known
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// A Python source file could not be parsed
+    Parse {
+        path: PathBuf,
+        /// Byte range of the syntax error within the file
+        span: TextRange,
+        /// 1-based line of the start of `span`
+        line: usize,
+        message: String,
+    },
+    /// Circular dependencies that cannot be bundled
+    Cycle {
+        /// Module names participating in each offending cycle
+        cycles: Vec<Vec<String>>,
+        message: String,
+    },
+    /// Transforming modules or writing the bundle failed
+    Emit {
+        /// Output file being written, when the failure happened during output
+        path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    /// The configuration is invalid or could not be loaded
+    Config {
+        /// Configuration file involved, if any
+        path: Option<PathBuf>,
+        message: String,
+    },
+}
+
+impl CriboError {
+    /// Build a parse error from the parser's error and the source it was raised for
+    pub fn parse(path: &Path, source: &str, error: &ruff_python_parser::ParseError) -> Self {
+        Self::Parse {
+            path: path.to_path_buf(),
+            span: error.location,
+            line: line_of(source, error.location),
+            message: error.error.to_string(),
+        }
+    }
+
+    /// Build a parse error for syntax the parser accepts but the target Python
+    /// version does not, such as template strings before Python 3.14
+    pub fn unsupported_syntax(
+        path: &Path,
+        source: &str,
+        error: &ruff_python_parser::UnsupportedSyntaxError,
+    ) -> Self {
+        Self::Parse {
+            path: path.to_path_buf(),
+            span: error.range,
+            line: line_of(source, error.range),
+            message: format!("{} (raise `target-version` to bundle it)", error),
+        }
+    }
+
+    /// File associated with the error, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Resolution { path, .. } | Self::Emit { path, .. } | Self::Config { path, .. } => {
+                path.as_deref()
+            }
+            Self::Parse { path, .. } => Some(path),
+            Self::Cycle { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for CriboError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resolution { message, .. }
+            | Self::Cycle { message, .. }
+            | Self::Config { message, .. } => f.write_str(message),
+            Self::Parse {
+                path,
+                line,
+                message,
+                ..
+            } => write!(
+                f,
+                "Failed to parse Python file: {:?} (line {}): {}",
+                path, line, message
+            ),
+            Self::Emit { source, .. } => write!(f, "{}", source),
+        }
+    }
+}
+
+impl std::error::Error for CriboError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Emit { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for CriboError {
+    /// Recover a typed error raised deeper in the pipeline, or classify the
+    /// remaining untyped failures as emit errors
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<Self>() {
+            Ok(typed) => typed,
+            Err(error) => Self::Emit {
+                path: None,
+                source: error,
+            },
+        }
+    }
+}
+
+/// 1-based line of the start of `span` within `source`
+fn line_of(source: &str, span: TextRange) -> usize {
+    let start = usize::from(span.start()).min(source.len());
+    source.as_bytes()[..start]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count()
+        + 1
+}
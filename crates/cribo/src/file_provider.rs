@@ -0,0 +1,249 @@
+//! Filesystem access of module resolution and source reading
+//!
+//! The resolver and the orchestrator find and read modules through a [`FileProvider`],
+//! so a bundle can be built from sources that only exist in memory, from an overlay of
+//! generated files over a checkout, or in a sandbox without a filesystem at all.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use walkdir::WalkDir;
+
+/// Source of the files that modules are resolved among and read from
+pub trait FileProvider: Send + Sync {
+    /// Contents of the file at `path`
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Contents of the UTF-8 text file at `path`
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read_file(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Whether `path` is a file or a directory
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Whether `path` is a directory
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Files and directories directly inside the directory `path`, in no particular order
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Whether `path` is a file
+    fn is_file(&self, path: &Path) -> bool {
+        self.exists(path) && !self.is_dir(path)
+    }
+
+    /// Canonical form of `path`, or `path` itself when it has none
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+
+    /// `root` and every file and directory below it, parents before their contents
+    fn walk(&self, root: &Path) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(path) = pending.pop() {
+            if self.is_dir(&path) {
+                let mut children = self.list_dir(&path).unwrap_or_default();
+                children.sort();
+                pending.extend(children.into_iter().rev());
+            }
+            paths.push(path);
+        }
+        paths
+    }
+}
+
+impl fmt::Debug for dyn FileProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileProvider")
+    }
+}
+
+/// Shared handle to a file provider
+pub type FileProviderHandle = Arc<dyn FileProvider>;
+
+/// The real filesystem, through `std::fs`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFileProvider;
+
+impl FileProvider for OsFileProvider {
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    /// Symbolic links to directories are listed but not followed, which also keeps
+    /// link cycles from being walked forever
+    fn walk(&self, root: &Path) -> Vec<PathBuf> {
+        WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(walkdir::DirEntry::into_path)
+            .collect()
+    }
+}
+
+/// Files held in memory, optionally layered over another provider
+///
+/// Directories are implied by the files below them. Paths the provider has no file or
+/// directory for are looked up in the fallback, so in-memory files can shadow or add to
+/// a real source tree.
+#[derive(Debug, Default)]
+pub struct MemoryFileProvider {
+    files: IndexMap<PathBuf, Vec<u8>>,
+    fallback: Option<FileProviderHandle>,
+}
+
+impl MemoryFileProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Files only present in memory, layered over `fallback`
+    pub fn with_fallback(fallback: FileProviderHandle) -> Self {
+        Self {
+            files: IndexMap::new(),
+            fallback: Some(fallback),
+        }
+    }
+
+    /// Add or replace the file at `path`
+    pub fn add_file(&mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        self.files.insert(path.into(), contents.into());
+    }
+
+    fn has_dir(&self, path: &Path) -> bool {
+        self.files
+            .keys()
+            .any(|file| file != path && file.starts_with(path))
+    }
+}
+
+impl FileProvider for MemoryFileProvider {
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        if let Some(contents) = self.files.get(path) {
+            return Ok(contents.clone());
+        }
+        match &self.fallback {
+            Some(fallback) => fallback.read_file(path),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in memory", path.display()),
+            )),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+            || self.has_dir(path)
+            || self
+                .fallback
+                .as_ref()
+                .is_some_and(|fallback| fallback.exists(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        !self.files.contains_key(path)
+            && (self.has_dir(path)
+                || self
+                    .fallback
+                    .as_ref()
+                    .is_some_and(|fallback| fallback.is_dir(path)))
+    }
+
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut children: Vec<PathBuf> = match &self.fallback {
+            Some(fallback) if fallback.is_dir(path) => fallback.list_dir(path)?,
+            _ if self.has_dir(path) => Vec::new(),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not a directory in memory", path.display()),
+                ));
+            }
+        };
+        for file in self.files.keys() {
+            let Ok(relative) = file.strip_prefix(path) else {
+                continue;
+            };
+            let Some(first) = relative.components().next() else {
+                continue;
+            };
+            let child = path.join(first);
+            if !children.contains(&child) {
+                children.push(child);
+            }
+        }
+        Ok(children)
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        match &self.fallback {
+            Some(fallback) if !self.files.contains_key(path) && !self.has_dir(path) => {
+                fallback.canonicalize(path)
+            }
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_provider_implies_directories() {
+        let mut files = MemoryFileProvider::new();
+        files.add_file("/src/main.py", "import app\n");
+        files.add_file("/src/app/__init__.py", "");
+        files.add_file("/src/app/util.py", "");
+
+        assert!(files.is_dir(Path::new("/src/app")));
+        assert!(files.is_file(Path::new("/src/app/util.py")));
+        assert!(!files.exists(Path::new("/src/other.py")));
+        assert_eq!(
+            files
+                .read_file(Path::new("/src/main.py"))
+                .expect("read main"),
+            b"import app\n"
+        );
+        assert_eq!(
+            files.walk(Path::new("/src")),
+            [
+                "/src",
+                "/src/app",
+                "/src/app/__init__.py",
+                "/src/app/util.py",
+                "/src/main.py"
+            ]
+            .map(PathBuf::from)
+        );
+    }
+}
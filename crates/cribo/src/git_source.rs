@@ -0,0 +1,192 @@
+//! Bundling the sources of a git revision instead of the working tree
+//!
+//! The files of the revision are read from the repository's object database and
+//! written to a temporary directory, so uncommitted changes in the workspace never
+//! leak into a release bundle and the working tree itself is left untouched.
+
+use std::path::{Component, Path, PathBuf};
+
+use git2::{ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use tempfile::TempDir;
+
+use crate::config::{Config, is_module_entry};
+use crate::error::{CriboError, CriboResult};
+
+/// Git file mode of symbolic links, which are not exported
+const SYMLINK_MODE: i32 = 0o120_000;
+
+/// The files of one git revision, exported to a temporary directory that is removed
+/// when the snapshot is dropped
+#[derive(Debug)]
+pub struct GitSnapshot {
+    dir: TempDir,
+    /// Working tree of the repository, which snapshot paths mirror
+    workdir: PathBuf,
+    /// Directory relative paths are resolved against
+    current_dir: PathBuf,
+    commit: String,
+}
+
+impl GitSnapshot {
+    /// Export `revision` (a branch, tag, commit or any other rev-parse expression) of
+    /// the repository containing the current directory
+    pub fn export(revision: &str) -> CriboResult<Self> {
+        let current_dir = std::env::current_dir().map_err(|err| CriboError::Resolution {
+            module: None,
+            path: None,
+            message: format!("Failed to read the current directory: {}", err),
+        })?;
+        Self::export_from(&current_dir, revision)
+    }
+
+    /// Export `revision` of the repository containing `current_dir`, resolving
+    /// relative paths against `current_dir`
+    pub fn export_from(current_dir: &Path, revision: &str) -> CriboResult<Self> {
+        let git_error = |message: String| CriboError::Resolution {
+            module: None,
+            path: Some(current_dir.to_path_buf()),
+            message,
+        };
+        let repo = Repository::discover(current_dir)
+            .map_err(|err| git_error(format!("Not inside a git repository: {}", err)))?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| git_error("Bare repositories have no working tree to mirror".into()))?;
+        let workdir = canonical(workdir);
+        let commit = repo
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|err| git_error(format!("Unknown git revision '{}': {}", revision, err)))?;
+        let tree = commit
+            .tree()
+            .map_err(|err| git_error(format!("Failed to read tree of '{}': {}", revision, err)))?;
+
+        let dir = TempDir::with_prefix("cribo-git-").map_err(|err| {
+            git_error(format!(
+                "Failed to create a directory for the sources: {}",
+                err
+            ))
+        })?;
+        let mut failure = None;
+        tree.walk(TreeWalkMode::PreOrder, |parent, entry| {
+            // Submodules are separate repositories, and links may point anywhere
+            if entry.kind() != Some(ObjectType::Blob) || entry.filemode() == SYMLINK_MODE {
+                return TreeWalkResult::Ok;
+            }
+            let Some(name) = entry.name() else {
+                return TreeWalkResult::Ok;
+            };
+            let path = dir.path().join(parent).join(name);
+            let written = repo
+                .find_blob(entry.id())
+                .map_err(|err| err.to_string())
+                .and_then(|blob| {
+                    if let Some(parent_dir) = path.parent() {
+                        std::fs::create_dir_all(parent_dir).map_err(|err| err.to_string())?;
+                    }
+                    std::fs::write(&path, blob.content()).map_err(|err| err.to_string())
+                });
+            match written {
+                Ok(()) => TreeWalkResult::Ok,
+                Err(err) => {
+                    failure = Some(format!("Failed to export {}{}: {}", parent, name, err));
+                    TreeWalkResult::Abort
+                }
+            }
+        })
+        .map_err(|err| git_error(format!("Failed to read '{}': {}", revision, err)))?;
+        if let Some(message) = failure {
+            return Err(git_error(message));
+        }
+
+        let commit = commit.id().to_string();
+        log::info!(
+            "Bundling sources of {} ({}) from {:?}",
+            revision,
+            &commit[..12],
+            dir.path()
+        );
+        Ok(Self {
+            dir,
+            workdir,
+            current_dir: canonical(current_dir),
+            commit,
+        })
+    }
+
+    /// Directory the revision's files were written to
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Full id of the exported commit
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+
+    /// The snapshot's copy of `path`, a working tree path that may be relative to the
+    /// current directory
+    ///
+    /// Paths outside the repository, like installed packages, are returned unchanged.
+    pub fn map_path(&self, path: &Path) -> PathBuf {
+        let absolute = self.current_dir.join(path);
+        let absolute = absolute
+            .canonicalize()
+            .unwrap_or_else(|_| normalize(&absolute));
+        match absolute.strip_prefix(&self.workdir) {
+            Ok(relative) => self.root().join(relative),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+
+    /// Point the configured source directories, entries and data files at the snapshot
+    ///
+    /// Outputs still go to the working tree.
+    pub fn apply_to(&self, config: &mut Config) {
+        for src in &mut config.src {
+            *src = self.map_path(src);
+        }
+        for path in config.data_files.values_mut() {
+            *path = self.map_path(path);
+        }
+        if let Some(entry) = &mut config.entry {
+            *entry = self.map_entry(entry);
+        }
+        for bundle in &mut config.bundles {
+            bundle.entry = self.map_entry(&bundle.entry);
+        }
+    }
+
+    /// Map an entry script or directory; dotted module entries are looked up in the
+    /// (mapped) source directories later
+    pub fn map_entry(&self, entry: &str) -> String {
+        if is_module_entry(entry) {
+            entry.to_owned()
+        } else {
+            self.map_path(Path::new(entry))
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+}
+
+/// `path` with symlinks resolved, or as given when it does not exist
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Resolve `.` and `..` in an absolute path without touching the filesystem, since
+/// the path may only exist in the revision
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
@@ -5,6 +5,7 @@ use ruff_python_ast::{self as ast, Expr, ModModule, Stmt};
 use rustc_hash::FxHashSet;
 
 use crate::cribo_graph::{ItemData, ItemType, ModuleDepGraph};
+use crate::visitors::interpolated_expressions;
 
 /// Context for for statement variable collection
 struct ForStmtContext<'a, 'b> {
@@ -75,6 +76,7 @@ impl<'a> GraphBuilder<'a> {
             Stmt::While(while_stmt) => self.process_while_stmt(while_stmt),
             Stmt::With(with_stmt) => self.process_with_stmt(with_stmt),
             Stmt::Try(try_stmt) => self.process_try_stmt(try_stmt),
+            Stmt::Delete(delete_stmt) => self.process_delete(delete_stmt),
             _ => Ok(()), // Other statements
         }
     }
@@ -443,6 +445,38 @@ impl<'a> GraphBuilder<'a> {
         Ok(())
     }
 
+    /// Process a delete statement
+    ///
+    /// Deleting a plain name unbinds it rather than reading it, so such names are only
+    /// recorded as targets. Subscript and attribute targets read their base expression.
+    fn process_delete(&mut self, delete_stmt: &ast::StmtDelete) -> Result<()> {
+        let mut targets = Vec::new();
+        let mut read_vars = FxHashSet::default();
+
+        for target in &delete_stmt.targets {
+            match target {
+                Expr::Name(name) => targets.push(name.id.to_string()),
+                _ => self.collect_vars_in_expr(target, &mut read_vars),
+            }
+        }
+
+        let item_data = ItemData {
+            item_type: ItemType::Delete { targets },
+            var_decls: FxHashSet::default(),
+            read_vars,
+            eventual_read_vars: FxHashSet::default(),
+            write_vars: FxHashSet::default(),
+            eventual_write_vars: FxHashSet::default(),
+            has_side_effects: !delete_stmt.targets.iter().all(Expr::is_name_expr),
+            span: None,
+            imported_names: FxHashSet::default(),
+            reexported_names: FxHashSet::default(),
+        };
+
+        self.graph.add_item(item_data);
+        Ok(())
+    }
+
     /// Process an expression statement
     fn process_expr_stmt(&mut self, expr: &Expr) -> Result<()> {
         let mut read_vars = FxHashSet::default();
@@ -837,12 +871,9 @@ impl<'a> GraphBuilder<'a> {
                     }
                 }
             }
-            Expr::FString(fstring) => {
-                // Process f-string value parts
-                for element in fstring.value.elements() {
-                    if let ast::InterpolatedStringElement::Interpolation(expr_element) = element {
-                        self.collect_vars_in_expr(&expr_element.expression, vars);
-                    }
+            Expr::FString(_) | Expr::TString(_) => {
+                for value in interpolated_expressions(expr) {
+                    self.collect_vars_in_expr(value, vars);
                 }
             }
             _ => {} // Literals and other non-variable expressions
@@ -0,0 +1,178 @@
+//! On-disk snapshots of the dependency graph for warm starts
+//!
+//! A snapshot stores every module of a `CriboGraph` together with the SHA-256 hash of
+//! its source file. When none of those files changed (and the configuration is the
+//! same), the graph can be restored without running discovery or parsing again.
+use anyhow::{Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cribo_graph::{CriboGraph, ModuleDepGraph, ModuleMetadata};
+
+/// Bumped whenever the snapshot layout changes so stale caches are ignored
+const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// A single module stored in a graph snapshot
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedModule {
+    name: String,
+    path: PathBuf,
+    /// SHA-256 of the source file when the snapshot was taken
+    source_hash: String,
+    dep_graph: ModuleDepGraph,
+    metadata: Option<ModuleMetadata>,
+}
+
+/// Serializable snapshot of a dependency graph
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    format_version: u32,
+    entry_path: PathBuf,
+    /// Fingerprint of the configuration the graph was built with
+    config_fingerprint: String,
+    /// Modules in insertion order, so restored module IDs match the original graph
+    modules: Vec<CachedModule>,
+    /// Module dependency edges as (dependent, dependency) name pairs
+    edges: Vec<(String, String)>,
+}
+
+impl GraphSnapshot {
+    /// Capture a snapshot of `graph`, hashing the current contents of every module file
+    pub fn capture(
+        graph: &CriboGraph,
+        entry_path: &Path,
+        config_fingerprint: &str,
+    ) -> Result<Self> {
+        let mut module_ids: Vec<_> = graph.modules.keys().copied().collect();
+        module_ids.sort_by_key(|id| id.as_u32());
+
+        let mut modules = Vec::with_capacity(module_ids.len());
+        let mut edges = Vec::new();
+        for module_id in module_ids {
+            let dep_graph = &graph.modules[&module_id];
+            let path = graph
+                .module_paths
+                .iter()
+                .find(|(_, id)| **id == module_id)
+                .map(|(path, _)| path.clone())
+                .with_context(|| {
+                    format!("No path recorded for module {}", dep_graph.module_name)
+                })?;
+
+            for dependency in graph.get_dependencies(module_id) {
+                edges.push((
+                    dep_graph.module_name.clone(),
+                    graph.modules[&dependency].module_name.clone(),
+                ));
+            }
+
+            modules.push(CachedModule {
+                name: dep_graph.module_name.clone(),
+                source_hash: hash_file(&path)?,
+                path,
+                dep_graph: dep_graph.clone(),
+                metadata: graph.get_metadata(module_id).cloned(),
+            });
+        }
+        edges.sort();
+
+        Ok(Self {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            entry_path: entry_path.to_path_buf(),
+            config_fingerprint: config_fingerprint.to_owned(),
+            modules,
+            edges,
+        })
+    }
+
+    /// Check whether the snapshot still describes the tree on disk
+    pub fn is_fresh(&self, entry_path: &Path, config_fingerprint: &str) -> bool {
+        if self.format_version != SNAPSHOT_FORMAT_VERSION
+            || self.entry_path != entry_path
+            || self.config_fingerprint != config_fingerprint
+        {
+            return false;
+        }
+
+        self.modules.iter().all(|module| {
+            let unchanged = hash_file(&module.path).is_ok_and(|hash| hash == module.source_hash);
+            if !unchanged {
+                debug!("Graph snapshot is stale: {:?} changed", module.path);
+            }
+            unchanged
+        })
+    }
+
+    /// Rebuild a `CriboGraph` from the snapshot
+    pub fn restore(self) -> CriboGraph {
+        let mut graph = CriboGraph::new();
+
+        for module in self.modules {
+            let module_id = graph.add_module(module.name, module.path);
+            let mut dep_graph = module.dep_graph;
+            dep_graph.module_id = module_id;
+            graph.modules.insert(module_id, dep_graph);
+            if let Some(metadata) = module.metadata {
+                graph.update_metadata(module_id, metadata);
+            }
+        }
+
+        for (dependent, dependency) in &self.edges {
+            if let (Some(&from), Some(&to)) = (
+                graph.module_names.get(dependent),
+                graph.module_names.get(dependency),
+            ) {
+                graph.add_module_dependency(from, to);
+            }
+        }
+
+        graph
+    }
+
+    /// Load the snapshot for `entry_path` from `cache_dir`, if one exists and is readable
+    pub fn load(cache_dir: &Path, entry_path: &Path) -> Option<Self> {
+        let snapshot_path = snapshot_path(cache_dir, entry_path);
+        let contents = fs::read_to_string(&snapshot_path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(snapshot) => Some(snapshot),
+            Err(err) => {
+                debug!(
+                    "Ignoring unreadable graph snapshot {:?}: {}",
+                    snapshot_path, err
+                );
+                None
+            }
+        }
+    }
+
+    /// Write the snapshot to `cache_dir`
+    pub fn store(&self, cache_dir: &Path) -> Result<()> {
+        fs::create_dir_all(cache_dir)
+            .with_context(|| format!("Failed to create cache directory: {:?}", cache_dir))?;
+        let snapshot_path = snapshot_path(cache_dir, &self.entry_path);
+        let contents = serde_json::to_string(self)?;
+        fs::write(&snapshot_path, contents)
+            .with_context(|| format!("Failed to write graph snapshot: {:?}", snapshot_path))?;
+        debug!("Stored graph snapshot at {:?}", snapshot_path);
+        Ok(())
+    }
+}
+
+/// Location of the snapshot for a given entry point inside `cache_dir`
+fn snapshot_path(cache_dir: &Path, entry_path: &Path) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(entry_path.to_string_lossy().as_bytes());
+    let key = format!("{:x}", hasher.finalize());
+    cache_dir.join(format!("graph-{}.json", &key[..16]))
+}
+
+/// SHA-256 of a file's contents, hex encoded
+fn hash_file(path: &Path) -> Result<String> {
+    let contents = fs::read(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+    let mut hasher = Sha256::new();
+    hasher.update(&contents);
+    Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,219 @@
+//! Dependency graph of a bundle, serialized for tools outside cribo
+//!
+//! Unlike [`crate::cribo_graph::CriboGraph`], which only knows first-party modules, the
+//! exported graph records how each module ended up in the bundle and also has a node
+//! for every standard library and third-party package the bundled modules import.
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::cribo_graph::ItemType;
+
+/// Version of the JSON export format, bumped on incompatible changes
+pub const BUNDLE_GRAPH_VERSION: u32 = 1;
+
+/// How a node of the exported graph relates to the bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BundleGraphNodeKind {
+    /// The entry script
+    Entry,
+    /// A first-party module inlined into the bundle's top level
+    Inlined,
+    /// A first-party module inlined behind a namespace object
+    Namespace,
+    /// A first-party module wrapped in an init function
+    Wrapper,
+    /// A standard library package, imported as written
+    Stdlib,
+    /// A third-party package the bundle requires
+    ThirdParty,
+}
+
+impl BundleGraphNodeKind {
+    /// Lowercase name used in labels and serialized output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Entry => "entry",
+            Self::Inlined => "inlined",
+            Self::Namespace => "namespace",
+            Self::Wrapper => "wrapper",
+            Self::Stdlib => "stdlib",
+            Self::ThirdParty => "third-party",
+        }
+    }
+
+    fn dot_attributes(self) -> &'static str {
+        match self {
+            Self::Entry => "fillcolor=\"gold\", penwidth=2",
+            Self::Inlined => "fillcolor=\"palegreen\"",
+            Self::Namespace => "fillcolor=\"lightblue\"",
+            Self::Wrapper => "fillcolor=\"orange\"",
+            Self::Stdlib => "shape=ellipse, fillcolor=\"lightgrey\"",
+            Self::ThirdParty => "shape=ellipse, fillcolor=\"plum\"",
+        }
+    }
+}
+
+/// A module or package in the exported graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BundleGraphNode {
+    /// Dotted module name, or the top-level package name for external imports
+    pub name: String,
+    pub kind: BundleGraphNodeKind,
+    /// Source file of first-party modules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Module-level statements of a first-party module that have side effects
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub side_effects: Vec<ItemType>,
+    /// Module-level names renamed in the bundle to avoid conflicts, original to new
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub renames: IndexMap<String, String>,
+}
+
+/// An import of `to` by the module `from`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BundleGraphEdge {
+    pub from: String,
+    pub to: String,
+}
+
+/// Modules of a bundle and the imports between them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BundleGraph {
+    /// First-party modules in bundle order, followed by external packages sorted by name
+    pub nodes: Vec<BundleGraphNode>,
+    pub edges: Vec<BundleGraphEdge>,
+}
+
+impl BundleGraph {
+    /// Render the graph in Graphviz DOT, e.g. for `dot -Tsvg`
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cribo {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=box, style=filled, fontname=\"Helvetica\"];\n");
+        for node in &self.nodes {
+            let _ = writeln!(
+                dot,
+                "    {} [label=\"{}\\n({})\", {}];",
+                dot_id(&node.name),
+                escape_dot(&node.name),
+                node.kind.as_str(),
+                node.kind.dot_attributes()
+            );
+        }
+        for edge in &self.edges {
+            let _ = writeln!(dot, "    {} -> {};", dot_id(&edge.from), dot_id(&edge.to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as pretty-printed JSON, with a format `version`
+    pub fn to_json(&self) -> Result<String> {
+        #[derive(Serialize)]
+        struct Versioned<'a> {
+            version: u32,
+            #[serde(flatten)]
+            graph: &'a BundleGraph,
+        }
+
+        serde_json::to_string_pretty(&Versioned {
+            version: BUNDLE_GRAPH_VERSION,
+            graph: self,
+        })
+        .context("Failed to serialize dependency graph")
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", escape_dot(name))
+}
+
+fn escape_dot(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, kind: BundleGraphNodeKind) -> BundleGraphNode {
+        BundleGraphNode {
+            name: name.to_owned(),
+            kind,
+            path: None,
+            side_effects: Vec::new(),
+            renames: IndexMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_to_dot_annotates_nodes_and_edges() {
+        let graph = BundleGraph {
+            nodes: vec![
+                node("__main__", BundleGraphNodeKind::Entry),
+                node("json", BundleGraphNodeKind::Stdlib),
+            ],
+            edges: vec![BundleGraphEdge {
+                from: "__main__".to_owned(),
+                to: "json".to_owned(),
+            }],
+        };
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph cribo {\n"));
+        assert!(dot.contains("\"__main__\" [label=\"__main__\\n(entry)\""));
+        assert!(dot.contains("\"json\" [label=\"json\\n(stdlib)\", shape=ellipse"));
+        assert!(dot.contains("    \"__main__\" -> \"json\";\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_to_json_is_versioned_and_omits_empty_fields() {
+        let mut entry = node("__main__", BundleGraphNodeKind::Entry);
+        entry.side_effects.push(ItemType::Expression);
+        entry
+            .renames
+            .insert("VALUE".to_owned(), "VALUE___main__".to_owned());
+        let graph = BundleGraph {
+            nodes: vec![entry, node("requests", BundleGraphNodeKind::ThirdParty)],
+            edges: Vec::new(),
+        };
+
+        let json: serde_json::Value =
+            serde_json::from_str(&graph.to_json().expect("serializes")).expect("valid JSON");
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": BUNDLE_GRAPH_VERSION,
+                "nodes": [
+                    {
+                        "name": "__main__",
+                        "kind": "entry",
+                        "side_effects": ["Expression"],
+                        "renames": {"VALUE": "VALUE___main__"},
+                    },
+                    {"name": "requests", "kind": "third-party"},
+                ],
+                "edges": [],
+            })
+        );
+    }
+
+    #[test]
+    fn test_dot_ids_are_escaped() {
+        assert_eq!(dot_id("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    }
+}
@@ -0,0 +1,100 @@
+//! Process-wide table of interned identifiers
+//!
+//! Symbol tables hold the same few identifiers (`logger`, `main`, `Config`, ...) for
+//! every module of a project. Interning stores each distinct name once and keys the
+//! tables by a 4-byte [`SymbolId`], which is cheaper to copy, hash and compare than a
+//! `String`. Names are never freed, so the table only grows with the number of
+//! distinct identifiers seen by the process.
+
+use std::fmt;
+use std::sync::{LazyLock, PoisonError, RwLock};
+
+use indexmap::IndexSet;
+use rustc_hash::FxBuildHasher;
+
+/// Interned names; a name's index is its id
+static TABLE: LazyLock<RwLock<IndexSet<&'static str, FxBuildHasher>>> =
+    LazyLock::new(|| RwLock::new(IndexSet::default()));
+
+/// An interned identifier
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(u32);
+
+impl SymbolId {
+    /// Id of `name`, interning it on first use
+    pub fn intern(name: &str) -> Self {
+        if let Some(id) = Self::lookup(name) {
+            return id;
+        }
+        let mut table = TABLE.write().unwrap_or_else(PoisonError::into_inner);
+        // Another thread may have interned it between the two locks
+        if let Some(index) = table.get_index_of(name) {
+            return Self::from_index(index);
+        }
+        let (index, _) = table.insert_full(Box::leak(Box::from(name)));
+        Self::from_index(index)
+    }
+
+    /// Id of `name` if it was interned; names that never were cannot be in any table
+    /// keyed by `SymbolId`, so lookups need not intern them
+    pub fn lookup(name: &str) -> Option<Self> {
+        TABLE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_index_of(name)
+            .map(Self::from_index)
+    }
+
+    /// The interned name
+    pub fn as_str(self) -> &'static str {
+        TABLE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_index(self.0 as usize)
+            .copied()
+            .expect("symbol ids are only created by interning")
+    }
+
+    fn from_index(index: usize) -> Self {
+        Self(u32::try_from(index).expect("more than u32::MAX distinct symbols"))
+    }
+}
+
+impl fmt::Debug for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for SymbolId {
+    fn from(name: &str) -> Self {
+        Self::intern(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning_returns_the_same_id() {
+        let first = SymbolId::intern("interner_test_name");
+        let second = SymbolId::intern(&String::from("interner_test_name"));
+        assert_eq!(first, second);
+        assert_eq!(first.as_str(), "interner_test_name");
+        assert_ne!(first, SymbolId::intern("interner_test_other"));
+    }
+
+    #[test]
+    fn test_lookup_does_not_intern() {
+        assert_eq!(SymbolId::lookup("interner_test_never_interned"), None);
+        let id = SymbolId::intern("interner_test_looked_up");
+        assert_eq!(SymbolId::lookup("interner_test_looked_up"), Some(id));
+    }
+}
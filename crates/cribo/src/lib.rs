@@ -1,15 +1,49 @@
+pub mod api;
+pub mod bundle_diff;
+pub mod cli;
 pub mod code_generator;
 pub mod combine;
 pub mod config;
 pub mod cribo_graph;
+pub mod data_files;
+pub mod defines;
 pub mod dirs;
+pub mod dispatcher;
+pub mod distributions;
+pub mod editable;
+pub mod error;
+pub mod file_provider;
+#[cfg(feature = "git")]
+pub mod git_source;
 pub mod graph_builder;
+pub mod graph_cache;
+pub mod graph_export;
 pub mod import_rewriter;
+pub mod interner;
+pub mod logging;
+pub mod markers;
+pub mod metrics;
+pub mod module_cache;
+pub mod module_filter;
+pub mod module_map;
 pub mod orchestrator;
+pub mod plugin;
+pub mod pyz;
+pub mod report;
 pub mod resolver;
+pub mod resolver_cache;
+pub mod self_extracting;
 pub mod semantic_bundler;
+pub mod server;
+pub mod source_encoding;
+pub mod stats;
+pub mod tree_shaking;
+pub mod type_check;
+pub mod type_checking_blocks;
 pub mod util;
 pub mod visitors;
+pub mod workspace;
 
 pub use config::Config;
+pub use error::{CriboError, CriboResult};
 pub use orchestrator::BundleOrchestrator;
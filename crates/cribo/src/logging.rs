@@ -0,0 +1,123 @@
+//! Pluggable log routing for applications embedding the bundler
+//!
+//! The bundler emits diagnostics through the `log` facade. Embedders that do not want
+//! a process-wide `env_logger` can attach a [`LogSink`] to a `BundleOrchestrator`;
+//! while that orchestrator runs, every record it produces is delivered to the sink.
+//! Records produced outside a sink scope go to the fallback logger, if any.
+//!
+//! Routing requires the cribo logger to be installed as the global `log` logger
+//! via [`install`]. The CLI installs it with `env_logger` as the fallback.
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Result, anyhow};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Receiver for log records emitted while bundling
+pub trait LogSink: Send + Sync {
+    /// Whether records at `level` should be formatted and delivered at all
+    fn enabled(&self, _level: Level) -> bool {
+        true
+    }
+
+    /// Deliver a single formatted record
+    fn log(&self, level: Level, target: &str, message: &str);
+}
+
+impl fmt::Debug for dyn LogSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LogSink")
+    }
+}
+
+/// Shared handle to a log sink
+pub type LogSinkHandle = Arc<dyn LogSink>;
+
+thread_local! {
+    static CURRENT_SINK: RefCell<Option<LogSinkHandle>> = const { RefCell::new(None) };
+}
+
+/// Fallback logger used when no sink is active on the current thread
+static FALLBACK: OnceLock<Option<Box<dyn Log>>> = OnceLock::new();
+
+/// Global `log` implementation that dispatches to the active sink
+struct CriboLogger;
+
+impl Log for CriboLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        match current_sink() {
+            Some(sink) => sink.enabled(metadata.level()),
+            None => fallback().is_some_and(|logger| logger.enabled(metadata)),
+        }
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        match current_sink() {
+            Some(sink) => {
+                if sink.enabled(record.level()) {
+                    sink.log(record.level(), record.target(), &record.args().to_string());
+                }
+            }
+            None => {
+                if let Some(logger) = fallback() {
+                    logger.log(record);
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(logger) = fallback() {
+            logger.flush();
+        }
+    }
+}
+
+fn fallback() -> Option<&'static dyn Log> {
+    FALLBACK.get().and_then(|logger| logger.as_deref())
+}
+
+/// Install the cribo logger as the global `log` logger
+///
+/// `fallback` receives records emitted outside of any sink scope, and `max_level`
+/// caps what the `log` macros produce at all. Fails if another global logger has
+/// already been installed.
+pub fn install(fallback: Option<Box<dyn Log>>, max_level: LevelFilter) -> Result<()> {
+    if FALLBACK.set(fallback).is_err() {
+        return Err(anyhow!("The cribo logger is already installed"));
+    }
+    log::set_logger(&CriboLogger)
+        .map_err(|err| anyhow!("Failed to install the cribo logger: {}", err))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// The sink active on the current thread, if any
+pub fn current_sink() -> Option<LogSinkHandle> {
+    CURRENT_SINK.with(|sink| sink.borrow().clone())
+}
+
+/// Guard that routes the current thread's log records to a sink until dropped
+#[must_use = "SinkScope must be held in scope for records to reach the sink"]
+pub struct SinkScope {
+    previous: Option<LogSinkHandle>,
+}
+
+impl SinkScope {
+    /// Route records on this thread to `sink`; `None` keeps the current routing
+    pub fn enter(sink: Option<LogSinkHandle>) -> Self {
+        let previous = current_sink();
+        if let Some(sink) = sink {
+            CURRENT_SINK.with(|current| *current.borrow_mut() = Some(sink));
+        }
+        Self { previous }
+    }
+}
+
+impl Drop for SinkScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_SINK.with(|current| *current.borrow_mut() = previous);
+    }
+}
@@ -0,0 +1,212 @@
+//! PEP 508 environment markers for requirements that are only imported conditionally
+//!
+//! Code such as `if sys.platform == "win32": import win32api` only needs its
+//! dependency on some platforms. Instead of requiring it everywhere, the requirements
+//! file lists it as `pywin32; sys_platform == "win32"`, and imports guarded by
+//! `try: ... except ImportError:` are listed as optional.
+use std::fmt;
+
+use crate::visitors::GuardedImport;
+
+/// Condition on the environment a requirement is installed in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marker {
+    /// `variable == "value"`, or `variable != "value"` when `equal` is false
+    Compare {
+        variable: &'static str,
+        equal: bool,
+        value: String,
+    },
+    /// All of the markers hold
+    All(Vec<Marker>),
+    /// Any of the markers holds
+    Any(Vec<Marker>),
+}
+
+impl Marker {
+    /// The marker that holds exactly when this one doesn't
+    pub fn negate(self) -> Self {
+        match self {
+            Self::Compare {
+                variable,
+                equal,
+                value,
+            } => Self::Compare {
+                variable,
+                equal: !equal,
+                value,
+            },
+            Self::All(markers) => Self::Any(markers.into_iter().map(Self::negate).collect()),
+            Self::Any(markers) => Self::All(markers.into_iter().map(Self::negate).collect()),
+        }
+    }
+
+    /// Conjunction of two optional markers, where `None` places no constraint
+    pub fn and(left: Option<Self>, right: Option<Self>) -> Option<Self> {
+        match (left, right) {
+            (Some(Self::All(mut markers)), Some(right)) => {
+                markers.push(right);
+                Some(Self::All(markers))
+            }
+            (Some(left), Some(right)) => Some(Self::All(vec![left, right])),
+            (left, None) => left,
+            (None, right) => right,
+        }
+    }
+
+    /// Disjunction of two markers, without repeating a marker both sides share
+    pub fn or(left: Self, right: Self) -> Self {
+        if left == right {
+            return left;
+        }
+        match left {
+            Self::Any(mut markers) => {
+                if !markers.contains(&right) {
+                    markers.push(right);
+                }
+                Self::Any(markers)
+            }
+            left => Self::Any(vec![left, right]),
+        }
+    }
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compare {
+                variable,
+                equal,
+                value,
+            } => {
+                let operator = if *equal { "==" } else { "!=" };
+                write!(f, "{} {} \"{}\"", variable, operator, value)
+            }
+            Self::All(markers) => write_joined(f, markers, " and "),
+            Self::Any(markers) => write_joined(f, markers, " or "),
+        }
+    }
+}
+
+fn write_joined(f: &mut fmt::Formatter<'_>, markers: &[Marker], separator: &str) -> fmt::Result {
+    for (index, marker) in markers.iter().enumerate() {
+        if index > 0 {
+            f.write_str(separator)?;
+        }
+        match marker {
+            Marker::Compare { .. } => write!(f, "{}", marker)?,
+            _ => write!(f, "({})", marker)?,
+        }
+    }
+    Ok(())
+}
+
+/// Why a third-party package is not needed in every environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequirementCondition {
+    /// Only imported where the marker holds
+    Marker(Marker),
+    /// Only imported inside `try` blocks that handle its absence
+    Optional,
+}
+
+impl RequirementCondition {
+    /// Condition under which a package imported at `sites` is needed
+    ///
+    /// `None` means the package is always needed: some import of it is unguarded, or
+    /// guarded by a condition cribo can't express as a marker.
+    pub fn of_sites<'a>(sites: impl IntoIterator<Item = &'a GuardedImport>) -> Option<Self> {
+        let mut condition = None;
+        for site in sites {
+            let site_condition = if site.optional {
+                Self::Optional
+            } else {
+                Self::Marker(site.marker.clone()?)
+            };
+            condition = Some(match condition {
+                Some(condition) => Self::either(condition, site_condition),
+                None => site_condition,
+            });
+        }
+        condition
+    }
+
+    /// Condition under which a package needed under `left` or under `right` is needed
+    pub fn either(left: Self, right: Self) -> Self {
+        match (left, right) {
+            (Self::Marker(left), Self::Marker(right)) => Self::Marker(Marker::or(left, right)),
+            (Self::Marker(marker), Self::Optional) | (Self::Optional, Self::Marker(marker)) => {
+                Self::Marker(marker)
+            }
+            (Self::Optional, Self::Optional) => Self::Optional,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform(value: &str) -> Marker {
+        Marker::Compare {
+            variable: "sys_platform",
+            equal: true,
+            value: value.to_owned(),
+        }
+    }
+
+    fn site(marker: Option<Marker>, optional: bool) -> GuardedImport {
+        GuardedImport {
+            module: "win32api".to_owned(),
+            marker,
+            optional,
+        }
+    }
+
+    #[test]
+    fn test_markers_render_as_pep_508() {
+        let marker = Marker::and(
+            Some(Marker::or(platform("win32"), platform("cygwin"))),
+            Some(Marker::Compare {
+                variable: "os_name",
+                equal: false,
+                value: "java".to_owned(),
+            }),
+        )
+        .expect("marker");
+        assert_eq!(
+            marker.to_string(),
+            "(sys_platform == \"win32\" or sys_platform == \"cygwin\") and os_name != \"java\""
+        );
+        assert_eq!(
+            marker.negate().to_string(),
+            "(sys_platform != \"win32\" and sys_platform != \"cygwin\") or os_name == \"java\""
+        );
+    }
+
+    #[test]
+    fn test_unguarded_import_makes_package_unconditional() {
+        let sites = [site(Some(platform("win32")), false), site(None, false)];
+        assert_eq!(RequirementCondition::of_sites(&sites), None);
+    }
+
+    #[test]
+    fn test_guarded_sites_combine() {
+        let sites = [
+            site(Some(platform("win32")), false),
+            site(None, true),
+            site(Some(platform("cygwin")), false),
+        ];
+        assert_eq!(
+            RequirementCondition::of_sites(&sites),
+            Some(RequirementCondition::Marker(Marker::Any(vec![
+                platform("win32"),
+                platform("cygwin"),
+            ])))
+        );
+        assert_eq!(
+            RequirementCondition::of_sites(&[site(None, true)]),
+            Some(RequirementCondition::Optional)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Hooks for observing bundling progress from embedding applications
+//!
+//! Library consumers implement [`Metrics`] to feed phase timings and per-module
+//! progress into their own telemetry instead of scraping log output. The bundler
+//! never reads the clock itself; implementations decide what to measure.
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A distinct phase of the bundling workflow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Walking imports from the entry point to find every first-party module
+    Discovery,
+    /// Parsing modules and building the dependency graph
+    GraphConstruction,
+    /// Detecting and classifying circular dependencies
+    CycleAnalysis,
+    /// Transforming modules and generating the bundled code
+    CodeGeneration,
+    /// Writing the bundle and any auxiliary artifacts
+    Output,
+}
+
+impl Phase {
+    /// Stable, machine-friendly name of the phase
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Discovery => "discovery",
+            Self::GraphConstruction => "graph_construction",
+            Self::CycleAnalysis => "cycle_analysis",
+            Self::CodeGeneration => "code_generation",
+            Self::Output => "output",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Receiver for bundling progress events
+///
+/// All methods have empty default implementations, so implementors only override
+/// the events they care about. Events are delivered on the thread driving the bundle.
+pub trait Metrics: Send + Sync {
+    /// Called when a phase begins
+    fn phase_started(&self, _phase: Phase) {}
+
+    /// Called when a phase completes successfully
+    fn phase_finished(&self, _phase: Phase) {}
+
+    /// Called once for every module parsed and added to the dependency graph
+    fn module_processed(&self, _module_name: &str, _path: &Path) {}
+
+    /// Called once the bundle's code has been generated, before it is written
+    fn bundle_finished(&self, _summary: &BundleSummary) {}
+}
+
+/// Size and shape of a generated bundle
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BundleSummary {
+    /// Modules whose statements run at the bundle's top level, including the entry
+    pub inlined_modules: usize,
+    /// Modules inlined together with a namespace object exposing their symbols
+    pub namespace_modules: usize,
+    /// Modules wrapped in an init function
+    pub wrapper_modules: usize,
+    /// Lines of all bundled source files
+    pub lines_in: usize,
+    /// Lines of the generated bundle
+    pub lines_out: usize,
+    /// Top-level third-party packages the bundle imports
+    pub third_party_packages: usize,
+}
+
+impl BundleSummary {
+    /// Total number of bundled modules
+    pub const fn modules(&self) -> usize {
+        self.inlined_modules + self.namespace_modules + self.wrapper_modules
+    }
+}
+
+impl std::ops::AddAssign for BundleSummary {
+    fn add_assign(&mut self, other: Self) {
+        self.inlined_modules += other.inlined_modules;
+        self.namespace_modules += other.namespace_modules;
+        self.wrapper_modules += other.wrapper_modules;
+        self.lines_in += other.lines_in;
+        self.lines_out += other.lines_out;
+        self.third_party_packages += other.third_party_packages;
+    }
+}
+
+/// Metrics sink that ignores every event
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+impl fmt::Debug for dyn Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Metrics")
+    }
+}
+
+/// Shared handle to a metrics sink
+pub type MetricsHandle = Arc<dyn Metrics>;
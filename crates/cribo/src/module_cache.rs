@@ -0,0 +1,219 @@
+//! Parsed modules shared between passes and between bundling runs
+//!
+//! Discovery, graph construction and code generation all need a module's AST, and the
+//! bundles of a workspace usually share most of their modules. The cache hands out a
+//! copy of the parsed module as long as the file's contents are unchanged, so every
+//! file is parsed once per process.
+//!
+//! Entries are keyed by a hash of the file's contents together with the semantic
+//! analysis computed from them, so a long-lived orchestrator (or several sharing one
+//! cache) rebuilding after an edit only re-parses and re-analyzes the edited files.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use indexmap::IndexMap;
+use ruff_python_ast::{ModModule, PythonVersion};
+use ruff_python_parser::{Mode, ParseOptions, Parsed};
+use sha2::{Digest, Sha256};
+
+use crate::error::{CriboError, CriboResult};
+use crate::file_provider::FileProvider;
+use crate::plugin::{BundlePluginHandle, PluginModule, run_hook};
+use crate::semantic_bundler::{ModuleSemanticAnalyzer, ModuleSymbols};
+use crate::source_encoding::decode_source;
+use crate::util::normalize_line_endings;
+
+/// Parsed modules keyed by the hash of their source
+///
+/// Safe to share between threads: different modules are parsed concurrently, while
+/// threads asking for the same module wait for a single parse.
+#[derive(Debug, Default)]
+pub struct ModuleCache {
+    // Bundling is synchronous, so blocking locks are the right tool here
+    entries: Mutex<CacheEntries>,
+    parses: AtomicUsize,
+    analyses: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct CacheEntries {
+    /// Content hash of the source last read from each path
+    paths: IndexMap<PathBuf, String>,
+    /// Modules keyed by content hash
+    modules: IndexMap<String, Arc<CachedModule>>,
+}
+
+#[derive(Debug, Default)]
+struct CachedModule {
+    /// Filled in by the first successful parse, together with the oldest Python minor
+    /// version the source was checked against
+    ast: Mutex<Option<(ModModule, u8)>>,
+    /// Semantic analysis keyed by the path it was computed for, since identical
+    /// sources at different paths may still be analyzed differently
+    symbols: Mutex<IndexMap<PathBuf, ModuleSymbols>>,
+}
+
+/// Shared handle to a module cache
+pub type ModuleCacheHandle = Arc<ModuleCache>;
+
+impl ModuleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of times a module actually had to be parsed
+    pub fn parse_count(&self) -> usize {
+        self.parses.load(Ordering::Relaxed)
+    }
+
+    /// Number of times a module actually had to be semantically analyzed
+    pub fn analysis_count(&self) -> usize {
+        self.analyses.load(Ordering::Relaxed)
+    }
+
+    /// Read the module at `path` from `files` and return its source with normalized line endings
+    /// and its AST, parsing only when the cached AST is missing or stale
+    ///
+    /// Syntax newer than Python 3.`python_minor` is an error; a source already
+    /// checked against an older version is not parsed again. The `pre_parse` hooks of
+    /// `plugins` rewrite the source before it is hashed, so rewritten sources are
+    /// cached under their own contents.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn load(
+        &self,
+        files: &dyn FileProvider,
+        path: &Path,
+        module_name: Option<&str>,
+        python_minor: u8,
+        plugins: &[BundlePluginHandle],
+    ) -> CriboResult<(String, ModModule)> {
+        let mut source = read_module_source(files, path, module_name)?;
+        let module = PluginModule::new(module_name, path);
+        run_hook(plugins, "pre_parse", |plugin| {
+            plugin.pre_parse(&module, &mut source)
+        })?;
+        let content_hash = content_hash(&source);
+
+        let entry = {
+            let mut entries = lock(&self.entries);
+            let previous = entries
+                .paths
+                .insert(path.to_path_buf(), content_hash.clone());
+            if let Some(previous) = previous.filter(|previous| *previous != content_hash) {
+                // Drop the stale version unless another file has the same contents
+                if !entries.paths.values().any(|hash| *hash == previous) {
+                    entries.modules.shift_remove(&previous);
+                }
+            }
+            Arc::clone(entries.modules.entry(content_hash).or_default())
+        };
+
+        let mut ast = lock(&entry.ast);
+        if let Some((ast, checked_minor)) = ast.as_ref() {
+            if *checked_minor <= python_minor {
+                return Ok((source, ast.clone()));
+            }
+        }
+        let parsed = parse_module_for_target(path, &source, python_minor)?.into_syntax();
+        self.parses.fetch_add(1, Ordering::Relaxed);
+        *ast = Some((parsed.clone(), python_minor));
+        Ok((source, parsed))
+    }
+
+    /// Module-level symbols and class scopes of the module at `path`, analyzing
+    /// `source` and its `ast` (as returned by [`Self::load`]) only once per contents
+    pub(crate) fn symbols(
+        &self,
+        path: &Path,
+        source: &str,
+        ast: &ModModule,
+    ) -> anyhow::Result<ModuleSymbols> {
+        let entry = lock(&self.entries)
+            .modules
+            .get(&content_hash(source))
+            .cloned();
+        let Some(entry) = entry else {
+            // Evicted by a concurrent edit; analyze without caching
+            return self.analyze(path, source, ast);
+        };
+
+        let mut symbols = lock(&entry.symbols);
+        if let Some(cached) = symbols.get(path) {
+            return Ok(cached.clone());
+        }
+        let analyzed = self.analyze(path, source, ast)?;
+        symbols.insert(path.to_path_buf(), analyzed.clone());
+        Ok(analyzed)
+    }
+
+    fn analyze(&self, path: &Path, source: &str, ast: &ModModule) -> anyhow::Result<ModuleSymbols> {
+        let (exported_symbols, class_scopes) =
+            ModuleSemanticAnalyzer::extract_symbols_and_class_scopes(source, path, ast)?;
+        self.analyses.fetch_add(1, Ordering::Relaxed);
+        Ok(ModuleSymbols {
+            exported_symbols,
+            class_scopes,
+        })
+    }
+}
+
+/// SHA-256 of a module's source, hex encoded
+fn content_hash(source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panic while holding a lock cannot leave an entry half-written
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Read a module's source, decoded from its declared encoding, with normalized line
+/// endings
+pub(crate) fn read_module_source(
+    files: &dyn FileProvider,
+    path: &Path,
+    module_name: Option<&str>,
+) -> CriboResult<String> {
+    let error = |message: String| CriboError::Resolution {
+        module: module_name.map(str::to_owned),
+        path: Some(path.to_path_buf()),
+        message,
+    };
+    let bytes = files
+        .read_file(path)
+        .map_err(|err| error(format!("Failed to read file: {:?}: {}", path, err)))?;
+    let source = decode_source(&bytes)
+        .map_err(|err| error(format!("Failed to decode file: {:?}: {}", path, err)))?;
+    Ok(normalize_line_endings(source))
+}
+
+/// Parse a module's source, reporting syntax errors with their location
+pub(crate) fn parse_module_source(path: &Path, source: &str) -> CriboResult<Parsed<ModModule>> {
+    ruff_python_parser::parse_module(source).map_err(|err| CriboError::parse(path, source, &err))
+}
+
+/// Parse a module's source like [`parse_module_source`], also rejecting syntax that
+/// Python 3.`python_minor` cannot run, such as PEP 701 f-strings with nested quotes
+/// before 3.12 or template strings before 3.14
+pub(crate) fn parse_module_for_target(
+    path: &Path,
+    source: &str,
+    python_minor: u8,
+) -> CriboResult<Parsed<ModModule>> {
+    let options = ParseOptions::from(Mode::Module).with_target_version(PythonVersion {
+        major: 3,
+        minor: python_minor,
+    });
+    let parsed = ruff_python_parser::parse(source, options)
+        .map_err(|err| CriboError::parse(path, source, &err))?
+        .try_into_module()
+        .expect("sources are parsed in module mode");
+    if let Some(error) = parsed.unsupported_syntax_errors().first() {
+        return Err(CriboError::unsupported_syntax(path, source, error));
+    }
+    Ok(parsed)
+}
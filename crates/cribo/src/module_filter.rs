@@ -0,0 +1,121 @@
+//! `include`/`exclude` globs deciding which files under the source directories are
+//! first-party
+//!
+//! Patterns are matched against a file's path relative to the source directory it was
+//! found in. A pattern without a `/` matches a file or directory name at any depth, so
+//! `tests` excludes every `tests/` directory and `conftest.py` every such file; patterns
+//! naming a directory also cover everything inside it.
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::error::{CriboError, CriboResult};
+
+/// Compiled `include` and `exclude` patterns
+#[derive(Debug, Clone)]
+pub struct ModuleFilter {
+    /// `None` when every file is included
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl Default for ModuleFilter {
+    fn default() -> Self {
+        Self {
+            include: None,
+            exclude: GlobSet::empty(),
+        }
+    }
+}
+
+impl ModuleFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> CriboResult<Self> {
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(build_glob_set("include", include)?)
+            },
+            exclude: build_glob_set("exclude", exclude)?,
+        })
+    }
+
+    /// Whether the file at `relative_path` within its source directory is first-party
+    pub fn allows(&self, relative_path: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(relative_path))
+            && !self.exclude.is_match(relative_path)
+    }
+}
+
+fn build_glob_set(setting: &str, patterns: &[String]) -> CriboResult<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let trimmed = pattern.trim_end_matches('/');
+        let expanded = if trimmed.contains('/') {
+            vec![trimmed.to_owned(), format!("{}/**", trimmed)]
+        } else {
+            vec![format!("**/{}", trimmed), format!("**/{}/**", trimmed)]
+        };
+        for glob in expanded {
+            let glob = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| CriboError::Config {
+                    path: None,
+                    message: format!("Invalid {} pattern '{}': {}", setting, pattern, err),
+                })?;
+            builder.add(glob);
+        }
+    }
+    builder.build().map_err(|err| CriboError::Config {
+        path: None,
+        message: format!("Invalid {} patterns: {}", setting, err),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns
+            .iter()
+            .map(|pattern| (*pattern).to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_names_without_slash_match_at_any_depth() {
+        let filter = ModuleFilter::new(&[], &patterns(&["tests", "conftest.py", "*_pb2.py"]))
+            .expect("valid patterns");
+        assert!(!filter.allows(Path::new("tests/test_app.py")));
+        assert!(!filter.allows(Path::new("app/tests/__init__.py")));
+        assert!(!filter.allows(Path::new("conftest.py")));
+        assert!(!filter.allows(Path::new("app/api/user_pb2.py")));
+        assert!(filter.allows(Path::new("app/testsuite.py")));
+        assert!(filter.allows(Path::new("app/main.py")));
+    }
+
+    #[test]
+    fn test_include_limits_first_party_files() {
+        let filter = ModuleFilter::new(&patterns(&["app/", "main.py"]), &patterns(&["app/gen"]))
+            .expect("valid patterns");
+        assert!(filter.allows(Path::new("main.py")));
+        assert!(filter.allows(Path::new("app/models/user.py")));
+        assert!(!filter.allows(Path::new("app/gen/schema.py")));
+        assert!(!filter.allows(Path::new("scripts/deploy.py")));
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_a_config_error() {
+        let error = ModuleFilter::new(&[], &patterns(&["gen/[a-"])).expect_err("invalid glob");
+        assert!(
+            error
+                .to_string()
+                .contains("Invalid exclude pattern 'gen/[a-'"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Machine-readable map relating each region of a bundle to the module it came from
+//!
+//! Coverage mappers, debuggers and auditing tools use it to translate positions in the
+//! bundle back to the source tree.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::code_generator::{BundleSection, ModuleStrategy};
+use crate::util::write_atomically;
+
+/// Version of the module map format, bumped on incompatible changes
+pub const MODULE_MAP_VERSION: u32 = 1;
+
+/// Where every bundled module ended up in the generated code
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModuleMap {
+    pub version: u32,
+    /// Modules in the order their code appears in the bundle
+    pub modules: Vec<ModuleMapEntry>,
+}
+
+/// Location of one module's code in the bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleMapEntry {
+    /// Dotted module name
+    pub name: String,
+    /// Source file, relative to the entry script's directory when possible
+    pub path: String,
+    pub strategy: ModuleStrategy,
+    /// Name of the init function's module object for wrapped modules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synthetic_name: Option<String>,
+    /// Half-open byte range in the bundle; absent when the module emitted no code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<[usize; 2]>,
+    /// Inclusive 1-based line range in the bundle; absent when the module emitted no code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<[usize; 2]>,
+}
+
+/// Position of a chunk of generated code in the bundle
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Span {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl ModuleMap {
+    /// Build the map from the bundler's sections and the span of every generated
+    /// statement, indexed like the bundled body
+    pub(crate) fn from_sections(
+        sections: &[BundleSection],
+        statement_spans: &[Span],
+        sorted_modules: &[(String, PathBuf, Vec<String>)],
+        entry_module_name: &str,
+    ) -> Self {
+        let entry_dir = sorted_modules
+            .iter()
+            .find(|(name, _, _)| name == entry_module_name)
+            .and_then(|(_, path, _)| path.parent());
+
+        let modules = sections
+            .iter()
+            .map(|section| {
+                let path = sorted_modules
+                    .iter()
+                    .find(|(name, _, _)| *name == section.module_name)
+                    .map(|(_, path, _)| relative_module_path(path, entry_dir))
+                    .unwrap_or_default();
+                let first = statement_spans.get(section.statements.start);
+                let last = section
+                    .statements
+                    .end
+                    .checked_sub(1)
+                    .and_then(|last| statement_spans.get(last));
+                let (bytes, lines) = match (first, last) {
+                    (Some(first), Some(last)) if !section.statements.is_empty() => (
+                        Some([first.start_byte, last.end_byte]),
+                        Some([first.start_line, last.end_line]),
+                    ),
+                    _ => (None, None),
+                };
+                ModuleMapEntry {
+                    name: section.module_name.clone(),
+                    path,
+                    strategy: section.strategy,
+                    synthetic_name: section.synthetic_name.clone(),
+                    bytes,
+                    lines,
+                }
+            })
+            .collect();
+
+        Self {
+            version: MODULE_MAP_VERSION,
+            modules,
+        }
+    }
+
+    /// Write the map as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize module map")?;
+        write_atomically(path, json, false)
+            .with_context(|| format!("Failed to write module map: {:?}", path))?;
+        log::info!("Module map written to: {:?}", path);
+        Ok(())
+    }
+}
+
+/// Where the module map of a bundle written to `output_path` goes: next to it, with
+/// `.map.json` appended to the file name
+pub fn module_map_path(output_path: &Path) -> PathBuf {
+    let mut file_name = output_path
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| "bundle.py".into());
+    file_name.push(".map.json");
+    output_path.with_file_name(file_name)
+}
+
+/// `path` relative to `base` with `/` separators, or the full path outside of `base`
+pub(crate) fn relative_module_path(path: &Path, base: Option<&Path>) -> String {
+    let relative = base
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_map_path_appends_suffix() {
+        assert_eq!(
+            module_map_path(Path::new("dist/app.py")),
+            PathBuf::from("dist/app.py.map.json")
+        );
+    }
+}
@@ -1,21 +1,48 @@
-use anyhow::{Context, Result, anyhow};
-use indexmap::IndexSet;
+use anyhow::{Context, Result};
+use indexmap::{IndexMap, IndexSet};
 use log::{debug, info, warn};
 use ruff_python_ast::{ModModule, Stmt, StmtImportFrom};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::code_generator::HybridStaticBundler;
+use crate::bundle_diff::unified_diff;
+use crate::code_generator::{BundleSection, HybridStaticBundler, ModuleStrategy, WrapperReason};
 use crate::config::Config;
 use crate::cribo_graph::{
     CircularDependencyAnalysis, CircularDependencyGroup, CircularDependencyType, CriboGraph,
     ResolutionStrategy,
 };
+use crate::data_files::embed_data_files;
+use crate::defines::{apply_defines, resolve_version_checks};
+use crate::dispatcher::ScriptDispatcher;
+use crate::distributions::requirement_lines;
+use crate::error::{CriboError, CriboResult};
+use crate::file_provider::{FileProviderHandle, MemoryFileProvider, OsFileProvider};
+use crate::graph_cache::GraphSnapshot;
+use crate::graph_export::{BundleGraph, BundleGraphEdge, BundleGraphNode, BundleGraphNodeKind};
 use crate::import_rewriter::{ImportDeduplicationStrategy, ImportRewriter};
-use crate::resolver::{ImportType, ModuleResolver};
-use crate::semantic_bundler::SemanticBundler;
-use crate::util::{module_name_from_relative, normalize_line_endings};
-use crate::visitors::{ImportDiscoveryVisitor, ImportLocation};
+use crate::logging::{LogSinkHandle, SinkScope};
+use crate::markers::RequirementCondition;
+use crate::metrics::{BundleSummary, MetricsHandle, NoopMetrics, Phase};
+use crate::module_cache::{
+    ModuleCache, ModuleCacheHandle, parse_module_for_target, parse_module_source,
+    read_module_source,
+};
+use crate::module_map::{ModuleMap, Span, module_map_path, relative_module_path};
+use crate::plugin::{BundlePluginHandle, PluginModule, run_hook};
+use crate::pyz::{pyz_path, write_pyz};
+use crate::report::{BundleReport, OrphanModule, StubbedModule, report_path};
+use crate::resolver::{
+    ClassificationCounts, ImportClassifierHandle, ImportType, ModuleResolver, stdlib_versions,
+};
+use crate::resolver_cache::ResolverCacheHandle;
+use crate::self_extracting::self_extracting;
+use crate::semantic_bundler::{ModuleSemanticAnalyzer, ModuleSymbols, SemanticBundler};
+use crate::type_check::{TypeCheckFinding, check_bundle};
+use crate::type_checking_blocks::resolve_type_checking_blocks;
+use crate::util::{make_executable, module_name_from_relative, write_atomically};
+use crate::visitors::{ImportDiscoveryVisitor, ImportGuardVisitor, ImportLocation};
 
 /// Type alias for module processing queue
 type ModuleQueue = Vec<(String, PathBuf)>;
@@ -24,6 +51,15 @@ type ProcessedModules = IndexSet<String>;
 /// Type alias for parsed module data with AST and source
 type ParsedModuleData = (String, PathBuf, Vec<String>, ModModule, String);
 
+/// Output path that artifact file names derive from when the bundle goes to stdout
+const STDOUT_ARTIFACT_BASE: &str = "bundle.py";
+
+/// Directory the sources given to `BundleOrchestrator::bundle_from_sources` appear in
+const IN_MEMORY_SOURCE_ROOT: &str = "/<sources>";
+
+/// Most import chains `BundleOrchestrator::explain` lists for one module
+pub const MAX_EXPLAINED_CHAINS: usize = 20;
+
 /// Context for import extraction operations
 struct ImportExtractionContext<'a> {
     imports: &'a mut Vec<String>,
@@ -54,6 +90,10 @@ struct StaticBundleParams<'a> {
     entry_module_name: &'a str,
     graph: &'a CriboGraph,
     circular_dep_analysis: Option<&'a CircularDependencyAnalysis>,
+    /// Sorted top-level third-party packages the bundle imports in every environment
+    requirements: &'a [String],
+    /// Distributions providing `requirements` whose names differ from them
+    distribution_names: &'a IndexMap<String, String>,
 }
 
 /// Context for dependency building operations
@@ -73,9 +113,55 @@ struct GraphBuildParams<'a> {
     graph: &'a mut CriboGraph,
 }
 
+/// Everything a successful bundling run produced, before any output is written
+pub(crate) struct BundleOutcome {
+    pub code: String,
+    /// Bundled first-party modules in emission order
+    pub modules: Vec<(String, PathBuf)>,
+    /// Sorted top-level third-party packages
+    pub requirements: Vec<String>,
+    /// Why some of `requirements` are only needed in some environments, by package
+    pub requirement_conditions: IndexMap<String, RequirementCondition>,
+    /// Distributions providing `requirements`, by package, where the names differ
+    pub distribution_names: IndexMap<String, String>,
+    /// Function-level cycles that were resolved during bundling
+    pub resolved_cycles: Vec<Vec<String>>,
+    pub classification_counts: ClassificationCounts,
+    /// Where each module's code lies in `code`
+    pub module_map: ModuleMap,
+    /// Why each wrapper module was not inlined
+    pub wrapper_reasons: Vec<(String, Vec<WrapperReason>)>,
+    /// First-party modules under the source roots that are not bundled
+    pub orphans: Vec<OrphanModule>,
+    /// Bundled modules whose configured stub replaced them
+    pub stubbed_modules: Vec<StubbedModule>,
+    /// What the configured type checker reported about the bundle
+    pub type_check_findings: Vec<TypeCheckFinding>,
+    /// Bundled modules, the packages they import and the imports between them
+    pub dependency_graph: BundleGraph,
+}
+
+/// Generated bundle source together with its module map
+struct EmittedBundle {
+    code: String,
+    module_map: ModuleMap,
+    wrapper_reasons: Vec<(String, Vec<WrapperReason>)>,
+}
+
 pub struct BundleOrchestrator {
     config: Config,
     semantic_bundler: SemanticBundler,
+    metrics: MetricsHandle,
+    log_sink: Option<LogSinkHandle>,
+    import_classifier: Option<ImportClassifierHandle>,
+    module_cache: ModuleCacheHandle,
+    /// Resolvers reused between runs instead of rescanning the source directories
+    resolver_cache: Option<ResolverCacheHandle>,
+    plugins: Vec<BundlePluginHandle>,
+    /// Where modules are found and read
+    file_provider: FileProviderHandle,
+    /// Generated entry of the last run, when its entry was a directory of scripts
+    script_dispatcher: Option<ScriptDispatcher>,
 }
 
 impl BundleOrchestrator {
@@ -83,9 +169,63 @@ impl BundleOrchestrator {
         Self {
             config,
             semantic_bundler: SemanticBundler::new(),
+            metrics: Arc::new(NoopMetrics),
+            log_sink: None,
+            import_classifier: None,
+            module_cache: Arc::new(ModuleCache::new()),
+            resolver_cache: None,
+            plugins: Vec::new(),
+            file_provider: Arc::new(OsFileProvider),
+            script_dispatcher: None,
         }
     }
 
+    /// Report bundling progress to `metrics` instead of discarding it
+    pub fn with_metrics(mut self, metrics: MetricsHandle) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Deliver log records produced by this orchestrator to `sink`
+    ///
+    /// Takes effect once the cribo logger is installed with `logging::install`.
+    pub fn with_log_sink(mut self, sink: LogSinkHandle) -> Self {
+        self.log_sink = Some(sink);
+        self
+    }
+
+    /// Let `classifier` decide how imports are classified before the built-in rules
+    pub fn with_import_classifier(mut self, classifier: ImportClassifierHandle) -> Self {
+        self.import_classifier = Some(classifier);
+        self
+    }
+
+    /// Find and read modules through `files` instead of the real filesystem
+    ///
+    /// Outputs and other artifacts are still written to the filesystem.
+    pub fn with_file_provider(mut self, files: FileProviderHandle) -> Self {
+        self.file_provider = files;
+        self
+    }
+
+    /// Reuse modules parsed by other orchestrators sharing `cache`
+    pub fn with_module_cache(mut self, cache: ModuleCacheHandle) -> Self {
+        self.module_cache = cache;
+        self
+    }
+
+    /// Reuse module resolvers built by other orchestrators sharing `cache`
+    pub fn with_resolver_cache(mut self, cache: ResolverCacheHandle) -> Self {
+        self.resolver_cache = Some(cache);
+        self
+    }
+
+    /// Run `plugin`'s hooks after those of the plugins registered before it
+    pub fn with_plugin(mut self, plugin: BundlePluginHandle) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+
     /// Format error message for unresolvable cycles
     fn format_unresolvable_cycles_error(cycles: &[CircularDependencyGroup]) -> String {
         let mut error_msg = String::from("Unresolvable circular dependencies detected:\n\n");
@@ -103,6 +243,11 @@ impl BundleOrchestrator {
         error_msg
     }
 
+    /// Module names of each cycle, for structured cycle errors
+    fn cycle_module_names(cycles: &[CircularDependencyGroup]) -> Vec<Vec<String>> {
+        cycles.iter().map(|cycle| cycle.modules.clone()).collect()
+    }
+
     /// Core bundling logic shared between file and string output modes
     /// Returns the entry module name, parsed modules, and circular dependency analysis, with graph and resolver populated via mutable references
     fn bundle_core(
@@ -115,7 +260,54 @@ impl BundleOrchestrator {
         Vec<ParsedModuleData>,
         Option<CircularDependencyAnalysis>,
     )> {
+        let (entry_module_name, parsed_modules, resolver) =
+            self.build_module_graph(entry_path, graph)?;
+        let circular_dep_analysis = self.analyze_cycles(graph)?;
+
+        // Set the resolver for the caller to use
+        *resolver_opt = Some(resolver);
+
+        Ok((entry_module_name, parsed_modules, circular_dep_analysis))
+    }
+
+    /// Discover and parse every module reachable from `entry_path` into `graph`
+    /// Returns the entry module name, the parsed modules and the resolver that found them
+    fn build_module_graph(
+        &mut self,
+        entry_path: &Path,
+        graph: &mut CriboGraph,
+    ) -> Result<(String, Vec<ParsedModuleData>, ModuleResolver)> {
         debug!("Entry: {:?}", entry_path);
+        // Module ids are per graph, so semantic state from a previous run is stale
+        self.semantic_bundler = SemanticBundler::new();
+
+        // A directory of scripts is bundled behind a generated dispatcher that imports
+        // them from the directory
+        self.script_dispatcher = if self.file_provider.is_dir(entry_path) {
+            let dispatcher = ScriptDispatcher::generate(
+                entry_path,
+                &self.config.runtime_prefix,
+                self.file_provider.as_ref(),
+            )?;
+            if !self
+                .config
+                .src
+                .iter()
+                .any(|src| src == dispatcher.scripts_dir())
+            {
+                self.config
+                    .src
+                    .insert(0, dispatcher.scripts_dir().to_path_buf());
+            }
+            Some(dispatcher)
+        } else {
+            None
+        };
+        let entry_path = match &self.script_dispatcher {
+            Some(dispatcher) => dispatcher.entry_path().to_path_buf(),
+            None => entry_path.to_path_buf(),
+        };
+        let entry_path = entry_path.as_path();
         debug!(
             "Using target Python version: {} (Python 3.{})",
             self.config.target_version,
@@ -123,26 +315,51 @@ impl BundleOrchestrator {
         );
 
         // Auto-detect the entry point's directory as a source directory
+        let mut entry_package = None;
         if let Some(entry_dir) = entry_path.parent() {
-            // Canonicalize the path to avoid duplicates due to different lexical representations
-            let entry_dir = match entry_dir.canonicalize() {
-                Ok(canonical_path) => canonical_path,
-                Err(_) => {
-                    // Fall back to the original path if canonicalization fails (e.g., path doesn't exist)
-                    entry_dir.to_path_buf()
+            // Canonicalize the path to avoid duplicates due to different lexical
+            // representations, falling back to the original path if that fails (e.g. the
+            // path doesn't exist)
+            let entry_dir = self.file_provider.canonicalize(entry_dir);
+            // An entry inside a package that uses relative imports can only run as
+            // `python -m package.entry`, so it is bundled as a submodule of that package
+            // with the directory above the outermost package as the source root
+            let package_context = if self.has_relative_imports(entry_path)? {
+                self.entry_package_context(&entry_dir)
+            } else {
+                None
+            };
+            let src_root = match package_context {
+                Some((package_root, package)) => {
+                    debug!("Entry script is part of package '{}'", package);
+                    entry_package = Some(package);
+                    package_root
                 }
+                None => entry_dir,
             };
-            if !self.config.src.contains(&entry_dir) {
-                debug!("Adding entry directory to src paths: {:?}", entry_dir);
-                self.config.src.insert(0, entry_dir);
+            if !self.config.src.contains(&src_root) {
+                debug!("Adding entry directory to src paths: {:?}", src_root);
+                self.config.src.insert(0, src_root);
             }
         }
 
         // Initialize resolver with the updated config
-        let mut resolver = ModuleResolver::new(self.config.clone())?;
+        let mut resolver = match &self.resolver_cache {
+            Some(cache) => cache.resolver(&self.config, &self.file_provider)?,
+            None => ModuleResolver::with_file_provider(
+                self.config.clone(),
+                Arc::clone(&self.file_provider),
+            )?,
+        };
+        if let Some(classifier) = &self.import_classifier {
+            resolver.set_classifier(classifier.clone());
+        }
 
         // Find the entry module name
-        let entry_module_name = self.find_entry_module_name(entry_path, &resolver)?;
+        let entry_module_name = match entry_package {
+            Some(package) => Self::entry_module_name_in_package(entry_path, &package)?,
+            None => self.find_entry_module_name(entry_path, &resolver)?,
+        };
         info!("Entry module: {}", entry_module_name);
 
         // Build dependency graph
@@ -154,28 +371,43 @@ impl BundleOrchestrator {
         };
         let parsed_modules = self.build_dependency_graph(&mut build_params)?;
 
+        let counts = resolver.classification_counts();
+        info!(
+            "Import classification: {} first-party, {} third-party, {} stdlib, {} ignored",
+            counts.first_party, counts.third_party, counts.standard_library, counts.ignored
+        );
+
         // In CriboGraph, we track all modules but focus on reachable ones
         debug!("Graph has {} modules", graph.modules.len());
 
+        Ok((entry_module_name, parsed_modules, resolver))
+    }
+
+    /// Fail on circular dependencies the bundler cannot handle; returns the analysis of
+    /// the ones it can
+    fn analyze_cycles(&self, graph: &CriboGraph) -> Result<Option<CircularDependencyAnalysis>> {
         // Enhanced circular dependency detection and analysis
+        self.metrics.phase_started(Phase::CycleAnalysis);
         let mut circular_dep_analysis = None;
         if graph.has_cycles() {
             let analysis = graph.analyze_circular_dependencies();
 
             if !analysis.unresolvable_cycles.is_empty() {
-                let error_msg =
-                    Self::format_unresolvable_cycles_error(&analysis.unresolvable_cycles);
-                return Err(anyhow!(error_msg));
+                return Err(CriboError::Cycle {
+                    cycles: Self::cycle_module_names(&analysis.unresolvable_cycles),
+                    message: Self::format_unresolvable_cycles_error(&analysis.unresolvable_cycles),
+                }
+                .into());
             }
 
             // Check if we can resolve the circular dependencies
             let all_resolvable = analysis
                 .resolvable_cycles
                 .iter()
-                .all(|cycle| matches!(cycle.cycle_type, CircularDependencyType::FunctionLevel));
+                .all(Self::is_bundleable_cycle);
 
             if all_resolvable && analysis.unresolvable_cycles.is_empty() {
-                // All cycles are function-level and resolvable - proceed with bundling
+                // All cycles are resolvable - proceed with bundling
                 warn!(
                     "Detected {} resolvable circular dependencies - proceeding with bundling",
                     analysis.resolvable_cycles.len()
@@ -191,15 +423,28 @@ impl BundleOrchestrator {
                     analysis.resolvable_cycles.len()
                 );
 
-                let error_msg = Self::build_cycle_error_message(&analysis);
-                return Err(anyhow!(error_msg));
+                let mut cycles = Self::cycle_module_names(&analysis.unresolvable_cycles);
+                cycles.extend(Self::cycle_module_names(&analysis.resolvable_cycles));
+                return Err(CriboError::Cycle {
+                    cycles,
+                    message: Self::build_cycle_error_message(&analysis),
+                }
+                .into());
             }
         }
 
-        // Set the resolver for the caller to use
-        *resolver_opt = Some(resolver);
+        self.metrics.phase_finished(Phase::CycleAnalysis);
 
-        Ok((entry_module_name, parsed_modules, circular_dep_analysis))
+        Ok(circular_dep_analysis)
+    }
+
+    /// Function-level cycles are resolved by moving imports into functions, and
+    /// import-time cycles by wrapper modules that initialize each other lazily
+    fn is_bundleable_cycle(cycle: &CircularDependencyGroup) -> bool {
+        matches!(
+            cycle.cycle_type,
+            CircularDependencyType::FunctionLevel | CircularDependencyType::ImportTime
+        )
     }
 
     /// Helper to get sorted modules from graph
@@ -213,15 +458,19 @@ impl BundleOrchestrator {
             let all_resolvable = analysis
                 .resolvable_cycles
                 .iter()
-                .all(|cycle| matches!(cycle.cycle_type, CircularDependencyType::FunctionLevel))
+                .all(Self::is_bundleable_cycle)
                 && analysis.unresolvable_cycles.is_empty();
 
             if all_resolvable {
                 // For resolvable cycles, use a custom ordering that breaks cycles
-                self.get_modules_with_cycle_resolution(graph, analysis)?
+                self.get_modules_with_cycle_resolution(graph)
             } else {
                 // This should have been caught earlier, but be safe
-                return Err(anyhow!("Unresolvable circular dependencies detected"));
+                return Err(CriboError::Cycle {
+                    cycles: Self::cycle_module_names(&analysis.unresolvable_cycles),
+                    message: "Unresolvable circular dependencies detected".to_string(),
+                }
+                .into());
             }
         } else {
             graph.topological_sort()?
@@ -273,13 +522,9 @@ impl BundleOrchestrator {
         Ok(sorted_modules)
     }
 
-    /// Bundle to string for stdout output
-    pub fn bundle_to_string(
-        &mut self,
-        entry_path: &Path,
-        emit_requirements: bool,
-    ) -> Result<String> {
-        info!("Starting bundle process for stdout output");
+    /// Run the full pipeline for `entry_path` and keep everything it learned
+    pub(crate) fn bundle_outcome(&mut self, entry_path: &Path) -> CriboResult<BundleOutcome> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
 
         // Initialize empty graph - resolver will be created in bundle_core
         let mut graph = CriboGraph::new();
@@ -295,143 +540,838 @@ impl BundleOrchestrator {
         let sorted_modules =
             self.get_sorted_modules_from_graph(&graph, circular_dep_analysis.as_ref())?;
 
-        // Extract module data from sorted_modules
-        let module_data = sorted_modules
+        let requirements = Self::collect_requirements(&sorted_modules, &resolver);
+        let requirement_conditions = Self::requirement_conditions(&parsed_modules, &requirements);
+        // Packages that are only imported on other platforms or with a fallback may
+        // legitimately be missing, so the preflight check leaves them out
+        let unconditional_requirements: Vec<String> = requirements
             .iter()
-            .map(|(name, path, imports)| (name.clone(), path.clone(), imports.clone()))
-            .collect::<Vec<_>>();
+            .filter(|package| !requirement_conditions.contains_key(*package))
+            .cloned()
+            .collect();
+        let distribution_names = self.distribution_names(&resolver, &requirements);
+        let orphans = Self::find_orphans(&resolver, &self.config.src, &sorted_modules);
+        let stubbed_modules = self.stubbed_modules(&sorted_modules);
 
         // Generate bundled code
         info!("Using hybrid static bundler");
-        let bundled_code = self.emit_static_bundle(StaticBundleParams {
-            sorted_modules: &module_data,
-            parsed_modules: Some(&parsed_modules),
+        let EmittedBundle {
+            code,
+            module_map,
+            wrapper_reasons,
+        } = self.emit_static_bundle(StaticBundleParams {
+            sorted_modules: &sorted_modules,
+            parsed_modules: Some(&parsed_modules), // Use pre-parsed modules to avoid double parsing
             _resolver: &resolver,
             entry_module_name: &entry_module_name,
             graph: &graph,
             circular_dep_analysis: circular_dep_analysis.as_ref(),
+            requirements: &unconditional_requirements,
+            distribution_names: &distribution_names,
         })?;
+        let resolved_cycles = circular_dep_analysis
+            .as_ref()
+            .map(|analysis| Self::cycle_module_names(&analysis.resolvable_cycles))
+            .unwrap_or_default();
+        let dependency_graph = self.bundle_graph(&graph, &sorted_modules, &module_map, &resolver);
+        self.metrics.bundle_finished(&Self::bundle_summary(
+            &parsed_modules,
+            &code,
+            &module_map,
+            &requirements,
+        ));
 
-        // Generate requirements.txt if requested
-        if emit_requirements {
-            self.write_requirements_file_for_stdout(&module_data, &resolver)?;
+        let type_check_findings = match &self.config.type_checker {
+            Some(command) => {
+                let findings = check_bundle(command, &code, &module_map)?;
+                for finding in &findings {
+                    warn!("Type checker: {}", finding);
+                }
+                findings
+            }
+            None => Vec::new(),
+        };
+
+        Ok(BundleOutcome {
+            code,
+            modules: sorted_modules
+                .into_iter()
+                .map(|(name, path, _imports)| (name, path))
+                .collect(),
+            requirements,
+            requirement_conditions,
+            distribution_names,
+            resolved_cycles,
+            classification_counts: resolver.classification_counts(),
+            module_map,
+            wrapper_reasons,
+            orphans,
+            stubbed_modules,
+            type_check_findings,
+            dependency_graph,
+        })
+    }
+
+    /// Export the dependency graph of a bundle, annotating first-party modules with
+    /// their strategy, side effects and renames and adding a node per imported
+    /// external package
+    fn bundle_graph(
+        &self,
+        graph: &CriboGraph,
+        modules: &[(String, PathBuf, Vec<String>)],
+        module_map: &ModuleMap,
+        resolver: &ModuleResolver,
+    ) -> BundleGraph {
+        let mut nodes = Vec::new();
+        let mut external = IndexMap::new();
+        let mut edges = IndexSet::new();
+
+        for (module_name, module_path, imports) in modules {
+            let kind = match module_map
+                .modules
+                .iter()
+                .find(|entry| entry.name == *module_name)
+                .map(|entry| entry.strategy)
+            {
+                Some(ModuleStrategy::Entry) => BundleGraphNodeKind::Entry,
+                Some(ModuleStrategy::Namespace) => BundleGraphNodeKind::Namespace,
+                Some(ModuleStrategy::Wrapper) => BundleGraphNodeKind::Wrapper,
+                Some(ModuleStrategy::Inlined) | None => BundleGraphNodeKind::Inlined,
+            };
+            let module_id = graph.module_names.get(module_name).copied();
+            nodes.push(BundleGraphNode {
+                name: module_name.clone(),
+                kind,
+                path: Some(module_path.clone()),
+                side_effects: module_id
+                    .map(|module_id| graph.side_effects(module_id).into_iter().cloned().collect())
+                    .unwrap_or_default(),
+                renames: module_id
+                    .map(|module_id| self.module_renames(module_id))
+                    .unwrap_or_default(),
+            });
+
+            if let Some(module_id) = module_id {
+                let mut dependencies: Vec<&str> = graph
+                    .get_dependencies(module_id)
+                    .into_iter()
+                    .filter_map(|dependency| graph.modules.get(&dependency))
+                    .map(|dependency| dependency.module_name.as_str())
+                    .collect();
+                dependencies.sort_unstable();
+                for dependency in dependencies {
+                    edges.insert((module_name.clone(), dependency.to_owned()));
+                }
+            }
+
+            for import in imports {
+                let kind = match resolver.classify_import(import) {
+                    ImportType::StandardLibrary => BundleGraphNodeKind::Stdlib,
+                    ImportType::ThirdParty => BundleGraphNodeKind::ThirdParty,
+                    ImportType::FirstParty | ImportType::Ignored => continue,
+                };
+                let package = import.split('.').next().unwrap_or(import);
+                external.entry(package.to_owned()).or_insert(kind);
+                edges.insert((module_name.clone(), package.to_owned()));
+            }
         }
 
-        Ok(bundled_code)
+        external.sort_keys();
+        nodes.extend(external.into_iter().map(|(name, kind)| BundleGraphNode {
+            name,
+            kind,
+            path: None,
+            side_effects: Vec::new(),
+            renames: IndexMap::new(),
+        }));
+        BundleGraph {
+            nodes,
+            edges: edges
+                .into_iter()
+                .map(|(from, to)| BundleGraphEdge { from, to })
+                .collect(),
+        }
     }
+
+    /// The configured stubs that were bundled, warning about the ones that were not
+    fn stubbed_modules(&self, modules: &[(String, PathBuf, Vec<String>)]) -> Vec<StubbedModule> {
+        let mut stubbed = Vec::new();
+        for (module_name, stub) in &self.config.stub_modules {
+            if modules.iter().any(|(name, _, _)| name == module_name) {
+                info!("Bundled stub {:?} in place of '{}'", stub, module_name);
+                stubbed.push(StubbedModule {
+                    name: module_name.clone(),
+                    stub: stub.clone(),
+                });
+            } else {
+                warn!(
+                    "Stub for '{}' was not used: nothing bundled imports the module",
+                    module_name
+                );
+            }
+        }
+        stubbed
+    }
+
+    /// Size and shape of a generated bundle, for metrics
+    fn bundle_summary(
+        parsed_modules: &[ParsedModuleData],
+        code: &str,
+        module_map: &ModuleMap,
+        requirements: &[String],
+    ) -> BundleSummary {
+        let mut summary = BundleSummary {
+            lines_in: parsed_modules
+                .iter()
+                .map(|(_, _, _, _, source)| source.lines().count())
+                .sum(),
+            lines_out: code.lines().count(),
+            third_party_packages: requirements.len(),
+            ..BundleSummary::default()
+        };
+        for module in &module_map.modules {
+            match module.strategy {
+                ModuleStrategy::Inlined | ModuleStrategy::Entry => summary.inlined_modules += 1,
+                ModuleStrategy::Namespace => summary.namespace_modules += 1,
+                ModuleStrategy::Wrapper => summary.wrapper_modules += 1,
+            }
+        }
+        summary
+    }
+
+    /// First-party modules under `src_roots` that are not among the bundled `modules`
+    ///
+    /// A package counts as used when one of its submodules is bundled, since importing
+    /// the submodule runs the package's `__init__.py`.
+    fn find_orphans(
+        resolver: &ModuleResolver,
+        src_roots: &[PathBuf],
+        modules: &[(String, PathBuf, Vec<String>)],
+    ) -> Vec<OrphanModule> {
+        let roots: Vec<PathBuf> = src_roots
+            .iter()
+            .map(|root| resolver.file_provider().canonicalize(root))
+            .collect();
+        let is_bundled = |module_name: &str, path: &Path| {
+            modules.iter().any(|(name, bundled_path, _)| {
+                name == module_name
+                    || bundled_path == path
+                    || name
+                        .strip_prefix(module_name)
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+        };
+
+        let mut orphans: Vec<OrphanModule> = resolver
+            .first_party_module_files()
+            .filter(|(_, path)| roots.iter().any(|root| path.starts_with(root)))
+            .filter(|(module_name, path)| !is_bundled(module_name, path))
+            .map(|(module_name, path)| OrphanModule {
+                name: module_name.to_owned(),
+                path: path.to_path_buf(),
+            })
+            .collect();
+        orphans.sort_by(|a, b| a.name.cmp(&b.name));
+        orphans
+    }
+
+    /// First-party modules under the source roots, including the entry's directory,
+    /// that nothing reachable from `entry_path` imports
+    pub fn orphans(&mut self, entry_path: &Path) -> CriboResult<Vec<OrphanModule>> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        Ok(self.bundle_outcome(entry_path)?.orphans)
+    }
+
+    /// Bundle to string for stdout output
+    pub fn bundle_to_string(
+        &mut self,
+        entry_path: &Path,
+        emit_requirements: bool,
+    ) -> CriboResult<String> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        info!("Starting bundle process for stdout output");
+
+        let outcome = self.bundle_outcome(entry_path)?;
+
+        self.metrics.phase_started(Phase::Output);
+        self.write_artifacts(&outcome, Path::new(STDOUT_ARTIFACT_BASE), emit_requirements)?;
+        let code = self.output_code(outcome.code)?;
+        self.metrics.phase_finished(Phase::Output);
+
+        Ok(code)
+    }
+
+    /// Bundle modules given as source code rather than files, for tools generating the
+    /// code they bundle
+    ///
+    /// `sources` maps paths relative to the source root (`"main.py"`, `"pkg/util.py"`)
+    /// to their contents, and `entry` is one of those paths. The sources are laid out
+    /// under a source root that only exists in memory, searched before the configured
+    /// `src` directories. Only the code is produced: no requirements file or other
+    /// artifact is written, whatever the configuration asks for.
+    pub fn bundle_from_sources<K, V>(
+        &self,
+        sources: impl IntoIterator<Item = (K, V)>,
+        entry: &str,
+    ) -> CriboResult<String>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let root = Path::new(IN_MEMORY_SOURCE_ROOT);
+        let mut files = MemoryFileProvider::with_fallback(Arc::clone(&self.file_provider));
+        let mut entry_path = None;
+        for (relative, source) in sources {
+            let relative = Path::new(relative.as_ref());
+            if relative.as_os_str().is_empty()
+                || !relative
+                    .components()
+                    .all(|component| matches!(component, std::path::Component::Normal(_)))
+            {
+                return Err(CriboError::Resolution {
+                    module: None,
+                    path: Some(relative.to_path_buf()),
+                    message: "Source paths must be relative to the source root".to_owned(),
+                });
+            }
+            let path = root.join(relative);
+            files.add_file(path.clone(), source.as_ref());
+            if relative == Path::new(entry) {
+                entry_path = Some(path);
+            }
+        }
+        let entry_path = entry_path.ok_or_else(|| CriboError::Resolution {
+            module: None,
+            path: Some(PathBuf::from(entry)),
+            message: "The entry is not one of the given sources".to_owned(),
+        })?;
+
+        // Bundled by an orchestrator of its own, as a run changes the source roots
+        let mut config = self.config.clone();
+        config.src.insert(0, root.to_path_buf());
+        let mut bundler = Self {
+            config,
+            semantic_bundler: SemanticBundler::new(),
+            metrics: Arc::clone(&self.metrics),
+            log_sink: self.log_sink.clone(),
+            import_classifier: self.import_classifier.clone(),
+            module_cache: Arc::clone(&self.module_cache),
+            resolver_cache: self.resolver_cache.clone(),
+            plugins: self.plugins.clone(),
+            file_provider: Arc::new(files),
+            script_dispatcher: None,
+        };
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        info!("Starting bundle process for in-memory sources");
+
+        let outcome = bundler.bundle_outcome(&entry_path)?;
+
+        bundler.metrics.phase_started(Phase::Output);
+        let code = bundler.output_code(outcome.code)?;
+        bundler.metrics.phase_finished(Phase::Output);
+
+        Ok(code)
+    }
+
     /// Main bundling function
     pub fn bundle(
         &mut self,
         entry_path: &Path,
         output_path: &Path,
         emit_requirements: bool,
-    ) -> Result<()> {
+    ) -> CriboResult<()> {
+        self.bundle_to_file(entry_path, output_path, emit_requirements)?;
+        Ok(())
+    }
+
+    /// Bundle `entry_path` into `output_path` like [`Self::bundle`] and return the
+    /// outcome of the run
+    pub(crate) fn bundle_to_file(
+        &mut self,
+        entry_path: &Path,
+        output_path: &Path,
+        emit_requirements: bool,
+    ) -> CriboResult<BundleOutcome> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
         info!("Starting bundle process");
         debug!("Output: {:?}", output_path);
 
-        // Initialize empty graph - resolver will be created in bundle_core
-        let mut graph = CriboGraph::new();
-        let mut resolver_opt = None;
+        let outcome = self.bundle_outcome(entry_path)?;
 
-        // Perform core bundling logic
-        let (entry_module_name, parsed_modules, circular_dep_analysis) =
-            self.bundle_core(entry_path, &mut graph, &mut resolver_opt)?;
+        self.metrics.phase_started(Phase::Output);
+        self.write_artifacts(&outcome, output_path, emit_requirements)?;
+        let code = self.output_code(outcome.code.clone())?;
 
-        // Extract the resolver (it's guaranteed to be Some after bundle_core)
-        let resolver = resolver_opt.expect("Resolver should be initialized by bundle_core");
+        // Write output file; an interrupted run leaves the previous bundle in place
+        write_atomically(output_path, code, self.config.backup_output).map_err(|err| {
+            CriboError::Emit {
+                path: Some(output_path.to_path_buf()),
+                source: anyhow::Error::new(err)
+                    .context(format!("Failed to write output file: {:?}", output_path)),
+            }
+        })?;
+        if self.config.executable {
+            make_executable(output_path).map_err(|err| CriboError::Emit {
+                path: Some(output_path.to_path_buf()),
+                source: anyhow::Error::new(err)
+                    .context(format!("Failed to make {:?} executable", output_path)),
+            })?;
+        }
+        self.metrics.phase_finished(Phase::Output);
 
-        let sorted_modules =
-            self.get_sorted_modules_from_graph(&graph, circular_dep_analysis.as_ref())?;
+        info!("Bundle written to: {:?}", output_path);
 
-        // Generate bundled code
-        info!("Using hybrid static bundler");
-        let bundled_code = self.emit_static_bundle(StaticBundleParams {
-            sorted_modules: &sorted_modules,
-            parsed_modules: Some(&parsed_modules), // Use pre-parsed modules to avoid double parsing
-            _resolver: &resolver,
-            entry_module_name: &entry_module_name,
-            graph: &graph,
-            circular_dep_analysis: circular_dep_analysis.as_ref(),
-        })?;
+        Ok(outcome)
+    }
 
-        // Generate requirements.txt if requested
-        if emit_requirements {
-            self.write_requirements_file(&sorted_modules, &resolver, output_path)?;
+    /// The bundle source to write out: `code` itself, or a self-extracting stub running
+    /// it when `self_extracting` is set
+    fn output_code(&self, code: String) -> Result<String> {
+        if self.config.self_extracting {
+            self_extracting(&code)
+        } else {
+            Ok(code)
         }
+    }
 
-        // Write output file
-        fs::write(output_path, bundled_code)
-            .with_context(|| format!("Failed to write output file: {:?}", output_path))?;
+    /// Write the configured artifacts that accompany the bundle at `output_path`
+    ///
+    /// All of them are derived from the same run, so asking for several costs no extra
+    /// analysis.
+    fn write_artifacts(
+        &self,
+        outcome: &BundleOutcome,
+        output_path: &Path,
+        emit_requirements: bool,
+    ) -> Result<()> {
+        if emit_requirements {
+            let requirements_path = output_path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join("requirements.txt");
+            let lines = requirement_lines(
+                &outcome.requirements,
+                &outcome.requirement_conditions,
+                &outcome.distribution_names,
+            );
+            Self::write_requirements_file(&lines, &requirements_path)?;
+        }
+        if self.config.module_map {
+            outcome.module_map.write(&module_map_path(output_path))?;
+        }
+        if self.config.emit_pyz {
+            write_pyz(&pyz_path(output_path), &outcome.code)?;
+        }
+        if self.config.emit_report {
+            BundleReport::from_outcome(outcome).write(&report_path(output_path))?;
+        }
+        Ok(())
+    }
 
-        info!("Bundle written to: {:?}", output_path);
+    /// Bundle `entry_path` in memory and return a unified diff against the bundle
+    /// currently at `output_path`, without writing anything
+    ///
+    /// Hunk headers name the modules the changed lines belong to. A missing output file
+    /// diffs as empty; the result is empty when the bundle would not change.
+    pub fn diff(&mut self, entry_path: &Path, output_path: &Path) -> CriboResult<String> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        info!("Starting bundle process for diff against {:?}", output_path);
+
+        let outcome = self.bundle_outcome(entry_path)?;
+
+        self.metrics.phase_started(Phase::Output);
+        let existing = match fs::read_to_string(output_path) {
+            Ok(existing) => existing,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                info!(
+                    "No existing bundle at {:?}, diffing against empty",
+                    output_path
+                );
+                String::new()
+            }
+            Err(err) => {
+                return Err(CriboError::Emit {
+                    path: Some(output_path.to_path_buf()),
+                    source: anyhow::Error::new(err)
+                        .context(format!("Failed to read existing bundle: {:?}", output_path)),
+                });
+            }
+        };
+        // Diff what `bundle` would write; the lines of a self-extracting stub belong to
+        // no module
+        let code = self.output_code(outcome.code)?;
+        let no_modules = ModuleMap::default();
+        let module_map = if self.config.self_extracting {
+            &no_modules
+        } else {
+            &outcome.module_map
+        };
+        let output_name = output_path.display().to_string();
+        let diff = unified_diff(
+            &existing,
+            &code,
+            &output_name,
+            &format!("{output_name} (new)"),
+            module_map,
+        );
+        self.metrics.phase_finished(Phase::Output);
 
-        Ok(())
+        Ok(diff)
     }
 
-    /// Get modules in a valid order for bundling when there are resolvable circular dependencies
-    fn get_modules_with_cycle_resolution(
-        &self,
-        graph: &CriboGraph,
-        analysis: &crate::cribo_graph::CircularDependencyAnalysis,
-    ) -> Result<Vec<crate::cribo_graph::ModuleId>> {
-        // For simple function-level cycles, we can use a modified topological sort
-        // that breaks cycles by removing edges within strongly connected components
+    /// Explain, for every module of the bundle of `entry_path` that is wrapped in an
+    /// init function, each rule and statement that kept it from being inlined
+    ///
+    /// Locations are `path:line` relative to the entry script's directory; the result is
+    /// empty when every module is inlined.
+    pub fn why_not_inlined(&mut self, entry_path: &Path) -> CriboResult<String> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        let outcome = self.bundle_outcome(entry_path)?;
+
+        let entry_dir = outcome.modules.last().and_then(|(_, path)| path.parent());
+        let module_path = |module_name: &str| {
+            outcome
+                .modules
+                .iter()
+                .find(|(name, _)| name == module_name)
+                .map(|(_, path)| path.as_path())
+        };
+        let mut sources = IndexMap::new();
+        let mut explanation = String::new();
+
+        for (module_name, reasons) in &outcome.wrapper_reasons {
+            let display_path = module_path(module_name)
+                .map(|path| relative_module_path(path, entry_dir))
+                .unwrap_or_default();
+            explanation.push_str(&format!(
+                "{} ({}) is not inlined:\n",
+                module_name, display_path
+            ));
 
-        // Get all module IDs
-        let all_module_ids: Vec<_> = graph.modules.keys().cloned().collect();
+            for reason in reasons {
+                let Some(path) = module_path(&reason.source_module) else {
+                    continue;
+                };
+                let location = relative_module_path(path, entry_dir);
+                let range = reason.range.filter(|range| !range.is_empty());
+                let Some(range) = range else {
+                    let detail = reason.detail.as_deref().unwrap_or_default();
+                    explanation.push_str(&format!("  {}: {}: {}\n", location, reason.rule, detail));
+                    continue;
+                };
 
-        // Collect all modules that are part of circular dependencies
-        let mut cycle_module_names = IndexSet::new();
-        for cycle in &analysis.resolvable_cycles {
-            for module_name in &cycle.modules {
-                cycle_module_names.insert(module_name.as_str());
+                if !sources.contains_key(path) {
+                    sources.insert(
+                        path.to_path_buf(),
+                        read_module_source(self.file_provider.as_ref(), path, None)?,
+                    );
+                }
+                let source = &sources[path];
+                let start = usize::from(range.start()).min(source.len());
+                let end = usize::from(range.end()).min(source.len());
+                let line = source[..start].matches('\n').count() + 1;
+                let statement = &source[start..end];
+                let first_line = statement.lines().next().unwrap_or_default().trim_end();
+                let continuation = if statement.contains('\n') { " ..." } else { "" };
+                explanation.push_str(&format!(
+                    "  {}:{}: {}: {}{}\n",
+                    location, line, reason.rule, first_line, continuation
+                ));
             }
         }
 
-        // Split modules into non-cycle and cycle modules
-        let (mut cycle_ids, non_cycle_ids): (Vec<_>, Vec<_>) =
-            all_module_ids.into_iter().partition(|&module_id| {
-                if let Some(module) = graph.modules.get(&module_id) {
-                    cycle_module_names.contains(module.module_name.as_str())
-                } else {
-                    false
-                }
-            });
+        Ok(explanation)
+    }
 
-        // For non-cycle modules, we can still use topological sorting on the subgraph
-        let mut result = Vec::new();
+    /// Module-level names of `module_id` that conflict resolution renamed, original
+    /// to new name
+    fn module_renames(&self, module_id: crate::cribo_graph::ModuleId) -> IndexMap<String, String> {
+        let Some(module_info) = self.semantic_bundler.get_module_info(&module_id) else {
+            return IndexMap::new();
+        };
+        let registry = self.semantic_bundler.symbol_registry();
+        module_info
+            .exported_symbols
+            .iter()
+            .filter_map(|&symbol| {
+                registry
+                    .get_rename(&module_id, symbol)
+                    .filter(|renamed| *renamed != symbol)
+                    .map(|renamed| (symbol.as_str().to_owned(), renamed.as_str().to_owned()))
+            })
+            .collect()
+    }
 
-        // Add non-cycle modules first (they should sort topologically)
-        result.extend(non_cycle_ids);
+    /// Dependency graph of the bundle for `entry_path`, with each first-party module's
+    /// strategy and the external packages the bundle imports
+    pub fn dependency_graph(&mut self, entry_path: &Path) -> CriboResult<BundleGraph> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        Ok(self.bundle_outcome(entry_path)?.dependency_graph)
+    }
 
-        // For cycle modules, try to maintain dependency order where possible
-        // Sort cycle modules by name to get deterministic output
-        cycle_ids.sort_by(|&a_id, &b_id| {
-            let a_name = &graph.modules[&a_id].module_name;
-            let b_name = &graph.modules[&b_id].module_name;
+    /// Every chain of imports from the entry of `entry_path` to `module_name`, with the
+    /// import statements that form each link, as a human-readable explanation
+    ///
+    /// Walks the module graph backwards from `module_name` to the entry; at most
+    /// [`MAX_EXPLAINED_CHAINS`] chains are listed, shortest first.
+    pub fn explain(&mut self, entry_path: &Path, module_name: &str) -> CriboResult<String> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        let mut graph = CriboGraph::new();
+        let (entry_module_name, parsed_modules, mut resolver) =
+            self.build_module_graph(entry_path, &mut graph)?;
+
+        let Some(&target_id) = graph.module_names.get(module_name) else {
+            return Ok(format!(
+                "{} is not bundled: no module reachable from the entry imports it\n",
+                module_name
+            ));
+        };
+        if module_name == entry_module_name {
+            return Ok(format!("{} is the entry module\n", module_name));
+        }
+        let Some(&entry_id) = graph.module_names.get(&entry_module_name) else {
+            return Ok(format!("{} is not reachable from the entry\n", module_name));
+        };
 
-            // For package hierarchies like mypackage.utils vs mypackage,
-            // put the deeper/more specific modules first (dependencies before dependents)
-            let a_depth = a_name.matches('.').count();
-            let b_depth = b_name.matches('.').count();
+        let mut chains = Vec::new();
+        Self::collect_import_chains(&graph, entry_id, &mut vec![target_id], &mut chains);
+        let module_name_of = |module_id: &crate::cribo_graph::ModuleId| {
+            graph
+                .modules
+                .get(module_id)
+                .map(|module| module.module_name.as_str())
+                .unwrap_or_default()
+        };
+        let mut chains: Vec<Vec<&str>> = chains
+            .iter()
+            .map(|chain| chain.iter().map(module_name_of).collect())
+            .collect();
+        chains.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
 
-            // If one is a submodule of the other, put the submodule first
-            if a_name.starts_with(&format!("{}.", b_name)) {
-                std::cmp::Ordering::Less // a (submodule) before b (parent)
-            } else if b_name.starts_with(&format!("{}.", a_name)) {
-                std::cmp::Ordering::Greater // b (submodule) before a (parent)
-            } else {
-                // Otherwise sort by depth (deeper modules first), then by name
-                match a_depth.cmp(&b_depth) {
-                    std::cmp::Ordering::Equal => a_name.cmp(b_name),
-                    other => other.reverse(), // Deeper modules first
+        let entry_dir = parsed_modules
+            .iter()
+            .find(|(name, _, _, _, _)| *name == entry_module_name)
+            .and_then(|(_, path, _, _, _)| path.parent());
+        let mut explanation = format!(
+            "{} is bundled through {} import chain{}:\n",
+            module_name,
+            chains.len(),
+            if chains.len() == 1 { "" } else { "s" }
+        );
+        let mut statements = IndexMap::new();
+        for chain in &chains {
+            explanation.push_str(&format!("\n{}\n", chain.join(" -> ")));
+            for link in chain.windows(2) {
+                let (importer, imported) = (link[0], link[1]);
+                let Some((_, path, _, ast, source)) = parsed_modules
+                    .iter()
+                    .find(|(name, _, _, _, _)| name == importer)
+                else {
+                    continue;
+                };
+                let key = (importer, imported);
+                if !statements.contains_key(&key) {
+                    let found = self.import_statements(path, ast, source, imported, &mut resolver);
+                    statements.insert(key, found);
+                }
+                let location = relative_module_path(path, entry_dir);
+                for (line, statement) in &statements[&key] {
+                    explanation.push_str(&format!("  {}:{}: {}\n", location, line, statement));
                 }
             }
+        }
+        if chains.len() == MAX_EXPLAINED_CHAINS {
+            explanation.push_str(&format!(
+                "\nOnly the first {} chains are listed\n",
+                MAX_EXPLAINED_CHAINS
+            ));
+        }
+        Ok(explanation)
+    }
+
+    /// Extend `chain`, which runs backwards from the explained module, through the
+    /// modules importing its last module until it reaches `entry_id`
+    fn collect_import_chains(
+        graph: &CriboGraph,
+        entry_id: crate::cribo_graph::ModuleId,
+        chain: &mut Vec<crate::cribo_graph::ModuleId>,
+        chains: &mut Vec<Vec<crate::cribo_graph::ModuleId>>,
+    ) {
+        if chains.len() >= MAX_EXPLAINED_CHAINS {
+            return;
+        }
+        let Some(&current) = chain.last() else {
+            return;
+        };
+        if current == entry_id {
+            chains.push(chain.iter().rev().copied().collect());
+            return;
+        }
+
+        let mut importers: Vec<_> = graph
+            .get_dependents(current)
+            .into_iter()
+            .filter(|importer| !chain.contains(importer))
+            .filter_map(|importer| {
+                graph
+                    .modules
+                    .get(&importer)
+                    .map(|module| (module.module_name.as_str(), importer))
+            })
+            .collect();
+        importers.sort_by(|a, b| a.0.cmp(b.0));
+        for (_, importer) in importers {
+            chain.push(importer);
+            Self::collect_import_chains(graph, entry_id, chain, chains);
+            chain.pop();
+        }
+    }
+
+    /// Line number and first line of each statement in the module at `path` that
+    /// imports `imported` or one of its submodules
+    fn import_statements(
+        &self,
+        path: &Path,
+        ast: &ModModule,
+        source: &str,
+        imported: &str,
+        resolver: &mut ModuleResolver,
+    ) -> Vec<(usize, String)> {
+        let mut visitor = ImportDiscoveryVisitor::new();
+        visitor.visit_module(ast);
+        let mut resolver = Some(resolver);
+
+        let mut statements = Vec::new();
+        for import in visitor.into_imports() {
+            if import.location == ImportLocation::TypeChecking {
+                continue;
+            }
+            let mut modules = IndexSet::new();
+            self.add_imported_modules(&import, path, &mut resolver, &mut modules);
+            let imports_target = modules.iter().any(|module| {
+                module == imported
+                    || module
+                        .strip_prefix(imported)
+                        .is_some_and(|rest| rest.starts_with('.'))
+            });
+            if !imports_target {
+                continue;
+            }
+
+            let start = usize::from(import.range.start()).min(source.len());
+            let end = usize::from(import.range.end()).min(source.len());
+            let line = source[..start].matches('\n').count() + 1;
+            let statement = &source[start..end];
+            let first_line = statement.lines().next().unwrap_or_default().trim_end();
+            let continuation = if statement.contains('\n') { " ..." } else { "" };
+            statements.push((line, format!("{}{}", first_line, continuation)));
+        }
+        statements
+    }
+
+    /// Discover, parse and analyze every module reachable from `entry_path` without
+    /// generating code
+    ///
+    /// Fails the same way bundling would on unresolvable modules, syntax errors and
+    /// unsupported circular dependencies.
+    pub fn build_graph(&mut self, entry_path: &Path) -> CriboResult<CriboGraph> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        let mut graph = CriboGraph::new();
+        let mut resolver_opt = None;
+        self.bundle_core(entry_path, &mut graph, &mut resolver_opt)?;
+        Ok(graph)
+    }
+
+    /// Mutual-import clusters (strongly connected components of the module graph)
+    /// among the modules reachable from `entry_path`, largest first
+    ///
+    /// Unlike bundling, this succeeds when a cluster cannot be bundled, so it can point
+    /// out where breaking an import pays off.
+    pub fn strongly_connected_components(
+        &mut self,
+        entry_path: &Path,
+    ) -> CriboResult<Vec<CircularDependencyGroup>> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        let mut graph = CriboGraph::new();
+        self.build_module_graph(entry_path, &mut graph)?;
+
+        let analysis = graph.analyze_circular_dependencies();
+        let mut components = analysis.unresolvable_cycles;
+        components.extend(analysis.resolvable_cycles);
+        for component in &mut components {
+            component.modules.sort();
+            component.import_chain.sort_by(|a, b| {
+                (&a.from_module, &a.to_module).cmp(&(&b.from_module, &b.to_module))
+            });
+        }
+        components.sort_by(|a, b| {
+            b.modules
+                .len()
+                .cmp(&a.modules.len())
+                .then_with(|| a.modules.cmp(&b.modules))
         });
+        Ok(components)
+    }
+
+    /// Build only the dependency graph for `entry_path`, reusing the snapshot stored in
+    /// `cache_dir` when no module in it changed since it was written.
+    ///
+    /// A cold run performs full discovery and parsing and then refreshes the snapshot;
+    /// a warm run restores the graph without parsing any module.
+    pub fn build_graph_cached(
+        &mut self,
+        entry_path: &Path,
+        cache_dir: &Path,
+    ) -> CriboResult<CriboGraph> {
+        let _log_scope = SinkScope::enter(self.log_sink.clone());
+        let config_fingerprint = self.config_fingerprint()?;
+
+        if let Some(snapshot) = GraphSnapshot::load(cache_dir, entry_path) {
+            if snapshot.is_fresh(entry_path, &config_fingerprint) {
+                info!("Reusing cached dependency graph for {:?}", entry_path);
+                return Ok(snapshot.restore());
+            }
+        }
 
-        result.extend(cycle_ids);
+        let graph = self.build_graph(entry_path)?;
+
+        // A failing cache write must not fail the build - the next run is simply cold again
+        if let Err(err) = GraphSnapshot::capture(&graph, entry_path, &config_fingerprint)
+            .and_then(|snapshot| snapshot.store(cache_dir))
+        {
+            warn!("Failed to store dependency graph snapshot: {}", err);
+        }
+
+        Ok(graph)
+    }
+
+    /// Fingerprint of the configuration that affects graph construction
+    fn config_fingerprint(&self) -> CriboResult<String> {
+        let serialized = toml::to_string(&self.config).map_err(|err| CriboError::Config {
+            path: None,
+            message: format!("Failed to serialize configuration: {}", err),
+        })?;
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        hasher.update(serialized.as_bytes());
+        Ok(format!("{:x}", hasher.finalize()))
+    }
 
-        Ok(result)
+    /// Get modules in a valid order for bundling when there are resolvable circular dependencies
+    ///
+    /// Each circular dependency is bundled as one unit placed after the modules it
+    /// imports and before the modules importing it; inside the unit, wrapper modules
+    /// bind each other lazily (see `HybridStaticBundler`), so their order only matters
+    /// for imports of names.
+    fn get_modules_with_cycle_resolution(
+        &self,
+        graph: &CriboGraph,
+    ) -> Vec<crate::cribo_graph::ModuleId> {
+        graph
+            .condensed_topological_sort()
+            .into_iter()
+            .flatten()
+            .collect()
     }
 
     /// Extract imports from module items
@@ -483,16 +1423,65 @@ impl BundleOrchestrator {
         let module_name = entry_path
             .file_stem()
             .and_then(|name| name.to_str())
-            .ok_or_else(|| {
-                anyhow!(
+            .ok_or_else(|| CriboError::Resolution {
+                module: None,
+                path: Some(entry_path.to_path_buf()),
+                message: format!(
                     "Cannot determine module name from entry path: {:?}",
                     entry_path
-                )
+                ),
             })?;
 
         Ok(module_name.to_owned())
     }
 
+    /// Whether the module at `path` contains any relative import
+    fn has_relative_imports(&self, path: &Path) -> Result<bool> {
+        let source = read_module_source(self.file_provider.as_ref(), path, None)?;
+        let parsed = parse_module_source(path, &source)?;
+        let mut visitor = ImportDiscoveryVisitor::new();
+        visitor.visit_module(parsed.syntax());
+        Ok(visitor.into_imports().iter().any(|import| import.level > 0))
+    }
+
+    /// Package containing the entry script's directory, if that directory is a package
+    ///
+    /// Returns the directory above the outermost enclosing package, which must be on the
+    /// search path for the package to be importable, and the dotted package name.
+    fn entry_package_context(&self, entry_dir: &Path) -> Option<(PathBuf, String)> {
+        let mut package_parts = Vec::new();
+        let mut current = entry_dir;
+        while self.file_provider.is_file(&current.join("__init__.py")) {
+            package_parts.push(current.file_name()?.to_str()?.to_owned());
+            current = current.parent()?;
+        }
+        if package_parts.is_empty() {
+            return None;
+        }
+        package_parts.reverse();
+        Some((current.to_path_buf(), package_parts.join(".")))
+    }
+
+    /// Dotted name of an entry script that lives in `package`
+    fn entry_module_name_in_package(entry_path: &Path, package: &str) -> Result<String> {
+        let stem = entry_path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| CriboError::Resolution {
+                module: None,
+                path: Some(entry_path.to_path_buf()),
+                message: format!(
+                    "Cannot determine module name from entry path: {:?}",
+                    entry_path
+                ),
+            })?;
+        if stem == "__init__" {
+            Ok(package.to_owned())
+        } else {
+            Ok(format!("{}.{}", package, stem))
+        }
+    }
+
     /// Convert a relative path to a module name
     fn path_to_module_name(&self, relative_path: &Path) -> Option<String> {
         module_name_from_relative(relative_path)
@@ -519,6 +1508,7 @@ impl BundleOrchestrator {
 
         // PHASE 1: Discover and collect all modules
         info!("Phase 1: Discovering all modules...");
+        self.metrics.phase_started(Phase::Discovery);
         while let Some((module_name, module_path)) = modules_to_process.pop() {
             debug!("Discovering module: {} ({:?})", module_name, module_path);
             if processed_modules.contains(&module_name) {
@@ -550,9 +1540,11 @@ impl BundleOrchestrator {
             "Phase 1 complete: discovered {} modules",
             discovered_modules.len()
         );
+        self.metrics.phase_finished(Phase::Discovery);
 
         // PHASE 2: Add all modules to graph and create dependency edges
         info!("Phase 2: Adding modules to graph...");
+        self.metrics.phase_started(Phase::GraphConstruction);
 
         // First, add all modules to the graph and parse them
         let mut module_id_map = indexmap::IndexMap::new();
@@ -569,23 +1561,46 @@ impl BundleOrchestrator {
             );
 
             // Parse the module AST and build detailed graph
-            let source = fs::read_to_string(module_path)
-                .with_context(|| format!("Failed to read file: {:?}", module_path))?;
-            let source = crate::util::normalize_line_endings(source);
-            let parsed = ruff_python_parser::parse_module(&source)
-                .with_context(|| format!("Failed to parse Python file: {:?}", module_path))?;
-
-            let ast = parsed.into_syntax();
+            let (source, mut ast, defined) = self.load_module(module_path, Some(module_name))?;
+            let embedded_data = embed_data_files(
+                &mut ast,
+                module_name,
+                &self.config.data_files,
+                self.file_provider.as_ref(),
+            )?;
+            let plugin_module = PluginModule::new(Some(module_name), module_path);
+            run_hook(&self.plugins, "post_resolve", |plugin| {
+                plugin.post_resolve(&plugin_module, &mut ast)
+            })?;
 
-            // Perform semantic analysis on this module
+            // Perform semantic analysis on this module, reusing the analysis of an
+            // earlier run when the source is unchanged. Defines, `TYPE_CHECKING` blocks,
+            // embedded data files and plugins change the AST but not the source, so those modules are always
+            // analyzed afresh
+            let symbols = if defined || embedded_data || !self.plugins.is_empty() {
+                let (exported_symbols, class_scopes) =
+                    ModuleSemanticAnalyzer::extract_symbols_and_class_scopes(
+                        &source,
+                        module_path,
+                        &ast,
+                    )?;
+                ModuleSymbols {
+                    exported_symbols,
+                    class_scopes,
+                }
+            } else {
+                self.module_cache.symbols(module_path, &source, &ast)?
+            };
             self.semantic_bundler
-                .analyze_module(module_id, &ast, &source, module_path)?;
+                .add_module_symbols(module_id, symbols, &source, module_path);
 
             if let Some(module) = params.graph.get_module_by_name_mut(module_name) {
                 let mut builder = crate::graph_builder::GraphBuilder::new(module);
                 builder.build_from_ast(&ast)?;
             }
 
+            self.metrics.module_processed(module_name, module_path);
+
             // Store parsed module data for later use
             parsed_modules.push((
                 module_name.clone(),
@@ -597,6 +1612,17 @@ impl BundleOrchestrator {
         }
 
         info!("Added {} modules to graph", params.graph.modules.len());
+        for target in self.config.data_files.keys() {
+            let bundled = target
+                .rsplit_once('.')
+                .is_some_and(|(module, _)| module_id_map.contains_key(module));
+            if !bundled {
+                warn!(
+                    "Data file target '{}' does not name a bundled module; the file is not embedded",
+                    target
+                );
+            }
+        }
 
         // Then, add all dependency edges
         info!("Phase 2: Creating dependency edges...");
@@ -620,22 +1646,45 @@ impl BundleOrchestrator {
             "Phase 2 complete: dependency graph built with {} modules",
             params.graph.modules.len()
         );
+        self.metrics.phase_finished(Phase::GraphConstruction);
         Ok(parsed_modules)
     }
 
+    /// Load a module's source and AST with the configured defines applied and its
+    /// `TYPE_CHECKING` blocks (and optionally `sys.version_info` checks) resolved, also
+    /// returning whether that changed the AST
+    fn load_module(
+        &self,
+        path: &Path,
+        module_name: Option<&str>,
+    ) -> CriboResult<(String, ModModule, bool)> {
+        let (source, mut ast) = self.module_cache.load(
+            self.file_provider.as_ref(),
+            path,
+            module_name,
+            self.config.python_version()?,
+            &self.plugins,
+        )?;
+        let defined = apply_defines(&mut ast, path, &self.config.defines)?;
+        let versions_folded = self.config.fold_version_checks
+            && resolve_version_checks(&mut ast, path, self.config.python_version()?);
+        let type_checking_resolved =
+            resolve_type_checking_blocks(&mut ast, self.config.runtime_type_checking_imports);
+        Ok((
+            source,
+            ast,
+            defined || versions_folded || type_checking_resolved,
+        ))
+    }
+
     /// Extract import statements from a Python file using AST parsing
     /// This handles all import variations including multi-line, aliased, relative, and parenthesized imports
     pub fn extract_imports(
         &self,
         file_path: &Path,
         resolver: Option<&mut ModuleResolver>,
-    ) -> Result<Vec<String>> {
-        let source = fs::read_to_string(file_path)
-            .with_context(|| format!("Failed to read file: {:?}", file_path))?;
-        let source = normalize_line_endings(source);
-
-        let parsed = ruff_python_parser::parse_module(&source)
-            .with_context(|| format!("Failed to parse Python file: {:?}", file_path))?;
+    ) -> CriboResult<Vec<String>> {
+        let (_, ast, _) = self.load_module(file_path, None)?;
 
         let mut imports = Vec::new();
 
@@ -645,7 +1694,7 @@ impl BundleOrchestrator {
             resolver,
         };
 
-        for stmt in parsed.syntax().body.iter() {
+        for stmt in ast.body.iter() {
             self.extract_imports_from_statement(stmt, &mut context);
         }
 
@@ -657,41 +1706,27 @@ impl BundleOrchestrator {
         &self,
         file_path: &Path,
         mut resolver: Option<&mut ModuleResolver>,
-    ) -> Result<Vec<String>> {
-        let source = fs::read_to_string(file_path)
-            .with_context(|| format!("Failed to read file: {:?}", file_path))?;
-        let source = normalize_line_endings(source);
-
-        let parsed = ruff_python_parser::parse_module(&source)
-            .with_context(|| format!("Failed to parse Python file: {:?}", file_path))?;
+    ) -> CriboResult<Vec<String>> {
+        let (_, ast, _) = self.load_module(file_path, None)?;
 
         // Use the visitor to discover all imports
         let mut visitor = ImportDiscoveryVisitor::new();
-        visitor.visit_module(parsed.syntax());
+        visitor.visit_module(&ast);
 
         let discovered_imports = visitor.into_imports();
         let mut imports_set = IndexSet::new();
 
         // Convert discovered imports to module names, handling relative imports
         for import in &discovered_imports {
-            // All import locations are valid for discovery
-            if import.level > 0 {
-                self.process_relative_import_set(import, file_path, &mut imports_set);
-            } else if let Some(ref module_name) = import.module_name {
-                // Absolute imports
-                imports_set.insert(module_name.clone());
-
-                // Check if any imported names are actually submodules
-                // Only do this for non-relative imports to avoid issues
-                self.check_submodule_imports_set(
-                    module_name,
-                    import,
-                    &mut resolver,
-                    &mut imports_set,
+            // Type-checking-only imports never run, so they are not dependencies
+            if import.location == ImportLocation::TypeChecking {
+                debug!(
+                    "Skipping TYPE_CHECKING import: {:?}",
+                    import.module_name.as_deref().unwrap_or("<names>")
                 );
-            } else if import.names.len() == 1 {
-                self.process_single_name_import_set(import, &mut resolver, &mut imports_set);
+                continue;
             }
+            self.add_imported_modules(import, file_path, &mut resolver, &mut imports_set);
         }
 
         // Convert IndexSet to Vec to maintain the existing API
@@ -714,6 +1749,28 @@ impl BundleOrchestrator {
         Ok(imports)
     }
 
+    /// Add the module names `import` brings in to `imports`, handling relative imports
+    fn add_imported_modules(
+        &self,
+        import: &crate::visitors::DiscoveredImport,
+        file_path: &Path,
+        resolver: &mut Option<&mut ModuleResolver>,
+        imports: &mut IndexSet<String>,
+    ) {
+        if import.level > 0 {
+            self.process_relative_import_set(import, file_path, imports);
+        } else if let Some(ref module_name) = import.module_name {
+            // Absolute imports
+            imports.insert(module_name.clone());
+
+            // Check if any imported names are actually submodules
+            // Only do this for non-relative imports to avoid issues
+            self.check_submodule_imports_set(module_name, import, resolver, imports);
+        } else if import.names.len() == 1 {
+            self.process_single_name_import_set(import, resolver, imports);
+        }
+    }
+
     /// Process relative imports and add to IndexSet
     fn process_relative_import_set(
         &self,
@@ -724,10 +1781,7 @@ impl BundleOrchestrator {
         let base_module = match self.resolve_relative_import(file_path, import.level) {
             Some(module) => module,
             None => {
-                debug!(
-                    "Could not resolve relative import with level {}",
-                    import.level
-                );
+                self.process_relative_import_fallback(import, file_path, imports);
                 return;
             }
         };
@@ -761,6 +1815,56 @@ impl BundleOrchestrator {
         }
     }
 
+    /// Map a relative import that climbs above every `src` root to the external package
+    /// configured in `relative_import_fallbacks`, warning when there is none
+    fn process_relative_import_fallback(
+        &self,
+        import: &crate::visitors::DiscoveredImport,
+        file_path: &Path,
+        imports: &mut IndexSet<String>,
+    ) {
+        let dots = ".".repeat(import.level as usize);
+        let written = format!("{}{}", dots, import.module_name.as_deref().unwrap_or(""));
+        let Some(module_name) = self.module_name_for_path(file_path) else {
+            debug!("Could not resolve relative import {}", written);
+            return;
+        };
+
+        match crate::util::relative_import_fallback(
+            &self.config.relative_import_fallbacks,
+            &module_name,
+            import.module_name.as_deref(),
+        ) {
+            Some(external) => {
+                debug!(
+                    "Relative import {} in module '{}' falls back to external module '{}'",
+                    written, module_name, external
+                );
+                imports.insert(external);
+            }
+            None => warn!(
+                "Relative import {} in module '{}' reaches above every source directory and \
+                 will fail at runtime; add the module's package to relative_import_fallbacks \
+                 to import it from an external package instead",
+                written, module_name
+            ),
+        }
+    }
+
+    /// Name of the first-party module at `file_path`, from the `src` directory containing it
+    fn module_name_for_path(&self, file_path: &Path) -> Option<String> {
+        let current_dir = std::env::current_dir().ok();
+        let absolute = |path: &Path| match &current_dir {
+            Some(cwd) if path.is_relative() => cwd.join(path),
+            _ => path.to_path_buf(),
+        };
+        let file_path = absolute(file_path);
+        self.config
+            .src
+            .iter()
+            .find_map(|src_dir| crate::util::path_to_module_name(&absolute(src_dir), &file_path))
+    }
+
     /// Process a single name import that might be a submodule (IndexSet version)
     fn process_single_name_import_set(
         &self,
@@ -774,6 +1878,7 @@ impl BundleOrchestrator {
                 ImportType::StandardLibrary | ImportType::ThirdParty | ImportType::FirstParty => {
                     imports.insert(name.clone());
                 }
+                ImportType::Ignored => {}
             }
         }
     }
@@ -1092,6 +2197,10 @@ impl BundleOrchestrator {
                     // Also add parent packages for submodules to ensure __init__.py files are included
                     // For example, if importing "greetings.irrelevant", also add "greetings"
                     self.add_parent_packages_to_discovery(import, params);
+                } else if !params.resolver.namespace_portions(import).is_empty() {
+                    // Namespace packages have no code of their own; each submodule is
+                    // discovered from whichever portion holds it once it is imported
+                    debug!("'{}' is a namespace package", import);
                 } else {
                     warn!("Failed to resolve path for first-party module: {}", import);
                 }
@@ -1099,6 +2208,9 @@ impl BundleOrchestrator {
             ImportType::ThirdParty | ImportType::StandardLibrary => {
                 debug!("'{}' classified as external (preserving)", import);
             }
+            ImportType::Ignored => {
+                debug!("'{}' ignored or excluded (preserving)", import);
+            }
         }
     }
 
@@ -1130,7 +2242,7 @@ impl BundleOrchestrator {
                 // For example, if importing "greetings.irrelevant", also add dependency on "greetings"
                 self.add_parent_package_dependencies(import, context);
             }
-            ImportType::ThirdParty | ImportType::StandardLibrary => {
+            ImportType::ThirdParty | ImportType::StandardLibrary | ImportType::Ignored => {
                 // These will be preserved in the output, not inlined
             }
         }
@@ -1171,44 +2283,12 @@ impl BundleOrchestrator {
         }
     }
 
-    /// Write requirements.txt file for stdout mode (current directory)
-    fn write_requirements_file_for_stdout(
-        &self,
-        sorted_modules: &[(String, PathBuf, Vec<String>)],
-        resolver: &ModuleResolver,
-    ) -> Result<()> {
-        let requirements_content = self.generate_requirements(sorted_modules, resolver)?;
-        if !requirements_content.is_empty() {
-            let requirements_path = Path::new("requirements.txt");
-
-            fs::write(requirements_path, requirements_content).with_context(|| {
-                format!("Failed to write requirements file: {:?}", requirements_path)
-            })?;
-
-            info!("Requirements written to: {:?}", requirements_path);
-        } else {
-            info!("No third-party dependencies found, skipping requirements.txt");
-        }
-        Ok(())
-    }
-
     /// Write requirements.txt file if there are dependencies
-    fn write_requirements_file(
-        &self,
-        sorted_modules: &[(String, PathBuf, Vec<String>)],
-        resolver: &ModuleResolver,
-        output_path: &Path,
-    ) -> Result<()> {
-        let requirements_content = self.generate_requirements(sorted_modules, resolver)?;
-        if !requirements_content.is_empty() {
-            let requirements_path = output_path
-                .parent()
-                .unwrap_or_else(|| Path::new("."))
-                .join("requirements.txt");
-
-            fs::write(&requirements_path, requirements_content).with_context(|| {
-                format!("Failed to write requirements file: {:?}", requirements_path)
-            })?;
+    fn write_requirements_file(requirements: &[String], requirements_path: &Path) -> Result<()> {
+        if !requirements.is_empty() {
+            write_atomically(requirements_path, requirements.join("\n"), false).with_context(
+                || format!("Failed to write requirements file: {:?}", requirements_path),
+            )?;
 
             info!("Requirements written to: {:?}", requirements_path);
         } else {
@@ -1347,7 +2427,9 @@ impl BundleOrchestrator {
     }
 
     /// Emit bundle using static bundler (no exec calls)
-    fn emit_static_bundle(&mut self, params: StaticBundleParams<'_>) -> Result<String> {
+    fn emit_static_bundle(&mut self, params: StaticBundleParams<'_>) -> Result<EmittedBundle> {
+        self.metrics.phase_started(Phase::CodeGeneration);
+
         // First, detect and resolve conflicts after all modules have been analyzed
         let conflicts = self.semantic_bundler.detect_and_resolve_conflicts();
         if !conflicts.is_empty() {
@@ -1389,9 +2471,11 @@ impl BundleOrchestrator {
         } else {
             // Fall back to parsing modules if not pre-parsed
             for (module_name, module_path, _imports) in params.sorted_modules {
-                let source = fs::read_to_string(module_path)
-                    .with_context(|| format!("Failed to read module file: {:?}", module_path))?;
-                let source = crate::util::normalize_line_endings(source);
+                let source = read_module_source(
+                    self.file_provider.as_ref(),
+                    module_path,
+                    Some(module_name),
+                )?;
                 // Calculate content hash for deterministic module naming
                 use sha2::{Digest, Sha256};
                 let mut hasher = Sha256::new();
@@ -1400,8 +2484,8 @@ impl BundleOrchestrator {
                 let content_hash = format!("{:x}", hash);
 
                 // Parse into AST
-                let ast = ruff_python_parser::parse_module(&source)
-                    .with_context(|| format!("Failed to parse module: {:?}", module_path))?;
+                let ast =
+                    parse_module_for_target(module_path, &source, self.config.python_version()?)?;
 
                 module_asts.push((
                     module_name.clone(),
@@ -1438,43 +2522,160 @@ impl BundleOrchestrator {
         }
 
         // Bundle all modules using static bundler
-        let bundled_ast = static_bundler.bundle_modules(crate::code_generator::BundleParams {
-            modules: module_asts,
-            sorted_modules: params.sorted_modules,
-            entry_module_name: params.entry_module_name,
-            graph: params.graph,
-            semantic_bundler: &self.semantic_bundler,
+        let mut bundled_ast =
+            static_bundler.bundle_modules(crate::code_generator::BundleParams {
+                modules: module_asts,
+                sorted_modules: params.sorted_modules,
+                entry_module_name: params.entry_module_name,
+                graph: params.graph,
+                semantic_bundler: &self.semantic_bundler,
+                dual_mode: self.config.dual_mode,
+                register_inlined_modules: self.config.register_inlined_modules,
+                preflight_requirements: if self.config.preflight_check {
+                    params.requirements
+                } else {
+                    &[]
+                },
+                distribution_names: params.distribution_names,
+                prune_wrapper_exports: self.config.prune_wrapper_exports,
+                runtime_prefix: &self.config.runtime_prefix,
+                inline_with_managers: &self.config.inline_with_managers,
+                relative_import_fallbacks: &self.config.relative_import_fallbacks,
+                dispatched_scripts: self
+                    .script_dispatcher
+                    .as_ref()
+                    .map(ScriptDispatcher::commands)
+                    .unwrap_or_default(),
+                tree_shake: self.config.tree_shake,
+                strip_asserts: self.config.strip_asserts,
+                strip_docstrings: self.config.strip_docstrings,
+                keep_entry_docstrings: self.config.keep_entry_docstrings,
+                strip_annotations: self.config.strip_annotations
+                    || !self.config.preserve_type_hints,
+                import_cycles: params
+                    .circular_dep_analysis
+                    .map(|analysis| analysis.resolvable_cycles.as_slice())
+                    .unwrap_or_default(),
+                force_inline: &self.config.force_inline,
+                force_wrapper: &self.config.force_wrapper,
+                python_version: self.config.python_version()?,
+            })?;
+
+        let statement_count = bundled_ast.body.len();
+        run_hook(&self.plugins, "pre_emit", |plugin| {
+            plugin.pre_emit(&mut bundled_ast)
         })?;
+        if bundled_ast.body.len() != statement_count {
+            warn!(
+                "Plugins changed the number of top-level statements; the module map and section markers may be off"
+            );
+        }
 
         // Generate Python code from AST
         let empty_parsed = ruff_python_parser::parse_module("")?;
         let stylist = ruff_python_codegen::Stylist::from_tokens(empty_parsed.tokens(), "");
 
-        let mut code_parts = Vec::new();
-        for stmt in &bundled_ast.body {
-            let generator = ruff_python_codegen::Generator::from(&stylist);
-            let stmt_code = generator.stmt(stmt);
-            code_parts.push(stmt_code);
-        }
+        let section_markers = if self.config.section_markers {
+            Self::section_marker_comments(
+                static_bundler.sections(),
+                params.sorted_modules,
+                params.entry_module_name,
+            )
+        } else {
+            Vec::new()
+        };
+        let mut section_markers = section_markers.into_iter().peekable();
 
         // Add shebang and header
         let mut final_output = vec![
-            "#!/usr/bin/env python3".to_string(),
+            self.config.shebang_line(),
             "# Generated by Cribo - Python Source Bundler".to_string(),
             "# https://github.com/ophidiarium/cribo".to_string(),
             String::new(), // Empty line
         ];
-        final_output.extend(code_parts);
 
-        Ok(final_output.join("\n"))
+        // Parts are joined with newlines, so track offsets as they are appended
+        let mut offset: usize = final_output.iter().map(|part| part.len() + 1).sum();
+        let mut line = final_output.len() + 1;
+        let mut statement_spans = Vec::with_capacity(bundled_ast.body.len());
+        let mut push_part = |part: String, final_output: &mut Vec<String>| {
+            let span = Span {
+                start_byte: offset,
+                end_byte: offset + part.len(),
+                start_line: line,
+                end_line: line + part.matches('\n').count(),
+            };
+            offset = span.end_byte + 1;
+            line = span.end_line + 1;
+            final_output.push(part);
+            span
+        };
+
+        for (index, stmt) in bundled_ast.body.iter().enumerate() {
+            while let Some((_, marker)) = section_markers.next_if(|(start, _)| *start == index) {
+                push_part(marker, &mut final_output);
+            }
+            let generator = ruff_python_codegen::Generator::from(&stylist);
+            let stmt_code = generator.stmt(stmt);
+            statement_spans.push(push_part(stmt_code, &mut final_output));
+        }
+
+        let module_map = ModuleMap::from_sections(
+            static_bundler.sections(),
+            &statement_spans,
+            params.sorted_modules,
+            params.entry_module_name,
+        );
+
+        let wrapper_reasons = static_bundler
+            .wrapper_reasons()
+            .map(|(module_name, reasons)| (module_name.to_owned(), reasons.to_vec()))
+            .collect();
+
+        self.metrics.phase_finished(Phase::CodeGeneration);
+        Ok(EmittedBundle {
+            code: final_output.join("\n"),
+            module_map,
+            wrapper_reasons,
+        })
     }
 
-    /// Generate requirements.txt content from third-party imports
-    fn generate_requirements(
-        &self,
+    /// Delimiter comments naming the module and source file each bundle section came from
+    ///
+    /// Paths are relative to the entry script's directory so the output stays stable
+    /// across checkouts.
+    fn section_marker_comments(
+        sections: &[BundleSection],
+        sorted_modules: &[(String, PathBuf, Vec<String>)],
+        entry_module_name: &str,
+    ) -> Vec<(usize, String)> {
+        let entry_dir = sorted_modules
+            .iter()
+            .find(|(name, _, _)| name == entry_module_name)
+            .and_then(|(_, path, _)| path.parent());
+
+        sections
+            .iter()
+            .map(|section| {
+                let module_name = &section.module_name;
+                let path = sorted_modules
+                    .iter()
+                    .find(|(name, _, _)| name == module_name)
+                    .map(|(_, path, _)| relative_module_path(path, entry_dir));
+                let marker = match path {
+                    Some(path) => format!("\n# ── module: {} ({}) ──", module_name, path),
+                    None => format!("\n# ── module: {} ──", module_name),
+                };
+                (section.statements.start, marker)
+            })
+            .collect()
+    }
+
+    /// Collect the sorted third-party packages required by the bundled modules
+    fn collect_requirements(
         modules: &[(String, PathBuf, Vec<String>)],
         resolver: &ModuleResolver,
-    ) -> Result<String> {
+    ) -> Vec<String> {
         let mut third_party_imports = IndexSet::new();
 
         for (_module_name, _module_path, imports) in modules {
@@ -1494,7 +2695,79 @@ impl BundleOrchestrator {
 
         let mut requirements: Vec<String> = third_party_imports.into_iter().collect();
         requirements.sort();
+        for package in &requirements {
+            let versions = stdlib_versions(package);
+            if let (Some(first), Some(last)) = (versions.first(), versions.last()) {
+                warn!(
+                    "'{}' is in the standard library of Python 3.{}-3.{} but not of {}; \
+                     it is listed as a third-party requirement",
+                    package,
+                    first,
+                    last,
+                    resolver.config().target_version
+                );
+            }
+        }
+        requirements
+    }
 
-        Ok(requirements.join("\n"))
+    /// Distribution names for `requirements`: the configured ones, then those recorded
+    /// by the distributions installed in the virtual environment
+    fn distribution_names(
+        &self,
+        resolver: &ModuleResolver,
+        requirements: &[String],
+    ) -> IndexMap<String, String> {
+        let mut names = self.config.distribution_names.clone();
+        if requirements.is_empty() {
+            return names;
+        }
+
+        let installed = resolver.installed_distributions();
+        for package in requirements {
+            if names.contains_key(package) {
+                continue;
+            }
+            match installed.get(package) {
+                Some(distribution) => {
+                    names.insert(package.clone(), distribution.clone());
+                }
+                None => debug!(
+                    "Third-party package '{}' is not installed in the virtual environment",
+                    package
+                ),
+            }
+        }
+        names
+    }
+
+    /// Conditions of the `requirements` that not every environment imports
+    ///
+    /// Packages absent from the result are imported unconditionally somewhere, or only
+    /// in ways the import guards can't see, such as `importlib.import_module`.
+    fn requirement_conditions(
+        parsed_modules: &[ParsedModuleData],
+        requirements: &[String],
+    ) -> IndexMap<String, RequirementCondition> {
+        let mut sites: IndexMap<&str, Vec<_>> = requirements
+            .iter()
+            .map(|package| (package.as_str(), Vec::new()))
+            .collect();
+        for (_module_name, _module_path, _imports, ast, _source) in parsed_modules {
+            for import in ImportGuardVisitor::find_guarded_imports(ast) {
+                let package = import.module.split('.').next().unwrap_or(&import.module);
+                if let Some(package_sites) = sites.get_mut(package) {
+                    package_sites.push(import);
+                }
+            }
+        }
+        sites
+            .into_iter()
+            .filter_map(|(package, package_sites)| {
+                let condition = RequirementCondition::of_sites(&package_sites)?;
+                debug!("Requirement '{}' is conditional: {:?}", package, condition);
+                Some((package.to_owned(), condition))
+            })
+            .collect()
     }
 }
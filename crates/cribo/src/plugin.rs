@@ -0,0 +1,87 @@
+//! Hooks that let embedders transform modules and the bundle while it is built
+//!
+//! A [`BundlePlugin`] is registered on the orchestrator with
+//! `BundleOrchestrator::with_plugin`. Registered plugins run in registration order
+//! at each hook; every hook has a no-op default, so a plugin implements only the
+//! stages it cares about.
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use ruff_python_ast::ModModule;
+
+use crate::error::{CriboError, CriboResult};
+
+/// A first-party module handed to a plugin hook
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct PluginModule<'a> {
+    /// Dotted module name, when known at this stage
+    pub name: Option<&'a str>,
+    /// Source file the module was read from
+    pub path: &'a Path,
+}
+
+impl<'a> PluginModule<'a> {
+    pub fn new(name: Option<&'a str>, path: &'a Path) -> Self {
+        Self { name, path }
+    }
+}
+
+/// Project-specific transforms applied while bundling
+///
+/// Errors returned from a hook abort the bundle and are reported with the plugin's
+/// name and the hook that failed.
+pub trait BundlePlugin: Send + Sync {
+    /// Name used in log records and errors
+    fn name(&self) -> &str;
+
+    /// Rewrite a module's source before it is parsed
+    ///
+    /// Runs for every first-party module, including while imports are discovered.
+    fn pre_parse(&self, _module: &PluginModule<'_>, _source: &mut String) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Transform a module's AST once it has been resolved into the dependency graph
+    ///
+    /// Runs before the module is analyzed, so the transformed AST is what gets
+    /// bundled, including any imports the hook adds or removes from nested scopes.
+    fn post_resolve(&self, _module: &PluginModule<'_>, _ast: &mut ModModule) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Transform the bundled module right before its code is generated
+    ///
+    /// Module maps and section markers refer to top-level statements by position;
+    /// a hook that adds or removes top-level statements makes them unreliable.
+    fn pre_emit(&self, _bundle: &mut ModModule) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for dyn BundlePlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BundlePlugin")
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+/// Shared handle to a registered plugin
+pub type BundlePluginHandle = Arc<dyn BundlePlugin>;
+
+/// Run `hook` for each plugin in order, stopping at the first failure
+pub(crate) fn run_hook(
+    plugins: &[BundlePluginHandle],
+    hook: &str,
+    mut run: impl FnMut(&dyn BundlePlugin) -> anyhow::Result<()>,
+) -> CriboResult<()> {
+    for plugin in plugins {
+        run(plugin.as_ref()).map_err(|err| CriboError::Emit {
+            path: None,
+            source: err.context(format!("Plugin `{}` failed in {}", plugin.name(), hook)),
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Executable zip applications (`.pyz`) wrapping a bundle, as produced by `zipapp`
+
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::util::{make_executable, write_atomically};
+
+/// Interpreter line prepended to the archive so it can be executed directly
+const SHEBANG: &[u8] = b"#!/usr/bin/env python3\n";
+
+/// Where the `.pyz` of a bundle written to `output_path` goes: next to it, with the
+/// extension replaced
+pub fn pyz_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("pyz")
+}
+
+/// Zip application bytes running `code` as its `__main__` module
+pub fn build_pyz(code: &str) -> Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+    buffer.write_all(SHEBANG)?;
+
+    let mut archive = ZipWriter::new(buffer);
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .unix_permissions(0o644);
+    archive
+        .start_file("__main__.py", options)
+        .context("Failed to add __main__.py to zip application")?;
+    archive.write_all(code.as_bytes())?;
+    let buffer = archive
+        .finish()
+        .context("Failed to finish zip application")?;
+    Ok(buffer.into_inner())
+}
+
+/// Write `code` as an executable zip application to `path`
+pub fn write_pyz(path: &Path, code: &str) -> Result<()> {
+    let contents = build_pyz(code)?;
+    write_atomically(path, contents, false)
+        .with_context(|| format!("Failed to write zip application: {:?}", path))?;
+
+    make_executable(path).with_context(|| format!("Failed to make {:?} executable", path))?;
+
+    log::info!("Zip application written to: {:?}", path);
+    Ok(())
+}
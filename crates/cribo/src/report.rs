@@ -0,0 +1,104 @@
+//! JSON summary of a bundling run for CI dashboards and auditing
+//!
+//! The report combines what the other artifacts describe separately: which modules were
+//! bundled and how, the third-party requirements, the cycles that were resolved, the
+//! modules replaced by stubs, the source files the bundle leaves out and the type
+//! checker's findings.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::module_map::ModuleMapEntry;
+use crate::orchestrator::BundleOutcome;
+use crate::type_check::TypeCheckFinding;
+use crate::util::write_atomically;
+
+/// Version of the report format, bumped on incompatible changes
+pub const REPORT_VERSION: u32 = 1;
+
+/// Summary of one bundling run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BundleReport {
+    pub version: u32,
+    /// Bundled modules in the order their code appears in the bundle, with their
+    /// locations in it
+    pub modules: Vec<ModuleMapEntry>,
+    /// Sorted top-level third-party packages the bundle imports
+    pub requirements: Vec<String>,
+    /// Function-level import cycles that were resolved, as module names per cycle
+    pub resolved_cycles: Vec<Vec<String>>,
+    pub imports: ImportCounts,
+    /// First-party modules under the source roots that the bundle does not include
+    pub orphans: Vec<OrphanModule>,
+    /// Modules whose configured stub was bundled in their place
+    pub stubbed_modules: Vec<StubbedModule>,
+    /// What the configured type checker reported about the bundle
+    pub type_check_findings: Vec<TypeCheckFinding>,
+}
+
+/// A first-party module that nothing reachable from the entry imports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OrphanModule {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// A module replaced by a stub from `stub_modules`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StubbedModule {
+    pub name: String,
+    /// Stub file bundled instead of the module
+    pub stub: PathBuf,
+}
+
+/// Number of distinct imports encountered per category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ImportCounts {
+    pub first_party: usize,
+    pub third_party: usize,
+    pub stdlib: usize,
+    pub ignored: usize,
+}
+
+impl BundleReport {
+    pub(crate) fn from_outcome(outcome: &BundleOutcome) -> Self {
+        let counts = outcome.classification_counts;
+        Self {
+            version: REPORT_VERSION,
+            modules: outcome.module_map.modules.to_vec(),
+            requirements: outcome.requirements.to_vec(),
+            resolved_cycles: outcome.resolved_cycles.to_vec(),
+            imports: ImportCounts {
+                first_party: counts.first_party,
+                third_party: counts.third_party,
+                stdlib: counts.standard_library,
+                ignored: counts.ignored,
+            },
+            orphans: outcome.orphans.to_vec(),
+            stubbed_modules: outcome.stubbed_modules.to_vec(),
+            type_check_findings: outcome.type_check_findings.to_vec(),
+        }
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize report")?;
+        write_atomically(path, json, false)
+            .with_context(|| format!("Failed to write report: {:?}", path))?;
+        log::info!("Report written to: {:?}", path);
+        Ok(())
+    }
+}
+
+/// Where the report of a bundle written to `output_path` goes: next to it, with
+/// `.report.json` appended to the file name
+pub fn report_path(output_path: &Path) -> PathBuf {
+    let mut file_name = output_path
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| "bundle.py".into());
+    file_name.push(".report.json");
+    output_path.with_file_name(file_name)
+}
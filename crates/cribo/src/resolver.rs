@@ -2,10 +2,17 @@ use anyhow::Result;
 use indexmap::{IndexMap, IndexSet};
 use log::debug;
 use std::cell::RefCell;
+use std::fmt;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
 
 use crate::config::Config;
+use crate::distributions;
+use crate::editable::EditableSources;
+use crate::file_provider::{FileProviderHandle, OsFileProvider};
+use crate::module_filter::ModuleFilter;
+use crate::util::get_python_executable;
 use ruff_python_stdlib::sys;
 
 /// Check if a module is part of the Python standard library using ruff_python_stdlib
@@ -23,6 +30,50 @@ fn is_stdlib_module(module_name: &str, python_version: u8) -> bool {
     }
 }
 
+/// Oldest and newest Python 3 minor versions accepted as `target-version`
+const MIN_TARGET_MINOR: u8 = 8;
+const MAX_TARGET_MINOR: u8 = 14;
+
+/// Python 3 minor versions cribo can target whose standard library has `module_name`
+///
+/// Modules come and go (`tomllib` was added in 3.11, `distutils` removed in 3.12), so
+/// a module outside the target version's standard library may still be in another's.
+pub fn stdlib_versions(module_name: &str) -> Vec<u8> {
+    (MIN_TARGET_MINOR..=MAX_TARGET_MINOR)
+        .filter(|minor| is_stdlib_module(module_name, *minor))
+        .collect()
+}
+
+/// First component of a dotted module name
+fn top_level_name(module_name: &str) -> &str {
+    module_name.split('.').next().unwrap_or(module_name)
+}
+
+/// `sys.prefix` of the Python interpreter on `PATH`, when that is a virtual environment
+///
+/// Covers environments activated without setting `VIRTUAL_ENV`, such as a venv's
+/// `bin` directory put first on `PATH`. The interpreter is only asked once per process.
+fn interpreter_virtualenv() -> Option<PathBuf> {
+    static PREFIX: OnceLock<Option<PathBuf>> = OnceLock::new();
+    PREFIX
+        .get_or_init(|| {
+            let output = Command::new(get_python_executable())
+                .args([
+                    "-c",
+                    "import sys; print(sys.prefix if sys.prefix != sys.base_prefix else '')",
+                ])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            let prefix = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+            (!prefix.is_empty()).then(|| {
+                debug!("Interpreter on PATH runs in virtual environment {}", prefix);
+                PathBuf::from(prefix)
+            })
+        })
+        .clone()
+}
+
 /// A scoped guard for safely setting and cleaning up the PYTHONPATH environment variable.
 ///
 /// This guard ensures that the PYTHONPATH environment variable is properly restored
@@ -172,17 +223,86 @@ pub enum ImportType {
     FirstParty,
     ThirdParty,
     StandardLibrary,
+    /// Left exactly as written: not bundled, not hoisted and not a requirement
+    Ignored,
 }
 
-#[derive(Debug)]
+/// Kind of module a name resolves to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionKind {
+    /// Part of the standard library of the target Python version
+    Stdlib,
+    /// A module or regular package found under a source directory
+    FirstParty,
+    /// Anything not found locally, expected to be installed at runtime
+    ThirdParty,
+    /// A first-party directory without an `__init__.py`
+    Namespace,
+    /// Left alone by the bundler, as decided by an [`ImportClassifier`]
+    Ignored,
+}
+
+/// Where a module name resolves to and how the bundler treats it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Resolution {
+    /// Source file of a first-party module; `None` for every other kind
+    pub path: Option<PathBuf>,
+    pub kind: ResolutionKind,
+    /// Source directory the module or namespace package was found in
+    pub origin_root: Option<PathBuf>,
+    /// Directories making up a namespace package, one per source directory holding a
+    /// portion of it; empty for every other kind
+    pub namespace_portions: Vec<PathBuf>,
+}
+
+/// Policy hook that classifies imports before the built-in rules
+///
+/// Lets embedders encode rules such as "everything under `corp.*` is external" without
+/// touching the resolver. Relative imports are always first-party and never reach the
+/// classifier.
+pub trait ImportClassifier: Send + Sync {
+    /// Classify `module_name`, or return `None` to fall back to the built-in rules
+    fn classify(&self, module_name: &str) -> Option<ImportType>;
+}
+
+impl fmt::Debug for dyn ImportClassifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ImportClassifier")
+    }
+}
+
+/// Shared handle to an import classifier
+pub type ImportClassifierHandle = Arc<dyn ImportClassifier>;
+
+#[derive(Debug, Clone)]
 pub struct ModuleResolver {
     config: Config,
     /// Cache of resolved module paths
     module_cache: IndexMap<String, Option<PathBuf>>,
     /// Set of all first-party modules discovered in src directories
     first_party_modules: IndexSet<String>,
+    /// Which files under the src directories are first-party (`include`/`exclude`)
+    module_filter: ModuleFilter,
+    /// Modules found in src directories that `module_filter` keeps out of the bundle
+    excluded_modules: IndexSet<String>,
     /// Cache of virtual environment packages to avoid repeated filesystem scans
     virtualenv_packages_cache: RefCell<Option<IndexSet<String>>>,
+    /// Memoized import classifications, shared by every pass that classifies imports
+    classification_cache: RefCell<IndexMap<String, ImportType>>,
+    /// User-supplied policy consulted before the built-in rules
+    classifier: Option<ImportClassifierHandle>,
+    /// Where source directories are scanned and modules are looked up
+    files: FileProviderHandle,
+}
+
+/// Number of distinct modules classified into each import category
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ClassificationCounts {
+    pub first_party: usize,
+    pub third_party: usize,
+    pub standard_library: usize,
+    pub ignored: usize,
 }
 
 impl ModuleResolver {
@@ -206,11 +326,37 @@ impl ModuleResolver {
         pythonpath_override: Option<&str>,
         virtualenv_override: Option<&str>,
     ) -> Result<Self> {
+        Self::build(
+            config,
+            pythonpath_override,
+            virtualenv_override,
+            Arc::new(OsFileProvider),
+        )
+    }
+
+    /// Create a ModuleResolver that finds modules among the files of `files` instead of
+    /// the real filesystem
+    pub fn with_file_provider(config: Config, files: FileProviderHandle) -> Result<Self> {
+        Self::build(config, None, None, files)
+    }
+
+    fn build(
+        config: Config,
+        pythonpath_override: Option<&str>,
+        virtualenv_override: Option<&str>,
+        files: FileProviderHandle,
+    ) -> Result<Self> {
+        let module_filter = ModuleFilter::new(&config.include, &config.exclude)?;
         let mut resolver = Self {
             config,
             module_cache: IndexMap::new(),
             first_party_modules: IndexSet::new(),
+            module_filter,
+            excluded_modules: IndexSet::new(),
             virtualenv_packages_cache: RefCell::new(None),
+            classification_cache: RefCell::new(IndexMap::new()),
+            classifier: None,
+            files,
         };
 
         resolver.discover_first_party_modules_with_overrides(
@@ -220,7 +366,7 @@ impl ModuleResolver {
         Ok(resolver)
     }
 
-    /// Get all directories to scan for modules (configured src + PYTHONPATH + VIRTUAL_ENV)
+    /// Get all directories to scan for modules (configured src + PYTHONPATH + extra_paths)
     /// Returns deduplicated, canonicalized paths
     pub fn get_scan_directories(&self) -> Vec<PathBuf> {
         self.get_scan_directories_with_overrides(None, None)
@@ -256,18 +402,18 @@ impl ModuleResolver {
 
         // Add configured src directories
         for dir in &self.config.src {
-            if let Ok(canonical) = dir.canonicalize() {
-                unique_dirs.insert(canonical);
-            } else {
-                // If canonicalize fails (e.g., path doesn't exist), use the original path
-                unique_dirs.insert(dir.clone());
-            }
+            // A directory that cannot be canonicalized (e.g. it doesn't exist) is kept as is
+            unique_dirs.insert(self.files.canonicalize(dir));
         }
 
-        // Add PYTHONPATH directories (for first-party module discovery)
-        let pythonpath = pythonpath_override
-            .map(|p| p.to_owned())
-            .or_else(|| std::env::var("PYTHONPATH").ok());
+        // Add PYTHONPATH directories (for first-party module discovery); an explicit
+        // override always applies, the environment only when the config allows it
+        let pythonpath = pythonpath_override.map(|p| p.to_owned()).or_else(|| {
+            self.config
+                .respect_pythonpath
+                .then(|| std::env::var("PYTHONPATH").ok())
+                .flatten()
+        });
 
         if let Some(pythonpath) = pythonpath {
             // Use platform-appropriate path separator: ';' on Windows, ':' on Unix
@@ -277,6 +423,11 @@ impl ModuleResolver {
             }
         }
 
+        // Add configured extra search paths, which come after PYTHONPATH
+        for dir in &self.config.extra_paths {
+            self.add_pythonpath_directory(&mut unique_dirs, &dir.to_string_lossy());
+        }
+
         unique_dirs.into_iter().collect()
     }
 
@@ -287,16 +438,11 @@ impl ModuleResolver {
         }
 
         let path = PathBuf::from(path_str);
-        if !path.exists() || !path.is_dir() {
+        if !self.files.is_dir(&path) {
             return;
         }
 
-        if let Ok(canonical) = path.canonicalize() {
-            unique_dirs.insert(canonical);
-        } else {
-            // If canonicalize fails but path exists, use the original path
-            unique_dirs.insert(path);
-        }
+        unique_dirs.insert(self.files.canonicalize(&path));
     }
 
     /// Detect common virtual environment directory names in the current working directory
@@ -344,7 +490,7 @@ impl ModuleResolver {
 
     /// Check if a path is a valid existing directory
     fn is_valid_directory(&self, path: &Path) -> bool {
-        path.exists() && path.is_dir()
+        self.files.is_dir(path)
     }
 
     /// Get site-packages directories from a virtual environment path
@@ -352,7 +498,7 @@ impl ModuleResolver {
     fn get_virtualenv_site_packages_directories(&self, virtualenv_path: &str) -> Vec<PathBuf> {
         let venv_root = PathBuf::from(virtualenv_path);
 
-        if !venv_root.exists() || !venv_root.is_dir() {
+        if !self.files.is_dir(&venv_root) {
             return Vec::new();
         }
 
@@ -367,7 +513,7 @@ impl ModuleResolver {
     fn get_windows_site_packages(&self, venv_root: &Path) -> Vec<PathBuf> {
         let site_packages = venv_root.join("Lib").join("site-packages");
 
-        if site_packages.exists() && site_packages.is_dir() {
+        if self.files.is_dir(&site_packages) {
             vec![site_packages]
         } else {
             Vec::new()
@@ -378,7 +524,7 @@ impl ModuleResolver {
     fn get_unix_site_packages(&self, venv_root: &Path) -> Vec<PathBuf> {
         let lib_dir = venv_root.join("lib");
 
-        if !lib_dir.exists() || !lib_dir.is_dir() {
+        if !self.files.is_dir(&lib_dir) {
             return Vec::new();
         }
 
@@ -389,12 +535,12 @@ impl ModuleResolver {
     fn scan_lib_directory_for_python_versions(&self, lib_dir: &Path) -> Vec<PathBuf> {
         let mut site_packages_dirs = Vec::new();
 
-        let Ok(entries) = std::fs::read_dir(lib_dir) else {
+        let Ok(entries) = self.files.list_dir(lib_dir) else {
             return site_packages_dirs;
         };
 
-        for entry in entries.flatten() {
-            if let Some(site_packages) = self.check_python_version_directory(&entry.path()) {
+        for entry in entries {
+            if let Some(site_packages) = self.check_python_version_directory(&entry) {
                 site_packages_dirs.push(site_packages);
             }
         }
@@ -404,7 +550,7 @@ impl ModuleResolver {
 
     /// Check if a directory is a Python version directory with site-packages
     fn check_python_version_directory(&self, path: &Path) -> Option<PathBuf> {
-        if !path.is_dir() {
+        if !self.files.is_dir(path) {
             return None;
         }
 
@@ -415,7 +561,7 @@ impl ModuleResolver {
         }
 
         let site_packages = path.join("site-packages");
-        if site_packages.exists() && site_packages.is_dir() {
+        if self.files.is_dir(&site_packages) {
             Some(site_packages)
         } else {
             None
@@ -445,7 +591,23 @@ impl ModuleResolver {
     /// Compute virtualenv packages by scanning the filesystem
     fn compute_virtualenv_packages(&self, virtualenv_override: Option<&str>) -> IndexSet<String> {
         let mut packages = IndexSet::new();
+        for site_packages_dir in self.virtualenv_site_packages(virtualenv_override) {
+            self.scan_site_packages_directory(&site_packages_dir, &mut packages);
+        }
 
+        // Cache the result if no override was specified (for subsequent calls)
+        if virtualenv_override.is_none() {
+            if let Ok(mut cache_ref) = self.virtualenv_packages_cache.try_borrow_mut() {
+                *cache_ref = Some(packages.clone());
+            }
+        }
+
+        packages
+    }
+
+    /// Site-packages directories of the virtual environment, or of the common
+    /// virtual environment directories when none is active
+    fn virtualenv_site_packages(&self, virtualenv_override: Option<&str>) -> Vec<PathBuf> {
         // First, try to get explicit VIRTUAL_ENV (either override or environment variable)
         let explicit_virtualenv = virtualenv_override
             .map(|v| v.to_owned())
@@ -455,42 +617,111 @@ impl ModuleResolver {
             // Use explicit VIRTUAL_ENV if provided
             vec![PathBuf::from(virtualenv_path)]
         } else {
-            // Fallback: detect common virtual environment directory names
-            self.detect_fallback_virtualenv_paths()
+            // Fallback: detect common virtual environment directory names, then ask the
+            // interpreter on PATH whether it runs in a virtual environment
+            let detected = self.detect_fallback_virtualenv_paths();
+            if detected.is_empty() {
+                interpreter_virtualenv().into_iter().collect()
+            } else {
+                detected
+            }
         };
 
-        // Scan all discovered virtual environment paths
-        for venv_path in virtualenv_paths {
-            let virtualenv_path_str = venv_path.to_string_lossy();
-            for site_packages_dir in
-                self.get_virtualenv_site_packages_directories(&virtualenv_path_str)
-            {
-                self.scan_site_packages_directory(&site_packages_dir, &mut packages);
-            }
-        }
+        virtualenv_paths
+            .iter()
+            .flat_map(|venv_path| {
+                self.get_virtualenv_site_packages_directories(&venv_path.to_string_lossy())
+            })
+            .collect()
+    }
 
-        // Cache the result if no override was specified (for subsequent calls)
-        if virtualenv_override.is_none() {
-            if let Ok(mut cache_ref) = self.virtualenv_packages_cache.try_borrow_mut() {
-                *cache_ref = Some(packages.clone());
+    /// Distributions installed in the virtual environment, by the top-level packages
+    /// they provide
+    pub fn installed_distributions(&self) -> IndexMap<String, String> {
+        let mut installed = IndexMap::new();
+        for site_packages_dir in self.virtualenv_site_packages(None) {
+            for (import_name, distribution) in
+                distributions::installed_distributions(&site_packages_dir, self.files.as_ref())
+            {
+                installed.entry(import_name).or_insert(distribution);
             }
         }
-
-        packages
+        installed
     }
 
     /// Scan a site-packages directory and add found packages to the set
+    ///
+    /// Directories listed in the `.pth` files of site-packages are on `sys.path` too, so
+    /// packages installed by extending the path (such as setuptools editable installs)
+    /// are scanned as well.
     fn scan_site_packages_directory(
         &self,
         site_packages_dir: &PathBuf,
         packages: &mut IndexSet<String>,
     ) {
-        let Ok(entries) = std::fs::read_dir(site_packages_dir) else {
+        self.scan_package_directory(site_packages_dir, packages);
+        for path_dir in self.pth_directories(site_packages_dir) {
+            if &path_dir != site_packages_dir {
+                self.scan_package_directory(&path_dir, packages);
+            }
+        }
+    }
+
+    /// Existing directories listed in the `.pth` files of a site-packages directory
+    ///
+    /// Like `site.addpackage`, blank lines and comments are skipped, lines starting with
+    /// `import` are executed by Python rather than added to the path, and relative
+    /// entries are resolved against the site-packages directory.
+    fn pth_directories(&self, site_packages_dir: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = self.files.list_dir(site_packages_dir) else {
+            return Vec::new();
+        };
+
+        let mut pth_files: Vec<PathBuf> = entries
+            .into_iter()
+            .filter(|path| {
+                path.extension().is_some_and(|ext| ext == "pth") && self.files.is_file(path)
+            })
+            .collect();
+        // Python processes .pth files in alphabetical order
+        pth_files.sort();
+
+        let mut directories = Vec::new();
+        for pth_file in pth_files {
+            let Ok(content) = self.files.read_to_string(&pth_file) else {
+                debug!("Failed to read .pth file {}", pth_file.display());
+                continue;
+            };
+            for line in content.lines() {
+                let line = line.trim_end();
+                if line.is_empty()
+                    || line.starts_with('#')
+                    || line.starts_with("import ")
+                    || line.starts_with("import\t")
+                {
+                    continue;
+                }
+                let directory = site_packages_dir.join(line);
+                if self.files.is_dir(&directory) && !directories.contains(&directory) {
+                    debug!(
+                        "Found path entry {} in {}",
+                        directory.display(),
+                        pth_file.display()
+                    );
+                    directories.push(directory);
+                }
+            }
+        }
+        directories
+    }
+
+    /// Add the packages and modules directly inside `directory` to the set
+    fn scan_package_directory(&self, directory: &Path, packages: &mut IndexSet<String>) {
+        let Ok(entries) = self.files.list_dir(directory) else {
             return;
         };
 
-        for entry in entries.flatten() {
-            let path = entry.path();
+        for path in entries {
             let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
                 continue;
             };
@@ -501,7 +732,7 @@ impl ModuleResolver {
             }
 
             // For directories, use the directory name as package name
-            if path.is_dir() {
+            if self.files.is_dir(&path) {
                 packages.insert(name.to_owned());
             }
             // For .py files, use the filename without extension
@@ -556,6 +787,11 @@ impl ModuleResolver {
             self.scan_directory_for_modules(src_dir)?;
         }
 
+        // Editable installs of the user's own projects are bundled like source directories
+        for site_packages_dir in self.virtualenv_site_packages(virtualenv_override) {
+            self.scan_editable_installs(&site_packages_dir, &directories_to_scan);
+        }
+
         // Add configured known first-party modules
         for module_name in &self.config.known_first_party {
             self.first_party_modules.insert(module_name.clone());
@@ -564,21 +800,73 @@ impl ModuleResolver {
         Ok(())
     }
 
+    /// Add the sources of the editable installs in a site-packages directory
+    ///
+    /// Packages already found in the scanned directories keep precedence, as those come
+    /// first on `sys.path` when the application runs from its checkout.
+    fn scan_editable_installs(&mut self, site_packages_dir: &Path, scanned_dirs: &[PathBuf]) {
+        let path_entries: Vec<PathBuf> = self
+            .pth_directories(site_packages_dir)
+            .into_iter()
+            .map(|dir| self.files.canonicalize(&dir))
+            .collect();
+        let sources = EditableSources::find(site_packages_dir, &path_entries, self.files.as_ref());
+        let shadowed: IndexSet<String> = self
+            .first_party_modules
+            .iter()
+            .map(|module_name| top_level_name(module_name).to_owned())
+            .collect();
+
+        for root in &sources.roots {
+            if !scanned_dirs.contains(root) {
+                debug!("Scanning editable install source directory: {:?}", root);
+                self.scan_editable_source(root, root, &shadowed);
+            }
+        }
+
+        for (module_name, location) in &sources.packages {
+            let matches_name = location
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem == module_name);
+            let Some(root) = location.parent().filter(|_| matches_name) else {
+                debug!(
+                    "Skipping editable mapping of '{}' to {:?}: the location is named differently",
+                    module_name, location
+                );
+                continue;
+            };
+            debug!(
+                "Scanning editable install of '{}': {:?}",
+                module_name, location
+            );
+            self.scan_editable_source(root, location, &shadowed);
+        }
+    }
+
+    /// Add the Python files under `location` as modules named relative to `root`, except
+    /// those in top-level packages that are `shadowed`
+    fn scan_editable_source(&mut self, root: &Path, location: &Path, shadowed: &IndexSet<String>) {
+        for path in self.files.walk(location) {
+            let is_shadowed = self
+                .path_to_module_name(root, &path)
+                .is_some_and(|module_name| shadowed.contains(top_level_name(&module_name)));
+            if !is_shadowed {
+                self.process_directory_entry(root, &path);
+            }
+        }
+    }
+
     /// Scan a single directory for Python modules
     fn scan_directory_for_modules(&mut self, src_dir: &Path) -> Result<()> {
-        if !src_dir.exists() {
+        if !self.files.exists(src_dir) {
             return Ok(());
         }
 
         debug!("Scanning source directory: {:?}", src_dir);
 
-        let entries = WalkDir::new(src_dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok());
-
-        for entry in entries {
-            self.process_directory_entry(src_dir, entry.path());
+        for path in self.files.walk(src_dir) {
+            self.process_directory_entry(src_dir, &path);
         }
 
         Ok(())
@@ -591,6 +879,20 @@ impl ModuleResolver {
         }
 
         if let Some(module_name) = self.path_to_module_name(src_dir, path) {
+            let relative_path = path.strip_prefix(src_dir).unwrap_or(path);
+            if !self.module_filter.allows(relative_path) {
+                debug!("Excluded module: {} ({:?})", module_name, relative_path);
+                self.excluded_modules.insert(module_name);
+                return;
+            }
+            // Like `sys.path`, the first directory providing a module wins
+            if let Some(Some(existing)) = self.module_cache.get(&module_name) {
+                debug!(
+                    "Module {} at {:?} is shadowed by {:?}",
+                    module_name, path, existing
+                );
+                return;
+            }
             debug!("Found first-party module: {}", module_name);
             self.first_party_modules.insert(module_name.clone());
 
@@ -614,7 +916,7 @@ impl ModuleResolver {
 
     /// Check if a path is a Python file
     fn is_python_file(&self, path: &Path) -> bool {
-        path.is_file()
+        self.files.is_file(path)
             && path
                 .extension()
                 .is_some_and(|ext| ext.eq_ignore_ascii_case("py"))
@@ -637,12 +939,88 @@ impl ModuleResolver {
     }
 
     /// Classify an import as first-party, third-party, or standard library
+    ///
+    /// Results are memoized, so repeated lookups of the same module across passes
+    /// are cheap and always agree with each other.
     pub fn classify_import(&self, module_name: &str) -> ImportType {
+        if let Some(cached) = self.classification_cache.borrow().get(module_name) {
+            return cached.clone();
+        }
+
+        let import_type = self.classify_import_uncached(module_name);
+        self.classification_cache
+            .borrow_mut()
+            .insert(module_name.to_owned(), import_type.clone());
+        import_type
+    }
+
+    /// Count how many distinct modules have been classified into each category so far
+    pub fn classification_counts(&self) -> ClassificationCounts {
+        let mut counts = ClassificationCounts::default();
+        for import_type in self.classification_cache.borrow().values() {
+            match import_type {
+                ImportType::FirstParty => counts.first_party += 1,
+                ImportType::ThirdParty => counts.third_party += 1,
+                ImportType::StandardLibrary => counts.standard_library += 1,
+                ImportType::Ignored => counts.ignored += 1,
+            }
+        }
+        counts
+    }
+
+    /// A copy of this resolver's discovered modules for another bundling run reading
+    /// through `files`, without its classifier or the classifications made so far
+    pub(crate) fn for_new_run(&self, files: FileProviderHandle) -> Self {
+        let mut resolver = self.clone();
+        resolver.files = files;
+        resolver.classifier = None;
+        resolver.classification_cache.get_mut().clear();
+        resolver
+    }
+
+    /// Consult `classifier` before the built-in rules for every later classification
+    pub fn set_classifier(&mut self, classifier: ImportClassifierHandle) {
+        self.classifier = Some(classifier);
+        self.classification_cache.borrow_mut().clear();
+    }
+
+    /// Classify an import without consulting the memoization cache
+    fn classify_import_uncached(&self, module_name: &str) -> ImportType {
         // Check if it's a relative import (starts with a dot)
         if module_name.starts_with('.') {
             return ImportType::FirstParty;
         }
 
+        // Stubs are bundled in place of whatever the name would otherwise refer to
+        if self.config.stub_modules.contains_key(module_name) {
+            return ImportType::FirstParty;
+        }
+
+        if let Some(import_type) = self
+            .classifier
+            .as_ref()
+            .and_then(|classifier| classifier.classify(module_name))
+        {
+            debug!(
+                "Import classifier classified '{}' as {:?}",
+                module_name, import_type
+            );
+            return import_type;
+        }
+
+        // Modules kept external stay out of the bundle wherever they are found
+        if Self::matches_configured_name(&self.config.keep_external, module_name) {
+            return ImportType::Ignored;
+        }
+
+        // Explicitly configured names override stdlib and filesystem-based detection
+        if Self::matches_configured_name(&self.config.known_first_party, module_name) {
+            return ImportType::FirstParty;
+        }
+        if Self::matches_configured_name(&self.config.known_third_party, module_name) {
+            return ImportType::ThirdParty;
+        }
+
         // Check if it's a standard library module
         if let Ok(python_version) = self.config.python_version() {
             if is_stdlib_module(module_name, python_version) {
@@ -650,9 +1028,9 @@ impl ModuleResolver {
             }
         }
 
-        // Check if it's explicitly configured as third-party
-        if self.config.known_third_party.contains(module_name) {
-            return ImportType::ThirdParty;
+        // Excluded files stay where they are and are imported from there at runtime
+        if Self::matches_configured_name(&self.excluded_modules, module_name) {
+            return ImportType::Ignored;
         }
 
         // Check if it's a first-party module (exact match or parent)
@@ -670,6 +1048,16 @@ impl ModuleResolver {
         ImportType::ThirdParty
     }
 
+    /// Whether `module_name` or one of its parent packages is listed in `names`
+    fn matches_configured_name(names: &IndexSet<String>, module_name: &str) -> bool {
+        !names.is_empty()
+            && module_name
+                .match_indices('.')
+                .map(|(dot, _)| &module_name[..dot])
+                .chain(std::iter::once(module_name))
+                .any(|prefix| names.contains(prefix))
+    }
+
     /// Check if a module is first-party
     fn is_first_party_module(&self, module_name: &str) -> bool {
         // Exact match
@@ -703,6 +1091,14 @@ impl ModuleResolver {
             return Ok(cached_path.clone());
         }
 
+        if let Some(stub) = self.config.stub_modules.get(module_name) {
+            debug!("Substituting stub {:?} for module '{}'", stub, module_name);
+            let stub = Some(stub.clone());
+            self.module_cache
+                .insert(module_name.to_owned(), stub.clone());
+            return Ok(stub);
+        }
+
         // Only resolve first-party modules
         if !self.is_first_party_module(module_name) {
             self.module_cache.insert(module_name.to_owned(), None);
@@ -723,6 +1119,77 @@ impl ModuleResolver {
         Ok(None)
     }
 
+    /// Resolve `module_name` exactly as the bundler does: classify it, then locate
+    /// first-party modules under the scanned source directories
+    pub fn resolve(&mut self, module_name: &str) -> Result<Resolution> {
+        let kind = match self.classify_import(module_name) {
+            ImportType::StandardLibrary => ResolutionKind::Stdlib,
+            ImportType::ThirdParty => ResolutionKind::ThirdParty,
+            ImportType::Ignored => ResolutionKind::Ignored,
+            ImportType::FirstParty => {
+                let scan_directories = self.get_scan_directories();
+                if let Some(path) = self.resolve_module_path(module_name)? {
+                    let origin_root = scan_directories
+                        .into_iter()
+                        .find(|root| path.starts_with(root));
+                    return Ok(Resolution {
+                        path: Some(path),
+                        kind: ResolutionKind::FirstParty,
+                        origin_root,
+                        namespace_portions: Vec::new(),
+                    });
+                }
+
+                let namespace_portions = self.namespace_portions(module_name);
+                if let Some(first_portion) = namespace_portions.first() {
+                    let origin_root = scan_directories
+                        .into_iter()
+                        .find(|root| first_portion.starts_with(root));
+                    return Ok(Resolution {
+                        path: None,
+                        kind: ResolutionKind::Namespace,
+                        origin_root,
+                        namespace_portions,
+                    });
+                }
+                // Declared first-party (e.g. in `known_first_party`) but not on disk
+                ResolutionKind::FirstParty
+            }
+        };
+        Ok(Resolution {
+            path: None,
+            kind,
+            origin_root: None,
+            namespace_portions: Vec::new(),
+        })
+    }
+
+    /// Directories that make up the namespace package `module_name`, in search order
+    ///
+    /// A PEP 420 namespace package has no `__init__.py` and may be split into portions
+    /// under several source directories. Like Python's import system, a regular package
+    /// or module of the same name under any source directory takes precedence over
+    /// every portion, in which case the result is empty.
+    pub fn namespace_portions(&self, module_name: &str) -> Vec<PathBuf> {
+        if module_name.is_empty() || module_name.starts_with('.') {
+            return Vec::new();
+        }
+        let relative_dir: PathBuf = module_name.split('.').collect();
+        let mut portions = Vec::new();
+        for root in self.get_scan_directories() {
+            let dir = root.join(&relative_dir);
+            if self.files.is_file(&dir.join("__init__.py"))
+                || self.files.is_file(&dir.with_extension("py"))
+            {
+                return Vec::new();
+            }
+            if self.files.is_dir(&dir) {
+                portions.push(dir);
+            }
+        }
+        portions
+    }
+
     /// Find the file for a given module name in a source directory
     fn find_module_file(&self, src_dir: &Path, module_name: &str) -> Result<Option<PathBuf>> {
         let parts: Vec<&str> = module_name.split('.').collect();
@@ -747,7 +1214,7 @@ impl ModuleResolver {
     fn try_resolve_final_part(&self, file_path: &mut PathBuf, part: &str) -> Option<PathBuf> {
         // Try as a .py file
         file_path.push(format!("{}.py", part));
-        if file_path.exists() {
+        if self.files.exists(file_path.as_path()) {
             return Some(file_path.clone());
         }
 
@@ -755,7 +1222,7 @@ impl ModuleResolver {
         file_path.pop();
         file_path.push(part);
         file_path.push("__init__.py");
-        if file_path.exists() {
+        if self.files.exists(file_path.as_path()) {
             Some(file_path.clone())
         } else {
             None
@@ -767,10 +1234,25 @@ impl ModuleResolver {
         &self.first_party_modules
     }
 
+    /// Discovered first-party modules that have a file, with that file
+    pub fn first_party_module_files(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.first_party_modules.iter().filter_map(|module_name| {
+            self.module_cache
+                .get(module_name)
+                .and_then(Option::as_deref)
+                .map(|path| (module_name.as_str(), path))
+        })
+    }
+
     /// Get a reference to the configuration
     pub fn config(&self) -> &Config {
         &self.config
     }
+
+    /// Files that modules are looked up among
+    pub fn file_provider(&self) -> &FileProviderHandle {
+        &self.files
+    }
 }
 
 #[cfg(test)]
@@ -788,7 +1270,11 @@ mod tests {
             config: Config::default(),
             module_cache: IndexMap::new(),
             first_party_modules: IndexSet::new(),
+            module_filter: ModuleFilter::default(),
+            excluded_modules: IndexSet::new(),
             virtualenv_packages_cache: RefCell::new(None),
+            classification_cache: RefCell::new(IndexMap::new()),
+            classifier: None,
         };
         assert_eq!(
             resolver.path_to_module_name(src_dir, file_path),
@@ -806,7 +1292,11 @@ mod tests {
             config,
             module_cache: IndexMap::new(),
             first_party_modules: IndexSet::new(),
+            module_filter: ModuleFilter::default(),
+            excluded_modules: IndexSet::new(),
             virtualenv_packages_cache: RefCell::new(None),
+            classification_cache: RefCell::new(IndexMap::new()),
+            classifier: None,
         };
 
         // Use scope guard to safely set PYTHONPATH for testing
@@ -839,7 +1329,11 @@ mod tests {
             config,
             module_cache: IndexMap::new(),
             first_party_modules: IndexSet::new(),
+            module_filter: ModuleFilter::default(),
+            excluded_modules: IndexSet::new(),
             virtualenv_packages_cache: RefCell::new(None),
+            classification_cache: RefCell::new(IndexMap::new()),
+            classifier: None,
         };
 
         // Use scope guard to ensure PYTHONPATH is not set
@@ -852,4 +1346,167 @@ mod tests {
         assert!(scan_dirs.contains(&PathBuf::from("/src1")));
         assert!(scan_dirs.contains(&PathBuf::from("/src2")));
     }
+
+    #[test]
+    fn test_configured_names_override_detection() {
+        let mut config = Config::default();
+        config.known_first_party.insert("generated".to_owned());
+        config.known_third_party.insert("vendored".to_owned());
+        config.known_third_party.insert("enum".to_owned());
+        let mut first_party_modules = IndexSet::new();
+        first_party_modules.insert("vendored".to_owned());
+        let resolver = ModuleResolver {
+            config,
+            module_cache: IndexMap::new(),
+            first_party_modules,
+            module_filter: ModuleFilter::default(),
+            excluded_modules: IndexSet::new(),
+            virtualenv_packages_cache: RefCell::new(None),
+            classification_cache: RefCell::new(IndexMap::new()),
+            classifier: None,
+        };
+
+        assert_eq!(
+            resolver.classify_import("generated.models"),
+            ImportType::FirstParty
+        );
+        assert_eq!(
+            resolver.classify_import("vendored.client"),
+            ImportType::ThirdParty
+        );
+        assert_eq!(resolver.classify_import("enum"), ImportType::ThirdParty);
+        assert_eq!(resolver.classify_import("os"), ImportType::StandardLibrary);
+    }
+
+    #[test]
+    fn test_classification_counts_are_memoized() {
+        let mut config = Config::default();
+        config.known_third_party.insert("requests".to_owned());
+        let mut first_party_modules = IndexSet::new();
+        first_party_modules.insert("mypkg".to_owned());
+        let resolver = ModuleResolver {
+            config,
+            module_cache: IndexMap::new(),
+            first_party_modules,
+            module_filter: ModuleFilter::default(),
+            excluded_modules: IndexSet::new(),
+            virtualenv_packages_cache: RefCell::new(None),
+            classification_cache: RefCell::new(IndexMap::new()),
+            classifier: None,
+        };
+
+        assert_eq!(
+            resolver.classify_import("mypkg.sub"),
+            ImportType::FirstParty
+        );
+        assert_eq!(resolver.classify_import("os"), ImportType::StandardLibrary);
+        assert_eq!(resolver.classify_import("os"), ImportType::StandardLibrary);
+        assert_eq!(resolver.classify_import("requests"), ImportType::ThirdParty);
+
+        assert_eq!(
+            resolver.classification_counts(),
+            ClassificationCounts {
+                first_party: 1,
+                third_party: 1,
+                standard_library: 1,
+                ignored: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_stdlib_follows_target_version() {
+        let classify = |target_version: &str, module_name: &str| {
+            let config = Config {
+                src: Vec::new(),
+                respect_pythonpath: false,
+                target_version: target_version.to_owned(),
+                ..Config::default()
+            };
+            ModuleResolver::new(config)
+                .expect("valid config")
+                .classify_import(module_name)
+        };
+        assert_eq!(classify("py310", "tomllib"), ImportType::ThirdParty);
+        assert_eq!(classify("py311", "tomllib"), ImportType::StandardLibrary);
+        assert_eq!(
+            classify("py311", "distutils.core"),
+            ImportType::StandardLibrary
+        );
+        assert_eq!(classify("py312", "distutils.core"), ImportType::ThirdParty);
+
+        assert_eq!(stdlib_versions("distutils"), vec![8, 9, 10, 11]);
+        assert_eq!(stdlib_versions("tomllib").first(), Some(&11));
+        assert!(stdlib_versions("requests").is_empty());
+    }
+
+    #[test]
+    fn test_excluded_files_are_left_as_written() {
+        let src_dir = tempfile::TempDir::new().expect("create src dir");
+        std::fs::create_dir(src_dir.path().join("tests")).expect("create tests dir");
+        for file in [
+            "app.py",
+            "conftest.py",
+            "tests/__init__.py",
+            "tests/helpers.py",
+        ] {
+            std::fs::write(src_dir.path().join(file), "").expect("write module");
+        }
+        let config = Config {
+            src: vec![src_dir.path().to_path_buf()],
+            exclude: vec!["tests".to_owned(), "conftest.py".to_owned()],
+            respect_pythonpath: false,
+            ..Config::default()
+        };
+        let mut resolver = ModuleResolver::new(config).expect("valid config");
+
+        assert_eq!(resolver.classify_import("app"), ImportType::FirstParty);
+        assert_eq!(resolver.classify_import("conftest"), ImportType::Ignored);
+        assert_eq!(
+            resolver.classify_import("tests.helpers"),
+            ImportType::Ignored
+        );
+        assert_eq!(
+            resolver
+                .resolve_module_path("tests.helpers")
+                .expect("resolves"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_site_packages_pth_entries_are_scanned() {
+        let root = tempfile::TempDir::new().expect("create temp dir");
+        let site_packages = root.path().join("site-packages");
+        let checkout = root.path().join("checkout").join("src");
+        std::fs::create_dir_all(site_packages.join("extra")).expect("create extra dir");
+        std::fs::create_dir_all(checkout.join("editable_pkg")).expect("create checkout");
+        std::fs::write(site_packages.join("requests.py"), "").expect("write module");
+        std::fs::write(site_packages.join("extra").join("extra_mod.py"), "")
+            .expect("write extra module");
+        std::fs::write(checkout.join("editable_pkg").join("__init__.py"), "")
+            .expect("write editable package");
+        std::fs::write(
+            site_packages.join("__editable__.editable_pkg-0.1.pth"),
+            format!("# editable install\n{}\n", checkout.display()),
+        )
+        .expect("write editable pth");
+        std::fs::write(
+            site_packages.join("local.pth"),
+            "import os; os.environ.setdefault('X', '1')\nextra\nmissing\n",
+        )
+        .expect("write relative pth");
+
+        let resolver = ModuleResolver::new(Config {
+            respect_pythonpath: false,
+            ..Config::default()
+        })
+        .expect("valid config");
+        let mut packages = IndexSet::new();
+        resolver.scan_site_packages_directory(&site_packages, &mut packages);
+
+        for name in ["requests", "editable_pkg", "extra_mod"] {
+            assert!(packages.contains(name), "{name} missing from {packages:?}");
+        }
+    }
 }
@@ -0,0 +1,91 @@
+//! Module resolvers shared between bundling runs
+//!
+//! Building a resolver scans every source directory for first-party modules, which a
+//! long-running process would otherwise repeat for each bundle. The cache keeps the
+//! resolver built for a configuration and hands out copies of it for as long as no
+//! file was added to, removed from or renamed in the scanned directories.
+//!
+//! Edits to a file's contents don't matter here: the resolver only knows which modules
+//! exist and where, and the module cache picks up the new contents on its own.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+
+use crate::config::Config;
+use crate::file_provider::{FileProvider, FileProviderHandle};
+use crate::resolver::ModuleResolver;
+
+/// Resolvers keyed by the configuration they were built for
+#[derive(Debug, Default)]
+pub struct ResolverCache {
+    // Bundling is synchronous, so blocking locks are the right tool here
+    entries: Mutex<IndexMap<String, CachedResolver>>,
+    builds: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct CachedResolver {
+    /// Every path found under the scanned directories when the resolver was built
+    tree: Vec<PathBuf>,
+    resolver: ModuleResolver,
+}
+
+/// Shared handle to a resolver cache
+pub type ResolverCacheHandle = Arc<ResolverCache>;
+
+impl ResolverCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of times a resolver actually had to be built
+    pub fn build_count(&self) -> usize {
+        self.builds.load(Ordering::Relaxed)
+    }
+
+    /// A resolver for `config` finding modules among `files`, copied from the cached
+    /// one when its scanned directories still hold the same paths, even if another
+    /// provider listed them
+    pub(crate) fn resolver(
+        &self,
+        config: &Config,
+        files: &FileProviderHandle,
+    ) -> Result<ModuleResolver> {
+        let key = serde_json::to_string(config)?;
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(cached) = entries.get(&key) {
+            if scanned_tree(files.as_ref(), &cached.resolver) == cached.tree {
+                return Ok(cached.resolver.for_new_run(Arc::clone(files)));
+            }
+            log::debug!("Source tree changed, rebuilding the module resolver");
+        }
+
+        let resolver = ModuleResolver::with_file_provider(config.clone(), Arc::clone(files))?;
+        self.builds.fetch_add(1, Ordering::Relaxed);
+        let tree = scanned_tree(files.as_ref(), &resolver);
+        entries.insert(
+            key,
+            CachedResolver {
+                tree,
+                resolver: resolver.for_new_run(Arc::clone(files)),
+            },
+        );
+        Ok(resolver)
+    }
+}
+
+/// Sorted paths under every directory `resolver` scans for first-party modules
+fn scanned_tree(files: &dyn FileProvider, resolver: &ModuleResolver) -> Vec<PathBuf> {
+    let mut tree: Vec<PathBuf> = resolver
+        .get_scan_directories()
+        .iter()
+        .flat_map(|dir| files.walk(dir))
+        .collect();
+    tree.sort();
+    tree
+}
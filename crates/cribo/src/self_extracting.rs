@@ -0,0 +1,37 @@
+//! Self-extracting bundles: the bundle compressed with zlib and base64-encoded behind a
+//! one-line stub that decompresses and runs it, for targets limiting the size of a
+//! single source file (e.g. inline AWS Lambda code)
+
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+/// Source that runs `code` after decompressing it
+///
+/// The leading comment lines of `code` (shebang and header) stay readable at the top of
+/// the stub. The code runs in the stub's own globals, so `__name__`, `__file__` and
+/// everything the bundle defines are the same as when it runs uncompressed, and the stub
+/// binds no names of its own.
+pub fn self_extracting(code: &str) -> Result<String> {
+    let header_len = code
+        .split_inclusive('\n')
+        .take_while(|line| line.starts_with('#'))
+        .map(str::len)
+        .sum::<usize>();
+    let (header, body) = code.split_at(header_len);
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(body.as_bytes())
+        .context("Failed to compress the bundle")?;
+    let compressed = encoder.finish().context("Failed to compress the bundle")?;
+
+    Ok(format!(
+        "{header}exec(compile(__import__(\"zlib\").decompress(__import__(\"base64\").b64decode(\"{}\")), globals().get(\"__file__\", \"<bundle>\"), \"exec\"))\n",
+        STANDARD.encode(compressed)
+    ))
+}
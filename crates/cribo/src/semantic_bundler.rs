@@ -8,7 +8,8 @@ use ruff_linter::source_kind::SourceKind;
 use ruff_python_ast::{Expr, ModModule, PySourceType, Stmt};
 use ruff_python_parser::parse_unchecked_source;
 use ruff_python_semantic::{
-    BindingFlags, BindingId, BindingKind, Module, ModuleKind, ModuleSource, SemanticModel,
+    BindingFlags, BindingId, BindingKind, Module, ModuleKind, ModuleSource, ScopeId, ScopeKind,
+    SemanticModel,
 };
 use ruff_python_stdlib::builtins::{MAGIC_GLOBALS, python_builtins};
 use ruff_text_size::{Ranged, TextRange};
@@ -17,6 +18,7 @@ use rustc_hash::FxHashSet as FxIndexSet;
 use std::path::Path;
 
 use crate::cribo_graph::ModuleId;
+use crate::interner::SymbolId;
 
 /// Semantic bundler that analyzes symbol conflicts across modules using full semantic models
 pub struct SemanticBundler {
@@ -29,15 +31,21 @@ pub struct SemanticBundler {
 /// Semantic model builder that properly populates bindings using visitor pattern
 struct SemanticModelBuilder<'a> {
     semantic: SemanticModel<'a>,
+    /// Names of the classes enclosing the statement being visited, outermost first
+    class_path: Vec<&'a str>,
+    /// Scope of every class body, with the class's dotted name within the module
+    class_scopes: Vec<(String, ScopeId)>,
 }
 
 impl<'a> SemanticModelBuilder<'a> {
     /// Create and populate a semantic model for a module
+    ///
+    /// Also returns the scope of each class body, keyed by the class's dotted name.
     fn build_semantic_model(
         source: &'a str,
         file_path: &'a Path,
         ast: &'a ModModule,
-    ) -> Result<SemanticModel<'a>> {
+    ) -> Result<(SemanticModel<'a>, Vec<(String, ScopeId)>)> {
         // Step 1: Parse source and create infrastructure
         let source_kind = SourceKind::Python(source.to_string());
         let source_type = PySourceType::from(file_path);
@@ -61,11 +69,15 @@ impl<'a> SemanticModelBuilder<'a> {
         let semantic = SemanticModel::new(&[], file_path, module);
 
         // Step 4: Create builder and populate semantic model
-        let mut builder = Self { semantic };
+        let mut builder = Self {
+            semantic,
+            class_path: Vec::new(),
+            class_scopes: Vec::new(),
+        };
         builder.bind_builtins();
         builder.traverse_and_bind(&ast.body)?;
 
-        Ok(builder.semantic)
+        Ok((builder.semantic, builder.class_scopes))
     }
 
     /// Bind builtin symbols to the semantic model
@@ -99,6 +111,16 @@ impl<'a> SemanticModelBuilder<'a> {
                     BindingKind::ClassDefinition(self.semantic.scope_id),
                     BindingFlags::empty(),
                 )?;
+
+                // Bind the class body in its own scope, so class attributes and methods
+                // are kept apart from module-level symbols of the same name
+                self.class_path.push(class_def.name.id.as_str());
+                self.semantic.push_scope(ScopeKind::Class(class_def));
+                self.class_scopes
+                    .push((self.class_path.join("."), self.semantic.scope_id));
+                self.traverse_and_bind(&class_def.body)?;
+                self.semantic.pop_scope();
+                self.class_path.pop();
             }
             Stmt::FunctionDef(func_def) => {
                 eprintln!(
@@ -126,6 +148,18 @@ impl<'a> SemanticModelBuilder<'a> {
                     }
                 }
             }
+            // Annotated class attributes; module-level symbols only come from plain
+            // assignments
+            Stmt::AnnAssign(ann_assign) if !self.class_path.is_empty() => {
+                if let Expr::Name(name_expr) = ann_assign.target.as_ref() {
+                    self.add_binding(
+                        name_expr.id.as_str(),
+                        name_expr.range(),
+                        BindingKind::AnnotatedAssignment,
+                        BindingFlags::empty(),
+                    )?;
+                }
+            }
             // Handle imports to enable qualified name resolution
             Stmt::Import(import) => {
                 for alias in &import.names {
@@ -211,7 +245,9 @@ impl<'a> SemanticModelBuilder<'a> {
     }
 
     /// Extract symbols from a populated semantic model
-    fn extract_symbols_from_semantic_model(semantic: &SemanticModel) -> Result<FxIndexSet<String>> {
+    fn extract_symbols_from_semantic_model(
+        semantic: &SemanticModel,
+    ) -> Result<FxIndexSet<SymbolId>> {
         let mut symbols = FxIndexSet::default();
 
         // Get the global scope (module scope)
@@ -232,20 +268,20 @@ impl<'a> SemanticModelBuilder<'a> {
                 BindingKind::ClassDefinition(_) => {
                     if !name.starts_with('_') || name.starts_with("__") {
                         eprintln!("DEBUG: Adding class symbol: {}", name);
-                        symbols.insert(name.to_string());
+                        symbols.insert(SymbolId::intern(name));
                     }
                 }
                 BindingKind::FunctionDefinition(_) => {
                     if !name.starts_with('_') || name.starts_with("__") {
                         eprintln!("DEBUG: Adding function symbol: {}", name);
-                        symbols.insert(name.to_string());
+                        symbols.insert(SymbolId::intern(name));
                     }
                 }
                 BindingKind::Assignment => {
                     // Include module-level assignments (variables)
                     if !name.starts_with('_') {
                         eprintln!("DEBUG: Adding assignment symbol: {}", name);
-                        symbols.insert(name.to_string());
+                        symbols.insert(SymbolId::intern(name));
                     }
                 }
                 // Skip imports, builtins, and other binding types for symbol extraction
@@ -264,6 +300,28 @@ impl<'a> SemanticModelBuilder<'a> {
         eprintln!("DEBUG: Final extracted symbols: {:?}", symbols);
         Ok(symbols)
     }
+
+    /// Build the symbol table of each class body from its scope in the semantic model
+    fn extract_class_scopes(
+        semantic: &SemanticModel,
+        class_scopes: &[(String, ScopeId)],
+    ) -> FxIndexMap<String, ClassScopeInfo> {
+        class_scopes
+            .iter()
+            .map(|(qualified_name, scope_id)| {
+                let mut info = ClassScopeInfo::default();
+                for (name, binding_id) in semantic.scopes[*scope_id].bindings() {
+                    let symbols = match &semantic.bindings[binding_id].kind {
+                        BindingKind::FunctionDefinition(_) => &mut info.methods,
+                        BindingKind::ClassDefinition(_) => &mut info.nested_classes,
+                        _ => &mut info.attributes,
+                    };
+                    symbols.insert(SymbolId::intern(name));
+                }
+                (qualified_name.clone(), info)
+            })
+            .collect()
+    }
 }
 
 /// Module semantic analyzer that provides static methods for symbol extraction
@@ -275,32 +333,85 @@ impl ModuleSemanticAnalyzer {
         source: &str,
         path: &Path,
         ast: &ModModule,
-    ) -> Result<FxIndexSet<String>> {
-        let semantic = SemanticModelBuilder::build_semantic_model(source, path, ast)?;
+    ) -> Result<FxIndexSet<SymbolId>> {
+        let (semantic, _) = SemanticModelBuilder::build_semantic_model(source, path, ast)?;
         SemanticModelBuilder::extract_symbols_from_semantic_model(&semantic)
     }
+
+    /// Extract module-level symbols together with the symbol table of every class body
+    pub fn extract_symbols_and_class_scopes(
+        source: &str,
+        path: &Path,
+        ast: &ModModule,
+    ) -> Result<(FxIndexSet<SymbolId>, FxIndexMap<String, ClassScopeInfo>)> {
+        let (semantic, class_scopes) =
+            SemanticModelBuilder::build_semantic_model(source, path, ast)?;
+        let symbols = SemanticModelBuilder::extract_symbols_from_semantic_model(&semantic)?;
+        let class_scopes = SemanticModelBuilder::extract_class_scopes(&semantic, &class_scopes);
+        Ok((symbols, class_scopes))
+    }
+}
+
+/// What semantic analysis extracts from one module, independent of the other modules
+#[derive(Debug, Clone, Default)]
+pub struct ModuleSymbols {
+    /// Module-level symbols
+    pub exported_symbols: FxIndexSet<SymbolId>,
+    /// Symbol tables of the module's class bodies, keyed by dotted class name
+    pub class_scopes: FxIndexMap<String, ClassScopeInfo>,
 }
 
 /// Semantic information for a single module
 pub struct ModuleSemanticInfo {
     /// Symbols exported by this module (from semantic analysis)
-    pub exported_symbols: FxIndexSet<String>,
+    pub exported_symbols: FxIndexSet<SymbolId>,
     /// Symbol conflicts detected in this module
-    pub conflicts: Vec<String>,
+    pub conflicts: Vec<SymbolId>,
+    /// Symbol tables of the module's class bodies, keyed by dotted class name within
+    /// the module (`Outer.Inner` for nested classes)
+    pub class_scopes: FxIndexMap<String, ClassScopeInfo>,
     /// Source code for re-analysis if needed
     pub source: String,
     /// File path for this module
     pub file_path: std::path::PathBuf,
 }
 
+/// Names bound directly in a class body
+///
+/// Inside the class body these shadow module-level symbols of the same name, so a
+/// rename of the module-level symbol must not touch them.
+#[derive(Debug, Clone, Default)]
+pub struct ClassScopeInfo {
+    /// Functions defined in the class body
+    pub methods: FxIndexSet<SymbolId>,
+    /// Class attributes: assignments, annotated assignments and imports in the body
+    pub attributes: FxIndexSet<SymbolId>,
+    /// Classes defined in the class body
+    pub nested_classes: FxIndexSet<SymbolId>,
+}
+
+impl ClassScopeInfo {
+    /// Whether the class body binds `name` itself
+    pub fn binds(&self, name: &str) -> bool {
+        SymbolId::lookup(name).is_some_and(|name| {
+            self.methods.contains(&name)
+                || self.attributes.contains(&name)
+                || self.nested_classes.contains(&name)
+        })
+    }
+}
+
 /// Global symbol registry across all modules with semantic information
+///
+/// Keyed by interned [`SymbolId`]s, so registering and looking up a symbol does not
+/// allocate.
 pub struct SymbolRegistry {
     /// Symbol name -> list of modules that define it
-    pub symbols: FxIndexMap<String, Vec<ModuleId>>,
+    pub symbols: FxIndexMap<SymbolId, Vec<ModuleId>>,
     /// Renames: (ModuleId, OriginalName) -> NewName
-    pub renames: FxIndexMap<(ModuleId, String), String>,
+    pub renames: FxIndexMap<(ModuleId, SymbolId), SymbolId>,
     /// Symbol binding information for scope analysis
-    pub symbol_bindings: FxIndexMap<(ModuleId, String), SymbolBindingInfo>,
+    pub symbol_bindings: FxIndexMap<(ModuleId, SymbolId), SymbolBindingInfo>,
 }
 
 /// Information about a symbol binding from semantic analysis
@@ -335,21 +446,18 @@ impl SymbolRegistry {
     /// Register a symbol from a module with semantic information
     pub fn register_symbol_with_binding(
         &mut self,
-        symbol: String,
+        symbol: SymbolId,
         module_id: ModuleId,
         binding_info: SymbolBindingInfo,
     ) {
-        self.symbols
-            .entry(symbol.clone())
-            .or_default()
-            .push(module_id);
+        self.symbols.entry(symbol).or_default().push(module_id);
 
         self.symbol_bindings
             .insert((module_id, symbol), binding_info);
     }
 
     /// Register a symbol from a module (legacy interface)
-    pub fn register_symbol(&mut self, symbol: String, module_id: ModuleId) {
+    pub fn register_symbol(&mut self, symbol: SymbolId, module_id: ModuleId) {
         self.symbols.entry(symbol).or_default().push(module_id);
     }
 
@@ -360,7 +468,7 @@ impl SymbolRegistry {
         for (symbol, modules) in &self.symbols {
             if modules.len() > 1 {
                 conflicts.push(SymbolConflict {
-                    symbol: symbol.clone(),
+                    symbol: *symbol,
                     modules: modules.clone(),
                 });
             }
@@ -373,26 +481,23 @@ impl SymbolRegistry {
     pub fn generate_rename(
         &mut self,
         module_id: ModuleId,
-        original: &str,
+        original: SymbolId,
         suffix: usize,
-    ) -> String {
-        let new_name = format!("{}_{}", original, suffix);
-        self.renames
-            .insert((module_id, original.to_string()), new_name.clone());
+    ) -> SymbolId {
+        let new_name = SymbolId::intern(&format!("{}_{}", original, suffix));
+        self.renames.insert((module_id, original), new_name);
         new_name
     }
 
     /// Get rename for a symbol if it exists
-    pub fn get_rename(&self, module_id: &ModuleId, original: &str) -> Option<&str> {
-        self.renames
-            .get(&(*module_id, original.to_string()))
-            .map(|s| s.as_str())
+    pub fn get_rename(&self, module_id: &ModuleId, original: SymbolId) -> Option<SymbolId> {
+        self.renames.get(&(*module_id, original)).copied()
     }
 
     /// Check if a symbol has conflicts
     pub fn has_conflict(&self, symbol: &str) -> bool {
-        self.symbols
-            .get(symbol)
+        SymbolId::lookup(symbol)
+            .and_then(|symbol| self.symbols.get(&symbol))
             .is_some_and(|modules| modules.len() > 1)
     }
 
@@ -402,7 +507,8 @@ impl SymbolRegistry {
         module_id: &ModuleId,
         symbol: &str,
     ) -> Option<&SymbolBindingInfo> {
-        self.symbol_bindings.get(&(*module_id, symbol.to_string()))
+        self.symbol_bindings
+            .get(&(*module_id, SymbolId::lookup(symbol)?))
     }
 
     /// Check if a symbol is module-level in a specific module
@@ -414,7 +520,7 @@ impl SymbolRegistry {
 
 /// Represents a symbol conflict across modules
 pub struct SymbolConflict {
-    pub symbol: String,
+    pub symbol: SymbolId,
     pub modules: Vec<ModuleId>,
 }
 
@@ -468,9 +574,35 @@ impl SemanticBundler {
             module_id.as_u32()
         );
 
-        // Extract module-level symbols using semantic analysis
-        let exported_symbols =
-            ModuleSemanticAnalyzer::extract_symbols_from_module(source, path, ast)?;
+        // Extract module-level symbols and class body symbol tables using semantic analysis
+        let (exported_symbols, class_scopes) =
+            ModuleSemanticAnalyzer::extract_symbols_and_class_scopes(source, path, ast)?;
+        self.add_module_symbols(
+            module_id,
+            ModuleSymbols {
+                exported_symbols,
+                class_scopes,
+            },
+            source,
+            path,
+        );
+
+        Ok(())
+    }
+
+    /// Register a module whose symbols were already extracted, e.g. by an earlier run
+    /// over the same source
+    pub fn add_module_symbols(
+        &mut self,
+        module_id: ModuleId,
+        symbols: ModuleSymbols,
+        source: &str,
+        path: &Path,
+    ) {
+        let ModuleSymbols {
+            exported_symbols,
+            class_scopes,
+        } = symbols;
         eprintln!(
             "SEMANTIC: Module {} has symbols: {:?}",
             module_id.as_u32(),
@@ -479,8 +611,7 @@ impl SemanticBundler {
 
         // Register symbols in global registry (simplified for now)
         for symbol in &exported_symbols {
-            self.global_symbols
-                .register_symbol(symbol.clone(), module_id);
+            self.global_symbols.register_symbol(*symbol, module_id);
         }
 
         // Store module semantic info
@@ -489,12 +620,11 @@ impl SemanticBundler {
             ModuleSemanticInfo {
                 exported_symbols,
                 conflicts: Vec::new(), // Will be populated later
+                class_scopes,
                 source: source.to_string(),
                 file_path: path.to_path_buf(),
             },
         );
-
-        Ok(())
     }
 
     /// Detect and resolve symbol conflicts across all modules
@@ -507,13 +637,13 @@ impl SemanticBundler {
                 // Generate renames for all modules in conflict (including first)
                 let _new_name = self.global_symbols.generate_rename(
                     *module_id,
-                    &conflict.symbol,
+                    conflict.symbol,
                     i + 1, // Start numbering from 1 instead of 0
                 );
 
                 // Update conflicts in module info
                 if let Some(module_info) = self.module_semantics.get_mut(module_id) {
-                    module_info.conflicts.push(conflict.symbol.clone());
+                    module_info.conflicts.push(conflict.symbol);
                 }
             }
         }
@@ -526,6 +656,14 @@ impl SemanticBundler {
         self.module_semantics.get(module_id)
     }
 
+    /// Symbol table of the class body with dotted name `class_name` in a module
+    pub fn class_scope(&self, module_id: &ModuleId, class_name: &str) -> Option<&ClassScopeInfo> {
+        self.module_semantics
+            .get(module_id)?
+            .class_scopes
+            .get(class_name)
+    }
+
     /// Get symbol registry
     pub fn symbol_registry(&self) -> &SymbolRegistry {
         &self.global_symbols
@@ -0,0 +1,264 @@
+//! Long-running bundling service behind `cribo serve`
+//!
+//! Build systems that bundle many times per run keep one process alive and send it
+//! JSON-RPC 2.0 requests, one per line, over stdin/stdout or a Unix socket. Every request
+//! shares one module cache, so a module is only parsed and analyzed again after its
+//! file changes, and one resolver cache, so source directories are only scanned again
+//! after files are added, removed or renamed.
+//!
+//! Methods:
+//! - `bundle` with `entry` (script path, package directory or dotted module name) and
+//!   optionally `output` (an absolute path), `emit_requirements` and `target_version`:
+//!   returns `{"code"}`, or writes the bundle and returns `{"output"}` when `output` is
+//!   given
+//! - `status`: returns how many times modules were parsed and analyzed and resolvers
+//!   were built so far
+//! - `shutdown`: returns `null` and stops the server once the response is written
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::config::Config;
+use crate::error::CriboError;
+use crate::module_cache::{ModuleCache, ModuleCacheHandle};
+use crate::orchestrator::BundleOrchestrator;
+use crate::resolver_cache::{ResolverCache, ResolverCacheHandle};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Bundling failed; `data.kind` tells which [`CriboError`] it was
+const BUNDLE_ERROR: i64 = -32000;
+
+/// Parameters of the `bundle` method
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BundleParams {
+    entry: String,
+    #[serde(default)]
+    output: Option<PathBuf>,
+    #[serde(default)]
+    emit_requirements: bool,
+    #[serde(default)]
+    target_version: Option<String>,
+}
+
+/// A JSON-RPC error response in the making
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<CriboError> for RpcError {
+    fn from(error: CriboError) -> Self {
+        let kind = match &error {
+            CriboError::Resolution { .. } => "ResolutionError",
+            CriboError::Parse { .. } => "ParseError",
+            CriboError::Cycle { .. } => "CycleError",
+            CriboError::Emit { .. } => "EmitError",
+            CriboError::Config { .. } => "ConfigError",
+        };
+        Self {
+            code: BUNDLE_ERROR,
+            message: error.to_string(),
+            data: Some(json!({ "kind": kind })),
+        }
+    }
+}
+
+/// Bundles requested over JSON-RPC with a configuration loaded once
+#[derive(Debug)]
+pub struct BundleServer {
+    config: Config,
+    module_cache: ModuleCacheHandle,
+    resolver_cache: ResolverCacheHandle,
+}
+
+impl BundleServer {
+    /// Serve bundles built with `config`, whose target version requests may override
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            module_cache: Arc::new(ModuleCache::new()),
+            resolver_cache: Arc::new(ResolverCache::new()),
+        }
+    }
+
+    /// Answer the requests read from `input` on `output` until `input` ends or a
+    /// `shutdown` request arrives; returns whether it was shut down
+    pub fn serve(&self, input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (response, shutdown) = self.handle(&line);
+            if let Some(response) = response {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+            if shutdown {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Answer the clients connecting to the Unix socket at `path`, one at a time, until
+    /// one of them sends `shutdown`
+    ///
+    /// A stale socket file left by a previous server is replaced. A client that
+    /// disconnects mid-request or a connection that fails is logged and skipped.
+    #[cfg(unix)]
+    pub fn serve_socket(&self, path: &Path) -> io::Result<()> {
+        use std::os::unix::net::UnixListener;
+
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        log::info!("Listening for bundle requests on {:?}", path);
+        let served = self.serve_clients(&listener);
+        let _ = std::fs::remove_file(path);
+        served
+    }
+
+    #[cfg(unix)]
+    fn serve_clients(&self, listener: &std::os::unix::net::UnixListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    log::warn!("Failed to accept a client connection: {}", err);
+                    continue;
+                }
+            };
+            match self.serve_client(stream) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(err) => log::warn!("Dropped a client connection: {}", err),
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn serve_client(&self, stream: std::os::unix::net::UnixStream) -> io::Result<bool> {
+        let reader = io::BufReader::new(stream.try_clone()?);
+        self.serve(reader, stream)
+    }
+
+    /// Unix sockets are not available on this platform
+    #[cfg(not(unix))]
+    pub fn serve_socket(&self, path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "Cannot listen on {:?}: Unix sockets are not supported",
+                path
+            ),
+        ))
+    }
+
+    /// The response to one request line (`None` for notifications) and whether it
+    /// asked the server to stop
+    fn handle(&self, line: &str) -> (Option<Value>, bool) {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => {
+                let error = RpcError::new(PARSE_ERROR, format!("Invalid JSON: {}", err));
+                return (Some(error_response(Value::Null, error)), false);
+            }
+        };
+        let id = request.get("id").cloned();
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            let error = RpcError::new(INVALID_REQUEST, "A request needs a method");
+            return (
+                Some(error_response(id.unwrap_or(Value::Null), error)),
+                false,
+            );
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let shutdown = method == "shutdown";
+        let result = match method {
+            "bundle" => self.bundle(params),
+            "status" => Ok(json!({
+                "parses": self.module_cache.parse_count(),
+                "analyses": self.module_cache.analysis_count(),
+                "resolver_builds": self.resolver_cache.build_count(),
+            })),
+            "shutdown" => Ok(Value::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        };
+
+        // Notifications have no id and get no response
+        let response = id.map(|id| match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        });
+        (response, shutdown)
+    }
+
+    fn bundle(&self, params: Value) -> Result<Value, RpcError> {
+        let params: BundleParams = serde_json::from_value(params)
+            .map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))?;
+        // The server's working directory is unrelated to the client's
+        if let Some(output) = params
+            .output
+            .as_deref()
+            .filter(|output| output.is_relative())
+        {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("output must be an absolute path, got {:?}", output),
+            ));
+        }
+        let mut config = self.config.clone();
+        if let Some(target_version) = params.target_version {
+            config.set_target_version(target_version)?;
+        }
+        let entry = config.resolve_entry(&params.entry)?;
+        let mut bundler = BundleOrchestrator::new(config)
+            .with_module_cache(Arc::clone(&self.module_cache))
+            .with_resolver_cache(Arc::clone(&self.resolver_cache));
+
+        match params.output {
+            Some(output) => {
+                bundler.bundle(&entry, &output, params.emit_requirements)?;
+                Ok(json!({ "output": output }))
+            }
+            None => {
+                let code = bundler.bundle_to_string(&entry, params.emit_requirements)?;
+                Ok(json!({ "code": code }))
+            }
+        }
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    let mut body = json!({ "code": error.code, "message": error.message });
+    if let Some(data) = error.data {
+        body["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": body })
+}
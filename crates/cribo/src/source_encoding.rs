@@ -0,0 +1,189 @@
+//! PEP 263 source encodings
+//!
+//! A module may declare its encoding with a `# -*- coding: <name> -*-` comment on one of
+//! its first two lines. Sources are decoded from that encoding when they are read, so
+//! parsing, code generation and the emitted bundle only ever deal with UTF-8.
+
+use anyhow::{Result, anyhow, bail};
+use cow_utils::CowUtils;
+use encoding_rs::Encoding;
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Decode the contents of a Python source file
+///
+/// Files without an encoding declaration must be UTF-8, optionally behind a byte order
+/// mark. A declaration of any other encoding no longer describes the decoded text, so its
+/// comment is blanked out; the line itself is kept so line numbers in diagnostics and
+/// source maps still match the original file.
+pub fn decode_source(bytes: &[u8]) -> Result<String> {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        if let Some((_, name)) = encoding_declaration(rest) {
+            let name = normalize_encoding_name(&name);
+            if name != "utf-8" {
+                bail!("encoding declaration `{name}` contradicts the UTF-8 byte order mark");
+            }
+        }
+        return decode_utf8(rest);
+    }
+
+    let Some((line, name)) = encoding_declaration(bytes) else {
+        return decode_utf8(bytes);
+    };
+    let name = normalize_encoding_name(&name);
+    if name == "utf-8" {
+        return decode_utf8(bytes);
+    }
+    let source = decode_with(bytes, &name)?;
+    Ok(blank_line(&source, line))
+}
+
+/// The line index and encoding name of the file's encoding declaration
+///
+/// Like CPython, the second line is only considered when the first one is blank or a
+/// comment (typically a shebang).
+fn encoding_declaration(bytes: &[u8]) -> Option<(usize, String)> {
+    // The rest of a declaring line may already be in the declared encoding
+    let mut lines = bytes
+        .split(|&byte| byte == b'\n')
+        .map(String::from_utf8_lossy);
+    let first = lines.next()?;
+    if let Some(name) = declared_encoding(&first) {
+        return Some((0, name.to_owned()));
+    }
+    let first = first.trim_start_matches([' ', '\t', '\x0c', '\r']);
+    if !first.is_empty() && !first.starts_with('#') {
+        return None;
+    }
+    let second = lines.next()?;
+    declared_encoding(&second).map(|name| (1, name.to_owned()))
+}
+
+/// The encoding named by a line matching `^[ \t\f]*#.*?coding[:=][ \t]*([-\w.]+)`
+fn declared_encoding(line: &str) -> Option<&str> {
+    let mut rest = line
+        .trim_start_matches([' ', '\t', '\x0c'])
+        .strip_prefix('#')?;
+    loop {
+        let at = rest.find("coding")?;
+        rest = &rest[at + "coding".len()..];
+        let Some(value) = rest.strip_prefix([':', '=']) else {
+            continue;
+        };
+        let value = value.trim_start_matches([' ', '\t']);
+        let len = value
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(value.len());
+        if len > 0 {
+            return Some(&value[..len]);
+        }
+    }
+}
+
+/// Lowercase the name and fold the spellings of UTF-8 and Latin-1 the way CPython does
+fn normalize_encoding_name(name: &str) -> String {
+    let name = name
+        .cow_to_ascii_lowercase()
+        .cow_replace('_', "-")
+        .into_owned();
+    let is_variant_of = |base: &str| {
+        name == base
+            || name
+                .strip_prefix(base)
+                .is_some_and(|suffix| suffix.starts_with('-'))
+    };
+    if is_variant_of("utf-8") || name == "utf8" {
+        "utf-8".to_owned()
+    } else if ["latin-1", "iso-8859-1", "iso-latin-1"]
+        .into_iter()
+        .any(is_variant_of)
+        || matches!(name.as_str(), "latin1" | "iso8859-1" | "l1" | "cp819")
+    {
+        "iso-8859-1".to_owned()
+    } else {
+        name
+    }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Result<String> {
+    String::from_utf8(bytes.to_vec()).map_err(|err| {
+        anyhow!("{err}; non-UTF-8 sources need a `# -*- coding: <name> -*-` declaration")
+    })
+}
+
+fn decode_with(bytes: &[u8], name: &str) -> Result<String> {
+    match name {
+        // The WHATWG labels used by encoding_rs map Latin-1 to windows-1252, which
+        // differs from Python's codec in 0x80..0xA0
+        "iso-8859-1" => Ok(bytes.iter().copied().map(char::from).collect()),
+        "ascii" | "us-ascii" => {
+            if !bytes.is_ascii() {
+                bail!("source declared as ASCII contains non-ASCII bytes");
+            }
+            decode_utf8(bytes)
+        }
+        _ => {
+            // Python spells some names with underscores the WHATWG labels keep
+            let encoding = Encoding::for_label(name.as_bytes())
+                .or_else(|| Encoding::for_label(name.cow_replace('-', "_").as_bytes()))
+                .ok_or_else(|| anyhow!("unknown encoding `{name}`"))?;
+            if !encoding.is_ascii_compatible() {
+                bail!("encoding `{name}` cannot be used for Python source");
+            }
+            let source = encoding
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .ok_or_else(|| anyhow!("source is not valid `{name}`"))?;
+            Ok(source.into_owned())
+        }
+    }
+}
+
+/// `source` with the contents of line `index` removed, keeping its line ending
+fn blank_line(source: &str, index: usize) -> String {
+    source
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(line_index, line)| {
+            if line_index == index {
+                &line[line.trim_end_matches(['\r', '\n']).len()..]
+            } else {
+                line
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_encodings_are_decoded_and_blanked() {
+        let source = decode_source(
+            b"#!/usr/bin/env python\n# -*- coding: latin-1 -*-\nname = '\xe9t\xe9'\n",
+        )
+        .expect("decode latin-1");
+        assert_eq!(source, "#!/usr/bin/env python\n\nname = 'été'\n");
+
+        let source = decode_source(b"# vim: set fileencoding=cp1252 :\nprice = '\x80 5'\n")
+            .expect("decode cp1252");
+        assert_eq!(source, "\nprice = '€ 5'\n");
+
+        // A UTF-8 declaration is left alone
+        let source =
+            decode_source("# coding: utf_8\nname = 'été'\n".as_bytes()).expect("decode utf-8");
+        assert_eq!(source, "# coding: utf_8\nname = 'été'\n");
+        let source = decode_source(b"\xef\xbb\xbfname = 1\n").expect("decode with BOM");
+        assert_eq!(source, "name = 1\n");
+    }
+
+    #[test]
+    fn test_undeclared_or_misplaced_encodings_require_utf8() {
+        assert!(decode_source(b"name = '\xe9'\n").is_err());
+        // Only a comment line may come before the declaration
+        assert!(decode_source(b"import os\n# coding: latin-1\nname = '\xe9'\n").is_err());
+        assert!(decode_source(b"# coding: utf-16\nname = 1\n").is_err());
+        assert!(decode_source(b"# coding: no-such-codec\nname = 1\n").is_err());
+        assert!(decode_source(b"\xef\xbb\xbf# coding: latin-1\nname = 1\n").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! The end-of-run table printed by `--stats`
+//!
+//! [`StatsCollector`] is a [`Metrics`] implementation that times every phase and adds
+//! up the summaries of all bundles built while it is attached, so one table covers a
+//! whole workspace run.
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+
+use crate::metrics::{BundleSummary, Metrics, Phase};
+
+/// Metrics sink collecting what the `--stats` table shows
+#[derive(Debug, Default)]
+pub struct StatsCollector {
+    state: Mutex<StatsState>,
+}
+
+#[derive(Debug, Default)]
+struct StatsState {
+    /// Start of each phase in progress, per bundling thread
+    running: IndexMap<(ThreadId, Phase), Instant>,
+    /// Time spent in each phase, summed over bundles
+    elapsed: IndexMap<Phase, Duration>,
+    summary: BundleSummary,
+    bundles: usize,
+}
+
+impl StatsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything collected so far, with the number of warnings the run logged
+    pub fn stats(&self, warnings: usize) -> BundleStats {
+        let state = self.lock();
+        BundleStats {
+            bundles: state.bundles,
+            summary: state.summary,
+            warnings,
+            phases: state
+                .elapsed
+                .iter()
+                .map(|(phase, elapsed)| (*phase, *elapsed))
+                .collect(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StatsState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Metrics for StatsCollector {
+    fn phase_started(&self, phase: Phase) {
+        // Timings are only displayed, they never influence the bundle
+        #[allow(clippy::disallowed_methods)]
+        let now = Instant::now();
+        self.lock()
+            .running
+            .insert((thread::current().id(), phase), now);
+    }
+
+    fn phase_finished(&self, phase: Phase) {
+        let mut state = self.lock();
+        if let Some(started) = state.running.shift_remove(&(thread::current().id(), phase)) {
+            *state.elapsed.entry(phase).or_default() += started.elapsed();
+        }
+    }
+
+    fn bundle_finished(&self, summary: &BundleSummary) {
+        let mut state = self.lock();
+        state.summary += *summary;
+        state.bundles += 1;
+    }
+}
+
+/// Totals of a run, displayed as the `--stats` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleStats {
+    pub bundles: usize,
+    pub summary: BundleSummary,
+    pub warnings: usize,
+    /// Time spent in each phase, in the order the phases first ran
+    pub phases: Vec<(Phase, Duration)>,
+}
+
+impl fmt::Display for BundleStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = &self.summary;
+        writeln!(f, "Bundle statistics")?;
+        if self.bundles > 1 {
+            writeln!(f, "  {:<20}{}", "bundles", self.bundles)?;
+        }
+        writeln!(
+            f,
+            "  {:<20}{} ({} inlined, {} namespace, {} wrapper)",
+            "modules",
+            summary.modules(),
+            summary.inlined_modules,
+            summary.namespace_modules,
+            summary.wrapper_modules
+        )?;
+        writeln!(
+            f,
+            "  {:<20}{} in, {} out",
+            "lines", summary.lines_in, summary.lines_out
+        )?;
+        writeln!(
+            f,
+            "  {:<20}{}",
+            "third-party deps", summary.third_party_packages
+        )?;
+        writeln!(f, "  {:<20}{}", "warnings", self.warnings)?;
+
+        let mut total = Duration::ZERO;
+        for (phase, elapsed) in &self.phases {
+            writeln!(f, "  {:<20}{}", phase.as_str(), DisplayDuration(*elapsed))?;
+            total += *elapsed;
+        }
+        writeln!(f, "  {:<20}{}", "total", DisplayDuration(total))
+    }
+}
+
+/// Milliseconds with one decimal, which is precise enough for a summary
+struct DisplayDuration(Duration);
+
+impl fmt::Display for DisplayDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} ms", self.0.as_secs_f64() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_lists_counts_and_phases() {
+        let stats = BundleStats {
+            bundles: 1,
+            summary: BundleSummary {
+                inlined_modules: 3,
+                namespace_modules: 0,
+                wrapper_modules: 1,
+                lines_in: 120,
+                lines_out: 98,
+                third_party_packages: 2,
+            },
+            warnings: 1,
+            phases: vec![
+                (Phase::Discovery, Duration::from_micros(1500)),
+                (Phase::CodeGeneration, Duration::from_millis(3)),
+            ],
+        };
+
+        assert_eq!(
+            stats.to_string(),
+            "Bundle statistics\n  modules             4 (3 inlined, 0 namespace, 1 wrapper)\n  lines               120 in, 98 out\n  third-party deps    2\n  warnings            1\n  discovery           1.5 ms\n  code_generation     3.0 ms\n  total               4.5 ms\n"
+        );
+    }
+}
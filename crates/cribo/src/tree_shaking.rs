@@ -0,0 +1,437 @@
+//! Removal of unused top-level definitions from inlined modules
+//!
+//! Inlining puts every module-level function, class and constant of an inlined module
+//! into the bundle, even when the entry only imports one helper. After renaming, the
+//! bundle is a single namespace, so reachability is a fixed point over names: a
+//! definition is kept when any kept statement mentions the name it binds. Statements
+//! that are not plain definitions (calls, attribute assignments, wrapper init
+//! functions, the entry's code) are always kept and seed the analysis.
+//!
+//! The analysis is deliberately conservative. Definitions are only removed when
+//! evaluating them cannot have side effects: decorated functions and classes whose
+//! creation could register them somewhere stay, and so do assignments of calls.
+//! Identifier-like words in string literals count as references, which covers
+//! `__all__`, string annotations and `getattr(module, "name")`. A bundle that looks up
+//! its globals dynamically (`globals()`, `vars()`, `eval`, ...) is left untouched.
+
+use std::ops::Range;
+
+use indexmap::IndexSet;
+use ruff_python_ast::visitor::{Visitor, walk_expr, walk_stmt};
+use ruff_python_ast::{Decorator, Expr, Stmt, StmtClassDef};
+
+/// Builtins that give code access to the globals by name
+const DYNAMIC_LOOKUPS: [&str; 5] = ["globals", "vars", "locals", "eval", "exec"];
+
+/// Decorators that only transform the decorated object
+const PURE_DECORATORS: [&str; 14] = [
+    "dataclass",
+    "dataclasses.dataclass",
+    "total_ordering",
+    "functools.total_ordering",
+    "lru_cache",
+    "functools.lru_cache",
+    "cache",
+    "functools.cache",
+    "final",
+    "typing.final",
+    "overload",
+    "typing.overload",
+    "runtime_checkable",
+    "typing.runtime_checkable",
+];
+
+/// Decorators of methods that only wrap the method
+const PURE_METHOD_DECORATORS: [&str; 7] = [
+    "property",
+    "staticmethod",
+    "classmethod",
+    "abstractmethod",
+    "abc.abstractmethod",
+    "cached_property",
+    "functools.cached_property",
+];
+
+/// Base classes whose subclasses are created without side effects
+const PURE_BASES: [&str; 30] = [
+    "object",
+    "Exception",
+    "BaseException",
+    "ValueError",
+    "TypeError",
+    "KeyError",
+    "LookupError",
+    "RuntimeError",
+    "AttributeError",
+    "NotImplementedError",
+    "OSError",
+    "dict",
+    "list",
+    "tuple",
+    "str",
+    "int",
+    "NamedTuple",
+    "typing.NamedTuple",
+    "TypedDict",
+    "typing.TypedDict",
+    "Protocol",
+    "typing.Protocol",
+    "Generic",
+    "typing.Generic",
+    "Enum",
+    "enum.Enum",
+    "IntEnum",
+    "enum.IntEnum",
+    "StrEnum",
+    "enum.StrEnum",
+];
+
+/// Calls that build values without side effects
+const PURE_CALLS: [&str; 6] = [
+    "TypeVar",
+    "typing.TypeVar",
+    "NewType",
+    "typing.NewType",
+    "frozenset",
+    "object",
+];
+
+/// Indices of the statements in `shakeable` ranges of `body` that define names no
+/// kept statement refers to, in ascending order
+pub(crate) fn unused_definitions(body: &[Stmt], shakeable: &[Range<usize>]) -> Vec<usize> {
+    let mut live = References::default();
+    for stmt in body {
+        live.visit_stmt(stmt);
+    }
+    if live.dynamic_lookup {
+        log::info!("Not tree shaking: the bundle looks up its globals dynamically");
+        return Vec::new();
+    }
+
+    let safe_classes = safe_classes(body, shakeable);
+    let mut candidates = Vec::new();
+    let mut live = References::default();
+    for (index, stmt) in body.iter().enumerate() {
+        let defined = shakeable
+            .iter()
+            .any(|range| range.contains(&index))
+            .then(|| defined_names(stmt, &safe_classes))
+            .flatten();
+        match defined {
+            Some(names) => candidates.push((index, names)),
+            None => live.visit_stmt(stmt),
+        }
+    }
+
+    // Each round keeps the definitions the kept statements mention, until none is added
+    let mut kept = vec![false; candidates.len()];
+    loop {
+        let mut changed = false;
+        for (position, (index, names)) in candidates.iter().enumerate() {
+            if !kept[position] && names.iter().any(|name| live.names.contains(*name)) {
+                kept[position] = true;
+                changed = true;
+                live.visit_stmt(&body[*index]);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    candidates
+        .iter()
+        .zip(kept)
+        .filter(|(_, kept)| !kept)
+        .map(|((index, _), _)| *index)
+        .collect()
+}
+
+/// Names a removable definition binds, or `None` when the statement must be kept
+///
+/// Dunder names such as `__all__` or `__version__` are read by tools and importers
+/// rather than by the bundle, so their definitions are always kept.
+fn defined_names<'a>(stmt: &'a Stmt, safe_classes: &IndexSet<&str>) -> Option<Vec<&'a str>> {
+    let names = match stmt {
+        Stmt::FunctionDef(function_def) => {
+            let pure = function_def.decorator_list.iter().all(is_pure_decorator);
+            pure.then(|| vec![function_def.name.as_str()])
+        }
+        Stmt::ClassDef(class_def) => safe_classes
+            .contains(class_def.name.as_str())
+            .then(|| vec![class_def.name.as_str()]),
+        Stmt::Assign(assign) if is_pure(&assign.value) => assign
+            .targets
+            .iter()
+            .map(|target| target.as_name_expr().map(|name| name.id.as_str()))
+            .collect(),
+        Stmt::AnnAssign(ann_assign) if ann_assign.value.as_deref().is_none_or(is_pure) => {
+            let name = ann_assign.target.as_name_expr()?;
+            Some(vec![name.id.as_str()])
+        }
+        _ => None,
+    }?;
+    let is_dunder = |name: &&str| name.starts_with("__") && name.ends_with("__");
+    (!names.iter().any(is_dunder)).then_some(names)
+}
+
+/// Classes in `shakeable` ranges whose definition has no effect besides binding the
+/// class: no keywords such as `metaclass=`, no `__init_subclass__` hook, only pure
+/// decorators, and only bases that are builtins, typing constructs or such classes
+fn safe_classes<'a>(body: &'a [Stmt], shakeable: &[Range<usize>]) -> IndexSet<&'a str> {
+    let classes: Vec<&StmtClassDef> = body
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| shakeable.iter().any(|range| range.contains(index)))
+        .filter_map(|(_, stmt)| stmt.as_class_def_stmt())
+        .collect();
+
+    let mut safe = IndexSet::new();
+    loop {
+        let mut changed = false;
+        for class_def in &classes {
+            if safe.contains(class_def.name.as_str()) || !is_self_contained(class_def) {
+                continue;
+            }
+            let bases_safe = class_def.bases().iter().all(|base| {
+                let base = match base {
+                    Expr::Subscript(subscript) => &subscript.value,
+                    base => base,
+                };
+                dotted_name(base).is_some_and(|name| {
+                    PURE_BASES.contains(&name.as_str()) || safe.contains(name.as_str())
+                })
+            });
+            if bases_safe {
+                safe.insert(class_def.name.as_str());
+                changed = true;
+            }
+        }
+        if !changed {
+            return safe;
+        }
+    }
+}
+
+fn is_self_contained(class_def: &StmtClassDef) -> bool {
+    class_def.keywords().is_empty()
+        && class_def.decorator_list.iter().all(is_pure_decorator)
+        && class_def.body.iter().all(|stmt| match stmt {
+            Stmt::FunctionDef(function_def) => {
+                function_def.name.as_str() != "__init_subclass__"
+                    && function_def
+                        .decorator_list
+                        .iter()
+                        .all(is_pure_method_decorator)
+            }
+            Stmt::ClassDef(nested) => is_self_contained(nested),
+            Stmt::Assign(assign) => is_pure(&assign.value),
+            Stmt::AnnAssign(ann_assign) => ann_assign.value.as_deref().is_none_or(is_pure),
+            Stmt::Expr(expr_stmt) => expr_stmt.value.is_string_literal_expr(),
+            Stmt::Pass(_) => true,
+            _ => false,
+        })
+}
+
+fn is_pure_method_decorator(decorator: &Decorator) -> bool {
+    is_pure_decorator(decorator)
+        || dotted_name(&decorator.expression).is_some_and(|name| {
+            PURE_METHOD_DECORATORS.contains(&name.as_str())
+                // `@value.setter` and friends of a property
+                || [".setter", ".getter", ".deleter"]
+                    .iter()
+                    .any(|suffix| name.ends_with(suffix))
+        })
+}
+
+fn is_pure_decorator(decorator: &Decorator) -> bool {
+    let expr = match &decorator.expression {
+        Expr::Call(call) if call.arguments.args.iter().all(is_pure) => &call.func,
+        expr => expr,
+    };
+    dotted_name(expr).is_some_and(|name| PURE_DECORATORS.contains(&name.as_str()))
+}
+
+/// Whether evaluating `expr` at module level cannot have side effects
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::StringLiteral(_)
+        | Expr::BytesLiteral(_)
+        | Expr::NumberLiteral(_)
+        | Expr::BooleanLiteral(_)
+        | Expr::NoneLiteral(_)
+        | Expr::EllipsisLiteral(_)
+        | Expr::Name(_)
+        | Expr::Lambda(_) => true,
+        Expr::Attribute(attribute) => is_pure(&attribute.value),
+        Expr::Tuple(tuple) => tuple.elts.iter().all(is_pure),
+        Expr::List(list) => list.elts.iter().all(is_pure),
+        Expr::Set(set) => set.elts.iter().all(is_pure),
+        Expr::Dict(dict) => dict
+            .items
+            .iter()
+            .all(|item| item.key.as_ref().is_none_or(is_pure) && is_pure(&item.value)),
+        Expr::Subscript(subscript) => is_pure(&subscript.value) && is_pure(&subscript.slice),
+        Expr::UnaryOp(unary) => is_pure(&unary.operand),
+        Expr::BinOp(binary) => is_pure(&binary.left) && is_pure(&binary.right),
+        Expr::Call(call) => {
+            dotted_name(&call.func).is_some_and(|name| PURE_CALLS.contains(&name.as_str()))
+                && call.arguments.args.iter().all(is_pure)
+                && call
+                    .arguments
+                    .keywords
+                    .iter()
+                    .all(|keyword| is_pure(&keyword.value))
+        }
+        _ => false,
+    }
+}
+
+/// `name` or `package.name` for a name or attribute chain
+fn dotted_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Name(name) => Some(name.id.to_string()),
+        Expr::Attribute(attribute) => {
+            dotted_name(&attribute.value).map(|value| format!("{}.{}", value, attribute.attr))
+        }
+        _ => None,
+    }
+}
+
+/// Names that statements mention
+#[derive(Default)]
+struct References<'a> {
+    names: IndexSet<&'a str>,
+    dynamic_lookup: bool,
+}
+
+impl<'a> Visitor<'a> for References<'a> {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::Global(global) => self.names.extend(global.names.iter().map(|n| n.as_str())),
+            Stmt::Nonlocal(nonlocal) => {
+                self.names.extend(nonlocal.names.iter().map(|n| n.as_str()));
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::Name(name) => {
+                if DYNAMIC_LOOKUPS.contains(&name.id.as_str()) {
+                    self.dynamic_lookup = true;
+                }
+                self.names.insert(name.id.as_str());
+            }
+            Expr::StringLiteral(string) => {
+                for literal in string.value.iter() {
+                    self.names.extend(
+                        literal
+                            .value
+                            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                            .filter(|word| !word.is_empty()),
+                    );
+                }
+            }
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_parser::parse_module;
+
+    /// Names of the definitions removed from `source`, all of which is shakeable
+    fn removed(source: &str) -> Vec<String> {
+        let module = parse_module(source).expect("valid source").into_syntax();
+        let body = &module.body;
+        unused_definitions(body, &[0..body.len()])
+            .into_iter()
+            .map(|index| match &body[index] {
+                Stmt::FunctionDef(function_def) => function_def.name.to_string(),
+                Stmt::ClassDef(class_def) => class_def.name.to_string(),
+                stmt => defined_names(stmt, &IndexSet::new())
+                    .expect("removed statements define names")
+                    .join(","),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keeps_transitively_used_definitions() {
+        let source = "\
+LIMIT = 3
+UNUSED = 4
+
+
+def helper():
+    return LIMIT
+
+
+def used():
+    return helper()
+
+
+def unused():
+    return used()
+
+
+class Model:
+    pass
+
+
+print(used())
+";
+        assert_eq!(removed(source), ["UNUSED", "unused", "Model"]);
+    }
+
+    #[test]
+    fn test_keeps_definitions_with_possible_side_effects() {
+        let source = "\
+import registry
+
+CONFIG = registry.load()
+
+
+@registry.register
+def plugin():
+    pass
+
+
+class Tracked:
+    def __init_subclass__(cls):
+        registry.add(cls)
+
+
+class Plugin(Tracked):
+    pass
+
+
+class Managed(metaclass=registry.Meta):
+    pass
+
+
+class Ordinary(Exception):
+    pass
+";
+        assert_eq!(removed(source), ["Ordinary"]);
+    }
+
+    #[test]
+    fn test_string_references_and_dynamic_lookups() {
+        assert_eq!(
+            removed(
+                "__all__ = ['exported']\n\n\ndef exported():\n    pass\n\n\ndef hidden():\n    pass\n"
+            ),
+            ["hidden"]
+        );
+        assert!(
+            removed("def hidden():\n    pass\n\n\nprint(globals()['hid' + 'den'])\n").is_empty()
+        );
+    }
+}
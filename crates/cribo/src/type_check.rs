@@ -0,0 +1,219 @@
+//! Running a type checker over the generated bundle
+//!
+//! Renaming and inlining can break code in ways that still parses, such as a name that
+//! now refers to another module's symbol. Type-checking the bundle catches many of
+//! these before the bundle ships; each finding is attributed to the module whose code
+//! contains the reported line.
+
+use std::process::Command;
+
+use serde::Serialize;
+use tempfile::TempDir;
+
+use crate::error::{CriboError, CriboResult};
+use crate::module_map::ModuleMap;
+
+/// File name the bundle is checked under
+const CHECKED_FILE: &str = "bundle.py";
+
+/// Severities type checkers prefix their messages with
+const SEVERITIES: [&str; 4] = ["error", "warning", "note", "information"];
+
+/// One message of the type checker about the bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeCheckFinding {
+    /// Module whose code contains the line, or `None` for the bundle's runtime code
+    pub module: Option<String>,
+    /// 1-based line in the bundle
+    pub line: usize,
+    /// Severity reported by the checker, e.g. `error` or `note`
+    pub severity: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for TypeCheckFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.module {
+            Some(module) => write!(f, "module '{}'", module)?,
+            None => f.write_str("bundle runtime")?,
+        }
+        write!(
+            f,
+            " (bundle line {}): {}: {}",
+            self.line, self.severity, self.message
+        )
+    }
+}
+
+/// Run `command` (e.g. `mypy --strict` or `pyright`) with the path of a copy of `code`
+/// appended, and collect what it reports about the bundle
+///
+/// The checker runs in a temporary directory, so project-level configuration files
+/// are not picked up unless the command names them.
+pub fn check_bundle(
+    command: &str,
+    code: &str,
+    module_map: &ModuleMap,
+) -> CriboResult<Vec<TypeCheckFinding>> {
+    let config_error = |message: String| CriboError::Config {
+        path: None,
+        message,
+    };
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| config_error("The type checker command is empty".into()))?;
+
+    let dir = TempDir::with_prefix("cribo-type-check-").map_err(|err| {
+        config_error(format!(
+            "Failed to create a directory for type checking: {}",
+            err
+        ))
+    })?;
+    let bundle_path = dir.path().join(CHECKED_FILE);
+    std::fs::write(&bundle_path, code)
+        .map_err(|err| config_error(format!("Failed to write the bundle to check: {}", err)))?;
+
+    log::info!("Type-checking the bundle with `{}`", command);
+    let output = Command::new(program)
+        .args(words)
+        .arg(&bundle_path)
+        .current_dir(dir.path())
+        .output()
+        .map_err(|err| {
+            config_error(format!("Failed to run type checker `{}`: {}", command, err))
+        })?;
+
+    let mut findings = parse_findings(&String::from_utf8_lossy(&output.stdout), module_map);
+    findings.extend(parse_findings(
+        &String::from_utf8_lossy(&output.stderr),
+        module_map,
+    ));
+    if findings.is_empty() && !output.status.success() {
+        log::warn!(
+            "Type checker `{}` failed without reporting anything about the bundle: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(findings)
+}
+
+/// Findings in checker output, from lines such as `bundle.py:12: error: ...` (mypy)
+/// or `/tmp/.../bundle.py:12:5 - error: ...` (pyright)
+fn parse_findings(output: &str, module_map: &ModuleMap) -> Vec<TypeCheckFinding> {
+    output
+        .lines()
+        .filter_map(|line| parse_finding(line, module_map))
+        .collect()
+}
+
+fn parse_finding(line: &str, module_map: &ModuleMap) -> Option<TypeCheckFinding> {
+    let location = format!("{}:", CHECKED_FILE);
+    let (_, rest) = line.split_once(&location)?;
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let line_number: usize = rest[..digits].parse().ok()?;
+    let mut rest = &rest[digits..];
+    // Skip the column, when the checker reports one
+    if let Some(after_colon) = rest.strip_prefix(':') {
+        let column_digits = after_colon
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after_colon.len());
+        if column_digits > 0 {
+            rest = &after_colon[column_digits..];
+        }
+    }
+    let text = rest.trim_start_matches([':', '-', ' ']).trim_end();
+    let (severity, message) = match text.split_once(':') {
+        Some((severity, message)) if SEVERITIES.contains(&severity.trim()) => {
+            (severity.trim(), message.trim())
+        }
+        _ => ("error", text),
+    };
+    Some(TypeCheckFinding {
+        module: module_at_line(module_map, line_number),
+        line: line_number,
+        severity: severity.to_owned(),
+        message: message.to_owned(),
+    })
+}
+
+/// Module whose code spans `line` of the bundle
+fn module_at_line(module_map: &ModuleMap, line: usize) -> Option<String> {
+    module_map
+        .modules
+        .iter()
+        .find(|entry| {
+            entry
+                .lines
+                .is_some_and(|[first, last]| first <= line && line <= last)
+        })
+        .map(|entry| entry.name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_generator::ModuleStrategy;
+    use crate::module_map::{MODULE_MAP_VERSION, ModuleMapEntry};
+
+    fn module_map() -> ModuleMap {
+        ModuleMap {
+            version: MODULE_MAP_VERSION,
+            modules: vec![ModuleMapEntry {
+                name: "utils".into(),
+                path: "utils.py".into(),
+                strategy: ModuleStrategy::Inlined,
+                synthetic_name: None,
+                bytes: Some([100, 200]),
+                lines: Some([5, 9]),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_parses_mypy_output() {
+        let findings = parse_findings(
+            "bundle.py:7: error: Incompatible return value type (got \"int\", expected \"str\")  [return-value]\n\
+             bundle.py:20:3: note: Revealed type is \"builtins.int\"\n\
+             Found 1 error in 1 file (checked 1 source file)\n",
+            &module_map(),
+        );
+        assert_eq!(
+            findings,
+            vec![
+                TypeCheckFinding {
+                    module: Some("utils".into()),
+                    line: 7,
+                    severity: "error".into(),
+                    message: "Incompatible return value type (got \"int\", expected \"str\")  [return-value]".into(),
+                },
+                TypeCheckFinding {
+                    module: None,
+                    line: 20,
+                    severity: "note".into(),
+                    message: "Revealed type is \"builtins.int\"".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parses_pyright_output() {
+        let findings = parse_findings(
+            "/tmp/cribo-type-check-x/bundle.py\n  /tmp/cribo-type-check-x/bundle.py:5:12 - error: \"foo\" is not defined (reportUndefinedVariable)\n1 error, 0 warnings, 0 informations\n",
+            &module_map(),
+        );
+        assert_eq!(
+            findings,
+            vec![TypeCheckFinding {
+                module: Some("utils".into()),
+                line: 5,
+                severity: "error".into(),
+                message: "\"foo\" is not defined (reportUndefinedVariable)".into(),
+            }]
+        );
+    }
+}
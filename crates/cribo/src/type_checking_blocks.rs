@@ -0,0 +1,118 @@
+//! Bundle-time resolution of `if TYPE_CHECKING:` blocks
+//!
+//! Code under such a guard never runs, so the blocks are dropped (keeping any `else`
+//! branch): the imports inside them stay out of the dependency graph and unused-import
+//! trimming no longer sees names that only type checkers use.
+//!
+//! With `runtime_type_checking_imports`, a module-level block holding nothing but
+//! imports becomes plain imports instead, as long as the module evaluates its
+//! annotations (it lacks `from __future__ import annotations`). Annotations naming the
+//! imported types, quoted or resolved later by `typing.get_type_hints`, then find them
+//! at runtime.
+
+use ruff_python_ast::{Expr, ExprBooleanLiteral, ModModule, Stmt};
+use ruff_text_size::TextRange;
+
+use crate::defines::{fold_body, nested_bodies};
+use crate::visitors::TypeCheckingGuards;
+
+/// Resolve the `TYPE_CHECKING` guards of `module`, returning whether it changed
+pub(crate) fn resolve_type_checking_blocks(module: &mut ModModule, runtime_imports: bool) -> bool {
+    let guards = TypeCheckingGuards::from_module(module);
+    let runtime_imports = runtime_imports && !has_future_annotations(module);
+    let resolved = resolve_guards(&mut module.body, &guards, runtime_imports);
+    if resolved {
+        fold_body(&mut module.body, false);
+    }
+    resolved
+}
+
+/// Replace the guards in `body` and the blocks nested in it with the constant they have
+/// at runtime, or `True` for import blocks kept at runtime
+fn resolve_guards(body: &mut [Stmt], guards: &TypeCheckingGuards, runtime_imports: bool) -> bool {
+    let mut resolved = false;
+    for stmt in body {
+        if let Stmt::If(if_stmt) = stmt {
+            if guards.is_guard(&if_stmt.test) {
+                let keep_imports = runtime_imports
+                    && if_stmt.elif_else_clauses.is_empty()
+                    && if_stmt
+                        .body
+                        .iter()
+                        .all(|stmt| matches!(stmt, Stmt::Import(_) | Stmt::ImportFrom(_)));
+                *if_stmt.test = Expr::BooleanLiteral(ExprBooleanLiteral {
+                    value: keep_imports,
+                    range: TextRange::default(),
+                });
+                resolved = true;
+            }
+        }
+        for nested in nested_bodies(stmt) {
+            if resolve_guards(nested, guards, false) {
+                fold_body(nested, true);
+                resolved = true;
+            }
+        }
+    }
+    resolved
+}
+
+/// Whether `module` starts with `from __future__ import annotations`
+fn has_future_annotations(module: &ModModule) -> bool {
+    module.body.iter().any(|stmt| match stmt {
+        Stmt::ImportFrom(import_from) => {
+            import_from
+                .module
+                .as_ref()
+                .is_some_and(|module| module.as_str() == "__future__")
+                && import_from
+                    .names
+                    .iter()
+                    .any(|alias| alias.name.as_str() == "annotations")
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_codegen::{Generator, Stylist};
+    use ruff_python_parser::parse_module;
+
+    fn resolve(source: &str, runtime_imports: bool) -> String {
+        let parsed = parse_module(source).expect("valid source");
+        let stylist = Stylist::from_tokens(parsed.tokens(), source);
+        let mut module = parsed.into_syntax();
+        resolve_type_checking_blocks(&mut module, runtime_imports);
+        module
+            .body
+            .iter()
+            .map(|stmt| Generator::from(&stylist).stmt(stmt))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_guarded_blocks_are_dropped() {
+        let source = "from typing import TYPE_CHECKING as TC\n\nif TC:\n    from models import User\nelse:\n    User = None\n\nclass Repo:\n    if TC:\n        from db import Session\n    name = 'repo'\n";
+        assert_eq!(
+            resolve(source, false),
+            "from typing import TYPE_CHECKING as TC\nUser = None\nclass Repo:\n    name = 'repo'"
+        );
+    }
+
+    #[test]
+    fn test_runtime_imports_unless_annotations_are_postponed() {
+        let source = "import typing\n\nif typing.TYPE_CHECKING:\n    from models import User\n";
+        assert_eq!(
+            resolve(source, true),
+            "import typing\nfrom models import User"
+        );
+        let postponed = format!("from __future__ import annotations\n{}", source);
+        assert_eq!(
+            resolve(&postponed, true),
+            "from __future__ import annotations\nimport typing"
+        );
+    }
+}
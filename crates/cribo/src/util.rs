@@ -1,5 +1,10 @@
 use cow_utils::CowUtils;
+use indexmap::IndexMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Convert a relative path to a Python module name, handling .py extension and __init__.py
 pub fn module_name_from_relative(relative_path: &Path) -> Option<String> {
@@ -50,6 +55,30 @@ pub fn path_to_module_name(src_dir: &Path, file_path: &Path) -> Option<String> {
     module_name_from_relative(relative_path)
 }
 
+/// Absolute module that a relative import in `module_name` resolves to when it climbs
+/// above every source root, using the `relative_import_fallbacks` entry of the closest
+/// enclosing package (see `Config::relative_import_fallbacks`)
+pub fn relative_import_fallback(
+    fallbacks: &IndexMap<String, String>,
+    module_name: &str,
+    imported_module: Option<&str>,
+) -> Option<String> {
+    let package = fallbacks
+        .iter()
+        .filter(|(prefix, _)| {
+            module_name == prefix.as_str()
+                || module_name
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, package)| package)?;
+    Some(match imported_module {
+        Some(imported) => format!("{package}.{imported}"),
+        None => package.clone(),
+    })
+}
+
 /// Normalize line endings to LF (\n) for cross-platform consistency
 /// This ensures reproducible builds regardless of the platform where bundling occurs
 pub fn normalize_line_endings(content: String) -> String {
@@ -60,6 +89,71 @@ pub fn normalize_line_endings(content: String) -> String {
         .into_owned()
 }
 
+/// Write `contents` to `path` so that readers see either the previous file or the
+/// complete new one, never a truncated write
+///
+/// The data is written to a hidden file in the same directory, flushed to disk and
+/// renamed over `path`. The permissions of an existing file carry over, and with
+/// `backup` its previous contents are kept at [`backup_path`].
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>, backup: bool) -> io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} does not name a file", path.display()),
+        )
+    })?;
+    // Unique per call, so threads writing the same file never share a staging file
+    static STAGING_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut staging_name = OsString::from(".");
+    staging_name.push(file_name);
+    staging_name.push(format!(
+        ".{}.{}.partial",
+        std::process::id(),
+        STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let staging_path = path.with_file_name(staging_name);
+
+    let result = (|| {
+        let mut file = fs::File::create(&staging_path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&staging_path, metadata.permissions())?;
+            if backup {
+                fs::copy(path, backup_path(path))?;
+            }
+        }
+        fs::rename(&staging_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&staging_path);
+    }
+    result
+}
+
+/// Let everyone who can read the file at `path` execute it; a no-op outside Unix
+pub fn make_executable(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut permissions = fs::metadata(path)?.permissions();
+        let mode = permissions.mode();
+        // Execute bits for the same classes that have read access
+        permissions.set_mode(mode | ((mode & 0o444) >> 2));
+        fs::set_permissions(path, permissions)?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Where [`write_atomically`] keeps the previous contents of `path`: `<path>.bak`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
 /// Get the Python executable path, with support for virtual environments
 ///
 /// This function checks for the VIRTUAL_ENV environment variable and constructs
@@ -109,6 +203,45 @@ mod tests {
     use super::*;
     use crate::resolver::VirtualEnvGuard;
 
+    #[test]
+    fn test_relative_import_fallback_uses_closest_package() {
+        let fallbacks = IndexMap::from([
+            ("vendored".to_owned(), "upstream".to_owned()),
+            ("vendored.legacy".to_owned(), "legacy_upstream".to_owned()),
+        ]);
+        assert_eq!(
+            relative_import_fallback(&fallbacks, "vendored.api", Some("models")),
+            Some("upstream.models".to_owned())
+        );
+        assert_eq!(
+            relative_import_fallback(&fallbacks, "vendored.legacy.io", None),
+            Some("legacy_upstream".to_owned())
+        );
+        assert_eq!(
+            relative_import_fallback(&fallbacks, "vendored_extra", None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_write_atomically_replaces_file_and_keeps_backup() {
+        let dir = tempfile::TempDir::new().expect("create temp dir");
+        let path = dir.path().join("bundle.py");
+
+        write_atomically(&path, "first", true).expect("first write");
+        assert!(!backup_path(&path).exists());
+
+        write_atomically(&path, "second", true).expect("second write");
+        assert_eq!(fs::read_to_string(&path).expect("read output"), "second");
+        assert_eq!(
+            fs::read_to_string(backup_path(&path)).expect("read backup"),
+            "first"
+        );
+
+        // Only the output and its backup remain, no staging files
+        assert_eq!(fs::read_dir(dir.path()).expect("list dir").count(), 2);
+    }
+
     #[test]
     #[serial_test::serial]
     fn test_get_python_executable_with_virtual_env() {
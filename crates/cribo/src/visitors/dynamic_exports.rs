@@ -0,0 +1,158 @@
+//! Detection of modules that build their namespace at runtime, e.g. with
+//! `globals().update(...)` or `setattr(sys.modules[__name__], ...)`.
+//!
+//! Names created this way are invisible to static analysis, so such modules must keep
+//! a real module namespace in the bundle instead of being inlined.
+
+use ruff_python_ast::visitor::{Visitor, walk_expr, walk_stmt};
+use ruff_python_ast::{Expr, ExprCall, ModModule, Stmt};
+
+/// Visitor that finds dynamic namespace construction anywhere in a module
+#[derive(Default)]
+pub struct DynamicExportVisitor {
+    /// Descriptions of the patterns found, in source order
+    patterns: Vec<&'static str>,
+}
+
+impl DynamicExportVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Visit a module and return the dynamic export patterns it uses
+    pub fn find_patterns(module: &ModModule) -> Vec<&'static str> {
+        let mut visitor = Self::new();
+        for stmt in &module.body {
+            visitor.visit_stmt(stmt);
+        }
+        visitor.patterns
+    }
+
+    fn record(&mut self, pattern: &'static str) {
+        if !self.patterns.contains(&pattern) {
+            self.patterns.push(pattern);
+        }
+    }
+
+    fn check_call(&mut self, call: &ExprCall) {
+        match call.func.as_ref() {
+            // globals().update(...), vars().setdefault(...), sys.modules[__name__].__dict__.update(...)
+            Expr::Attribute(attr) if matches!(attr.attr.as_str(), "update" | "setdefault") => {
+                if is_namespace_dict(&attr.value) {
+                    self.record("globals().update()");
+                }
+            }
+            // setattr(sys.modules[__name__], name, value)
+            Expr::Name(name) if name.id.as_str() == "setattr" => {
+                if call
+                    .arguments
+                    .args
+                    .first()
+                    .is_some_and(is_current_module_object)
+                {
+                    self.record("setattr(sys.modules[__name__], ...)");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_assign_target(&mut self, target: &Expr) {
+        match target {
+            // globals()[name] = value
+            Expr::Subscript(subscript) if is_namespace_dict(&subscript.value) => {
+                self.record("globals()[...] = ...");
+            }
+            // sys.modules[__name__].name = value
+            Expr::Attribute(attr) if is_current_module_object(&attr.value) => {
+                self.record("sys.modules[__name__].<name> = ...");
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for DynamicExportVisitor {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        if let Stmt::Assign(assign) = stmt {
+            for target in &assign.targets {
+                self.check_assign_target(target);
+            }
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        if let Expr::Call(call) = expr {
+            self.check_call(call);
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// `globals()`, `vars()` or `sys.modules[__name__].__dict__`
+fn is_namespace_dict(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(call) => {
+            call.arguments.args.is_empty()
+                && call.arguments.keywords.is_empty()
+                && matches!(
+                    call.func.as_ref(),
+                    Expr::Name(name) if matches!(name.id.as_str(), "globals" | "vars")
+                )
+        }
+        Expr::Attribute(attr) => {
+            attr.attr.as_str() == "__dict__" && is_current_module_object(&attr.value)
+        }
+        _ => false,
+    }
+}
+
+/// `sys.modules[__name__]`
+fn is_current_module_object(expr: &Expr) -> bool {
+    let Expr::Subscript(subscript) = expr else {
+        return false;
+    };
+    let is_sys_modules = matches!(
+        subscript.value.as_ref(),
+        Expr::Attribute(attr)
+            if attr.attr.as_str() == "modules"
+                && matches!(attr.value.as_ref(), Expr::Name(name) if name.id.as_str() == "sys")
+    );
+    is_sys_modules
+        && matches!(subscript.slice.as_ref(), Expr::Name(name) if name.id.as_str() == "__name__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_parser::parse_module;
+
+    fn patterns(source: &str) -> Vec<&'static str> {
+        let parsed = parse_module(source).expect("valid source");
+        DynamicExportVisitor::find_patterns(parsed.syntax())
+    }
+
+    #[test]
+    fn test_detects_dynamic_namespace_construction() {
+        assert_eq!(
+            patterns("globals().update({'a': 1})\n"),
+            vec!["globals().update()"]
+        );
+        assert_eq!(
+            patterns(
+                "import sys\n\ndef export(name, value):\n    setattr(sys.modules[__name__], name, value)\n"
+            ),
+            vec!["setattr(sys.modules[__name__], ...)"]
+        );
+        assert_eq!(
+            patterns("for name in ('a', 'b'):\n    globals()[name] = name\n"),
+            vec!["globals()[...] = ..."]
+        );
+    }
+
+    #[test]
+    fn test_ignores_static_code() {
+        assert!(patterns("config = {}\nconfig.update(a=1)\nsetattr(config, 'b', 2)\n").is_empty());
+    }
+}
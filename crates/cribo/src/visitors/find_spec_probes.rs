@@ -0,0 +1,68 @@
+//! Detection of feature probes such as `importlib.util.find_spec("optional_mod")`.
+//!
+//! Modules that are bundled no longer exist on disk next to the bundle, so the regular
+//! import machinery reports them as missing unless the bundle tells it otherwise.
+
+use ruff_python_ast::visitor::{Visitor, walk_expr};
+use ruff_python_ast::{Expr, ModModule};
+
+/// Visitor that collects the module names passed as string literals to `find_spec`
+#[derive(Default)]
+pub struct FindSpecProbeVisitor {
+    /// Probed module names, in source order and without duplicates
+    names: Vec<String>,
+}
+
+impl FindSpecProbeVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Visit a module and return the module names it probes
+    pub fn find_probed_names(module: &ModModule) -> Vec<String> {
+        let mut visitor = Self::new();
+        for stmt in &module.body {
+            visitor.visit_stmt(stmt);
+        }
+        visitor.names
+    }
+}
+
+impl<'a> Visitor<'a> for FindSpecProbeVisitor {
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        if let Expr::Call(call) = expr {
+            // find_spec(...), util.find_spec(...) and importlib.util.find_spec(...)
+            let is_find_spec = match call.func.as_ref() {
+                Expr::Name(name) => name.id.as_str() == "find_spec",
+                Expr::Attribute(attr) => attr.attr.as_str() == "find_spec",
+                _ => false,
+            };
+            if is_find_spec {
+                if let Some(Expr::StringLiteral(literal)) = call.arguments.args.first() {
+                    let name = literal.value.to_str();
+                    // Relative names need a package argument we cannot resolve statically
+                    if !name.starts_with('.') && !self.names.iter().any(|n| n == name) {
+                        self.names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_parser::parse_module;
+
+    #[test]
+    fn test_collects_literal_find_spec_probes() {
+        let source = "import importlib.util\nfrom importlib.util import find_spec\n\nHAS_FAST = importlib.util.find_spec(\"fastjson\") is not None\n\n\ndef has_plugin():\n    return find_spec(\"plugins.extra\") is not None or find_spec(name) is not None\n";
+        let parsed = parse_module(source).expect("valid source");
+        assert_eq!(
+            FindSpecProbeVisitor::find_probed_names(parsed.syntax()),
+            vec!["fastjson".to_string(), "plugins.extra".to_string()]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Collection of every identifier a module binds or reads as a plain name.
+//!
+//! The bundle adds its own runtime names, such as the module registries and the
+//! `module` object of wrapper init functions, to the code it emits. Those must not be
+//! names the bundled modules already use.
+
+use indexmap::IndexSet;
+use ruff_python_ast::visitor::{
+    Visitor, walk_except_handler, walk_expr, walk_parameter, walk_pattern, walk_stmt,
+};
+use ruff_python_ast::{ExceptHandler, Expr, ModModule, Parameter, Pattern, Stmt};
+
+/// Visitor that collects the names a module uses, at any depth
+///
+/// Attribute names are not collected, since `obj.module` cannot clash with a variable.
+#[derive(Default)]
+pub struct IdentifierVisitor {
+    names: IndexSet<String>,
+}
+
+impl IdentifierVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Visit a module and return the names it uses, in source order
+    pub fn collect_names(module: &ModModule) -> IndexSet<String> {
+        let mut visitor = Self::new();
+        for stmt in &module.body {
+            visitor.visit_stmt(stmt);
+        }
+        visitor.names
+    }
+
+    fn add(&mut self, name: &str) {
+        if !self.names.contains(name) {
+            self.names.insert(name.to_owned());
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for IdentifierVisitor {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::FunctionDef(function_def) => self.add(function_def.name.as_str()),
+            Stmt::ClassDef(class_def) => self.add(class_def.name.as_str()),
+            Stmt::Import(import) => {
+                for alias in &import.names {
+                    match &alias.asname {
+                        Some(asname) => self.add(asname.as_str()),
+                        None => {
+                            let name = alias.name.as_str();
+                            self.add(name.split('.').next().unwrap_or(name));
+                        }
+                    }
+                }
+            }
+            Stmt::ImportFrom(import_from) => {
+                for alias in &import_from.names {
+                    self.add(alias.asname.as_ref().unwrap_or(&alias.name).as_str());
+                }
+            }
+            Stmt::Global(global) => {
+                for name in &global.names {
+                    self.add(name.as_str());
+                }
+            }
+            Stmt::Nonlocal(nonlocal) => {
+                for name in &nonlocal.names {
+                    self.add(name.as_str());
+                }
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        if let Expr::Name(name) = expr {
+            self.add(name.id.as_str());
+        }
+        walk_expr(self, expr);
+    }
+
+    fn visit_parameter(&mut self, parameter: &'a Parameter) {
+        self.add(parameter.name.as_str());
+        walk_parameter(self, parameter);
+    }
+
+    fn visit_except_handler(&mut self, except_handler: &'a ExceptHandler) {
+        let ExceptHandler::ExceptHandler(handler) = except_handler;
+        if let Some(name) = &handler.name {
+            self.add(name.as_str());
+        }
+        walk_except_handler(self, except_handler);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'a Pattern) {
+        match pattern {
+            Pattern::MatchAs(match_as) => {
+                if let Some(name) = &match_as.name {
+                    self.add(name.as_str());
+                }
+            }
+            Pattern::MatchStar(match_star) => {
+                if let Some(name) = &match_star.name {
+                    self.add(name.as_str());
+                }
+            }
+            Pattern::MatchMapping(match_mapping) => {
+                if let Some(rest) = &match_mapping.rest {
+                    self.add(rest.as_str());
+                }
+            }
+            _ => {}
+        }
+        walk_pattern(self, pattern);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_parser::parse_module;
+
+    #[test]
+    fn test_collects_bound_and_read_names_but_not_attributes() {
+        let parsed = parse_module(
+            "import os.path\nfrom pkg import a as module\n\n\nclass CriboBundledFinder:\n    pass\n\n\ndef run(value, *args, **kwargs):\n    try:\n        return value.attribute\n    except KeyError as error:\n        raise error\n",
+        )
+        .expect("valid source");
+
+        let names = IdentifierVisitor::collect_names(parsed.syntax());
+
+        for name in [
+            "os",
+            "module",
+            "CriboBundledFinder",
+            "run",
+            "value",
+            "args",
+            "kwargs",
+            "KeyError",
+            "error",
+        ] {
+            assert!(names.contains(name), "missing {name}: {names:?}");
+        }
+        assert!(!names.contains("attribute"));
+        assert!(!names.contains("a"));
+    }
+}
@@ -5,6 +5,8 @@ use ruff_python_ast::visitor::{Visitor, walk_stmt};
 use ruff_python_ast::{ModModule, Stmt, StmtImport, StmtImportFrom};
 use ruff_text_size::TextRange;
 
+use super::type_checking::TypeCheckingGuards;
+
 /// An import discovered during AST traversal
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DiscoveredImport {
@@ -35,6 +37,8 @@ pub enum ImportLocation {
     Conditional { depth: usize },
     /// Import inside other nested scope
     Nested(Vec<ScopeElement>),
+    /// Import inside an `if TYPE_CHECKING:` block, which never runs
+    TypeChecking,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,6 +50,7 @@ pub enum ScopeElement {
     For,
     With,
     Try,
+    TypeChecking,
 }
 
 /// Visitor that discovers all imports in a Python module
@@ -55,6 +60,8 @@ pub struct ImportDiscoveryVisitor {
     imports: Vec<DiscoveredImport>,
     /// Current scope stack
     scope_stack: Vec<ScopeElement>,
+    /// Spellings of `TYPE_CHECKING` known in the visited module
+    type_checking_guards: TypeCheckingGuards,
 }
 
 impl ImportDiscoveryVisitor {
@@ -73,6 +80,9 @@ impl ImportDiscoveryVisitor {
         if self.scope_stack.is_empty() {
             return ImportLocation::Module;
         }
+        if self.scope_stack.contains(&ScopeElement::TypeChecking) {
+            return ImportLocation::TypeChecking;
+        }
 
         // Analyze the scope stack to determine location
         match &self.scope_stack[..] {
@@ -150,6 +160,7 @@ impl ImportDiscoveryVisitor {
 
     /// Visit a module and discover all imports
     pub fn visit_module(&mut self, module: &ModModule) {
+        self.type_checking_guards = TypeCheckingGuards::from_module(module);
         for stmt in &module.body {
             self.visit_stmt(stmt);
         }
@@ -181,6 +192,23 @@ impl<'a> Visitor<'a> for ImportDiscoveryVisitor {
                 self.scope_stack.pop();
                 return;
             }
+            Stmt::If(if_stmt) if self.type_checking_guards.is_guard(&if_stmt.test) => {
+                // Only the guarded body is type-checking-only; `else` branches run
+                self.scope_stack.push(ScopeElement::TypeChecking);
+                for body_stmt in &if_stmt.body {
+                    self.visit_stmt(body_stmt);
+                }
+                self.scope_stack.pop();
+
+                self.scope_stack.push(ScopeElement::If);
+                for clause in &if_stmt.elif_else_clauses {
+                    for body_stmt in &clause.body {
+                        self.visit_stmt(body_stmt);
+                    }
+                }
+                self.scope_stack.pop();
+                return;
+            }
             Stmt::If(_) => {
                 self.scope_stack.push(ScopeElement::If);
                 walk_stmt(self, stmt);
@@ -309,6 +337,42 @@ if True:
         ));
     }
 
+    #[test]
+    fn test_type_checking_imports_with_aliased_guards() {
+        let source = r#"
+import typing as t
+from typing import TYPE_CHECKING as TC
+
+if TC:
+    from models import User
+if t.TYPE_CHECKING:
+    import schemas
+else:
+    import fallback
+if TYPE_CHECKING:
+    import stubs
+"#;
+        let parsed = parse_module(source).expect("Failed to parse test module");
+        let mut visitor = ImportDiscoveryVisitor::new();
+        visitor.visit_module(parsed.syntax());
+        let imports = visitor.into_imports();
+
+        let location_of = |module: &str| {
+            imports
+                .iter()
+                .find(|import| import.module_name.as_deref() == Some(module))
+                .map(|import| import.location.clone())
+                .expect("import should be discovered")
+        };
+        assert_eq!(location_of("models"), ImportLocation::TypeChecking);
+        assert_eq!(location_of("schemas"), ImportLocation::TypeChecking);
+        assert_eq!(location_of("stubs"), ImportLocation::TypeChecking);
+        assert_eq!(
+            location_of("fallback"),
+            ImportLocation::Conditional { depth: 1 }
+        );
+    }
+
     #[test]
     fn test_nested_function_in_method_not_misclassified() {
         let source = r#"
@@ -0,0 +1,397 @@
+//! Detection of the conditions imports run under, such as `if sys.platform == "win32":`
+//! or `try: ... except ImportError:`.
+//!
+//! Requirements files use these to list third-party packages that only some
+//! environments import with a PEP 508 marker, instead of requiring them everywhere.
+
+use ruff_python_ast::{
+    BoolOp, CmpOp, ExceptHandler, Expr, ExprCall, ExprCompare, ModModule, Stmt, StmtIf, UnaryOp,
+};
+
+use crate::markers::Marker;
+
+/// An absolute import and the condition it runs under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardedImport {
+    /// Dotted name of the imported module
+    pub module: String,
+    /// Environments the import runs in; `None` for every environment
+    pub marker: Option<Marker>,
+    /// Whether a surrounding `try` block handles the module being missing
+    pub optional: bool,
+}
+
+/// Visitor that collects a module's absolute imports with the guards around them
+#[derive(Default)]
+pub struct ImportGuardVisitor {
+    imports: Vec<GuardedImport>,
+}
+
+impl ImportGuardVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Visit a module and return its absolute imports in source order
+    pub fn find_guarded_imports(module: &ModModule) -> Vec<GuardedImport> {
+        let mut visitor = Self::new();
+        visitor.visit_body(&module.body, None, false);
+        visitor.imports
+    }
+
+    fn visit_body(&mut self, body: &[Stmt], marker: Option<&Marker>, optional: bool) {
+        for stmt in body {
+            self.visit_stmt(stmt, marker, optional);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt, marker: Option<&Marker>, optional: bool) {
+        match stmt {
+            Stmt::Import(import) => {
+                for alias in &import.names {
+                    self.record(alias.name.as_str(), marker, optional);
+                }
+            }
+            Stmt::ImportFrom(import_from) if import_from.level == 0 => {
+                if let Some(module) = &import_from.module {
+                    self.record(module.as_str(), marker, optional);
+                }
+            }
+            Stmt::If(if_stmt) => self.visit_if(if_stmt, marker, optional),
+            Stmt::Try(try_stmt) => {
+                let handles_missing = try_stmt.handlers.iter().any(handles_import_error);
+                self.visit_body(&try_stmt.body, marker, optional || handles_missing);
+                for handler in &try_stmt.handlers {
+                    let ExceptHandler::ExceptHandler(handler) = handler;
+                    self.visit_body(&handler.body, marker, optional);
+                }
+                self.visit_body(&try_stmt.orelse, marker, optional);
+                self.visit_body(&try_stmt.finalbody, marker, optional);
+            }
+            Stmt::FunctionDef(function_def) => {
+                self.visit_body(&function_def.body, marker, optional);
+            }
+            Stmt::ClassDef(class_def) => self.visit_body(&class_def.body, marker, optional),
+            Stmt::For(for_stmt) => {
+                self.visit_body(&for_stmt.body, marker, optional);
+                self.visit_body(&for_stmt.orelse, marker, optional);
+            }
+            Stmt::While(while_stmt) => {
+                self.visit_body(&while_stmt.body, marker, optional);
+                self.visit_body(&while_stmt.orelse, marker, optional);
+            }
+            Stmt::With(with_stmt) => self.visit_body(&with_stmt.body, marker, optional),
+            Stmt::Match(match_stmt) => {
+                for case in &match_stmt.cases {
+                    self.visit_body(&case.body, marker, optional);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_if(&mut self, if_stmt: &StmtIf, marker: Option<&Marker>, optional: bool) {
+        // Each branch runs where its own test holds and every earlier test failed
+        let branches = std::iter::once((Some(&*if_stmt.test), &if_stmt.body)).chain(
+            if_stmt
+                .elif_else_clauses
+                .iter()
+                .map(|clause| (clause.test.as_ref(), &clause.body)),
+        );
+        let mut earlier_failed = marker.cloned();
+        for (test, body) in branches {
+            let Some(test) = test else {
+                self.visit_body(body, earlier_failed.as_ref(), optional);
+                break;
+            };
+            let branch = Marker::and(earlier_failed.clone(), condition(test, true));
+            self.visit_body(body, branch.as_ref(), optional);
+            earlier_failed = Marker::and(earlier_failed, condition(test, false));
+        }
+    }
+
+    fn record(&mut self, module: &str, marker: Option<&Marker>, optional: bool) {
+        self.imports.push(GuardedImport {
+            module: module.to_owned(),
+            marker: marker.cloned(),
+            optional,
+        });
+    }
+}
+
+/// Whether an `except` clause catches a failed import
+pub fn handles_import_error(handler: &ExceptHandler) -> bool {
+    let ExceptHandler::ExceptHandler(handler) = handler;
+    let catches = |expr: &Expr| {
+        matches!(
+            expr,
+            Expr::Name(name) if matches!(
+                name.id.as_str(),
+                "ImportError" | "ModuleNotFoundError" | "Exception" | "BaseException"
+            )
+        )
+    };
+    match handler.type_.as_deref() {
+        None => true,
+        Some(Expr::Tuple(tuple)) => tuple.elts.iter().any(catches),
+        Some(expr) => catches(expr),
+    }
+}
+
+/// Marker that holds wherever `expr` is truthy, or falsy when `truthy` is false
+///
+/// Parts of the test that can't be expressed as markers are left out, so the marker
+/// may hold in more environments than the test does but never in fewer. `None` places
+/// no constraint.
+fn condition(expr: &Expr, truthy: bool) -> Option<Marker> {
+    match expr {
+        Expr::UnaryOp(unary) if unary.op == UnaryOp::Not => condition(&unary.operand, !truthy),
+        Expr::BoolOp(bool_op) => {
+            let parts = bool_op.values.iter().map(|value| condition(value, truthy));
+            // `a and b` holds where both hold and fails where either fails
+            if (bool_op.op == BoolOp::And) == truthy {
+                parts.fold(None, Marker::and)
+            } else {
+                parts
+                    .collect::<Option<Vec<_>>>()?
+                    .into_iter()
+                    .reduce(Marker::or)
+            }
+        }
+        Expr::Compare(compare) => compare_marker(compare).map(|marker| polarize(marker, truthy)),
+        Expr::Call(call) => prefix_marker(call).map(|marker| polarize(marker, truthy)),
+        _ => None,
+    }
+}
+
+fn polarize(marker: Marker, truthy: bool) -> Marker {
+    if truthy { marker } else { marker.negate() }
+}
+
+/// Marker for comparisons like `sys.platform == "win32"` or `os.name in ("nt", "ce")`
+fn compare_marker(compare: &ExprCompare) -> Option<Marker> {
+    let [op] = &*compare.ops else {
+        return None;
+    };
+    let [comparator] = &*compare.comparators else {
+        return None;
+    };
+    let (variable, other) = match (marker_variable(&compare.left), marker_variable(comparator)) {
+        (Some(variable), None) => (variable, comparator),
+        (None, Some(variable)) if matches!(*op, CmpOp::Eq | CmpOp::NotEq) => {
+            (variable, compare.left.as_ref())
+        }
+        _ => return None,
+    };
+    let equals = |value: &Expr| {
+        Some(Marker::Compare {
+            variable,
+            equal: true,
+            value: marker_value(value)?,
+        })
+    };
+    match *op {
+        CmpOp::Eq => equals(other),
+        CmpOp::NotEq => equals(other).map(Marker::negate),
+        CmpOp::In | CmpOp::NotIn => {
+            let values = match other {
+                Expr::Tuple(tuple) => &tuple.elts,
+                Expr::List(list) => &list.elts,
+                Expr::Set(set) => &set.elts,
+                _ => return None,
+            };
+            let any = values
+                .iter()
+                .map(equals)
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .reduce(Marker::or)?;
+            Some(polarize(any, *op == CmpOp::In))
+        }
+        _ => None,
+    }
+}
+
+/// Marker for `sys.platform.startswith("win")` and similar platform prefix checks
+fn prefix_marker(call: &ExprCall) -> Option<Marker> {
+    let Expr::Attribute(method) = call.func.as_ref() else {
+        return None;
+    };
+    if method.attr.as_str() != "startswith"
+        || marker_variable(&method.value) != Some("sys_platform")
+    {
+        return None;
+    }
+    let [Expr::StringLiteral(prefix)] = &*call.arguments.args else {
+        return None;
+    };
+    // Only prefixes of a single platform name have an equivalent marker
+    let platform = match prefix.value.to_str() {
+        "win" | "win32" => "win32",
+        "linux" => "linux",
+        "darwin" => "darwin",
+        "cygwin" => "cygwin",
+        "aix" => "aix",
+        _ => return None,
+    };
+    Some(Marker::Compare {
+        variable: "sys_platform",
+        equal: true,
+        value: platform.to_owned(),
+    })
+}
+
+/// The PEP 508 marker variable whose value `expr` reads
+fn marker_variable(expr: &Expr) -> Option<&'static str> {
+    let is_name = |expr: &Expr, expected: &str| matches!(expr, Expr::Name(name) if name.id.as_str() == expected);
+    match expr {
+        Expr::Attribute(attribute) => match attribute.attr.as_str() {
+            "platform" if is_name(&attribute.value, "sys") => Some("sys_platform"),
+            "name" if is_name(&attribute.value, "os") => Some("os_name"),
+            "name" => match attribute.value.as_ref() {
+                Expr::Attribute(inner)
+                    if inner.attr.as_str() == "implementation" && is_name(&inner.value, "sys") =>
+                {
+                    Some("implementation_name")
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        Expr::Call(call)
+            if call.arguments.args.is_empty() && call.arguments.keywords.is_empty() =>
+        {
+            let Expr::Attribute(function) = call.func.as_ref() else {
+                return None;
+            };
+            if !is_name(&function.value, "platform") {
+                return None;
+            }
+            match function.attr.as_str() {
+                "system" => Some("platform_system"),
+                "machine" => Some("platform_machine"),
+                "python_implementation" => Some("platform_python_implementation"),
+                "release" => Some("platform_release"),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A string literal that can be quoted in a marker as is
+fn marker_value(expr: &Expr) -> Option<String> {
+    let Expr::StringLiteral(literal) = expr else {
+        return None;
+    };
+    let value = literal.value.to_str();
+    (!value.contains(['"', '\\'])).then(|| value.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_parser::parse_module;
+
+    fn guarded_imports(source: &str) -> Vec<(String, Option<String>, bool)> {
+        let parsed = parse_module(source).expect("valid source");
+        ImportGuardVisitor::find_guarded_imports(parsed.syntax())
+            .into_iter()
+            .map(|import| {
+                (
+                    import.module,
+                    import.marker.map(|marker| marker.to_string()),
+                    import.optional,
+                )
+            })
+            .collect()
+    }
+
+    fn guarded(
+        module: &str,
+        marker: Option<&str>,
+        optional: bool,
+    ) -> (String, Option<String>, bool) {
+        (module.to_owned(), marker.map(str::to_owned), optional)
+    }
+
+    #[test]
+    fn test_platform_branches_become_markers() {
+        let source = r#"
+import sys
+if sys.platform == "win32":
+    import win32api
+elif sys.platform.startswith("linux"):
+    import pyinotify
+else:
+    import fsevents
+if "nt" != os.name and DEBUG:
+    import colorama
+"#;
+        assert_eq!(
+            guarded_imports(source),
+            vec![
+                guarded("sys", None, false),
+                guarded("win32api", Some("sys_platform == \"win32\""), false),
+                guarded(
+                    "pyinotify",
+                    Some("sys_platform != \"win32\" and sys_platform == \"linux\""),
+                    false
+                ),
+                guarded(
+                    "fsevents",
+                    Some("sys_platform != \"win32\" and sys_platform != \"linux\""),
+                    false
+                ),
+                guarded("colorama", Some("os_name != \"nt\""), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_conditions_do_not_narrow() {
+        let source = r#"
+if sys.platform == "win32" or DEBUG:
+    import win32api
+if not (sys.platform == "darwin" and DEBUG):
+    import pyinotify
+if platform.system() in ("Linux", "Darwin"):
+    import uvloop
+"#;
+        assert_eq!(
+            guarded_imports(source),
+            vec![
+                guarded("win32api", None, false),
+                guarded("pyinotify", None, false),
+                guarded(
+                    "uvloop",
+                    Some("platform_system == \"Linux\" or platform_system == \"Darwin\""),
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_except_import_error_is_optional() {
+        let source = r#"
+try:
+    import ujson as json
+except ImportError:
+    import json
+try:
+    import yaml
+except ValueError:
+    pass
+from . import sibling
+"#;
+        assert_eq!(
+            guarded_imports(source),
+            vec![
+                guarded("ujson", None, true),
+                guarded("json", None, false),
+                guarded("yaml", None, false),
+            ]
+        );
+    }
+}
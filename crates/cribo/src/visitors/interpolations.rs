@@ -0,0 +1,143 @@
+//! The expressions interpolated into f-strings and t-strings
+//!
+//! Transformations that rename references walk expressions by hand, so they reach the
+//! `{...}` parts of f-strings and PEP 750 template strings through these helpers. The
+//! expressions are changed in place, which keeps each string's prefix and quoting
+//! (triple quotes, PEP 701 nested quotes) and any implicitly concatenated parts.
+
+use ruff_python_ast::{
+    Expr, FStringPart, InterpolatedElement, InterpolatedStringElement, TStringPart,
+};
+
+/// Interpolated expressions of an f-string or t-string, including those nested in
+/// format specs (`f"{value:{width}}"`); empty for any other expression
+pub fn interpolated_expressions(expr: &Expr) -> Vec<&Expr> {
+    let mut elements = Vec::new();
+    match expr {
+        Expr::FString(fstring) => {
+            for part in fstring.value.iter() {
+                if let FStringPart::FString(fstring) = part {
+                    collect(&fstring.elements, &mut elements);
+                }
+            }
+        }
+        Expr::TString(tstring) => {
+            for part in tstring.value.iter() {
+                match part {
+                    TStringPart::FString(fstring) => collect(&fstring.elements, &mut elements),
+                    TStringPart::TString(tstring) => collect(&tstring.elements, &mut elements),
+                    TStringPart::Literal(_) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    elements
+}
+
+/// Mutable counterpart of [`interpolated_expressions`]
+pub fn interpolated_expressions_mut(expr: &mut Expr) -> Vec<&mut Expr> {
+    let mut elements = Vec::new();
+    match expr {
+        Expr::FString(fstring) => {
+            for part in fstring.value.iter_mut() {
+                if let FStringPart::FString(fstring) = part {
+                    collect_mut(&mut fstring.elements, &mut elements);
+                }
+            }
+        }
+        Expr::TString(tstring) => {
+            for part in tstring.value.iter_mut() {
+                match part {
+                    TStringPart::FString(fstring) => {
+                        collect_mut(&mut fstring.elements, &mut elements);
+                    }
+                    TStringPart::TString(tstring) => {
+                        collect_mut(&mut tstring.elements, &mut elements);
+                    }
+                    TStringPart::Literal(_) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    elements
+}
+
+fn collect<'a>(elements: &'a [InterpolatedStringElement], into: &mut Vec<&'a Expr>) {
+    for element in elements {
+        if let InterpolatedStringElement::Interpolation(InterpolatedElement {
+            expression,
+            format_spec,
+            ..
+        }) = element
+        {
+            into.push(expression);
+            if let Some(format_spec) = format_spec {
+                collect(&format_spec.elements, into);
+            }
+        }
+    }
+}
+
+fn collect_mut<'a>(elements: &'a mut [InterpolatedStringElement], into: &mut Vec<&'a mut Expr>) {
+    for element in elements {
+        if let InterpolatedStringElement::Interpolation(InterpolatedElement {
+            expression,
+            format_spec,
+            ..
+        }) = element
+        {
+            into.push(expression);
+            if let Some(format_spec) = format_spec {
+                collect_mut(&mut format_spec.elements, into);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_ast::Stmt;
+    use ruff_python_codegen::{Generator, Stylist};
+    use ruff_python_parser::parse_module;
+
+    fn rename_all(source: &str, from: &str, to: &str) -> String {
+        let parsed = parse_module(source).expect("valid source");
+        let stylist = Stylist::from_tokens(parsed.tokens(), source);
+        let mut module = parsed.into_syntax();
+        let Stmt::Expr(stmt) = &mut module.body[0] else {
+            panic!("expected an expression statement");
+        };
+        for expr in interpolated_expressions_mut(&mut stmt.value) {
+            if let Expr::Name(name) = expr {
+                if name.id.as_str() == from {
+                    name.id = to.into();
+                }
+            }
+        }
+        Generator::from(&stylist).stmt(&module.body[0])
+    }
+
+    #[test]
+    fn test_reaches_format_specs_and_keeps_concatenated_parts() {
+        assert_eq!(
+            rename_all("'a' f'{x:{x}}' f'{y}'\n", "x", "x_1"),
+            "'a' f'{x_1:{x_1}}' f'{y}'"
+        );
+    }
+
+    #[test]
+    fn test_collects_template_string_interpolations() {
+        let parsed = parse_module("t'{greeting}, {name!r:>{width}}'\n").expect("valid source");
+        let Stmt::Expr(stmt) = &parsed.syntax().body[0] else {
+            panic!("expected an expression statement");
+        };
+        let names: Vec<_> = interpolated_expressions(&stmt.value)
+            .into_iter()
+            .filter_map(|expr| expr.as_name_expr().map(|name| name.id.as_str()))
+            .collect();
+        assert_eq!(names, ["greeting", "name", "width"]);
+    }
+}
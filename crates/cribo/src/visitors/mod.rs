@@ -3,6 +3,22 @@
 //! This module contains visitor patterns for traversing Python AST nodes,
 //! enabling comprehensive import discovery and AST transformations.
 
+mod dynamic_exports;
+mod find_spec_probes;
+mod identifiers;
 mod import_discovery;
+mod import_guards;
+mod interpolations;
+mod module_attributes;
+mod module_name;
+mod type_checking;
 
+pub use dynamic_exports::DynamicExportVisitor;
+pub use find_spec_probes::FindSpecProbeVisitor;
+pub use identifiers::IdentifierVisitor;
 pub use import_discovery::{DiscoveredImport, ImportDiscoveryVisitor, ImportLocation};
+pub use import_guards::{GuardedImport, ImportGuardVisitor, handles_import_error};
+pub use interpolations::{interpolated_expressions, interpolated_expressions_mut};
+pub use module_attributes::{ModuleAttributeAccesses, ModuleAttributeVisitor};
+pub use module_name::ModuleNameRewriter;
+pub use type_checking::TypeCheckingGuards;
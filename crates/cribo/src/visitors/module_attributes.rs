@@ -0,0 +1,361 @@
+//! Detection of which attributes of bundled modules a module actually uses, such as
+//! `helper` in `utils.helper()` after `import utils` or in `from utils import helper`.
+//!
+//! Wrapper modules only need module-attribute assignments for these names. Modules whose
+//! object is used in ways that cannot be followed statically, like being passed around,
+//! star-imported or probed with `getattr`, are reported as escaping instead.
+
+use indexmap::{IndexMap, IndexSet};
+use ruff_python_ast::visitor::{Visitor, walk_expr, walk_stmt};
+use ruff_python_ast::{Expr, ExprContext, ModModule, Stmt, StmtImportFrom};
+
+/// Attribute uses of bundled modules by one module
+#[derive(Debug, Default)]
+pub struct ModuleAttributeAccesses {
+    /// Attribute names used per bundled module
+    pub accessed: IndexMap<String, IndexSet<String>>,
+    /// Bundled modules whose object is used other than through attribute access
+    pub escaped: IndexSet<String>,
+    /// Whether a module is looked up by a name computed at runtime, e.g. with
+    /// `importlib.import_module(name)`, which could be any bundled module
+    pub dynamic_lookup: bool,
+}
+
+/// Visitor that follows module objects bound by imports to their attribute accesses
+pub struct ModuleAttributeVisitor<'b> {
+    /// Dotted name of the module being visited
+    module_name: &'b str,
+    /// Whether the module being visited is a package's `__init__.py`
+    is_package: bool,
+    /// Names of all bundled modules
+    bundled_modules: &'b IndexSet<String>,
+    /// Local names bound to bundled module objects
+    module_bindings: IndexMap<String, String>,
+    accesses: ModuleAttributeAccesses,
+}
+
+impl<'b> ModuleAttributeVisitor<'b> {
+    pub fn new(
+        module_name: &'b str,
+        is_package: bool,
+        bundled_modules: &'b IndexSet<String>,
+    ) -> Self {
+        Self {
+            module_name,
+            is_package,
+            bundled_modules,
+            module_bindings: IndexMap::new(),
+            accesses: ModuleAttributeAccesses::default(),
+        }
+    }
+
+    /// Visit a module and return the attributes of bundled modules it uses
+    ///
+    /// Imports are collected first, so functions using a module imported further down
+    /// are followed too.
+    pub fn find_accesses(mut self, module: &ModModule) -> ModuleAttributeAccesses {
+        let mut imports = ImportCollector::default();
+        for stmt in &module.body {
+            imports.visit_stmt(stmt);
+        }
+        for import in imports.imports {
+            match import {
+                CollectedImport::Import { name, asname } => self.bind_import(name, asname),
+                CollectedImport::ImportFrom(import_from) => self.bind_import_from(import_from),
+            }
+        }
+
+        for stmt in &module.body {
+            self.visit_stmt(stmt);
+        }
+        self.accesses
+    }
+
+    fn bind_import(&mut self, name: &str, asname: Option<&str>) {
+        match asname {
+            Some(asname) => {
+                if self.bundled_modules.contains(name) {
+                    self.module_bindings
+                        .insert(asname.to_owned(), name.to_owned());
+                }
+            }
+            // `import a.b` binds `a`, from which `a.b` is reached by attribute access,
+            // even when `a` itself is not bundled
+            None => {
+                let top_level = name.split('.').next().unwrap_or(name);
+                self.module_bindings
+                    .insert(top_level.to_owned(), top_level.to_owned());
+            }
+        }
+    }
+
+    fn bind_import_from(&mut self, import_from: &StmtImportFrom) {
+        let Some(from_module) = self.resolve_from_module(import_from) else {
+            return;
+        };
+        for alias in &import_from.names {
+            let name = alias.name.as_str();
+            if name == "*" {
+                self.escape(&from_module);
+                continue;
+            }
+            let submodule = if from_module.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{}.{}", from_module, name)
+            };
+            if self.bundled_modules.contains(&submodule) {
+                let local_name = alias.asname.as_ref().unwrap_or(&alias.name);
+                self.module_bindings
+                    .insert(local_name.to_string(), submodule);
+            }
+            self.access(&from_module, name);
+        }
+    }
+
+    /// Absolute name of the module an import-from statement imports from
+    fn resolve_from_module(&self, import_from: &StmtImportFrom) -> Option<String> {
+        let module = import_from.module.as_ref().map(|module| module.as_str());
+        if import_from.level == 0 {
+            return module.map(str::to_owned);
+        }
+
+        // A package's own name is the base of its relative imports; a plain module's
+        // parent package is
+        let mut parts: Vec<&str> = self.module_name.split('.').collect();
+        let levels_up = if self.is_package {
+            import_from.level - 1
+        } else {
+            import_from.level
+        };
+        for _ in 0..levels_up {
+            parts.pop()?;
+        }
+        if let Some(module) = module {
+            parts.push(module);
+        }
+        Some(parts.join("."))
+    }
+
+    fn access(&mut self, module: &str, attribute: &str) {
+        if self.bundled_modules.contains(module) {
+            self.accesses
+                .accessed
+                .entry(module.to_owned())
+                .or_default()
+                .insert(attribute.to_owned());
+        }
+    }
+
+    fn escape(&mut self, module: &str) {
+        if self.bundled_modules.contains(module) {
+            self.accesses.escaped.insert(module.to_owned());
+        }
+    }
+
+    /// Modules looked up by name with `importlib.import_module`, `__import__` or
+    /// `sys.modules[...]` escape, since their object is then used in unknown ways
+    fn check_lookup_by_name(&mut self, expr: &Expr) {
+        let name_argument = match expr {
+            Expr::Call(call) => {
+                let is_import_call = match call.func.as_ref() {
+                    Expr::Name(name) => {
+                        matches!(name.id.as_str(), "import_module" | "__import__")
+                    }
+                    Expr::Attribute(attribute) => attribute.attr.as_str() == "import_module",
+                    _ => false,
+                };
+                if !is_import_call {
+                    return;
+                }
+                call.arguments.args.first()
+            }
+            Expr::Subscript(subscript) => {
+                let is_sys_modules = matches!(
+                    subscript.value.as_ref(),
+                    Expr::Attribute(attribute) if attribute.attr.as_str() == "modules"
+                        && matches!(attribute.value.as_ref(), Expr::Name(name) if name.id.as_str() == "sys")
+                );
+                if !is_sys_modules {
+                    return;
+                }
+                Some(subscript.slice.as_ref())
+            }
+            _ => return,
+        };
+        match name_argument {
+            Some(Expr::StringLiteral(literal)) => {
+                let name = literal.value.to_str().to_owned();
+                self.escape(&name);
+            }
+            // `sys.modules[__name__]` is the module itself
+            Some(Expr::Name(name)) if name.id.as_str() == "__name__" => {}
+            _ => self.accesses.dynamic_lookup = true,
+        }
+    }
+
+    /// Follow an attribute chain rooted at a name bound to a module, such as
+    /// `pkg.sub.helper`, and record the first attribute that is not a submodule
+    ///
+    /// Returns whether the chain was rooted at a module binding.
+    fn record_attribute_chain(&mut self, expr: &Expr) -> bool {
+        let mut attributes = Vec::new();
+        let mut current = expr;
+        while let Expr::Attribute(attribute) = current {
+            attributes.push(attribute.attr.as_str());
+            current = &attribute.value;
+        }
+        let Expr::Name(base) = current else {
+            return false;
+        };
+        let Some(mut module) = self.module_bindings.get(base.id.as_str()).cloned() else {
+            return false;
+        };
+
+        for attribute in attributes.into_iter().rev() {
+            let submodule = format!("{}.{}", module, attribute);
+            if self.bundled_modules.contains(&submodule) {
+                self.access(&module, attribute);
+                module = submodule;
+            } else {
+                self.access(&module, attribute);
+                return true;
+            }
+        }
+
+        // The chain ends at a module object, which may be used in any way from here
+        self.escape(&module);
+        true
+    }
+}
+
+impl<'a> Visitor<'a> for ModuleAttributeVisitor<'_> {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            // Already handled when collecting the module bindings
+            Stmt::Import(_) | Stmt::ImportFrom(_) => {}
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        match expr {
+            Expr::Attribute(attribute) if attribute.ctx == ExprContext::Load => {
+                if !self.record_attribute_chain(expr) {
+                    walk_expr(self, expr);
+                }
+            }
+            Expr::Name(name) if name.ctx == ExprContext::Load => {
+                if let Some(module) = self.module_bindings.get(name.id.as_str()).cloned() {
+                    self.escape(&module);
+                }
+            }
+            _ => {
+                self.check_lookup_by_name(expr);
+                walk_expr(self, expr);
+            }
+        }
+    }
+}
+
+enum CollectedImport<'a> {
+    Import {
+        name: &'a str,
+        asname: Option<&'a str>,
+    },
+    ImportFrom(&'a StmtImportFrom),
+}
+
+/// Collects the import statements of a module at any depth
+#[derive(Default)]
+struct ImportCollector<'a> {
+    imports: Vec<CollectedImport<'a>>,
+}
+
+impl<'a> Visitor<'a> for ImportCollector<'a> {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::Import(import) => {
+                for alias in &import.names {
+                    self.imports.push(CollectedImport::Import {
+                        name: alias.name.as_str(),
+                        asname: alias.asname.as_ref().map(|asname| asname.as_str()),
+                    });
+                }
+            }
+            Stmt::ImportFrom(import_from) => {
+                self.imports.push(CollectedImport::ImportFrom(import_from));
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_parser::parse_module;
+
+    fn accesses(source: &str, module_name: &str) -> ModuleAttributeAccesses {
+        let bundled: IndexSet<String> = ["utils", "pkg", "pkg.sub", "pkg.other", "app.helpers"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        let parsed = parse_module(source).expect("valid source");
+        ModuleAttributeVisitor::new(module_name, false, &bundled).find_accesses(parsed.syntax())
+    }
+
+    #[test]
+    fn test_collects_attribute_accesses_on_imported_modules() {
+        let result = accesses(
+            "def run():\n    return utils.helper() + u.VALUE + pkg.sub.compute()\n\nimport utils\nimport utils as u\nimport pkg.sub\nfrom . import helpers\nfrom pkg import other\n\nhelpers.fmt(other.NAME)\n",
+            "app.main",
+        );
+
+        assert_eq!(
+            result.accessed["utils"],
+            IndexSet::from(["helper".to_owned(), "VALUE".to_owned()])
+        );
+        assert_eq!(
+            result.accessed["pkg.sub"],
+            IndexSet::from(["compute".to_owned()])
+        );
+        assert_eq!(
+            result.accessed["app.helpers"],
+            IndexSet::from(["fmt".to_owned()])
+        );
+        assert_eq!(
+            result.accessed["pkg.other"],
+            IndexSet::from(["NAME".to_owned()])
+        );
+        assert!(result.escaped.is_empty(), "{:?}", result.escaped);
+    }
+
+    #[test]
+    fn test_reports_modules_used_as_objects() {
+        let result = accesses(
+            "import utils\nimport pkg.sub\nfrom pkg.other import *\n\nregister(utils)\nmodule = pkg.sub\nhelpers = importlib.import_module(\"app.helpers\")\n",
+            "main",
+        );
+
+        assert_eq!(
+            result.escaped,
+            IndexSet::from([
+                "pkg.other".to_owned(),
+                "utils".to_owned(),
+                "pkg.sub".to_owned(),
+                "app.helpers".to_owned()
+            ])
+        );
+        assert!(!result.dynamic_lookup);
+
+        let result = accesses(
+            "import sys
+
+plugin = sys.modules[name]
+",
+            "main",
+        );
+        assert!(result.dynamic_lookup);
+    }
+}
@@ -0,0 +1,89 @@
+//! Rewrites that keep `__name__`-based idioms working in bundled modules.
+//!
+//! Code of bundled modules no longer runs in its own module namespace, so `__name__`
+//! evaluates to the name of the bundle (usually `__main__`) instead of the original
+//! dotted module name.
+
+use ruff_python_ast::visitor::transformer::{Transformer, walk_expr};
+use ruff_python_ast::visitor::{Visitor, walk_expr as walk_expr_ref};
+use ruff_python_ast::{
+    Expr, ExprContext, ExprStringLiteral, ModModule, StringLiteral, StringLiteralFlags,
+    StringLiteralValue,
+};
+use ruff_text_size::TextRange;
+
+/// Replaces reads of `__name__` with the original module name, so loggers, cache keys,
+/// registries and `if __name__ == "__main__":` checks see the value they had before
+/// bundling
+pub struct ModuleNameRewriter<'a> {
+    module_name: &'a str,
+}
+
+impl<'a> ModuleNameRewriter<'a> {
+    pub fn new(module_name: &'a str) -> Self {
+        Self { module_name }
+    }
+
+    /// Substitute every read of `__name__` in `module`
+    ///
+    /// Modules that rebind `__name__` themselves are left untouched.
+    pub fn rewrite_module(&self, module: &mut ModModule) {
+        if rebinds_dunder_name(module) {
+            log::debug!(
+                "Module '{}' rebinds __name__; leaving its reads as they are",
+                self.module_name
+            );
+            return;
+        }
+        for stmt in &mut module.body {
+            self.visit_stmt(stmt);
+        }
+    }
+}
+
+impl Transformer for ModuleNameRewriter<'_> {
+    fn visit_expr(&self, expr: &mut Expr) {
+        if matches!(expr, Expr::Name(name) if name.id.as_str() == "__name__" && name.ctx == ExprContext::Load)
+        {
+            *expr = module_name_literal(self.module_name);
+            return;
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Whether `module` assigns or deletes `__name__` anywhere
+fn rebinds_dunder_name(module: &ModModule) -> bool {
+    #[derive(Default)]
+    struct RebindFinder {
+        found: bool,
+    }
+
+    impl<'a> Visitor<'a> for RebindFinder {
+        fn visit_expr(&mut self, expr: &'a Expr) {
+            if matches!(expr, Expr::Name(name) if name.id.as_str() == "__name__" && name.ctx != ExprContext::Load)
+            {
+                self.found = true;
+            }
+            walk_expr_ref(self, expr);
+        }
+    }
+
+    let mut finder = RebindFinder::default();
+    for stmt in &module.body {
+        finder.visit_stmt(stmt);
+    }
+    finder.found
+}
+
+/// A string literal holding `module_name`
+fn module_name_literal(module_name: &str) -> Expr {
+    Expr::StringLiteral(ExprStringLiteral {
+        value: StringLiteralValue::single(StringLiteral {
+            value: Box::from(module_name),
+            range: TextRange::default(),
+            flags: StringLiteralFlags::empty(),
+        }),
+        range: TextRange::default(),
+    })
+}
@@ -0,0 +1,120 @@
+//! Recognition of `if TYPE_CHECKING:` guards, including aliased spellings such as
+//! `from typing import TYPE_CHECKING as TC` or `import typing as t` with
+//! `if t.TYPE_CHECKING:`.
+//!
+//! Code under such a guard only runs for type checkers, so imports there are not
+//! runtime dependencies.
+
+use indexmap::IndexSet;
+use ruff_python_ast::{Expr, ModModule, Stmt};
+
+/// Modules that export the `TYPE_CHECKING` constant
+const TYPING_MODULES: &[&str] = &["typing", "typing_extensions"];
+
+/// The names under which a module can refer to `TYPE_CHECKING`
+#[derive(Debug, Clone)]
+pub struct TypeCheckingGuards {
+    /// Local names bound to the `TYPE_CHECKING` constant itself
+    constant_names: IndexSet<String>,
+    /// Local names bound to a module exporting `TYPE_CHECKING`
+    module_names: IndexSet<String>,
+}
+
+impl Default for TypeCheckingGuards {
+    /// Guards recognized without looking at a module's imports: the bare name and the
+    /// fully qualified attributes
+    fn default() -> Self {
+        Self {
+            constant_names: IndexSet::from(["TYPE_CHECKING".to_owned()]),
+            module_names: TYPING_MODULES
+                .iter()
+                .map(|module| (*module).to_owned())
+                .collect(),
+        }
+    }
+}
+
+impl TypeCheckingGuards {
+    /// Collect the aliases a module binds at its top level for `TYPE_CHECKING` and the
+    /// modules that export it
+    pub fn from_module(module: &ModModule) -> Self {
+        let mut guards = Self::default();
+        for stmt in &module.body {
+            match stmt {
+                Stmt::Import(import) => {
+                    for alias in &import.names {
+                        if let Some(asname) = &alias.asname {
+                            if TYPING_MODULES.contains(&alias.name.as_str()) {
+                                guards.module_names.insert(asname.to_string());
+                            }
+                        }
+                    }
+                }
+                Stmt::ImportFrom(import_from) if import_from.level == 0 => {
+                    let Some(module_name) = &import_from.module else {
+                        continue;
+                    };
+                    if !TYPING_MODULES.contains(&module_name.as_str()) {
+                        continue;
+                    }
+                    for alias in &import_from.names {
+                        if alias.name.as_str() == "TYPE_CHECKING" {
+                            let local_name = alias.asname.as_ref().unwrap_or(&alias.name);
+                            guards.constant_names.insert(local_name.to_string());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        guards
+    }
+
+    /// Whether `test` is a `TYPE_CHECKING` guard condition, such as `TYPE_CHECKING`,
+    /// `TC` or `t.TYPE_CHECKING`
+    pub fn is_guard(&self, test: &Expr) -> bool {
+        match test {
+            Expr::Name(name) => self.constant_names.contains(name.id.as_str()),
+            Expr::Attribute(attribute) => {
+                attribute.attr.as_str() == "TYPE_CHECKING"
+                    && matches!(
+                        attribute.value.as_ref(),
+                        Expr::Name(module) if self.module_names.contains(module.id.as_str())
+                    )
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ruff_python_parser::parse_module;
+
+    fn guard_tests(source: &str) -> Vec<bool> {
+        let parsed = parse_module(source).expect("valid source");
+        let guards = TypeCheckingGuards::from_module(parsed.syntax());
+        parsed
+            .syntax()
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::If(if_stmt) => Some(guards.is_guard(&if_stmt.test)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_recognizes_aliased_guards() {
+        let source = "from typing import TYPE_CHECKING as TC\nimport typing as t\nimport typing_extensions as te\n\nif TC:\n    pass\nif t.TYPE_CHECKING:\n    pass\nif te.TYPE_CHECKING:\n    pass\nif TYPE_CHECKING:\n    pass\nif typing.TYPE_CHECKING:\n    pass\n";
+        assert_eq!(guard_tests(source), vec![true, true, true, true, true]);
+    }
+
+    #[test]
+    fn test_ignores_other_conditions() {
+        let source = "import os as t\nfrom config import TYPE_CHECKING as TC\n\nif TC:\n    pass\nif t.TYPE_CHECKING:\n    pass\nif DEBUG:\n    pass\n";
+        assert_eq!(guard_tests(source), vec![false, false, false]);
+    }
+}
@@ -0,0 +1,222 @@
+//! Building the named bundles of a workspace, declared as `[[bundles]]` in the
+//! configuration or given as several entries on the command line, in one run
+//!
+//! Bundles are built concurrently and share one module cache, so modules they have in
+//! common are parsed once.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use indexmap::{IndexMap, IndexSet};
+use log::{error, info};
+
+use crate::config::{Config, is_module_entry};
+use crate::error::{CriboError, CriboResult};
+use crate::metrics::{MetricsHandle, NoopMetrics};
+use crate::module_cache::{ModuleCache, ModuleCacheHandle};
+use crate::orchestrator::BundleOrchestrator;
+use crate::report::OrphanModule;
+
+/// One selected bundle with its effective configuration
+#[derive(Debug, Clone)]
+pub struct WorkspaceBundle {
+    pub name: String,
+    pub entry: PathBuf,
+    pub output: PathBuf,
+    pub config: Config,
+}
+
+/// Bundles selected from a workspace configuration
+#[derive(Debug)]
+pub struct Workspace {
+    bundles: Vec<WorkspaceBundle>,
+    module_cache: ModuleCacheHandle,
+    metrics: MetricsHandle,
+    /// Maximum number of bundles built at the same time; defaults to the number of cores
+    jobs: Option<usize>,
+}
+
+impl Workspace {
+    /// Select the bundles named in `only`, or all configured bundles when it is empty
+    ///
+    /// Fails before anything is built if a name is unknown, a bundle's overrides are
+    /// invalid, or its entry cannot be found.
+    pub fn from_config(config: &Config, only: &[String]) -> CriboResult<Self> {
+        let bundles = config
+            .select_bundles(only)?
+            .into_iter()
+            .map(|target| {
+                let bundle_config = config.for_bundle(target)?;
+                let entry = bundle_config
+                    .entry_path()?
+                    .ok_or_else(|| CriboError::Config {
+                        path: None,
+                        message: format!("Bundle '{}' has no entry", target.name),
+                    })?;
+                Ok(WorkspaceBundle {
+                    name: target.name.clone(),
+                    entry,
+                    output: target.output.clone(),
+                    config: bundle_config,
+                })
+            })
+            .collect::<CriboResult<Vec<_>>>()?;
+
+        Ok(Self {
+            bundles,
+            module_cache: Arc::new(ModuleCache::new()),
+            metrics: Arc::new(NoopMetrics),
+            jobs: None,
+        })
+    }
+
+    /// One bundle per entry, written to `output_dir` under the entry's file stem (the
+    /// directory name for a directory, the last part of a dotted module name), all with
+    /// the same configuration
+    ///
+    /// Fails if two entries would be written to the same file.
+    pub fn from_entries(
+        config: &Config,
+        entries: &[PathBuf],
+        output_dir: &Path,
+    ) -> CriboResult<Self> {
+        let mut bundles: Vec<WorkspaceBundle> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let entry_name = entry.to_string_lossy();
+            let name = if is_module_entry(&entry_name) {
+                entry_name.rsplit('.').next()
+            } else {
+                entry.file_stem().and_then(|stem| stem.to_str())
+            }
+            .ok_or_else(|| CriboError::Config {
+                path: None,
+                message: format!("Cannot name a bundle after entry {:?}", entry),
+            })?
+            .to_owned();
+            let entry = config.resolve_entry(&entry_name)?;
+            if let Some(other) = bundles.iter().find(|bundle| bundle.name == name) {
+                return Err(CriboError::Config {
+                    path: None,
+                    message: format!(
+                        "Entries {:?} and {:?} would both be bundled to {:?}",
+                        other.entry, entry, other.output
+                    ),
+                });
+            }
+            bundles.push(WorkspaceBundle {
+                output: output_dir.join(format!("{}.py", name)),
+                name,
+                entry,
+                config: config.clone(),
+            });
+        }
+
+        Ok(Self {
+            bundles,
+            module_cache: Arc::new(ModuleCache::new()),
+            metrics: Arc::new(NoopMetrics),
+            jobs: None,
+        })
+    }
+
+    /// Report the progress of every bundle to `metrics`
+    pub fn with_metrics(mut self, metrics: MetricsHandle) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Build at most `jobs` bundles at the same time
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs.max(1));
+        self
+    }
+
+    pub fn bundles(&self) -> &[WorkspaceBundle] {
+        &self.bundles
+    }
+
+    /// Cache of parsed modules shared by all bundles
+    pub fn module_cache(&self) -> &ModuleCacheHandle {
+        &self.module_cache
+    }
+
+    /// Build every selected bundle, several at a time
+    ///
+    /// A failing bundle does not stop the others; once all of them are done, the first
+    /// failure in configuration order is returned.
+    pub fn bundle_all(&self, emit_requirements: bool) -> CriboResult<()> {
+        let build = |bundle: &WorkspaceBundle| self.bundle_one(bundle, emit_requirements);
+
+        let worker_count = self
+            .jobs
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(std::num::NonZeroUsize::get)
+                    .unwrap_or(1)
+            })
+            .min(self.bundles.len());
+        if worker_count <= 1 {
+            return self.bundles.iter().map(build).collect();
+        }
+
+        let chunk_size = self.bundles.len().div_ceil(worker_count);
+        // Keep routing worker thread logs to the caller's log sink
+        let log_sink = crate::logging::current_sink();
+        let results: Vec<CriboResult<()>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .bundles
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let log_sink = log_sink.clone();
+                    scope.spawn(move || {
+                        let _log_scope = crate::logging::SinkScope::enter(log_sink);
+                        chunk.iter().map(build).collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("bundle thread panicked"))
+                .collect()
+        });
+        results.into_iter().collect()
+    }
+
+    /// First-party modules under the source roots of any selected bundle that no
+    /// selected bundle includes
+    pub fn orphans(&self) -> CriboResult<Vec<OrphanModule>> {
+        let mut candidates = IndexMap::new();
+        let mut bundled = IndexSet::new();
+        for bundle in &self.bundles {
+            let outcome = BundleOrchestrator::new(bundle.config.clone())
+                .with_module_cache(Arc::clone(&self.module_cache))
+                .bundle_outcome(&bundle.entry)
+                .inspect_err(|_| error!("Bundle '{}' failed", bundle.name))?;
+            bundled.extend(outcome.modules.into_iter().map(|(_, path)| path));
+            for orphan in outcome.orphans {
+                candidates.entry(orphan.path.clone()).or_insert(orphan);
+            }
+        }
+
+        let mut orphans: Vec<OrphanModule> = candidates
+            .into_values()
+            .filter(|orphan| !bundled.contains(&orphan.path))
+            .collect();
+        orphans.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(orphans)
+    }
+
+    fn bundle_one(&self, bundle: &WorkspaceBundle, emit_requirements: bool) -> CriboResult<()> {
+        info!("Building bundle '{}'", bundle.name);
+        BundleOrchestrator::new(bundle.config.clone())
+            .with_module_cache(Arc::clone(&self.module_cache))
+            .with_metrics(Arc::clone(&self.metrics))
+            .bundle(&bundle.entry, &bundle.output, emit_requirements)
+            .inspect_err(|_| error!("Bundle '{}' failed", bundle.name))?;
+        info!(
+            "Bundle '{}' created successfully at {:?}",
+            bundle.name, bundle.output
+        );
+        Ok(())
+    }
+}
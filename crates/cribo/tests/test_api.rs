@@ -0,0 +1,163 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::api::{self, BundleRequest};
+use std::path::Path;
+
+#[test]
+fn test_api_bundle_reports_modules_and_code() {
+    let entry =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bundling/simple_math/main.py");
+    let request = BundleRequest::new(&entry);
+
+    let result = api::bundle(&request).expect("simple_math should bundle");
+
+    let module_names: Vec<_> = result
+        .modules
+        .iter()
+        .map(|module| module.name.as_str())
+        .collect();
+    assert!(module_names.contains(&"calculator"));
+    assert!(module_names.contains(&"utils"));
+    assert_eq!(module_names.last(), Some(&"main"));
+    assert!(result.code.contains("def add"));
+    assert!(result.requirements.is_empty());
+    assert!(result.diagnostics.resolved_cycles.is_empty());
+    assert!(result.diagnostics.first_party_imports >= 2);
+}
+
+#[test]
+fn test_api_graph_lists_modules_with_dependencies() {
+    let entry =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bundling/simple_math/main.py");
+    let request = BundleRequest::new(&entry);
+
+    api::check(&request).expect("simple_math should pass the check");
+    let modules = api::graph(&request).expect("simple_math graph should build");
+
+    let main = modules
+        .iter()
+        .find(|module| module.name == "main")
+        .expect("entry module should be in the graph");
+    assert!(main.dependencies.contains(&"calculator".to_string()));
+    assert!(main.dependencies.contains(&"utils".to_string()));
+}
+
+#[test]
+fn test_api_check_rejects_unresolvable_cycles() {
+    let entry = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/bundling/xfail_class_level_cycles/main.py");
+
+    assert!(api::check(&BundleRequest::new(&entry)).is_err());
+}
+
+/// Treats everything under `corp` as external and leaves `legacy` alone
+struct CorpPolicy;
+
+impl api::ImportClassifier for CorpPolicy {
+    fn classify(&self, module_name: &str) -> Option<api::ImportType> {
+        if module_name == "corp" || module_name.starts_with("corp.") {
+            Some(api::ImportType::ThirdParty)
+        } else if module_name == "legacy" {
+            Some(api::ImportType::Ignored)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_api_import_classifier_overrides_builtin_rules() {
+    let project_dir = tempfile::TempDir::new().expect("create project dir");
+    let corp_dir = project_dir.path().join("corp");
+    std::fs::create_dir(&corp_dir).expect("create corp package");
+    std::fs::write(corp_dir.join("__init__.py"), "").expect("write corp/__init__");
+    std::fs::write(corp_dir.join("tools.py"), "def tool():\n    return 1\n")
+        .expect("write corp/tools");
+    std::fs::write(
+        project_dir.path().join("legacy.py"),
+        "def old():\n    return 2\n",
+    )
+    .expect("write legacy");
+    std::fs::write(
+        project_dir.path().join("helpers.py"),
+        "def helper():\n    return 3\n",
+    )
+    .expect("write helpers");
+    let entry = project_dir.path().join("main.py");
+    std::fs::write(
+        &entry,
+        "import corp.tools\nimport legacy\nfrom helpers import helper\n\nprint(corp.tools.tool(), legacy.old(), helper())\n",
+    )
+    .expect("write entry");
+
+    let request =
+        BundleRequest::new(&entry).with_import_classifier(std::sync::Arc::new(CorpPolicy));
+    let result = api::bundle(&request).expect("bundling should succeed");
+
+    let module_names: Vec<_> = result
+        .modules
+        .iter()
+        .map(|module| module.name.as_str())
+        .collect();
+    assert_eq!(module_names, ["helpers", "main"]);
+    assert_eq!(result.requirements, ["corp"]);
+    assert_eq!(result.diagnostics.ignored_imports, 1);
+    assert!(result.code.contains("import corp.tools"));
+    assert!(result.code.contains("import legacy"));
+    assert!(!result.code.contains("def old"));
+}
+
+#[test]
+fn test_api_resolve_reports_kind_path_and_root() {
+    let project_dir = tempfile::TempDir::new().expect("create project dir");
+    let root = project_dir.path().canonicalize().expect("canonical root");
+    std::fs::create_dir_all(root.join("pkg")).expect("create pkg");
+    std::fs::create_dir_all(root.join("ns")).expect("create ns");
+    std::fs::write(root.join("pkg/__init__.py"), "").expect("write pkg init");
+    std::fs::write(root.join("pkg/mod.py"), "VALUE = 1\n").expect("write pkg.mod");
+    std::fs::write(root.join("ns/sub.py"), "VALUE = 2\n").expect("write ns.sub");
+    let entry = root.join("main.py");
+    std::fs::write(&entry, "import pkg.mod\n").expect("write entry");
+    let request = BundleRequest::new(&entry);
+
+    let module = api::resolve(&request, "pkg.mod").expect("resolve pkg.mod");
+    assert_eq!(module.kind, api::ResolutionKind::FirstParty);
+    assert_eq!(module.path, Some(root.join("pkg/mod.py")));
+    assert_eq!(module.origin_root.as_deref(), Some(root.as_path()));
+
+    let namespace = api::resolve(&request, "ns").expect("resolve ns");
+    assert_eq!(namespace.kind, api::ResolutionKind::Namespace);
+    assert_eq!(namespace.path, None);
+    assert_eq!(namespace.origin_root.as_deref(), Some(root.as_path()));
+    assert_eq!(namespace.namespace_portions, [root.join("ns")]);
+
+    let stdlib = api::resolve(&request, "json").expect("resolve json");
+    assert_eq!(stdlib.kind, api::ResolutionKind::Stdlib);
+    assert_eq!(stdlib.origin_root, None);
+
+    let third_party = api::resolve(&request, "requests").expect("resolve requests");
+    assert_eq!(third_party.kind, api::ResolutionKind::ThirdParty);
+}
+
+#[test]
+fn test_api_bundle_to_file_writes_bundle_and_requirements() {
+    let project_dir = tempfile::TempDir::new().expect("create project dir");
+    let entry = project_dir.path().join("main.py");
+    std::fs::write(&entry, "import requests\n\nprint(requests.__name__)\n").expect("write entry");
+    let output = project_dir.path().join("dist/bundle.py");
+    std::fs::create_dir(project_dir.path().join("dist")).expect("create dist");
+
+    let result = api::bundle_to_file(&BundleRequest::new(&entry), &output, true)
+        .expect("bundling should succeed");
+
+    assert_eq!(
+        std::fs::read_to_string(&output).expect("read bundle"),
+        result.code
+    );
+    assert_eq!(result.requirements, ["requests"]);
+    assert_eq!(
+        std::fs::read_to_string(project_dir.path().join("dist/requirements.txt"))
+            .expect("read requirements"),
+        "requests"
+    );
+}
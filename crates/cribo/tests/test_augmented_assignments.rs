@@ -0,0 +1,75 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Bundle the project's `main.py`, run the bundle away from the sources and return
+/// the bundle and its stdout
+fn bundle_and_run(project_dir: &TempDir) -> (String, String) {
+    let entry = project_dir.path().join("main.py");
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    (bundled, stdout)
+}
+
+#[test]
+fn test_accumulating_module_is_inlined_with_renames() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("plugins.py"),
+        "NAMES = [\"core\"]\nNAMES += [\"extra\"]\nCOUNT = 1\nCOUNT += 1\n",
+    )
+    .expect("write plugins");
+    fs::write(
+        project_dir.path().join("limits.py"),
+        "COUNT = 10\nCOUNT *= 3\n",
+    )
+    .expect("write limits");
+    fs::write(
+        project_dir.path().join("main.py"),
+        "from plugins import NAMES, COUNT\nfrom limits import COUNT as LIMIT\n\nprint(NAMES, COUNT, LIMIT)\n",
+    )
+    .expect("write entry");
+
+    let (bundled, stdout) = bundle_and_run(&project_dir);
+    assert!(!bundled.contains("def __cribo_init_"), "{bundled}");
+    assert_eq!(stdout, "['core', 'extra'] 2 30");
+}
+
+#[test]
+fn test_wrapper_module_exports_augmented_value() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("counter.py"),
+        "print(\"loading counter\")\n\nTOTAL = 1\nTOTAL += 41\nLABEL = \"total\"\nLABEL += \":\"\n",
+    )
+    .expect("write counter");
+    fs::write(
+        project_dir.path().join("main.py"),
+        "import counter\n\nprint(counter.LABEL, counter.TOTAL)\n",
+    )
+    .expect("write entry");
+
+    let (_, stdout) = bundle_and_run(&project_dir);
+    assert_eq!(stdout, "loading counter\ntotal: 42");
+}
@@ -0,0 +1,76 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use tempfile::TempDir;
+
+fn write_project(project_dir: &TempDir, greeting: &str) -> std::path::PathBuf {
+    fs::write(
+        project_dir.path().join("greet.py"),
+        format!("def greet(name):\n    return \"{greeting}, \" + name\n"),
+    )
+    .expect("write greet");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from greet import greet\n\nprint(greet(\"world\"))\n",
+    )
+    .expect("write entry");
+    entry
+}
+
+#[test]
+fn test_diff_attributes_changes_to_modules() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let output_dir = TempDir::new().expect("create output dir");
+    let output = output_dir.path().join("app.py");
+
+    let entry = write_project(&project_dir, "Hello");
+    BundleOrchestrator::new(Config::default())
+        .bundle(&entry, &output, false)
+        .expect("bundling should succeed");
+    let before = fs::read_to_string(&output).expect("read bundle");
+
+    // An unchanged project produces no diff
+    let diff = BundleOrchestrator::new(Config::default())
+        .diff(&entry, &output)
+        .expect("diff should succeed");
+    assert!(diff.is_empty(), "unexpected diff:\n{diff}");
+
+    write_project(&project_dir, "Goodbye");
+    let diff = BundleOrchestrator::new(Config::default())
+        .diff(&entry, &output)
+        .expect("diff should succeed");
+
+    assert!(diff.starts_with("--- "), "unexpected diff:\n{diff}");
+    assert!(
+        diff.contains(" greet (inlined)\n"),
+        "unexpected diff:\n{diff}"
+    );
+    assert!(diff.contains("-    return \"Hello, \" + name\n"));
+    assert!(diff.contains("+    return \"Goodbye, \" + name\n"));
+    assert!(!diff.contains("main (entry)"), "unexpected diff:\n{diff}");
+
+    // Diffing never touches the existing bundle
+    assert_eq!(fs::read_to_string(&output).expect("read bundle"), before);
+}
+
+#[test]
+fn test_diff_against_missing_bundle_adds_everything() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let output_dir = TempDir::new().expect("create output dir");
+    let output = output_dir.path().join("app.py");
+    let entry = write_project(&project_dir, "Hello");
+
+    let diff = BundleOrchestrator::new(Config::default())
+        .diff(&entry, &output)
+        .expect("diff should succeed");
+
+    assert!(diff.contains("+#!/usr/bin/env python3\n"));
+    assert!(
+        diff.contains("greet (inlined), main (entry)"),
+        "unexpected diff:\n{diff}"
+    );
+    assert!(!output.exists());
+}
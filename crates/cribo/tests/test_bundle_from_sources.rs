@@ -0,0 +1,73 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::module_map::module_map_path;
+use cribo::orchestrator::BundleOrchestrator;
+use indexmap::IndexMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_bundle_from_sources_without_files() {
+    let sources = IndexMap::from([
+        (
+            "main.py",
+            "from app.greetings import greet\n\nprint(greet(\"world\"))\n",
+        ),
+        ("app/__init__.py", ""),
+        (
+            "app/greetings.py",
+            "def greet(name):\n    return f\"Hello, {name}\"\n",
+        ),
+    ]);
+    let config = Config {
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    let bundler = BundleOrchestrator::new(config);
+    let bundled = bundler
+        .bundle_from_sources(&sources, "main.py")
+        .expect("bundle from sources");
+    assert!(bundled.contains("def greet"), "{bundled}");
+
+    let project_dir = TempDir::new().expect("create project dir");
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Hello, world\n");
+
+    let error = bundler
+        .bundle_from_sources([("main.py", "print(1)\n")], "other.py")
+        .expect_err("the entry must be one of the sources");
+    assert!(error.to_string().contains("entry"), "{error}");
+    assert!(
+        bundler
+            .bundle_from_sources([("../main.py", "print(1)\n")], "../main.py")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_bundle_from_sources_writes_no_artifacts() {
+    let config = Config {
+        respect_pythonpath: false,
+        module_map: true,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_from_sources([("main.py", "print(1)\n")], "main.py")
+        .expect("bundle from sources");
+    assert!(bundled.contains("print(1)"), "{bundled}");
+    // The stdout bundle's module map would land in the working directory
+    assert!(!module_map_path(Path::new("bundle.py")).exists());
+}
@@ -0,0 +1,116 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use cribo::plugin::{BundlePlugin, PluginModule};
+use ruff_python_ast::{ModModule, Stmt};
+use std::fs;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+/// Fills in a version placeholder, drops module-level asserts and records every hook
+#[derive(Default)]
+struct ReleasePlugin {
+    calls: Mutex<Vec<String>>,
+}
+
+impl BundlePlugin for ReleasePlugin {
+    fn name(&self) -> &str {
+        "release"
+    }
+
+    fn pre_parse(&self, _module: &PluginModule<'_>, source: &mut String) -> anyhow::Result<()> {
+        *source = source.replace("@VERSION@", "1.2.3");
+        Ok(())
+    }
+
+    fn post_resolve(&self, module: &PluginModule<'_>, ast: &mut ModModule) -> anyhow::Result<()> {
+        if let Some(name) = module.name {
+            self.calls
+                .lock()
+                .expect("lock")
+                .push(format!("resolved {name}"));
+        }
+        ast.body.retain(|stmt| !matches!(stmt, Stmt::Assert(_)));
+        Ok(())
+    }
+
+    fn pre_emit(&self, bundle: &mut ModModule) -> anyhow::Result<()> {
+        assert!(!bundle.body.is_empty());
+        self.calls.lock().expect("lock").push("emitted".to_owned());
+        Ok(())
+    }
+}
+
+struct FailingPlugin;
+
+impl BundlePlugin for FailingPlugin {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    fn post_resolve(&self, _module: &PluginModule<'_>, _ast: &mut ModModule) -> anyhow::Result<()> {
+        anyhow::bail!("refusing to transform")
+    }
+}
+
+fn write_project(dir: &std::path::Path) -> std::path::PathBuf {
+    fs::write(
+        dir.join("about.py"),
+        "VERSION = \"@VERSION@\"\nassert False, \"debug builds only\"\n",
+    )
+    .expect("write about");
+    let entry = dir.join("main.py");
+    fs::write(&entry, "from about import VERSION\n\nprint(VERSION)\n").expect("write entry");
+    entry
+}
+
+#[test]
+fn test_plugin_hooks_transform_the_bundle() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(project_dir.path());
+
+    let plugin = Arc::new(ReleasePlugin::default());
+    let bundled = BundleOrchestrator::new(Config::default())
+        .with_plugin(plugin.clone())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    assert!(bundled.contains("1.2.3"), "{bundled}");
+    assert!(!bundled.contains("assert"), "{bundled}");
+    let calls = plugin.calls.lock().expect("lock").clone();
+    assert!(calls.contains(&"resolved about".to_owned()), "{calls:?}");
+    assert_eq!(
+        calls.last().map(String::as_str),
+        Some("emitted"),
+        "{calls:?}"
+    );
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "1.2.3");
+}
+
+#[test]
+fn test_failing_plugin_aborts_the_bundle() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(project_dir.path());
+
+    let err = BundleOrchestrator::new(Config::default())
+        .with_plugin(Arc::new(FailingPlugin))
+        .bundle_to_string(&entry, false)
+        .expect_err("a failing hook should abort bundling");
+    assert_eq!(err.to_string(), "Plugin `failing` failed in post_resolve");
+}
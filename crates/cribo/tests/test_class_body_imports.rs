@@ -0,0 +1,52 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_class_body_imports_are_bundled() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("constants.py"),
+        "GREETING = \"hello\"\n",
+    )
+    .expect("write constants");
+    fs::write(
+        project_dir.path().join("models.py"),
+        "class Greeter:\n    import json as j\n    from constants import GREETING\n\n    def render(self):\n        return self.j.dumps({\"greeting\": self.GREETING})\n",
+    )
+    .expect("write models");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from models import Greeter\n\nprint(Greeter().render())\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    // Run the bundle away from the sources so only bundled code can be imported
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "{\"greeting\": \"hello\"}"
+    );
+}
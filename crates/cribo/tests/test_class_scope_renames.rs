@@ -0,0 +1,72 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Bundle the project's `main.py`, run the bundle away from the sources and return
+/// its stdout
+fn bundle_and_run(project_dir: &TempDir) -> String {
+    let entry = project_dir.path().join("main.py");
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_owned()
+}
+
+#[test]
+fn test_renamed_module_constant_leaves_class_attribute_alone() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("limits.py"),
+        "LIMIT = 10\n\n\nclass Settings:\n    LIMIT = 5\n    DOUBLE = LIMIT * 2\n\n    def module_limit(self):\n        return LIMIT\n\n    class Nested:\n        LIMIT = 1\n",
+    )
+    .expect("write limits");
+    fs::write(project_dir.path().join("quota.py"), "LIMIT = 20\n").expect("write quota");
+    fs::write(
+        project_dir.path().join("main.py"),
+        "from limits import Settings, LIMIT as A\nfrom quota import LIMIT as B\n\nprint(Settings.LIMIT, Settings.DOUBLE, Settings().module_limit(), Settings.Nested.LIMIT, A, B)\n",
+    )
+    .expect("write entry");
+
+    assert_eq!(bundle_and_run(&project_dir), "5 10 10 1 10 20");
+}
+
+#[test]
+fn test_renamed_function_leaves_method_of_same_name_alone() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("jobs.py"),
+        "def run():\n    return \"module\"\n\n\nclass Job:\n    def run(self):\n        return \"method\"\n\n    alias = run\n\n    def both(self):\n        return run() + \"/\" + self.alias()\n",
+    )
+    .expect("write jobs");
+    fs::write(
+        project_dir.path().join("tasks.py"),
+        "def run():\n    return \"task\"\n",
+    )
+    .expect("write tasks");
+    fs::write(
+        project_dir.path().join("main.py"),
+        "from jobs import Job\nfrom tasks import run\n\nprint(Job().both(), run())\n",
+    )
+    .expect("write entry");
+
+    assert_eq!(bundle_and_run(&project_dir), "module/method task");
+}
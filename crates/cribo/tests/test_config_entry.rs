@@ -0,0 +1,124 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn write_project() -> TempDir {
+    let project_dir = TempDir::new().expect("create project dir");
+    let app_dir = project_dir.path().join("src/app");
+    fs::create_dir_all(&app_dir).expect("create app package");
+    fs::write(app_dir.join("__init__.py"), "").expect("write __init__");
+    fs::write(app_dir.join("main.py"), "print(\"from main\")\n").expect("write main");
+    let cli_dir = app_dir.join("cli");
+    fs::create_dir(&cli_dir).expect("create cli package");
+    fs::write(cli_dir.join("__init__.py"), "").expect("write cli __init__");
+    fs::write(cli_dir.join("__main__.py"), "print(\"from cli\")\n").expect("write cli main");
+    project_dir
+}
+
+fn config_with_entry(project_dir: &TempDir, entry: &str) -> Config {
+    Config {
+        entry: Some(entry.to_owned()),
+        src: vec![project_dir.path().join("src")],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_entry_path_accepts_script_paths() {
+    let project_dir = write_project();
+    let entry = project_dir.path().join("src/app/main.py");
+    let config = config_with_entry(&project_dir, &entry.to_string_lossy());
+
+    assert_eq!(config.entry_path().expect("resolve entry"), Some(entry));
+}
+
+#[test]
+fn test_entry_path_resolves_dotted_module_names() {
+    let project_dir = write_project();
+    // Modules are resolved in the canonical source directories
+    let src = project_dir
+        .path()
+        .join("src")
+        .canonicalize()
+        .expect("canonical src");
+
+    let config = config_with_entry(&project_dir, "app.main");
+    assert_eq!(
+        config.entry_path().expect("resolve module"),
+        Some(src.join("app/main.py"))
+    );
+
+    // Packages run through their __main__ module, like `python -m`
+    let config = config_with_entry(&project_dir, "app.cli");
+    assert_eq!(
+        config.entry_path().expect("resolve package"),
+        Some(src.join("app/cli/__main__.py"))
+    );
+
+    let config = config_with_entry(&project_dir, "app.missing");
+    assert!(config.entry_path().is_err());
+}
+
+#[test]
+fn test_resolve_entry_accepts_package_directories() {
+    let project_dir = write_project();
+    let config = Config {
+        src: vec![project_dir.path().join("src")],
+        ..Default::default()
+    };
+    let app_dir = project_dir.path().join("src/app");
+
+    // A package runs as its __main__.py, or its __init__.py without one
+    assert_eq!(
+        config
+            .resolve_entry(&format!("{}/", app_dir.join("cli").display()))
+            .expect("resolve package with __main__"),
+        app_dir.join("cli/__main__.py")
+    );
+    assert_eq!(
+        config
+            .resolve_entry(&app_dir.to_string_lossy())
+            .expect("resolve package without __main__"),
+        app_dir.join("__init__.py")
+    );
+
+    // Any other directory is a directory of scripts
+    let scripts_dir = project_dir.path().join("scripts");
+    fs::create_dir(&scripts_dir).expect("create scripts dir");
+    assert_eq!(
+        config
+            .resolve_entry(&scripts_dir.to_string_lossy())
+            .expect("resolve scripts dir"),
+        scripts_dir
+    );
+}
+
+#[test]
+fn test_configured_entry_bundles() {
+    let project_dir = write_project();
+    let config = config_with_entry(&project_dir, "app.main");
+    let entry: PathBuf = config
+        .entry_path()
+        .expect("resolve entry")
+        .expect("entry is configured");
+
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(bundled.contains("print(\"from main\")"));
+}
+
+#[test]
+fn test_entry_and_output_load_from_config_file() {
+    let dir = TempDir::new().expect("create config dir");
+    let path = dir.path().join("cribo.toml");
+    fs::write(&path, "entry = \"app.main\"\noutput = \"dist/app.py\"\n").expect("write config");
+
+    let config = Config::load(Some(&path)).expect("load config");
+    assert_eq!(config.entry.as_deref(), Some("app.main"));
+    assert_eq!(config.output, Some(PathBuf::from("dist/app.py")));
+}
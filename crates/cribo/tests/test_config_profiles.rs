@@ -0,0 +1,58 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use std::fs;
+use tempfile::TempDir;
+
+const CONFIG: &str = r#"
+src = ["src"]
+section_markers = false
+preflight_check = false
+
+[profile.dev]
+section_markers = true
+
+[profile.release]
+preflight_check = true
+target-version = "py312"
+"#;
+
+fn write_config() -> (TempDir, std::path::PathBuf) {
+    let dir = TempDir::new().expect("create config dir");
+    let path = dir.path().join("cribo.toml");
+    fs::write(&path, CONFIG).expect("write config");
+    (dir, path)
+}
+
+#[test]
+fn test_profile_overrides_top_level_settings() {
+    let (_dir, path) = write_config();
+
+    let release = Config::load_with_profile(Some(&path), Some("release")).expect("load release");
+    assert!(release.preflight_check);
+    assert!(!release.section_markers);
+    assert_eq!(release.target_version, "py312");
+    assert_eq!(release.src, [std::path::PathBuf::from("src")]);
+
+    let dev = Config::load_with_profile(Some(&path), Some("dev")).expect("load dev");
+    assert!(dev.section_markers);
+    assert!(!dev.preflight_check);
+}
+
+#[test]
+fn test_profiles_are_ignored_unless_selected() {
+    let (_dir, path) = write_config();
+
+    let config = Config::load(Some(&path)).expect("load without profile");
+    assert!(!config.section_markers);
+    assert!(!config.preflight_check);
+}
+
+#[test]
+fn test_unknown_profile_is_an_error() {
+    let (_dir, path) = write_config();
+
+    let error = Config::load_with_profile(Some(&path), Some("staging"))
+        .expect_err("undefined profile should be rejected");
+    assert!(error.to_string().contains("staging"), "{error}");
+}
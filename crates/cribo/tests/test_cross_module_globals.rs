@@ -0,0 +1,51 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_global_of_name_imported_from_inlined_module_is_lifted() {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    fs::write(temp_dir.path().join("state.py"), "counter = 0\n").expect("write state");
+    fs::write(
+        temp_dir.path().join("tracker.py"),
+        "from state import counter\n\nprint(\"tracker loaded\")\n\n\ndef bump():\n    global counter\n    counter = counter + 1\n    return counter\n",
+    )
+    .expect("write tracker");
+    let entry = temp_dir.path().join("main.py");
+    fs::write(&entry, "import tracker\n\nprint(tracker.bump())\n").expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    assert!(
+        bundled.contains("global __cribo_tracker_counter"),
+        "the global declaration should use the lifted name:\n{bundled}"
+    );
+    assert!(
+        bundled.contains("__cribo_tracker_counter = counter"),
+        "the lifted global should be initialized from the imported value:\n{bundled}"
+    );
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "tracker loaded\n1"
+    );
+}
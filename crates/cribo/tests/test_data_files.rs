@@ -0,0 +1,133 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::error::CriboError;
+use cribo::file_provider::MemoryFileProvider;
+use cribo::orchestrator::BundleOrchestrator;
+use indexmap::IndexMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn write_project(project_dir: &TempDir) -> PathBuf {
+    let root = project_dir.path();
+    fs::write(
+        root.join("settings.py"),
+        "\"\"\"Settings.\"\"\"\nfrom __future__ import annotations\n\ndef mode():\n    return DEFAULTS[\"mode\"]\n",
+    )
+    .expect("write settings");
+    let entry = root.join("main.py");
+    fs::write(
+        &entry,
+        "import settings\n\nprint(settings.mode(), settings.DEFAULTS[\"retries\"], LIMITS[\"size\"], FEATURES)\n",
+    )
+    .expect("write entry");
+    fs::write(
+        root.join("defaults.json"),
+        "{\"mode\": \"fast\", \"retries\": 3, \"proxy\": null}",
+    )
+    .expect("write json");
+    fs::write(root.join("limits.toml"), "size = 10\nstrict = true\n").expect("write toml");
+    fs::write(root.join("features.yaml"), "- search\n- export\n").expect("write yaml");
+    entry
+}
+
+fn data_files(project_dir: &TempDir, entries: &[(&str, &str)]) -> IndexMap<String, PathBuf> {
+    entries
+        .iter()
+        .map(|(target, file)| ((*target).to_owned(), project_dir.path().join(file)))
+        .collect()
+}
+
+#[test]
+fn test_data_files_are_embedded_as_literals() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+    let config = Config {
+        data_files: data_files(
+            &project_dir,
+            &[
+                ("settings.DEFAULTS", "defaults.json"),
+                ("main.LIMITS", "limits.toml"),
+                ("main.FEATURES", "features.yaml"),
+            ],
+        ),
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(bundled.contains("\"proxy\": None"), "{bundled}");
+
+    // The data files are not next to the bundle when it runs
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "fast 3 10 ['search', 'export']"
+    );
+}
+
+#[test]
+fn test_unsupported_data_file_is_a_config_error() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+    fs::write(project_dir.path().join("defaults.ini"), "[main]\n").expect("write ini");
+    let config = Config {
+        data_files: data_files(&project_dir, &[("settings.DEFAULTS", "defaults.ini")]),
+        ..Config::default()
+    };
+
+    let error = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect_err("unsupported formats must fail");
+    match error {
+        CriboError::Config { path, .. } => {
+            assert_eq!(path, Some(project_dir.path().join("defaults.ini")));
+        }
+        other => panic!("expected a config error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_data_files_are_read_through_the_file_provider_in_order() {
+    let mut files = MemoryFileProvider::new();
+    files.add_file("/project/main.py", "print(LIMITS)\n");
+    files.add_file(
+        "/project/limits.yaml",
+        "size: 10\nratio: .nan\nceiling: .inf\nalpha: a\n",
+    );
+    let config = Config {
+        src: vec![PathBuf::from("/project")],
+        respect_pythonpath: false,
+        data_files: IndexMap::from([(
+            "main.LIMITS".to_owned(),
+            PathBuf::from("/project/limits.yaml"),
+        )]),
+        ..Config::default()
+    };
+
+    let bundled = BundleOrchestrator::new(config)
+        .with_file_provider(Arc::new(files))
+        .bundle_to_string(Path::new("/project/main.py"), false)
+        .expect("bundling should succeed");
+    assert!(
+        bundled.contains(
+            "LIMITS = {\"size\": 10, \"ratio\": float(\"nan\"), \"ceiling\": float(\"inf\"), \"alpha\": \"a\"}"
+        ),
+        "{bundled}"
+    );
+}
@@ -0,0 +1,107 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use indexmap::IndexMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn write_project(project_dir: &TempDir) -> PathBuf {
+    let root = project_dir.path();
+    fs::write(root.join("flags.py"), "FEATURE_X = True\n").expect("write flags");
+    fs::write(root.join("heavy.py"), "NAME = \"heavy feature\"\n").expect("write heavy");
+    let entry = root.join("main.py");
+    fs::write(
+        &entry,
+        "from flags import FEATURE_X\n\nif FEATURE_X:\n    import heavy\n\n    print(heavy.NAME)\nelse:\n    print(\"light\", __BUNDLE_VERSION__)\n",
+    )
+    .expect("write entry");
+    entry
+}
+
+#[test]
+fn test_defines_drop_disabled_branches_and_their_imports() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+
+    let with_feature = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(with_feature.contains("heavy feature"), "{with_feature}");
+
+    let defines: IndexMap<String, toml::Value> = [
+        ("FEATURE_X".to_owned(), toml::Value::Boolean(false)),
+        (
+            "__BUNDLE_VERSION__".to_owned(),
+            toml::Value::String("1.4.2".to_owned()),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let config = Config {
+        defines,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(!bundled.contains("heavy"), "{bundled}");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "light 1.4.2"
+    );
+}
+
+#[test]
+fn test_version_checks_drop_backport_imports() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import sys\n\nif sys.version_info >= (3, 8):\n    from importlib import metadata\nelse:\n    import importlib_metadata as metadata\n\nprint(metadata.__name__)\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        fold_version_checks: true,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(!bundled.contains("importlib_metadata"), "{bundled}");
+
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "importlib.metadata"
+    );
+}
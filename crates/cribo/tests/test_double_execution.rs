@@ -0,0 +1,50 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_bundle_run_as_main_and_imported_initializes_once() {
+    let project_dir = TempDir::new().expect("create project dir");
+    // The counter makes `plugins` a wrapper module
+    fs::write(
+        project_dir.path().join("plugins.py"),
+        "import builtins\n\nbuiltins.cribo_test_loads = getattr(builtins, \"cribo_test_loads\", 0) + 1\nNAME = \"plugins\"\n",
+    )
+    .expect("write plugins");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "import plugins\n\nprint(plugins.NAME)\n").expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    // Execute the bundle as `__main__`, then import it under its own name and under a
+    // second name, as a copied bundle would be
+    let run_dir = TempDir::new().expect("create run dir");
+    fs::write(run_dir.path().join("bundle.py"), &bundled).expect("write bundle");
+    fs::write(run_dir.path().join("bundle_copy.py"), &bundled).expect("write copy");
+    fs::write(
+        run_dir.path().join("driver.py"),
+        "import builtins\nimport runpy\nimport sys\n\nrunpy.run_path(\"bundle.py\", run_name=\"__main__\")\nimport bundle\nimport bundle_copy\n\nfinders = [finder for finder in sys.meta_path if type(finder).__name__ == \"CriboBundledFinder\"]\nprint(builtins.cribo_test_loads, len(finders))\n",
+    )
+    .expect("write driver");
+    let output = Command::new("python3")
+        .arg("driver.py")
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "plugins\nplugins\nplugins\n1 1",
+        "{bundled}"
+    );
+}
@@ -0,0 +1,92 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn bundle_project(dual_mode: bool) -> String {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    fs::write(
+        temp_dir.path().join("helpers.py"),
+        "def greet(name):\n    return f\"Hello, {name}!\"\n",
+    )
+    .expect("write helpers");
+    let entry = temp_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from helpers import greet\n\n\ndef main():\n    print(greet(\"world\"))\n\n\nVERSION = \"1.0\"\n\nmain()\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        dual_mode,
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed")
+}
+
+#[test]
+fn test_dual_mode_guards_entry_calls_and_exports_entry_api() {
+    let bundled = bundle_project(true);
+
+    assert!(
+        bundled.contains("if __name__ == \"__main__\":\n    main()"),
+        "entry call should be guarded:\n{bundled}"
+    );
+    let all_line = bundled
+        .lines()
+        .find(|line| line.starts_with("__all__ = "))
+        .expect("bundle should declare __all__");
+    assert!(all_line.contains("\"main\""));
+    assert!(all_line.contains("\"VERSION\""));
+}
+
+#[test]
+fn test_dual_mode_bundle_runs_as_script_and_as_import() {
+    let bundled = bundle_project(true);
+    let run_dir = TempDir::new().expect("create run dir");
+    fs::write(run_dir.path().join("bundle.py"), &bundled).expect("write bundle");
+
+    let as_script = Command::new("python3")
+        .arg("bundle.py")
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        as_script.status.success(),
+        "bundle failed as a script: {}\n{bundled}",
+        String::from_utf8_lossy(&as_script.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&as_script.stdout).trim(),
+        "Hello, world!"
+    );
+
+    // Imported, the entry call stays quiet and the entry's API is available
+    let as_import = Command::new("python3")
+        .args(["-c", "from bundle import *\n\nprint(VERSION)\nmain()"])
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        as_import.status.success(),
+        "bundle failed as an import: {}\n{bundled}",
+        String::from_utf8_lossy(&as_import.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&as_import.stdout).trim(),
+        "1.0\nHello, world!"
+    );
+}
+
+#[test]
+fn test_default_mode_keeps_entry_calls_unguarded() {
+    let bundled = bundle_project(false);
+
+    assert!(bundled.lines().any(|line| line == "main()"));
+    assert!(!bundled.lines().any(|line| line.starts_with("__all__ = ")));
+}
@@ -0,0 +1,145 @@
+#![allow(clippy::disallowed_methods)]
+
+use cow_utils::CowUtils;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+use cribo::config::Config;
+use cribo::resolver::{ImportType, ModuleResolver};
+
+fn site_packages(virtualenv_dir: &Path) -> PathBuf {
+    let site_packages_dir = if cfg!(windows) {
+        virtualenv_dir.join("Lib").join("site-packages")
+    } else {
+        virtualenv_dir
+            .join("lib")
+            .join("python3.12")
+            .join("site-packages")
+    };
+    fs::create_dir_all(&site_packages_dir).expect("create site-packages");
+    site_packages_dir
+}
+
+/// Record `project` as installed in editable mode, the way pip does
+fn write_direct_url(site_packages_dir: &Path, distribution: &str, project: &Path) {
+    let dist_info = site_packages_dir.join(format!("{}-0.1.dist-info", distribution));
+    fs::create_dir_all(&dist_info).expect("create dist-info");
+    let project = project.canonicalize().expect("canonical project");
+    let project = project.to_string_lossy();
+    let url = if project.starts_with('/') {
+        format!("file://{}", project)
+    } else {
+        format!("file:///{}", project.cow_replace('\\', "/"))
+    };
+    fs::write(
+        dist_info.join("direct_url.json"),
+        format!(r#"{{"url": {:?}, "dir_info": {{"editable": true}}}}"#, url),
+    )
+    .expect("write direct_url.json");
+}
+
+fn resolver(src_dir: &Path, virtualenv_dir: &Path) -> ModuleResolver {
+    let config = Config {
+        src: vec![src_dir.to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    ModuleResolver::new_with_virtualenv(config, Some(&virtualenv_dir.to_string_lossy()))
+        .expect("create resolver")
+}
+
+#[test]
+fn test_pth_editable_install_resolves_to_source_tree() {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    let app_dir = temp_dir.path().join("app");
+    let library = temp_dir.path().join("shared-lib");
+    let virtualenv_dir = temp_dir.path().join("venv");
+    fs::create_dir_all(&app_dir).expect("create app dir");
+    fs::create_dir_all(library.join("src").join("shared")).expect("create library");
+    fs::write(library.join("src").join("shared").join("__init__.py"), "").expect("write init");
+    fs::write(
+        library.join("src").join("shared").join("text.py"),
+        "def shout(text):\n    return text.upper()\n",
+    )
+    .expect("write module");
+
+    let site_packages_dir = site_packages(&virtualenv_dir);
+    write_direct_url(&site_packages_dir, "shared_lib", &library);
+    fs::write(
+        site_packages_dir.join("__editable__.shared_lib-0.1.pth"),
+        format!("{}\n", library.join("src").display()),
+    )
+    .expect("write pth");
+    // A path entry that isn't an editable install stays third-party
+    let plugins_dir = temp_dir.path().join("plugins");
+    fs::create_dir_all(&plugins_dir).expect("create plugins dir");
+    fs::write(plugins_dir.join("plugin.py"), "").expect("write plugin");
+    fs::write(
+        site_packages_dir.join("plugins.pth"),
+        format!("{}\n", plugins_dir.display()),
+    )
+    .expect("write plugins pth");
+
+    let mut resolver = resolver(&app_dir, &virtualenv_dir);
+    assert_eq!(
+        resolver.classify_import("shared.text"),
+        ImportType::FirstParty
+    );
+    assert_eq!(resolver.classify_import("plugin"), ImportType::ThirdParty);
+    let resolved = resolver
+        .resolve_module_path("shared.text")
+        .expect("resolve")
+        .expect("editable module is found");
+    assert!(
+        resolved.ends_with(Path::new("src").join("shared").join("text.py")),
+        "{resolved:?}"
+    );
+}
+
+#[test]
+fn test_finder_editable_install_resolves_to_source_tree() {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    let app_dir = temp_dir.path().join("app");
+    let library = temp_dir.path().join("toolbox");
+    let virtualenv_dir = temp_dir.path().join("venv");
+    fs::create_dir_all(&app_dir).expect("create app dir");
+    fs::create_dir_all(library.join("toolbox")).expect("create library");
+    fs::write(library.join("toolbox").join("__init__.py"), "VERSION = 1\n").expect("write init");
+    fs::write(library.join("single.py"), "VALUE = 2\n").expect("write single module");
+    // The checkout also has a local copy of a module the application provides itself
+    fs::write(library.join("settings.py"), "DEBUG = False\n").expect("write settings");
+    fs::write(app_dir.join("settings.py"), "DEBUG = True\n").expect("write app settings");
+
+    let site_packages_dir = site_packages(&virtualenv_dir);
+    write_direct_url(&site_packages_dir, "toolbox", &library);
+    fs::write(
+        site_packages_dir.join("__editable___toolbox_0_1_finder.py"),
+        format!(
+            "import sys\n\nMAPPING: dict[str, str] = {{'toolbox': {:?}, 'single': {:?}, 'settings': {:?}}}\nNAMESPACES: dict[str, list[str]] = {{}}\n",
+            library.join("toolbox").display().to_string(),
+            library.join("single.py").display().to_string(),
+            library.join("settings.py").display().to_string(),
+        ),
+    )
+    .expect("write finder");
+
+    let mut resolver = resolver(&app_dir, &virtualenv_dir);
+    assert_eq!(resolver.classify_import("toolbox"), ImportType::FirstParty);
+    assert_eq!(resolver.classify_import("single"), ImportType::FirstParty);
+    let toolbox = resolver
+        .resolve_module_path("toolbox")
+        .expect("resolve")
+        .expect("editable package is found");
+    assert!(toolbox.ends_with(Path::new("toolbox").join("__init__.py")));
+
+    // Modules in the configured source directories keep precedence
+    let settings = resolver
+        .resolve_module_path("settings")
+        .expect("resolve")
+        .expect("settings is found");
+    assert_eq!(
+        fs::read_to_string(settings).expect("read settings"),
+        "DEBUG = True\n"
+    );
+}
@@ -0,0 +1,29 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::api::{self, BundleRequest};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_entry_with_relative_imports_is_bundled_in_its_package() {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    let package_dir = temp_dir.path().join("app");
+    fs::create_dir(&package_dir).expect("create package");
+    fs::write(package_dir.join("__init__.py"), "").expect("write __init__");
+    fs::write(package_dir.join("config.py"), "GREETING = \"hello\"\n").expect("write config");
+    let entry = package_dir.join("main.py");
+    fs::write(
+        &entry,
+        "from . import config\nfrom .config import GREETING\n\nprint(config.GREETING, GREETING)\n",
+    )
+    .expect("write entry");
+
+    let request = BundleRequest::new(&entry);
+    let modules = api::graph(&request).expect("graph should build");
+    let names: Vec<_> = modules.iter().map(|module| module.name.as_str()).collect();
+    assert!(names.contains(&"app.main"), "modules: {names:?}");
+    assert!(names.contains(&"app.config"), "modules: {names:?}");
+
+    let result = api::bundle(&request).expect("entry inside a package should bundle");
+    assert!(result.code.contains("\"hello\""));
+}
@@ -0,0 +1,51 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::error::CriboError;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_syntax_error_is_reported_as_parse_error_with_location() {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    let entry = temp_dir.path().join("main.py");
+    fs::write(&entry, "import os\n\ndef broken(:\n    pass\n").expect("write entry");
+
+    let mut orchestrator = BundleOrchestrator::new(Config::default());
+    let error = orchestrator
+        .bundle_to_string(&entry, false)
+        .expect_err("bundling a syntax error must fail");
+
+    match &error {
+        CriboError::Parse { path, line, .. } => {
+            assert_eq!(path, &entry);
+            assert_eq!(*line, 3);
+        }
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+    assert_eq!(error.path(), Some(entry.as_path()));
+}
+
+#[test]
+fn test_unresolvable_cycle_is_reported_as_cycle_error() {
+    let entry = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/bundling/xfail_class_level_cycles/main.py");
+
+    let mut orchestrator = BundleOrchestrator::new(Config::default());
+    let error = orchestrator
+        .bundle_to_string(&entry, false)
+        .expect_err("unresolvable cycles must fail");
+
+    match error {
+        CriboError::Cycle { cycles, .. } => assert!(!cycles.is_empty()),
+        other => panic!("expected a cycle error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_invalid_target_version_is_reported_as_config_error() {
+    let error = Config::parse_target_version("py27").expect_err("py27 is unsupported");
+    assert!(matches!(error, CriboError::Config { .. }));
+}
@@ -0,0 +1,64 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use tempfile::TempDir;
+
+fn write_project(dir: &std::path::Path) -> std::path::PathBuf {
+    fs::write(
+        dir.join("helpers.py"),
+        "def shout(text):\n    return text.upper()\n",
+    )
+    .expect("write helpers");
+    fs::write(
+        dir.join("app.py"),
+        "def run():\n    import helpers\n    return helpers.shout(\"run\")\n",
+    )
+    .expect("write app");
+    fs::write(dir.join("unused.py"), "VALUE = 1\n").expect("write unused");
+    let entry = dir.join("main.py");
+    fs::write(
+        &entry,
+        "import app\nfrom helpers import shout\n\nprint(app.run(), shout(\"main\"))\n",
+    )
+    .expect("write entry");
+    entry
+}
+
+#[test]
+fn test_explain_lists_every_import_chain() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(project_dir.path());
+
+    let explanation = BundleOrchestrator::new(Config::default())
+        .explain(&entry, "helpers")
+        .expect("explain should succeed");
+
+    assert!(
+        explanation.starts_with("helpers is bundled through 2 import chains:\n"),
+        "{explanation}"
+    );
+    // The direct import is the shortest chain, so it comes first
+    let direct = explanation
+        .find("main.py:2: from helpers import shout\n")
+        .expect("direct import listed");
+    let through_app = explanation
+        .find(" -> app -> helpers\n  main.py:1: import app\n  app.py:2: import helpers\n")
+        .expect("chain through app listed");
+    assert!(direct < through_app, "{explanation}");
+}
+
+#[test]
+fn test_explain_reports_modules_outside_the_bundle() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(project_dir.path());
+
+    let explanation = BundleOrchestrator::new(Config::default())
+        .explain(&entry, "unused")
+        .expect("explain should succeed");
+    assert_eq!(
+        explanation,
+        "unused is not bundled: no module reachable from the entry imports it\n"
+    );
+}
@@ -0,0 +1,64 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use cribo::resolver::ModuleResolver;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_extra_paths_are_searched_after_source_directories() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let app_dir = project_dir.path().join("app");
+    let lib_dir = project_dir.path().join("lib");
+    fs::create_dir_all(&app_dir).expect("create app dir");
+    fs::create_dir_all(&lib_dir).expect("create lib dir");
+    fs::write(
+        lib_dir.join("formatting.py"),
+        "def banner(text):\n    return \"== \" + text + \" ==\"\n",
+    )
+    .expect("write formatting");
+    // The application's own copy shadows the one in lib/, as it would at runtime
+    fs::write(lib_dir.join("settings.py"), "NAME = \"from lib\"\n").expect("write lib settings");
+    fs::write(app_dir.join("settings.py"), "NAME = \"from app\"\n").expect("write app settings");
+    let entry = app_dir.join("main.py");
+    fs::write(
+        &entry,
+        "from formatting import banner\nfrom settings import NAME\n\nprint(banner(NAME))\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![app_dir.clone()],
+        extra_paths: vec![lib_dir.clone()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+
+    let mut resolver = ModuleResolver::new(config.clone()).expect("create resolver");
+    let formatting = resolver
+        .resolve_module_path("formatting")
+        .expect("resolve")
+        .expect("module in extra path is found");
+    assert!(formatting.starts_with(lib_dir.canonicalize().expect("canonical lib dir")));
+
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle with extra paths");
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "== from app =="
+    );
+}
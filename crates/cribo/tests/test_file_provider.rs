@@ -0,0 +1,93 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::file_provider::{FileProviderHandle, MemoryFileProvider, OsFileProvider};
+use cribo::orchestrator::BundleOrchestrator;
+use cribo::resolver::ModuleResolver;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A project that only exists in memory
+fn in_memory_project() -> (FileProviderHandle, Config) {
+    let mut files = MemoryFileProvider::new();
+    files.add_file(
+        "/project/src/main.py",
+        "from app.util import double\n\nprint(double(21))\n",
+    );
+    files.add_file("/project/src/app/__init__.py", "");
+    files.add_file(
+        "/project/src/app/util.py",
+        "def double(value):\n    return value * 2\n",
+    );
+    let config = Config {
+        src: vec![PathBuf::from("/project/src")],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    (Arc::new(files), config)
+}
+
+#[test]
+fn test_resolver_finds_modules_through_the_provider() {
+    let (files, config) = in_memory_project();
+    let mut resolver = ModuleResolver::with_file_provider(config, files).expect("create resolver");
+
+    assert_eq!(
+        resolver
+            .resolve_module_path("app.util")
+            .expect("resolve app.util"),
+        Some(PathBuf::from("/project/src/app/util.py"))
+    );
+    assert_eq!(
+        resolver.resolve_module_path("app").expect("resolve app"),
+        Some(PathBuf::from("/project/src/app/__init__.py"))
+    );
+    assert_eq!(
+        resolver
+            .resolve_module_path("app.missing")
+            .expect("resolve app.missing"),
+        None
+    );
+}
+
+#[test]
+fn test_bundle_from_an_in_memory_project() {
+    let (files, config) = in_memory_project();
+    let bundled = BundleOrchestrator::new(config)
+        .with_file_provider(files)
+        .bundle_to_string(Path::new("/project/src/main.py"), false)
+        .expect("bundle in-memory project");
+
+    assert!(bundled.contains("def double(value):"), "{bundled}");
+    assert!(bundled.contains("print(double(21))"), "{bundled}");
+}
+
+#[test]
+fn test_bundle_a_directory_of_in_memory_scripts() {
+    // The generated dispatcher is written to the filesystem, so the scripts are layered
+    // over it
+    let mut files = MemoryFileProvider::with_fallback(Arc::new(OsFileProvider));
+    files.add_file("/tools/common.py", "GREETING = 'Hello'\n");
+    files.add_file(
+        "/tools/greet.py",
+        "from common import GREETING\n\nprint(GREETING)\n",
+    );
+    files.add_file(
+        "/tools/count.py",
+        "import common\n\nprint(len(common.GREETING))\n",
+    );
+    let config = Config {
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+
+    let bundled = BundleOrchestrator::new(config)
+        .with_file_provider(Arc::new(files))
+        .bundle_to_string(Path::new("/tools"), false)
+        .expect("bundle in-memory scripts");
+
+    assert!(bundled.contains("command == \"greet\""), "{bundled}");
+    assert!(bundled.contains("command == \"count\""), "{bundled}");
+    // Imported by the other scripts, so a helper rather than a command
+    assert!(!bundled.contains("command == \"common\""), "{bundled}");
+}
@@ -0,0 +1,49 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_find_spec_sees_bundled_modules_only() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(project_dir.path().join("helpers.py"), "VALUE = 1\n").expect("write helpers");
+    fs::write(
+        project_dir.path().join("feature.py"),
+        "def enabled():\n    return True\n",
+    )
+    .expect("write feature");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import importlib.util\n\nimport feature\nfrom helpers import VALUE\n\nprint(importlib.util.find_spec(\"helpers\") is not None)\nprint(importlib.util.find_spec(\"feature\") is not None and feature.enabled())\nprint(importlib.util.find_spec(\"cribo_missing_optional_dep\") is None)\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    // Run the bundle away from the sources so only bundled code can be found
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "True\nTrue\nTrue",
+        "{bundled}"
+    );
+}
@@ -0,0 +1,119 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn config_for(target_version: &str) -> Config {
+    Config {
+        target_version: target_version.to_owned(),
+        ..Config::default()
+    }
+}
+
+fn python_minor() -> u32 {
+    let output = Command::new("python3")
+        .args(["-c", "import sys; print(sys.version_info[1])"])
+        .output()
+        .expect("run python3");
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .expect("python3 prints its minor version")
+}
+
+/// Two modules whose conflicting names are only referenced inside PEP 701 f-strings
+fn write_pep701_project(dir: &Path) -> std::path::PathBuf {
+    fs::write(
+        dir.join("users.py"),
+        "LABEL = \"user\"\n\n\ndef describe(record):\n    return f\"{LABEL}: {record[\"name\"]!r:>{len(LABEL) + 8}}\"\n",
+    )
+    .expect("write users");
+    fs::write(
+        dir.join("groups.py"),
+        "LABEL = \"group\"\n\n\ndef describe(record):\n    return f'''{LABEL}\n  members: {\", \".join(str(m[\"id\"]) for m in record[\"members\"])}'''\n",
+    )
+    .expect("write groups");
+    let entry = dir.join("main.py");
+    fs::write(
+        &entry,
+        "import groups\nimport users\n\nprint(users.describe({\"name\": \"ada\"}))\nprint(groups.describe({\"members\": [{\"id\": 1}, {\"id\": 2}]}))\n",
+    )
+    .expect("write entry");
+    entry
+}
+
+#[test]
+fn test_pep701_fstrings_survive_renames() {
+    if python_minor() < 12 {
+        eprintln!("Skipping: PEP 701 f-strings need Python 3.12");
+        return;
+    }
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_pep701_project(project_dir.path());
+
+    let bundled = BundleOrchestrator::new(config_for("py312"))
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    // Same working directory as `python_minor`, which may select the interpreter
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "user:        'ada'\ngroup\n  members: 1, 2\n",
+        "{bundled}"
+    );
+}
+
+#[test]
+fn test_pep701_fstrings_need_python_312_target() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_pep701_project(project_dir.path());
+
+    let err = BundleOrchestrator::new(config_for("py311"))
+        .bundle_to_string(&entry, false)
+        .expect_err("nested quotes in f-strings should be rejected for Python 3.11");
+    let message = err.to_string();
+    assert!(message.contains("users.py"), "{message}");
+    assert!(message.contains("raise `target-version`"), "{message}");
+}
+
+#[test]
+fn test_template_strings_need_python_314_target() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("views.py"),
+        "def render(name):\n    return t\"<p>{name}</p>\"\n",
+    )
+    .expect("write views");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from views import render\n\nprint(render(\"ada\").values)\n",
+    )
+    .expect("write entry");
+
+    let err = BundleOrchestrator::new(config_for("py313"))
+        .bundle_to_string(&entry, false)
+        .expect_err("template strings should be rejected for Python 3.13");
+    assert!(err.to_string().contains("views.py"), "{err}");
+
+    let bundled = BundleOrchestrator::new(config_for("py314"))
+        .bundle_to_string(&entry, false)
+        .expect("template strings should bundle for Python 3.14");
+    assert!(bundled.contains("t\"<p>{name}</p>\""), "{bundled}");
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "git")]
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::error::CriboError;
+use cribo::git_source::GitSnapshot;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn commit_all(repo: &git2::Repository, message: &str) -> git2::Oid {
+    let mut index = repo.index().expect("open index");
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .expect("stage files");
+    index.write().expect("write index");
+    let tree = repo
+        .find_tree(index.write_tree().expect("write tree"))
+        .expect("find tree");
+    let signature = git2::Signature::now("Release", "release@example.com").expect("signature");
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .expect("commit")
+}
+
+fn write_sources(root: &Path, version: &str) {
+    fs::create_dir_all(root.join("src/app")).expect("create package");
+    fs::write(root.join("src/app/__init__.py"), "").expect("write package");
+    fs::write(
+        root.join("src/app/version.py"),
+        format!("VERSION = \"{version}\"\n"),
+    )
+    .expect("write version");
+    fs::write(
+        root.join("src/app/main.py"),
+        "from app.version import VERSION\n\nprint(VERSION)\n",
+    )
+    .expect("write entry");
+}
+
+#[test]
+fn test_git_ref_bundles_committed_sources() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let root = project_dir.path();
+    let repo = git2::Repository::init(root).expect("init repository");
+    write_sources(root, "1.0");
+    let release = commit_all(&repo, "Release 1.0");
+    let release_commit = repo.find_commit(release).expect("find commit");
+    repo.tag_lightweight("v1.0", release_commit.as_object(), false)
+        .expect("tag release");
+    write_sources(root, "2.0");
+    commit_all(&repo, "Start 2.0");
+    // Uncommitted work in progress
+    fs::write(root.join("src/app/version.py"), "VERSION = \"dirty\"\n").expect("edit version");
+    fs::write(root.join("src/app/extra.py"), "EXTRA = 1\n").expect("add untracked");
+
+    for (revision, expected) in [("v1.0", "1.0"), ("HEAD", "2.0")] {
+        let snapshot = GitSnapshot::export_from(root, revision).expect("export revision");
+        let mut config = Config {
+            src: vec![root.join("src")],
+            ..Config::default()
+        };
+        snapshot.apply_to(&mut config);
+        assert!(!snapshot.root().join("src/app/extra.py").exists());
+
+        let entry = snapshot.map_path(&root.join("src/app/main.py"));
+        assert!(entry.starts_with(snapshot.root()), "{entry:?}");
+        let bundled = BundleOrchestrator::new(config)
+            .bundle_to_string(&entry, false)
+            .expect("bundling should succeed");
+        assert!(
+            bundled.contains(&format!("VERSION = \"{expected}\"")),
+            "{revision}: {bundled}"
+        );
+        assert!(!bundled.contains("dirty"), "{revision}: {bundled}");
+    }
+}
+
+#[test]
+fn test_unknown_git_ref_is_an_error() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let repo = git2::Repository::init(project_dir.path()).expect("init repository");
+    write_sources(project_dir.path(), "1.0");
+    commit_all(&repo, "Initial");
+
+    let error = GitSnapshot::export_from(project_dir.path(), "no-such-tag")
+        .expect_err("unknown revisions must fail");
+    assert!(
+        matches!(&error, CriboError::Resolution { message, .. } if message.contains("no-such-tag")),
+        "{error:?}"
+    );
+}
@@ -0,0 +1,74 @@
+#![allow(clippy::disallowed_methods)]
+
+use anyhow::Result;
+use cribo::config::Config;
+use cribo::cribo_graph::CriboGraph;
+use cribo::graph_cache::GraphSnapshot;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use tempfile::TempDir;
+
+fn write_project(root: &std::path::Path) -> Result<()> {
+    fs::write(
+        root.join("main.py"),
+        "from utils import greet\n\nprint(greet())\n",
+    )?;
+    fs::write(root.join("utils.py"), "def greet():\n    return 'hi'\n")?;
+    Ok(())
+}
+
+#[test]
+fn test_graph_snapshot_round_trip() -> Result<()> {
+    let project = TempDir::new()?;
+    let cache = TempDir::new()?;
+    write_project(project.path())?;
+    let entry = project.path().join("main.py");
+
+    let mut orchestrator = BundleOrchestrator::new(Config::default());
+    let cold = orchestrator.build_graph_cached(&entry, cache.path())?;
+
+    let mut orchestrator = BundleOrchestrator::new(Config::default());
+    let warm = orchestrator.build_graph_cached(&entry, cache.path())?;
+
+    let mut cold_names: Vec<_> = cold.module_names.keys().cloned().collect();
+    let mut warm_names: Vec<_> = warm.module_names.keys().cloned().collect();
+    cold_names.sort();
+    warm_names.sort();
+    assert_eq!(cold_names, warm_names);
+
+    let main_id = warm.module_names["main"];
+    let utils_id = warm.module_names["utils"];
+    assert_eq!(warm.get_dependencies(main_id), vec![utils_id]);
+    assert_eq!(
+        warm.modules[&utils_id].items.len(),
+        cold.modules[&cold.module_names["utils"]].items.len()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_graph_snapshot_detects_changed_sources() -> Result<()> {
+    let project = TempDir::new()?;
+    let cache = TempDir::new()?;
+    write_project(project.path())?;
+    let entry = project.path().join("main.py");
+
+    let mut graph = CriboGraph::new();
+    let main_id = graph.add_module("main".to_string(), entry.clone());
+    let utils_id = graph.add_module("utils".to_string(), project.path().join("utils.py"));
+    graph.add_module_dependency(main_id, utils_id);
+
+    GraphSnapshot::capture(&graph, &entry, "config")?.store(cache.path())?;
+    let snapshot = GraphSnapshot::load(cache.path(), &entry).expect("snapshot should be stored");
+    assert!(snapshot.is_fresh(&entry, "config"));
+    assert!(!snapshot.is_fresh(&entry, "other-config"));
+
+    fs::write(
+        project.path().join("utils.py"),
+        "def greet():\n    return 'hello'\n",
+    )?;
+    assert!(!snapshot.is_fresh(&entry, "config"));
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::graph_export::BundleGraphNodeKind;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_dependency_graph_marks_module_kinds() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("settings.py"),
+        "DEBUG = True\nprint(\"loading settings\")\n",
+    )
+    .expect("write settings");
+    fs::write(
+        project_dir.path().join("helpers.py"),
+        "import json\n\n\ndef dump(value):\n    return json.dumps(value)\n\n\ndef fetch():\n    import requests\n    return requests\n",
+    )
+    .expect("write helpers");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from settings import DEBUG\nfrom helpers import dump\n\nprint(dump(DEBUG))\n",
+    )
+    .expect("write entry");
+
+    let graph = BundleOrchestrator::new(Config::default())
+        .dependency_graph(&entry)
+        .expect("graph export should succeed");
+
+    let kind_of = |name: &str| {
+        graph
+            .nodes
+            .iter()
+            .find(|node| node.name == name)
+            .map(|node| node.kind)
+    };
+    assert_eq!(kind_of("settings"), Some(BundleGraphNodeKind::Wrapper));
+    assert_eq!(kind_of("helpers"), Some(BundleGraphNodeKind::Inlined));
+    assert_eq!(kind_of("json"), Some(BundleGraphNodeKind::Stdlib));
+    assert_eq!(kind_of("requests"), Some(BundleGraphNodeKind::ThirdParty));
+
+    let entry_node = graph
+        .nodes
+        .iter()
+        .find(|node| node.kind == BundleGraphNodeKind::Entry)
+        .expect("entry node");
+    let has_edge = |from: &str, to: &str| {
+        graph
+            .edges
+            .iter()
+            .any(|edge| edge.from == from && edge.to == to)
+    };
+    assert!(has_edge(&entry_node.name, "settings"), "{:?}", graph.edges);
+    assert!(has_edge(&entry_node.name, "helpers"), "{:?}", graph.edges);
+    assert!(has_edge("helpers", "json"), "{:?}", graph.edges);
+    assert!(has_edge("helpers", "requests"), "{:?}", graph.edges);
+
+    let dot = graph.to_dot();
+    assert!(
+        dot.contains("\"settings\" [label=\"settings\\n(wrapper)\""),
+        "{dot}"
+    );
+    assert!(dot.contains("\"helpers\" -> \"json\";"), "{dot}");
+}
+
+#[test]
+fn test_json_export_lists_side_effects_and_renames() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("settings.py"),
+        "DEBUG = True\nprint(\"loading settings\")\n",
+    )
+    .expect("write settings");
+    for module in ["alpha", "beta"] {
+        fs::write(
+            project_dir.path().join(format!("{module}.py")),
+            format!("VALUE = \"{module}\"\n\n\ndef get():\n    return VALUE\n"),
+        )
+        .expect("write module");
+    }
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from settings import DEBUG\nfrom alpha import get as get_alpha\nfrom beta import get as get_beta\n\nprint(DEBUG, get_alpha(), get_beta())\n",
+    )
+    .expect("write entry");
+
+    let mut bundler = BundleOrchestrator::new(Config::default());
+    let graph = bundler
+        .dependency_graph(&entry)
+        .expect("graph export should succeed");
+    let json: serde_json::Value =
+        serde_json::from_str(&graph.to_json().expect("serializes")).expect("valid JSON");
+
+    let nodes = json["nodes"].as_array().expect("nodes");
+    let node = |name: &str| {
+        nodes
+            .iter()
+            .find(|node| node["name"] == name)
+            .unwrap_or_else(|| panic!("no node {name}: {json}"))
+    };
+    assert_eq!(node("settings")["kind"], "wrapper");
+    assert!(
+        node("settings")["side_effects"]
+            .as_array()
+            .is_some_and(|effects| effects.contains(&serde_json::json!("Expression"))),
+        "{json}"
+    );
+    let renamed = ["alpha", "beta"]
+        .iter()
+        .any(|module| node(module)["renames"].get("VALUE").is_some());
+    assert!(renamed, "conflicting VALUE should be renamed: {json}");
+
+    let structure = bundler.build_graph(&entry).expect("graph").to_json();
+    let modules = structure["modules"].as_array().expect("modules");
+    let names: Vec<&str> = modules
+        .iter()
+        .filter_map(|module| module["name"].as_str())
+        .collect();
+    assert!(names.windows(2).all(|pair| pair[0] <= pair[1]), "{names:?}");
+    assert!(names.contains(&"settings"), "{names:?}");
+}
@@ -0,0 +1,51 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_import_time_cycle_initializes_lazily() {
+    let project_dir = TempDir::new().expect("create project dir");
+    // `shapes` calls into `registry` while it initializes, and `registry` imports
+    // `shapes` back, so neither module can be initialized entirely before the other
+    fs::write(
+        project_dir.path().join("shapes.py"),
+        "import registry\n\nprint(\"loading shapes\")\n\n\ndef square(side):\n    return side * side\n\n\nAREA_OF_TWO = registry.describe(2)\n",
+    )
+    .expect("write shapes");
+    fs::write(
+        project_dir.path().join("registry.py"),
+        "import shapes\n\nprint(\"loading registry\")\n\n\ndef describe(value):\n    return \"square(%d)=%d\" % (value, shapes.square(value))\n",
+    )
+    .expect("write registry");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "import shapes\n\nprint(shapes.AREA_OF_TWO)\n").expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("import-time cycles should bundle");
+    assert!(bundled.contains("class __cribo_LazyModule"), "{bundled}");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{stdout}");
+    assert!(lines.contains(&"loading shapes"), "{stdout}");
+    assert!(lines.contains(&"loading registry"), "{stdout}");
+    assert_eq!(lines.last(), Some(&"square(2)=4"), "{stdout}");
+}
@@ -0,0 +1,69 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::graph_export::BundleGraphNodeKind;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_import_fallback_modules_are_inlined() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("compat.py"),
+        "try:\n    import ujson as json\n    FAST_JSON = True\nexcept ImportError:\n    import json\n    FAST_JSON = False\n\n\ndef dumps(value):\n    return json.dumps(value, sort_keys=True)\n",
+    )
+    .expect("write compat");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from compat import dumps\n\nprint(dumps({\"b\": 1, \"a\": 2}))\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    let graph = BundleOrchestrator::new(config.clone())
+        .dependency_graph(&entry)
+        .expect("graph export should succeed");
+    let compat = graph
+        .nodes
+        .iter()
+        .find(|node| node.name == "compat")
+        .expect("compat node");
+    assert_eq!(compat.kind, BundleGraphNodeKind::Inlined);
+
+    let output = project_dir.path().join("bundle.py");
+    BundleOrchestrator::new(config)
+        .bundle(&entry, &output, true)
+        .expect("bundle with import fallback");
+    let bundled = fs::read_to_string(&output).expect("read bundle");
+    // The try/except stays in place instead of hoisting either import
+    assert!(bundled.contains("    import ujson as json\n"), "{bundled}");
+    assert!(
+        bundled.contains("except ImportError:\n    import json\n"),
+        "{bundled}"
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.path().join("requirements.txt")).expect("read requirements"),
+        "# ujson  (optional, imported with a fallback)"
+    );
+
+    let run = Command::new("python3")
+        .arg(&output)
+        .output()
+        .expect("run python3");
+    assert!(
+        run.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout).trim(),
+        r#"{"a": 2, "b": 1}"#
+    );
+}
@@ -0,0 +1,53 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::module_cache::ModuleCache;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[test]
+fn test_rebuild_only_reprocesses_changed_files() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("greetings.py"),
+        "def greet(name):\n    return f\"hello {name}\"\n",
+    )
+    .expect("write greetings");
+    fs::write(project_dir.path().join("names.py"), "NAME = \"world\"\n").expect("write names");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from greetings import greet\nfrom names import NAME\n\nprint(greet(NAME))\n",
+    )
+    .expect("write entry");
+
+    let cache = Arc::new(ModuleCache::new());
+    let mut orchestrator =
+        BundleOrchestrator::new(Config::default()).with_module_cache(Arc::clone(&cache));
+
+    let first = orchestrator
+        .bundle_to_string(&entry, false)
+        .expect("first build should succeed");
+    assert_eq!(cache.parse_count(), 3);
+    assert_eq!(cache.analysis_count(), 3);
+
+    // Nothing changed: everything comes from the cache and the output is identical
+    let second = orchestrator
+        .bundle_to_string(&entry, false)
+        .expect("second build should succeed");
+    assert_eq!(first, second);
+    assert_eq!(cache.parse_count(), 3);
+    assert_eq!(cache.analysis_count(), 3);
+
+    // Only the edited module is parsed and analyzed again
+    fs::write(project_dir.path().join("names.py"), "NAME = \"cribo\"\n").expect("edit names");
+    let third = orchestrator
+        .bundle_to_string(&entry, false)
+        .expect("third build should succeed");
+    assert!(third.contains("\"cribo\""), "{third}");
+    assert!(!third.contains("\"world\""), "{third}");
+    assert_eq!(cache.parse_count(), 4);
+    assert_eq!(cache.analysis_count(), 4);
+}
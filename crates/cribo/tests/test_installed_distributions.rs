@@ -0,0 +1,77 @@
+#![allow(clippy::disallowed_methods)]
+
+use indexmap::IndexMap;
+use serial_test::serial;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use cribo::resolver::VirtualEnvGuard;
+
+fn install(site_packages_dir: &Path, dist_info: &str, metadata: &str, record: &str) {
+    let dist_info = site_packages_dir.join(dist_info);
+    fs::create_dir_all(&dist_info).expect("create dist-info");
+    fs::write(dist_info.join("METADATA"), metadata).expect("write metadata");
+    fs::write(dist_info.join("RECORD"), record).expect("write record");
+}
+
+#[test]
+#[serial]
+fn test_requirements_name_installed_distributions() {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    let project_dir = temp_dir.path().join("project");
+    let virtualenv_dir = temp_dir.path().join("venv");
+    let site_packages_dir = if cfg!(windows) {
+        virtualenv_dir.join("Lib").join("site-packages")
+    } else {
+        virtualenv_dir
+            .join("lib")
+            .join("python3.12")
+            .join("site-packages")
+    };
+    fs::create_dir_all(&project_dir).expect("create project dir");
+    fs::create_dir_all(&site_packages_dir).expect("create site-packages");
+
+    install(
+        &site_packages_dir,
+        "acme_sdk-2.1.0.dist-info",
+        "Metadata-Version: 2.1\nName: acme-sdk\nVersion: 2.1.0\n",
+        "acme/__init__.py,sha256=abc,10\nacme_sdk-2.1.0.dist-info/RECORD,,\n",
+    );
+    install(
+        &site_packages_dir,
+        "opencv_python-4.9.0.dist-info",
+        "Metadata-Version: 2.1\nName: opencv-python\nVersion: 4.9.0\n",
+        "cv2/__init__.py,sha256=def,20\n",
+    );
+    let entry = project_dir.join("main.py");
+    fs::write(
+        &entry,
+        "import acme\nimport cv2\nimport requests\n\nprint(acme, cv2, requests)\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.clone()],
+        respect_pythonpath: false,
+        distribution_names: IndexMap::from([(
+            "cv2".to_owned(),
+            "opencv-python-headless".to_owned(),
+        )]),
+        ..Config::default()
+    };
+    let _guard = VirtualEnvGuard::new(&virtualenv_dir.to_string_lossy());
+    let output = project_dir.join("bundle.py");
+    BundleOrchestrator::new(config)
+        .bundle(&entry, &output, true)
+        .expect("bundle with requirements");
+
+    // Installed metadata names `acme`, configuration still wins for `cv2`, and packages
+    // that aren't installed keep their import name
+    assert_eq!(
+        fs::read_to_string(project_dir.join("requirements.txt")).expect("read requirements"),
+        "acme-sdk\nopencv-python-headless\nrequests"
+    );
+}
@@ -0,0 +1,60 @@
+#![allow(clippy::disallowed_methods)]
+
+use anyhow::Result;
+use cribo::config::Config;
+use cribo::logging::{self, LogSink};
+use cribo::orchestrator::BundleOrchestrator;
+use log::{Level, LevelFilter};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct CollectingSink {
+    records: Mutex<Vec<(Level, String)>>,
+}
+
+impl LogSink for CollectingSink {
+    fn enabled(&self, level: Level) -> bool {
+        level <= Level::Info
+    }
+
+    fn log(&self, level: Level, _target: &str, message: &str) {
+        self.records
+            .lock()
+            .expect("sink lock should not be poisoned")
+            .push((level, message.to_string()));
+    }
+}
+
+#[test]
+fn test_log_records_are_routed_to_orchestrator_sink() -> Result<()> {
+    logging::install(None, LevelFilter::Trace)?;
+
+    let entry =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bundling/simple_math/main.py");
+    let sink = Arc::new(CollectingSink::default());
+
+    let mut orchestrator = BundleOrchestrator::new(Config::default()).with_log_sink(sink.clone());
+    orchestrator.bundle_to_string(&entry, false)?;
+
+    // Records emitted outside of the orchestrator are not delivered to its sink
+    log::info!("outside of any bundling scope");
+
+    let records = sink
+        .records
+        .lock()
+        .expect("sink lock should not be poisoned");
+    assert!(
+        records
+            .iter()
+            .any(|(_, message)| message == "Entry module: main")
+    );
+    assert!(records.iter().all(|(level, _)| *level <= Level::Info));
+    assert!(
+        !records
+            .iter()
+            .any(|(_, message)| message == "outside of any bundling scope")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+#![allow(clippy::disallowed_methods)]
+
+use anyhow::Result;
+use cribo::config::Config;
+use cribo::metrics::{Metrics, Phase};
+use cribo::orchestrator::BundleOrchestrator;
+use cribo::stats::StatsCollector;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct RecordingMetrics {
+    events: Mutex<Vec<String>>,
+}
+
+impl RecordingMetrics {
+    fn record(&self, event: String) {
+        self.events
+            .lock()
+            .expect("metrics lock should not be poisoned")
+            .push(event);
+    }
+}
+
+impl Metrics for RecordingMetrics {
+    fn phase_started(&self, phase: Phase) {
+        self.record(format!("start:{phase}"));
+    }
+
+    fn phase_finished(&self, phase: Phase) {
+        self.record(format!("finish:{phase}"));
+    }
+
+    fn module_processed(&self, module_name: &str, _path: &Path) {
+        self.record(format!("module:{module_name}"));
+    }
+}
+
+#[test]
+fn test_metrics_hooks_receive_phase_and_module_events() -> Result<()> {
+    let entry =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bundling/simple_math/main.py");
+    let metrics = Arc::new(RecordingMetrics::default());
+
+    let mut orchestrator = BundleOrchestrator::new(Config::default()).with_metrics(metrics.clone());
+    orchestrator.bundle_to_string(&entry, false)?;
+
+    let events = metrics
+        .events
+        .lock()
+        .expect("metrics lock should not be poisoned")
+        .clone();
+
+    let phase_events: Vec<_> = events
+        .iter()
+        .filter(|event| !event.starts_with("module:"))
+        .cloned()
+        .collect();
+    assert_eq!(
+        phase_events,
+        vec![
+            "start:discovery",
+            "finish:discovery",
+            "start:graph_construction",
+            "finish:graph_construction",
+            "start:cycle_analysis",
+            "finish:cycle_analysis",
+            "start:code_generation",
+            "finish:code_generation",
+            "start:output",
+            "finish:output",
+        ]
+    );
+    assert!(events.contains(&"module:main".to_string()));
+    assert!(events.contains(&"module:calculator".to_string()));
+
+    Ok(())
+}
+
+#[test]
+fn test_stats_collector_summarizes_the_bundle() -> Result<()> {
+    let entry =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bundling/simple_math/main.py");
+    let stats = Arc::new(StatsCollector::new());
+
+    let mut orchestrator = BundleOrchestrator::new(Config::default()).with_metrics(stats.clone());
+    let bundled = orchestrator.bundle_to_string(&entry, false)?;
+
+    let stats = stats.stats(0);
+    assert_eq!(stats.bundles, 1);
+    assert_eq!(stats.summary.modules(), 3);
+    assert_eq!(stats.summary.wrapper_modules, 0);
+    assert_eq!(stats.summary.lines_in, 26);
+    assert_eq!(stats.summary.lines_out, bundled.lines().count());
+    assert_eq!(stats.summary.third_party_packages, 0);
+    let phases: Vec<Phase> = stats.phases.iter().map(|(phase, _)| *phase).collect();
+    assert_eq!(
+        phases,
+        [
+            Phase::Discovery,
+            Phase::GraphConstruction,
+            Phase::CycleAnalysis,
+            Phase::CodeGeneration,
+            Phase::Output,
+        ]
+    );
+    assert!(
+        stats
+            .to_string()
+            .contains("3 (3 inlined, 0 namespace, 0 wrapper)")
+    );
+
+    Ok(())
+}
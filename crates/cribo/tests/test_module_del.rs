@@ -0,0 +1,39 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_deleted_module_names_are_not_exported_or_imported() {
+    let temp_dir = TempDir::new().expect("create temp dir");
+    fs::write(
+        temp_dir.path().join("helpers.py"),
+        "import json\nimport textwrap\n\nprint(\"loading helpers\")\n\ntmp = {\"a\": 1}\nvalue = json.dumps(tmp)\nkept = 2\n\ndel tmp\ndel textwrap\n",
+    )
+    .expect("write helpers");
+    let entry = temp_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import helpers\n\nprint(helpers.value, helpers.kept)\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    assert!(
+        bundled.contains("module.value = value"),
+        "public names should still be exported:\n{bundled}"
+    );
+    assert!(
+        !bundled.contains("module.tmp = tmp"),
+        "deleted names should not be exported:\n{bundled}"
+    );
+    assert!(
+        !bundled.contains("textwrap"),
+        "an import that is only deleted is unused:\n{bundled}"
+    );
+}
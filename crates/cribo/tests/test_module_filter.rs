@@ -0,0 +1,64 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_excluded_modules_stay_external() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let generated_dir = project_dir.path().join("generated");
+    fs::create_dir(&generated_dir).expect("create generated dir");
+    fs::write(generated_dir.join("__init__.py"), "").expect("write package");
+    fs::write(
+        generated_dir.join("schema.py"),
+        "SCHEMA_VERSION = \"generated-at-runtime\"\n",
+    )
+    .expect("write schema");
+    fs::write(
+        project_dir.path().join("helpers.py"),
+        "from generated.schema import SCHEMA_VERSION\n\n\ndef describe():\n    return \"schema \" + SCHEMA_VERSION\n",
+    )
+    .expect("write helpers");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from helpers import describe\n\nprint(describe())\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        exclude: vec!["generated".to_owned()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle with excluded modules");
+    assert!(!bundled.contains("generated-at-runtime"), "{bundled}");
+    assert!(
+        bundled.contains("from generated.schema import SCHEMA_VERSION"),
+        "{bundled}"
+    );
+
+    // The excluded package is imported from next to the bundle at runtime
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(project_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "schema generated-at-runtime"
+    );
+}
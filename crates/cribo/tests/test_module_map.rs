@@ -0,0 +1,102 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+fn write_project(project_dir: &TempDir) -> std::path::PathBuf {
+    fs::write(
+        project_dir.path().join("util.py"),
+        "def helper():\n    return \"helped\"\n",
+    )
+    .expect("write util");
+    fs::write(
+        project_dir.path().join("noisy.py"),
+        "print(\"loading noisy\")\n\nVALUE = 42\n",
+    )
+    .expect("write noisy");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import noisy\nfrom util import helper\n\nprint(helper(), noisy.VALUE)\n",
+    )
+    .expect("write entry");
+    entry
+}
+
+#[test]
+fn test_module_map_locates_every_module() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+    let output_dir = TempDir::new().expect("create output dir");
+    let output = output_dir.path().join("app.py");
+
+    let config = Config {
+        module_map: true,
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle(&entry, &output, false)
+        .expect("bundling should succeed");
+
+    let bundled = fs::read_to_string(&output).expect("read bundle");
+    let map: Value = serde_json::from_str(
+        &fs::read_to_string(output_dir.path().join("app.py.map.json")).expect("read module map"),
+    )
+    .expect("module map is valid JSON");
+    assert_eq!(map["version"], 1);
+
+    let modules = map["modules"].as_array().expect("modules array");
+    let module = |name: &str| {
+        modules
+            .iter()
+            .find(|module| module["name"] == name)
+            .unwrap_or_else(|| panic!("{name} missing from module map: {map:#}"))
+    };
+    let region = |module: &Value| {
+        let start = module["bytes"][0].as_u64().expect("start byte") as usize;
+        let end = module["bytes"][1].as_u64().expect("end byte") as usize;
+        let first_line = module["lines"][0].as_u64().expect("first line") as usize;
+        let last_line = module["lines"][1].as_u64().expect("last line") as usize;
+        let text = &bundled[start..end];
+        // Byte and line ranges describe the same region
+        assert_eq!(bundled[..start].matches('\n').count() + 1, first_line);
+        assert_eq!(first_line + text.matches('\n').count(), last_line);
+        text
+    };
+
+    let util = module("util");
+    assert_eq!(util["strategy"], "inlined");
+    assert_eq!(util["path"], "util.py");
+    assert!(util.get("synthetic_name").is_none());
+    assert!(region(util).contains("def helper"));
+
+    let noisy = module("noisy");
+    assert_eq!(noisy["strategy"], "wrapper");
+    let synthetic_name = noisy["synthetic_name"].as_str().expect("synthetic name");
+    let noisy_code = region(noisy);
+    assert!(noisy_code.starts_with("def "));
+    assert!(noisy_code.contains(synthetic_name));
+    assert!(noisy_code.contains("loading noisy"));
+
+    let main = module("main");
+    assert_eq!(main["strategy"], "entry");
+    assert!(region(main).contains("print(helper(), "));
+    assert_eq!(modules.last(), Some(main));
+}
+
+#[test]
+fn test_module_map_is_not_written_by_default() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+    let output_dir = TempDir::new().expect("create output dir");
+    let output = output_dir.path().join("app.py");
+
+    BundleOrchestrator::new(Config::default())
+        .bundle(&entry, &output, false)
+        .expect("bundling should succeed");
+
+    assert!(!output_dir.path().join("app.py.map.json").exists());
+}
@@ -0,0 +1,83 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn run_bundle(bundled: &str) -> String {
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn test_loggers_keep_original_module_names() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let package_dir = project_dir.path().join("pkg");
+    fs::create_dir(&package_dir).expect("create package");
+    fs::write(package_dir.join("__init__.py"), "").expect("write __init__");
+    // A module-level logger makes this a wrapper module
+    fs::write(
+        package_dir.join("worker.py"),
+        "import logging\n\nlogger = logging.getLogger(__name__)\n\n\ndef worker_logger_name():\n    return logger.name\n",
+    )
+    .expect("write worker");
+    // Only definitions, so this module is inlined
+    fs::write(
+        package_dir.join("util.py"),
+        "import logging\n\n\ndef util_logger_name():\n    return logging.getLogger(__name__).name\n",
+    )
+    .expect("write util");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import logging\n\nfrom pkg.util import util_logger_name\nfrom pkg.worker import worker_logger_name\n\nprint(worker_logger_name())\nprint(util_logger_name())\nprint(logging.getLogger(__name__).name)\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    assert_eq!(
+        run_bundle(&bundled),
+        "pkg.worker\npkg.util\n__main__",
+        "{bundled}"
+    );
+}
+
+#[test]
+fn test_bundled_modules_see_their_original_name() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("registry.py"),
+        "REGISTRY = {}\n\n\ndef register(key):\n    REGISTRY[f\"{__name__}.{key}\"] = True\n    return sorted(REGISTRY)\n\n\nif __name__ == \"__main__\":\n    print(\"registry run as a script\")\n",
+    )
+    .expect("write registry");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from registry import register\n\nprint(register(\"widget\"))\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    assert_eq!(run_bundle(&bundled), "['registry.widget']", "{bundled}");
+}
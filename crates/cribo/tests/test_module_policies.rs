@@ -0,0 +1,101 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::graph_export::BundleGraphNodeKind;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn write_project(project_dir: &Path) {
+    fs::write(
+        project_dir.join("settings.py"),
+        "DEBUG = True\nprint(\"loading settings\")\n",
+    )
+    .expect("write settings");
+    fs::write(
+        project_dir.join("helpers.py"),
+        "def shout(text):\n    return text.upper()\n",
+    )
+    .expect("write helpers");
+    fs::write(
+        project_dir.join("plugins.py"),
+        "NAME = \"plugins from disk\"\n",
+    )
+    .expect("write plugins");
+    fs::write(
+        project_dir.join("main.py"),
+        "from settings import DEBUG\nfrom helpers import shout\nfrom plugins import NAME\n\nprint(shout(\"debug\" if DEBUG else \"quiet\"), NAME)\n",
+    )
+    .expect("write entry");
+}
+
+#[test]
+fn test_configured_policies_override_heuristics() {
+    let project_dir = TempDir::new().expect("create project dir");
+    write_project(project_dir.path());
+    let entry = project_dir.path().join("main.py");
+
+    let mut config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    config.force_inline.insert("settings".to_owned());
+    config.force_wrapper.insert("helpers".to_owned());
+    config.keep_external.insert("plugins".to_owned());
+
+    let graph = BundleOrchestrator::new(config.clone())
+        .dependency_graph(&entry)
+        .expect("graph export should succeed");
+    let kind_of = |name: &str| {
+        graph
+            .nodes
+            .iter()
+            .find(|node| node.name == name)
+            .map(|node| node.kind)
+    };
+    assert_eq!(kind_of("settings"), Some(BundleGraphNodeKind::Inlined));
+    assert_eq!(kind_of("helpers"), Some(BundleGraphNodeKind::Wrapper));
+    assert_eq!(kind_of("plugins"), None, "{:?}", graph.nodes);
+
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle with module policies");
+    assert!(!bundled.contains("plugins from disk"), "{bundled}");
+
+    // The module kept external is imported from next to the bundle at runtime
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(project_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "loading settings\nDEBUG plugins from disk\n"
+    );
+}
+
+#[test]
+fn test_conflicting_policies_are_rejected() {
+    let mut config = Config::default();
+    config.force_inline.insert("settings".to_owned());
+    config.force_wrapper.insert("settings".to_owned());
+    let error = config
+        .validate_module_policies()
+        .expect_err("conflicting policies");
+    assert!(
+        error
+            .to_string()
+            .contains("'settings' is listed in both force_inline and force_wrapper"),
+        "{error}"
+    );
+}
@@ -0,0 +1,90 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_bundle(bundle_path: &Path, args: &[&str]) -> Output {
+    Command::new("python3")
+        .arg(bundle_path)
+        .args(args)
+        .current_dir(bundle_path.parent().expect("bundle has a parent directory"))
+        .output()
+        .expect("run python3")
+}
+
+#[test]
+fn test_directory_entry_dispatches_to_scripts() {
+    let tools_dir = TempDir::new().expect("create tools dir");
+    // Imported by both scripts, so it is a shared helper rather than a command
+    fs::write(
+        tools_dir.path().join("common.py"),
+        "import sys\n\n\ndef args():\n    return sys.argv[1:]\n",
+    )
+    .expect("write common");
+    fs::write(
+        tools_dir.path().join("greet.py"),
+        "import common\n\n\ndef main():\n    print(\"Hello, \" + \" \".join(common.args()))\n\n\nif __name__ == \"__main__\":\n    main()\n",
+    )
+    .expect("write greet");
+    fs::write(
+        tools_dir.path().join("count.py"),
+        "from common import args\n\nif __name__ == \"__main__\":\n    print(len(args()))\n",
+    )
+    .expect("write count");
+    fs::write(tools_dir.path().join("_private.py"), "VALUE = 1\n").expect("write private");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(tools_dir.path(), false)
+        .expect("bundling should succeed");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("tools.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+
+    let output = run_bundle(&bundle_path, &["greet", "cribo", "users"]);
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Only the dispatched script runs
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "Hello, cribo users",
+        "{bundled}"
+    );
+
+    let output = run_bundle(&bundle_path, &["count", "a", "b", "c"]);
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3");
+
+    let output = run_bundle(&bundle_path, &["common"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("{count,greet}"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_directory_entry_without_scripts_fails() {
+    let tools_dir = TempDir::new().expect("create tools dir");
+    fs::write(tools_dir.path().join("_helpers.py"), "VALUE = 1\n").expect("write helpers");
+
+    let result =
+        BundleOrchestrator::new(Config::default()).bundle_to_string(tools_dir.path(), false);
+    let err = result.expect_err("bundling a directory without scripts should fail");
+    assert!(
+        err.to_string().contains("no scripts to dispatch to"),
+        "{err}"
+    );
+}
@@ -0,0 +1,96 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use cribo::resolver::{ModuleResolver, ResolutionKind};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A `company` namespace package split across two source roots, with no
+/// `__init__.py` anywhere
+fn write_monorepo(root: &Path) {
+    let utils = root.join("libs/company/util");
+    let api = root.join("services/company/api");
+    fs::create_dir_all(&utils).expect("create libs portion");
+    fs::create_dir_all(&api).expect("create services portion");
+    fs::write(
+        utils.join("strings.py"),
+        "def shout(text):\n    return text.upper()\n",
+    )
+    .expect("write strings");
+    fs::write(
+        api.join("handlers.py"),
+        "from company.util.strings import shout\n\n\ndef handle(text):\n    return \"handled \" + shout(text)\n",
+    )
+    .expect("write handlers");
+    fs::write(
+        root.join("services/main.py"),
+        "from company.api import handlers\n\nprint(handlers.handle(\"hi\"))\n",
+    )
+    .expect("write entry");
+}
+
+fn monorepo_config(root: &Path) -> Config {
+    let root = root.canonicalize().expect("canonical root");
+    Config {
+        src: vec![root.join("services"), root.join("libs")],
+        respect_pythonpath: false,
+        ..Config::default()
+    }
+}
+
+#[test]
+fn test_namespace_portions_span_source_roots() {
+    let project_dir = TempDir::new().expect("create project dir");
+    write_monorepo(project_dir.path());
+    let config = monorepo_config(project_dir.path());
+    let services = config.src[0].clone();
+    let libs = config.src[1].clone();
+
+    let mut resolver = ModuleResolver::new(config).expect("create resolver");
+    let company = resolver.resolve("company").expect("resolve company");
+    assert_eq!(company.kind, ResolutionKind::Namespace);
+    assert_eq!(
+        company.namespace_portions,
+        [services.join("company"), libs.join("company")]
+    );
+    let util = resolver
+        .resolve("company.util")
+        .expect("resolve company.util");
+    assert_eq!(util.kind, ResolutionKind::Namespace);
+    assert_eq!(util.origin_root, Some(libs.clone()));
+    let strings = resolver
+        .resolve("company.util.strings")
+        .expect("resolve company.util.strings");
+    assert_eq!(strings.kind, ResolutionKind::FirstParty);
+    assert_eq!(strings.path, Some(libs.join("company/util/strings.py")));
+}
+
+#[test]
+fn test_namespace_package_split_across_roots_bundles() {
+    let project_dir = TempDir::new().expect("create project dir");
+    write_monorepo(project_dir.path());
+    let entry = project_dir.path().join("services/main.py");
+
+    let bundled = BundleOrchestrator::new(monorepo_config(project_dir.path()))
+        .bundle_to_string(&entry, false)
+        .expect("namespace packages should bundle");
+
+    // Run from an empty directory, so nothing can be imported from the source tree
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "handled HI");
+}
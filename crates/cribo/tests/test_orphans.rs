@@ -0,0 +1,68 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+fn write_project(project_dir: &TempDir) -> std::path::PathBuf {
+    let root = project_dir.path();
+    fs::create_dir_all(root.join("pkg")).expect("create pkg");
+    fs::create_dir_all(root.join("legacy")).expect("create legacy");
+    fs::write(root.join("pkg/__init__.py"), "").expect("write pkg");
+    fs::write(root.join("pkg/used.py"), "VALUE = 1\n").expect("write used");
+    fs::write(root.join("pkg/dead.py"), "VALUE = 2\n").expect("write dead");
+    fs::write(root.join("legacy/__init__.py"), "").expect("write legacy");
+    fs::write(root.join("legacy/old.py"), "VALUE = 3\n").expect("write old");
+    fs::write(root.join("unused.py"), "VALUE = 4\n").expect("write unused");
+    let entry = root.join("main.py");
+    fs::write(&entry, "from pkg.used import VALUE\n\nprint(VALUE)\n").expect("write entry");
+    entry
+}
+
+#[test]
+fn test_orphans_lists_unreachable_first_party_modules() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+
+    let orphans = BundleOrchestrator::new(Config::default())
+        .orphans(&entry)
+        .expect("analysis should succeed");
+    let names: Vec<&str> = orphans.iter().map(|orphan| orphan.name.as_str()).collect();
+    // `pkg` itself runs whenever `pkg.used` is imported, so it is not an orphan
+    assert_eq!(names, ["legacy", "legacy.old", "pkg.dead", "unused"]);
+    let unused = orphans
+        .iter()
+        .find(|orphan| orphan.name == "unused")
+        .expect("unused is listed");
+    assert!(unused.path.ends_with("unused.py"), "{:?}", unused.path);
+}
+
+#[test]
+fn test_report_includes_orphans() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+
+    let output_dir = TempDir::new().expect("create output dir");
+    let output = output_dir.path().join("app.py");
+    let config = Config {
+        emit_report: true,
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle(&entry, &output, false)
+        .expect("bundling should succeed");
+
+    let report: Value = serde_json::from_str(
+        &fs::read_to_string(output_dir.path().join("app.py.report.json")).expect("read report"),
+    )
+    .expect("report is valid JSON");
+    let names: Vec<&str> = report["orphans"]
+        .as_array()
+        .expect("orphans array")
+        .iter()
+        .map(|orphan| orphan["name"].as_str().expect("orphan name"))
+        .collect();
+    assert_eq!(names, ["legacy", "legacy.old", "pkg.dead", "unused"]);
+}
@@ -0,0 +1,111 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_all_artifacts_from_one_run() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("greet.py"),
+        "def greet(name):\n    return \"Hello, \" + name\n",
+    )
+    .expect("write greet");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import json\n\nfrom greet import greet\n\nprint(json.dumps(greet(\"world\")))\n",
+    )
+    .expect("write entry");
+
+    let output_dir = TempDir::new().expect("create output dir");
+    let output = output_dir.path().join("app.py");
+    let config = Config {
+        module_map: true,
+        emit_pyz: true,
+        emit_report: true,
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle(&entry, &output, true)
+        .expect("bundling should succeed");
+
+    assert!(output.exists());
+    assert!(output_dir.path().join("app.py.map.json").exists());
+
+    let report: Value = serde_json::from_str(
+        &fs::read_to_string(output_dir.path().join("app.py.report.json")).expect("read report"),
+    )
+    .expect("report is valid JSON");
+    assert_eq!(report["version"], 1);
+    let names: Vec<&str> = report["modules"]
+        .as_array()
+        .expect("modules array")
+        .iter()
+        .map(|module| module["name"].as_str().expect("module name"))
+        .collect();
+    assert_eq!(names, ["greet", "main"]);
+    assert_eq!(report["requirements"], Value::Array(Vec::new()));
+    assert_eq!(report["imports"]["stdlib"], 1);
+
+    // The zip application runs the same code as the flat bundle
+    let pyz = output_dir.path().join("app.pyz");
+    let run = |path: &std::path::Path| {
+        let result = Command::new("python3")
+            .arg(path)
+            .current_dir(output_dir.path())
+            .output()
+            .expect("run python3");
+        assert!(
+            result.status.success(),
+            "{path:?} failed: {}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        String::from_utf8_lossy(&result.stdout).into_owned()
+    };
+    assert_eq!(run(&pyz), "\"Hello, world\"\n");
+    assert_eq!(run(&pyz), run(&output));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_executable_bundle_runs_through_its_shebang() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "print(\"direct\")\n").expect("write entry");
+
+    let output = project_dir.path().join("app.py");
+    let config = Config {
+        executable: true,
+        shebang: "/usr/bin/env python3".to_owned(),
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle(&entry, &output, false)
+        .expect("bundling should succeed");
+
+    let bundled = fs::read_to_string(&output).expect("read bundle");
+    assert!(bundled.starts_with("#!/usr/bin/env python3\n"), "{bundled}");
+    let mode = fs::metadata(&output)
+        .expect("bundle metadata")
+        .permissions()
+        .mode();
+    assert_ne!(mode & 0o100, 0, "{mode:o}");
+
+    let result = Command::new(&output)
+        .current_dir(project_dir.path())
+        .output()
+        .expect("run bundle directly");
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&result.stdout), "direct\n");
+}
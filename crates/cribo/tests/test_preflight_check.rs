@@ -0,0 +1,101 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn bundle_project(preflight_check: bool) -> String {
+    let project_dir = TempDir::new().expect("create project dir");
+    // Third-party imports must not be mistaken for first-party ones
+    fs::write(
+        project_dir.path().join("client.py"),
+        "import cribo_missing_http\n\n\ndef fetch():\n    return cribo_missing_http.get()\n",
+    )
+    .expect("write client");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from __future__ import annotations\n\nimport cribo_missing_yaml\n\nfrom client import fetch\n\nprint(fetch())\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        preflight_check,
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed")
+}
+
+fn run_bundle(bundled: &str) -> std::process::Output {
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, bundled).expect("write bundle");
+    Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3")
+}
+
+#[test]
+fn test_preflight_check_lists_missing_packages() {
+    let bundled = bundle_project(true);
+
+    let output = run_bundle(&bundled);
+    assert_eq!(output.status.code(), Some(1), "bundle:\n{bundled}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Install them with: pip install cribo_missing_http cribo_missing_yaml"),
+        "unexpected stderr:\n{stderr}"
+    );
+    assert!(
+        !stderr.contains("Traceback"),
+        "unexpected stderr:\n{stderr}"
+    );
+}
+
+#[test]
+fn test_preflight_check_is_off_by_default() {
+    let bundled = bundle_project(false);
+
+    assert!(!bundled.contains("__cribo_preflight"));
+    let output = run_bundle(&bundled);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ModuleNotFoundError"));
+}
+
+#[test]
+fn test_preflight_check_follows_every_future_import() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("client.py"),
+        "from __future__ import generator_stop\n\nimport cribo_missing_http\n\n\ndef fetch():\n    return cribo_missing_http.get()\n",
+    )
+    .expect("write client");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "\"\"\"Fetch and print.\"\"\"\n\nfrom __future__ import annotations\nfrom __future__ import division\n\nfrom client import fetch\n\nprint(fetch())\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        preflight_check: true,
+        ..Default::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    // A future import after the check would be a SyntaxError
+    let output = run_bundle(&bundled);
+    assert_eq!(output.status.code(), Some(1), "bundle:\n{bundled}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("pip install cribo_missing_http"),
+        "unexpected stderr:\n{stderr}"
+    );
+}
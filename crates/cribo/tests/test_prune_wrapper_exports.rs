@@ -0,0 +1,77 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// `utils` has a side effect, so it is bundled as a wrapper module
+fn write_project(project_dir: &TempDir, entry_source: &str) -> std::path::PathBuf {
+    fs::write(
+        project_dir.path().join("utils.py"),
+        "print(\"loading utils\")\n\nLIMIT = 3\n\n\ndef helper():\n    return \"helped\"\n\n\ndef unused():\n    return \"never called\"\n",
+    )
+    .expect("write utils");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, entry_source).expect("write entry");
+    entry
+}
+
+fn bundle(entry: &std::path::Path, prune: bool) -> String {
+    let config = Config {
+        prune_wrapper_exports: prune,
+        ..Config::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle_to_string(entry, false)
+        .expect("bundling should succeed")
+}
+
+fn run(bundled: &str) -> String {
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_owned()
+}
+
+#[test]
+fn test_only_accessed_attributes_are_exported() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(
+        &project_dir,
+        "import utils\n\n\ndef main():\n    return utils.helper() * utils.LIMIT\n\n\nprint(main())\n",
+    );
+
+    let full = bundle(&entry, false);
+    assert!(full.contains("module.unused = unused"), "{full}");
+
+    let pruned = bundle(&entry, true);
+    assert!(pruned.contains("module.helper = helper"), "{pruned}");
+    assert!(pruned.contains("module.LIMIT = LIMIT"), "{pruned}");
+    assert!(!pruned.contains("module.unused = unused"), "{pruned}");
+    assert_eq!(run(&pruned), "loading utils\nhelpedhelpedhelped");
+}
+
+#[test]
+fn test_module_used_as_object_keeps_all_exports() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(
+        &project_dir,
+        "import utils\n\nprint(getattr(utils, \"un\" + \"used\")())\n",
+    );
+
+    let pruned = bundle(&entry, true);
+    assert!(pruned.contains("module.unused = unused"), "{pruned}");
+    assert_eq!(run(&pruned), "loading utils\nnever called");
+}
@@ -0,0 +1,58 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+#[test]
+fn test_pyproject_settings_come_from_tool_cribo() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let pyproject = project_dir.path().join("pyproject.toml");
+    fs::write(
+        &pyproject,
+        r#"[project]
+name = "app"
+version = "1.0"
+
+[build-system]
+build-backend = "cribo.build_backend"
+
+[tool.cribo]
+entry = "src/app/main.py"
+output = "src/app/_bundle.py"
+build_backend = "setuptools.build_meta"
+"#,
+    )
+    .expect("write pyproject");
+
+    let config = Config::load_from_file(&pyproject).expect("load pyproject");
+    assert_eq!(config.entry.as_deref(), Some("src/app/main.py"));
+    assert_eq!(config.output, Some(PathBuf::from("src/app/_bundle.py")));
+}
+
+#[test]
+fn test_pyproject_without_tool_cribo_uses_defaults() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let pyproject = project_dir.path().join("pyproject.toml");
+    fs::write(&pyproject, "[tool.ruff]\nline-length = 100\n").expect("write pyproject");
+
+    let config = Config::load_from_file(&pyproject).expect("load pyproject");
+    assert_eq!(config.entry, None);
+    assert_eq!(config.src, Config::default().src);
+}
+
+#[test]
+fn test_pyproject_accepts_isort_style_classification_keys() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let pyproject = project_dir.path().join("pyproject.toml");
+    fs::write(
+        &pyproject,
+        "[tool.cribo]\nknown-first-party = [\"app\"]\nknown-third-party = [\"vendored\"]\n",
+    )
+    .expect("write pyproject");
+
+    let config = Config::load_from_file(&pyproject).expect("load pyproject");
+    assert!(config.known_first_party.contains("app"));
+    assert!(config.known_third_party.contains("vendored"));
+}
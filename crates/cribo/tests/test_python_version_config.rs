@@ -28,11 +28,12 @@ mod tests {
         assert_eq!(Config::parse_target_version("py311").unwrap(), 11);
         assert_eq!(Config::parse_target_version("py312").unwrap(), 12);
         assert_eq!(Config::parse_target_version("py313").unwrap(), 13);
+        assert_eq!(Config::parse_target_version("py314").unwrap(), 14);
 
         // Test invalid version strings
         assert!(Config::parse_target_version("invalid").is_err());
         assert!(Config::parse_target_version("py37").is_err()); // too old
-        assert!(Config::parse_target_version("py314").is_err()); // too new
+        assert!(Config::parse_target_version("py315").is_err()); // too new
         assert!(Config::parse_target_version("3.10").is_err()); // wrong format
     }
 
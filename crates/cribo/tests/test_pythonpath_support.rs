@@ -1,11 +1,12 @@
 #![allow(clippy::disallowed_methods)]
 
+use serial_test::serial;
 use std::fs;
 use std::path::PathBuf;
 use tempfile::TempDir;
 
 use cribo::config::Config;
-use cribo::resolver::ModuleResolver;
+use cribo::resolver::{ModuleResolver, PythonPathGuard};
 
 #[test]
 fn test_pythonpath_module_discovery() {
@@ -319,3 +320,31 @@ fn test_path_canonicalization() {
         scan_dirs
     );
 }
+
+#[test]
+#[serial]
+fn test_pythonpath_environment_can_be_ignored() {
+    let temp_dir = TempDir::new().unwrap();
+    let pythonpath_dir = temp_dir.path().join("pythonpath_modules");
+    let src_dir = temp_dir.path().join("src");
+    fs::create_dir_all(&pythonpath_dir).unwrap();
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(pythonpath_dir.join("env_module.py"), "VALUE = 1").unwrap();
+
+    let _guard = PythonPathGuard::new(&pythonpath_dir.to_string_lossy());
+
+    let respecting = ModuleResolver::new(Config {
+        src: vec![src_dir.clone()],
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(respecting.get_first_party_modules().contains("env_module"));
+
+    let ignoring = ModuleResolver::new(Config {
+        src: vec![src_dir],
+        respect_pythonpath: false,
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(!ignoring.get_first_party_modules().contains("env_module"));
+}
@@ -0,0 +1,67 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn bundle_project(register_inlined_modules: bool) -> String {
+    let project_dir = TempDir::new().expect("create project dir");
+    let package_dir = project_dir.path().join("pkg");
+    fs::create_dir(&package_dir).expect("create package");
+    fs::write(package_dir.join("__init__.py"), "").expect("write __init__");
+    fs::write(
+        package_dir.join("util.py"),
+        "def helper():\n    return \"helped\"\n",
+    )
+    .expect("write util");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import importlib\nimport sys\n\nfrom pkg.util import helper\n\nprint(helper())\nprint(sys.modules[\"pkg.util\"].helper())\nprint(importlib.import_module(\"pkg.util\").helper())\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        register_inlined_modules,
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed")
+}
+
+#[test]
+fn test_inlined_modules_are_registered_when_enabled() {
+    let bundled = bundle_project(true);
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "helped\nhelped\nhelped"
+    );
+}
+
+#[test]
+fn test_inlined_modules_are_not_registered_by_default() {
+    let bundled = bundle_project(false);
+
+    assert!(
+        !bundled.contains("__cribo_module"),
+        "inlined modules should not be registered:\n{bundled}"
+    );
+}
@@ -0,0 +1,64 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use indexmap::IndexMap;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_relative_import_above_src_uses_external_fallback() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let src_dir = project_dir.path().join("src");
+    let vendored_dir = src_dir.join("vendored");
+    fs::create_dir_all(&vendored_dir).expect("create vendored package");
+    fs::write(vendored_dir.join("__init__.py"), "").expect("write package init");
+    fs::write(
+        vendored_dir.join("api.py"),
+        "from ...shared import greet\n\n\ndef hello():\n    return greet(\"bundle\")\n",
+    )
+    .expect("write api");
+    let entry = src_dir.join("main.py");
+    fs::write(&entry, "from vendored.api import hello\n\nprint(hello())\n").expect("write entry");
+
+    let config = Config {
+        src: vec![src_dir],
+        relative_import_fallbacks: IndexMap::from([("vendored".to_owned(), "upstream".to_owned())]),
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(
+        bundled.contains("from upstream.shared import greet"),
+        "{bundled}"
+    );
+
+    // The fallback package is provided by the runtime environment
+    let run_dir = TempDir::new().expect("create run dir");
+    let upstream_dir = run_dir.path().join("upstream");
+    fs::create_dir_all(&upstream_dir).expect("create upstream package");
+    fs::write(upstream_dir.join("__init__.py"), "").expect("write upstream init");
+    fs::write(
+        upstream_dir.join("shared.py"),
+        "def greet(name):\n    return f\"hello from {name}\"\n",
+    )
+    .expect("write upstream shared");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "hello from bundle"
+    );
+}
@@ -0,0 +1,52 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_user_names_matching_runtime_identifiers_are_kept() {
+    let project_dir = TempDir::new().expect("create project dir");
+    // The print makes `plugins` a wrapper module, whose init function builds `module`
+    fs::write(
+        project_dir.path().join("plugins.py"),
+        "print(\"loading plugins\")\n\nmodule = \"user module\"\n__cribo_modules = [\"user registry\"]\n\n\nclass CriboBundledFinder:\n    kind = \"user finder\"\n\n\ndef describe():\n    return module + \", \" + __cribo_modules[0] + \", \" + CriboBundledFinder.kind\n",
+    )
+    .expect("write plugins");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import plugins\n\nprint(plugins.describe())\nprint(plugins.module, plugins.CriboBundledFinder.kind)\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(
+        bundled.contains("module_1 = types.ModuleType("),
+        "{bundled}"
+    );
+    assert!(bundled.contains("__cribo_modules_1 = {"), "{bundled}");
+    assert!(bundled.contains("class CriboBundledFinder_1"), "{bundled}");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "loading plugins\nuser module, user registry, user finder\nuser module user finder"
+    );
+}
@@ -0,0 +1,114 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+use tempfile::{NamedTempFile, TempDir};
+
+#[test]
+fn test_runtime_names_use_configured_prefix() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(project_dir.path().join("state.py"), "counter = 0\n").expect("write state");
+    fs::write(
+        project_dir.path().join("tracker.py"),
+        "from state import counter\n\nprint(\"tracker loaded\")\n\n\ndef bump():\n    global counter\n    counter = counter + 1\n    return counter\n",
+    )
+    .expect("write tracker");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "import tracker\n\nprint(tracker.bump())\n").expect("write entry");
+
+    let config = Config {
+        runtime_prefix: "__app_".to_owned(),
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    assert!(!bundled.contains("__cribo_"), "{bundled}");
+    assert!(bundled.contains("def __app_init_"), "{bundled}");
+    assert!(bundled.contains("__app_modules = {"), "{bundled}");
+    assert!(
+        bundled.contains("global __app_tracker_counter"),
+        "{bundled}"
+    );
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "tracker loaded\n1"
+    );
+}
+
+#[test]
+fn test_bundled_module_finder_uses_prefix_and_avoids_collisions() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(project_dir.path().join("helpers.py"), "VALUE = 1\n").expect("write helpers");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import importlib.util\n\nfrom helpers import VALUE\n\n__app_BundledModuleFinder = \"user finder\"\n\nprint(importlib.util.find_spec(\"helpers\") is not None, __app_BundledModuleFinder)\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        runtime_prefix: "__app_".to_owned(),
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(
+        bundled.contains("class __app_BundledModuleFinder_1:"),
+        "{bundled}"
+    );
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "True user finder"
+    );
+}
+
+#[test]
+fn test_runtime_prefix_must_start_an_identifier() {
+    for prefix in ["", "1x_", "my-app_"] {
+        let mut config_file = NamedTempFile::new().expect("create config file");
+        writeln!(config_file, "runtime_prefix = {prefix:?}").expect("write config");
+
+        let err = Config::load_from_file(config_file.path())
+            .expect_err("invalid prefix should be rejected");
+        assert!(err.to_string().contains("runtime_prefix"), "{err}");
+    }
+
+    let mut config_file = NamedTempFile::new().expect("create config file");
+    writeln!(config_file, "runtime_prefix = \"_bundle_\"").expect("write config");
+    let config = Config::load_from_file(config_file.path()).expect("valid prefix");
+    assert_eq!(config.runtime_prefix, "_bundle_");
+}
@@ -0,0 +1,37 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::path::Path;
+
+fn bundle_simple_math(section_markers: bool) -> String {
+    let entry =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bundling/simple_math/main.py");
+    let config = Config {
+        section_markers,
+        ..Default::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("simple_math should bundle")
+}
+
+#[test]
+fn test_section_markers_precede_each_module() {
+    let bundled = bundle_simple_math(true);
+
+    let calculator = bundled
+        .find("# ── module: calculator (calculator.py) ──")
+        .expect("calculator marker");
+    let main = bundled
+        .find("# ── module: main (main.py) ──")
+        .expect("entry marker");
+    assert!(bundled.contains("# ── module: utils (utils.py) ──"));
+    assert!(calculator < main);
+    assert!(bundled[main..].contains("def main"));
+}
+
+#[test]
+fn test_section_markers_are_off_by_default() {
+    assert!(!bundle_simple_math(false).contains("# ── module:"));
+}
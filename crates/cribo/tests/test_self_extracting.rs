@@ -0,0 +1,107 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_self_extracting_bundle_runs_like_the_plain_one() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let greetings: String = (0..50)
+        .map(|index| {
+            format!("def greet_{index}(name):\n    return f\"Hello #{index}, {{name}}\"\n\n\n")
+        })
+        .collect();
+    fs::write(project_dir.path().join("greetings.py"), greetings).expect("write greetings");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from greetings import greet_7\n\nprint(greet_7(\"world\"), __name__)\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    let plain = BundleOrchestrator::new(config.clone())
+        .bundle_to_string(&entry, false)
+        .expect("plain bundle");
+    let compressed = BundleOrchestrator::new(Config {
+        self_extracting: true,
+        ..config
+    })
+    .bundle_to_string(&entry, false)
+    .expect("self-extracting bundle");
+
+    assert!(compressed.len() < plain.len() / 2, "{compressed}");
+    assert!(!compressed.contains("greet_7"), "{compressed}");
+    // The header stays readable and the stub is a single statement
+    assert!(compressed.starts_with("#!/usr/bin/env python3\n"));
+    assert_eq!(
+        compressed
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .count(),
+        1
+    );
+
+    let run = |code: &str, name: &str| {
+        let path = project_dir.path().join(name);
+        fs::write(&path, code).expect("write bundle");
+        let output = Command::new("python3")
+            .arg(&path)
+            .output()
+            .expect("run python3");
+        assert!(
+            output.status.success(),
+            "bundle failed: {}\n{code}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(
+        run(&compressed, "compressed.py"),
+        "Hello #7, world __main__\n"
+    );
+    assert_eq!(run(&compressed, "compressed.py"), run(&plain, "plain.py"));
+}
+
+#[test]
+fn test_diff_compares_self_extracting_bundles() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "print(\"Hello\")\n").expect("write entry");
+    let output = project_dir.path().join("bundle.py");
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        self_extracting: true,
+        ..Config::default()
+    };
+
+    BundleOrchestrator::new(config.clone())
+        .bundle(&entry, &output, false)
+        .expect("write self-extracting bundle");
+    let unchanged = BundleOrchestrator::new(config.clone())
+        .diff(&entry, &output)
+        .expect("diff unchanged bundle");
+    assert!(unchanged.is_empty(), "{unchanged}");
+
+    fs::write(&entry, "print(\"Goodbye\")\n").expect("change entry");
+    let changed = BundleOrchestrator::new(config)
+        .diff(&entry, &output)
+        .expect("diff changed bundle");
+    // Only the compressed stub line differs
+    assert_eq!(
+        changed
+            .lines()
+            .filter(|line| line.starts_with('+') && !line.starts_with("+++"))
+            .count(),
+        1,
+        "{changed}"
+    );
+}
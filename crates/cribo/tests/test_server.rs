@@ -0,0 +1,169 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::server::BundleServer;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+fn responses(output: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("response is JSON"))
+        .collect()
+}
+
+#[test]
+fn test_server_reuses_parsed_modules_between_requests() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("greetings.py"),
+        "def greet(name):\n    return 'Hello, ' + name\n",
+    )
+    .expect("write greetings");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from greetings import greet\n\nprint(greet('world'))\n",
+    )
+    .expect("write entry");
+    let output = project_dir.path().join("bundle.py");
+
+    let server = BundleServer::new(Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    });
+    let requests = [
+        serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "bundle", "params": {"entry": entry}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "bundle", "params": {"entry": entry, "output": output}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "status"}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "bundle", "params": {"entry": "missing.py"}}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 5, "method": "frobnicate"}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 6, "method": "shutdown"}),
+        serde_json::json!({"jsonrpc": "2.0", "id": 7, "method": "status"}),
+    ]
+    .map(|request| request.to_string())
+    .join("\n");
+
+    let mut written = Vec::new();
+    let shut_down = server
+        .serve(requests.as_bytes(), &mut written)
+        .expect("serve requests");
+    assert!(shut_down);
+    let responses = responses(&written);
+    // Nothing is answered after `shutdown`
+    assert_eq!(responses.len(), 6, "{responses:?}");
+
+    let code = responses[0]["result"]["code"]
+        .as_str()
+        .expect("bundle returns code");
+    assert!(code.contains("def greet(name):"), "{code}");
+    assert_eq!(
+        responses[1]["result"]["output"],
+        Value::String(output.display().to_string())
+    );
+    assert!(
+        fs::read_to_string(&output)
+            .expect("read bundle")
+            .contains("def greet(name):")
+    );
+    // The second bundle parsed nothing
+    assert_eq!(responses[2]["result"]["parses"], 2, "{responses:?}");
+    assert_eq!(
+        responses[2]["result"]["resolver_builds"], 1,
+        "{responses:?}"
+    );
+    assert_eq!(responses[3]["error"]["code"], -32000);
+    assert_eq!(responses[3]["error"]["data"]["kind"], "ResolutionError");
+    assert_eq!(responses[4]["error"]["code"], -32601);
+    assert_eq!(responses[5]["id"], 6);
+    assert_eq!(responses[5]["result"], Value::Null);
+}
+
+#[test]
+fn test_server_reports_malformed_requests() {
+    let server = BundleServer::new(Config::default());
+    let mut written = Vec::new();
+    let shut_down = server
+        .serve(
+            "not json\n{\"jsonrpc\": \"2.0\", \"id\": 1}\n{\"jsonrpc\": \"2.0\", \"method\": \"status\"}\n"
+                .as_bytes(),
+            &mut written,
+        )
+        .expect("serve requests");
+    assert!(!shut_down);
+
+    // The notification gets no response
+    let responses = responses(&written);
+    assert_eq!(responses.len(), 2, "{responses:?}");
+    assert_eq!(responses[0]["error"]["code"], -32700);
+    assert_eq!(responses[0]["id"], Value::Null);
+    assert_eq!(responses[1]["error"]["code"], -32600);
+    assert_eq!(responses[1]["id"], 1);
+}
+
+#[test]
+fn test_server_rescans_sources_after_files_are_added() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "print('first')\n").expect("write entry");
+    let server = BundleServer::new(Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    });
+    let serve = |requests: &[Value]| {
+        let input = requests
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut written = Vec::new();
+        server
+            .serve(input.as_bytes(), &mut written)
+            .expect("serve requests");
+        responses(&written)
+    };
+    let bundle = serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "bundle", "params": {"entry": entry}});
+    let status = serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "status"});
+
+    let first = serve(&[bundle.clone(), bundle.clone(), status.clone()]);
+    assert_eq!(first[2]["result"]["resolver_builds"], 1, "{first:?}");
+
+    fs::write(
+        project_dir.path().join("greetings.py"),
+        "def greet(name):\n    return 'Hello, ' + name\n",
+    )
+    .expect("write greetings");
+    fs::write(
+        &entry,
+        "from greetings import greet\n\nprint(greet('world'))\n",
+    )
+    .expect("rewrite entry");
+
+    let second = serve(&[bundle, status]);
+    let code = second[0]["result"]["code"]
+        .as_str()
+        .expect("bundle returns code");
+    assert!(code.contains("def greet(name):"), "{code}");
+    assert_eq!(second[1]["result"]["resolver_builds"], 2, "{second:?}");
+}
+
+#[test]
+fn test_server_rejects_relative_output() {
+    let server = BundleServer::new(Config::default());
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "bundle",
+        "params": {"entry": "main.py", "output": "dist/bundle.py"},
+    });
+    let mut written = Vec::new();
+    server
+        .serve(request.to_string().as_bytes(), &mut written)
+        .expect("serve requests");
+
+    let responses = responses(&written);
+    assert_eq!(responses[0]["error"]["code"], -32602, "{responses:?}");
+}
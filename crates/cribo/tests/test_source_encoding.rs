@@ -0,0 +1,76 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_latin1_module_is_bundled_as_utf8() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("greetings.py"),
+        b"# -*- coding: latin-1 -*-\nGREETING = 'Ol\xe1, caf\xe9'\n",
+    )
+    .expect("write greetings");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "#!/usr/bin/env python3\n# coding: utf-8\nfrom greetings import GREETING\n\nprint(ascii(GREETING))\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle latin-1 module");
+    assert!(bundled.contains("'Olá, café'"), "{bundled}");
+    assert!(!bundled.contains("latin-1"), "{bundled}");
+
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "'Ol\\xe1, caf\\xe9'"
+    );
+}
+
+#[test]
+fn test_undeclared_non_utf8_module_is_reported() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("greetings.py"),
+        b"GREETING = 'caf\xe9'\n",
+    )
+    .expect("write greetings");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from greetings import GREETING\n\nprint(GREETING)\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    let error = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect_err("undeclared latin-1 is rejected");
+    assert!(format!("{error:#}").contains("coding"), "{error:#}");
+}
@@ -0,0 +1,77 @@
+#![allow(clippy::disallowed_methods)]
+
+use anyhow::Result;
+use cribo::config::Config;
+use cribo::logging::{self, LogSink};
+use cribo::orchestrator::BundleOrchestrator;
+use log::{Level, LevelFilter};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+#[derive(Default)]
+struct WarningSink {
+    warnings: Mutex<Vec<String>>,
+}
+
+impl LogSink for WarningSink {
+    fn enabled(&self, level: Level) -> bool {
+        level <= Level::Warn
+    }
+
+    fn log(&self, _level: Level, _target: &str, message: &str) {
+        self.warnings
+            .lock()
+            .expect("sink lock should not be poisoned")
+            .push(message.to_string());
+    }
+}
+
+#[test]
+fn test_star_imports_with_unknown_exports_are_reported() -> Result<()> {
+    logging::install(None, LevelFilter::Trace)?;
+
+    let project_dir = TempDir::new()?;
+    fs::write(
+        project_dir.path().join("registry.py"),
+        "HANDLERS = {\"a\": 1}\n__all__ = [name for name in HANDLERS]\n",
+    )?;
+    fs::write(
+        project_dir.path().join("plugins.py"),
+        "__all__ = [\"load\"]\n\n\ndef load():\n    return \"loaded\"\n",
+    )?;
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from registry import *\nfrom plugins import *\nfrom json import *\n\nprint(load())\n",
+    )?;
+
+    let sink = Arc::new(WarningSink::default());
+    let mut orchestrator = BundleOrchestrator::new(Config::default()).with_log_sink(sink.clone());
+    // Only the diagnostics matter here, not whether such a project bundles cleanly
+    let _ = orchestrator.bundle_to_string(&entry, false);
+
+    let warnings = sink
+        .warnings
+        .lock()
+        .expect("sink lock should not be poisoned");
+    let star_warnings: Vec<&String> = warnings
+        .iter()
+        .filter(|message| message.contains("import *"))
+        .collect();
+    assert_eq!(star_warnings.len(), 2, "{star_warnings:?}");
+    assert!(
+        star_warnings.iter().any(|message| message
+            .contains("`from registry import *` cannot be determined statically because its `__all__` is computed at runtime")),
+        "{star_warnings:?}"
+    );
+    assert!(
+        star_warnings
+            .iter()
+            .any(|message| message
+                .contains("`from json import *` targets a module outside the bundle")),
+        "{star_warnings:?}"
+    );
+
+    Ok(())
+}
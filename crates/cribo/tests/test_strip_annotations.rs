@@ -0,0 +1,56 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_strip_annotations_keeps_class_fields() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("shapes.py"),
+        "from dataclasses import dataclass\n\nLIMIT: int = 3\npending: list\n\n\n@dataclass\nclass Point:\n    x: int\n    y: int = 0\n\n\ndef scale(point: Point, factor: \"float\" = 2, *args: int, **kwargs: str) -> Point:\n    result: Point = Point(point.x * factor, point.y * factor)\n    return result\n",
+    )
+    .expect("write shapes");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from shapes import LIMIT, Point, scale\n\nprint(scale(Point(1, 2)), LIMIT)\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        strip_annotations: true,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle without annotations");
+    for annotation in [
+        "-> Point", "factor: ", "*args: ", "LIMIT: ", "pending", "result: ",
+    ] {
+        assert!(!bundled.contains(annotation), "{annotation}: {bundled}");
+    }
+    // Dataclass fields are annotations too
+    assert!(bundled.contains("    x: int\n"), "{bundled}");
+
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "Point(x=2, y=4) 3"
+    );
+}
@@ -0,0 +1,48 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_strip_asserts_removes_assert_statements() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("checks.py"),
+        "assert True, \"module check\"\n\n\ndef halve(value):\n    assert value % 2 == 0, \"odd value\"\n    return value // 2\n\n\ndef validate(value):\n    assert value > 0\n",
+    )
+    .expect("write checks");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from checks import halve, validate\n\nvalidate(-1)\nprint(halve(7))\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        strip_asserts: true,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle without asserts");
+    assert!(!bundled.contains("assert"), "{bundled}");
+
+    // The function left without statements still has a body
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3");
+}
@@ -0,0 +1,56 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_strip_docstrings_keeps_entry_docstrings_on_request() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("text.py"),
+        "\"\"\"Text helpers.\"\"\"\n\n\nclass Formatter:\n    \"\"\"Formats text.\"\"\"\n\n    def shout(self, text):\n        \"\"\"Upper-case text.\"\"\"\n        return text.upper()\n\n\ndef noop():\n    \"\"\"Does nothing.\"\"\"\n",
+    )
+    .expect("write text");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from text import Formatter, noop\n\n\ndef main():\n    \"\"\"Print a greeting.\"\"\"\n    noop()\n    print(Formatter().shout(\"hi\"))\n\n\nmain()\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        strip_docstrings: true,
+        keep_entry_docstrings: true,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle without docstrings");
+    for docstring in [
+        "Text helpers.",
+        "Formats text.",
+        "Upper-case text.",
+        "Does nothing.",
+    ] {
+        assert!(!bundled.contains(docstring), "{docstring}: {bundled}");
+    }
+    assert!(bundled.contains("Print a greeting."), "{bundled}");
+
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "HI");
+}
@@ -0,0 +1,77 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::cribo_graph::CircularDependencyType;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_components_are_reported_even_when_not_bundleable() {
+    let entry = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/bundling/xfail_class_level_cycles/main.py");
+
+    let components = BundleOrchestrator::new(Config::default())
+        .strongly_connected_components(&entry)
+        .expect("analysis should succeed");
+
+    assert_eq!(components.len(), 1);
+    let component = &components[0];
+    assert_eq!(component.modules, ["admin_class", "user_class"]);
+    assert_ne!(component.cycle_type, CircularDependencyType::FunctionLevel);
+    let edges: Vec<(&str, &str)> = component
+        .import_chain
+        .iter()
+        .map(|edge| (edge.from_module.as_str(), edge.to_module.as_str()))
+        .collect();
+    assert_eq!(
+        edges,
+        [("admin_class", "user_class"), ("user_class", "admin_class")]
+    );
+}
+
+#[test]
+fn test_components_are_listed_largest_first() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let root = project_dir.path();
+    let modules = [
+        (
+            "pair_a",
+            "import pair_b\n\ndef run():\n    return pair_b.run()\n",
+        ),
+        ("pair_b", "import pair_a\n\ndef run():\n    return pair_a\n"),
+        (
+            "ring_a",
+            "import ring_b\n\ndef run():\n    return ring_b.run()\n",
+        ),
+        (
+            "ring_b",
+            "import ring_c\n\ndef run():\n    return ring_c.run()\n",
+        ),
+        ("ring_c", "import ring_a\n\ndef run():\n    return ring_a\n"),
+        ("leaf", "VALUE = 1\n"),
+    ];
+    for (name, source) in modules {
+        fs::write(root.join(format!("{name}.py")), source).expect("write module");
+    }
+    let entry = root.join("main.py");
+    fs::write(&entry, "import leaf\nimport pair_a\nimport ring_a\n").expect("write entry");
+
+    let components = BundleOrchestrator::new(Config::default())
+        .strongly_connected_components(&entry)
+        .expect("analysis should succeed");
+
+    let clusters: Vec<&[String]> = components
+        .iter()
+        .map(|component| component.modules.as_slice())
+        .collect();
+    assert_eq!(
+        clusters,
+        [
+            &["ring_a", "ring_b", "ring_c"][..],
+            &["pair_a", "pair_b"][..]
+        ]
+    );
+    assert_eq!(components[0].import_chain.len(), 3);
+}
@@ -0,0 +1,87 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_stub_is_bundled_in_place_of_module() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let root = project_dir.path();
+    fs::create_dir_all(root.join("stubs")).expect("create stubs");
+    let stub = root.join("stubs/cloud_sdk_stub.py");
+    fs::write(
+        &stub,
+        "def upload(name):\n    return \"stub upload \" + name\n",
+    )
+    .expect("write stub");
+    let entry = root.join("main.py");
+    fs::write(
+        &entry,
+        "import cloud_sdk\n\nprint(cloud_sdk.upload(\"report.csv\"))\n",
+    )
+    .expect("write entry");
+
+    let output_dir = TempDir::new().expect("create output dir");
+    let output = output_dir.path().join("app.py");
+    let config = Config {
+        stub_modules: IndexMap::from([("cloud_sdk".to_owned(), stub.clone())]),
+        emit_report: true,
+        ..Config::default()
+    };
+    BundleOrchestrator::new(config)
+        .bundle(&entry, &output, false)
+        .expect("bundling should succeed");
+
+    // `cloud_sdk` is not installed; the bundle only works if the stub replaced it
+    let run = Command::new("python3")
+        .arg(&output)
+        .current_dir(output_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        run.status.success(),
+        "bundle failed: {}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout).trim(),
+        "stub upload report.csv"
+    );
+
+    let report: Value = serde_json::from_str(
+        &fs::read_to_string(output_dir.path().join("app.py.report.json")).expect("read report"),
+    )
+    .expect("report is valid JSON");
+    assert_eq!(report["requirements"], Value::Array(Vec::new()));
+    let stubbed = report["stubbed_modules"]
+        .as_array()
+        .expect("stubbed modules array");
+    assert_eq!(stubbed.len(), 1);
+    assert_eq!(stubbed[0]["name"], "cloud_sdk");
+    assert_eq!(stubbed[0]["stub"], stub.to_string_lossy().as_ref());
+}
+
+#[test]
+fn test_replace_table_configures_stubs() {
+    let config_dir = TempDir::new().expect("create config dir");
+    let config_path = config_dir.path().join("cribo.toml");
+    fs::write(
+        &config_path,
+        "[replace]\n\"heavy.telemetry\" = \"shims/noop_telemetry.py\"\n",
+    )
+    .expect("write config");
+
+    let config = Config::load_from_file(&config_path).expect("load config");
+    assert_eq!(
+        config.stub_modules,
+        IndexMap::from([(
+            "heavy.telemetry".to_owned(),
+            "shims/noop_telemetry.py".into()
+        )])
+    );
+}
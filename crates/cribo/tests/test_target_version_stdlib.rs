@@ -0,0 +1,58 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_newer_stdlib_modules_are_hoisted_for_newer_targets() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("settings.py"),
+        "import tomllib\n\n\ndef load(text):\n    return tomllib.loads(text)\n",
+    )
+    .expect("write settings");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from settings import load\n\nprint(load('name = \"cribo\"')[\"name\"])\n",
+    )
+    .expect("write entry");
+
+    let config = Config {
+        src: vec![project_dir.path().to_path_buf()],
+        respect_pythonpath: false,
+        target_version: "py312".to_owned(),
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundle for py312");
+
+    // `tomllib` is in the 3.12 standard library, so its import joins the hoisted ones
+    let lines: Vec<&str> = bundled.lines().collect();
+    let import_line = lines
+        .iter()
+        .position(|line| *line == "import tomllib")
+        .expect("top-level tomllib import");
+    let definition_line = lines
+        .iter()
+        .position(|line| line.starts_with("def load"))
+        .expect("inlined load function");
+    assert!(import_line < definition_line, "{bundled}");
+
+    let bundle_path = project_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "cribo");
+}
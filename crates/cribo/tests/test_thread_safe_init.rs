@@ -0,0 +1,50 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_concurrent_first_imports_initialize_once() {
+    let project_dir = TempDir::new().expect("create project dir");
+    // The counter and the sleep make `slow` a wrapper module whose initialization
+    // stays in progress while the other threads import it
+    fs::write(
+        project_dir.path().join("slow.py"),
+        "import builtins\nimport time\n\nbuiltins.cribo_test_loads = getattr(builtins, \"cribo_test_loads\", 0) + 1\ntime.sleep(0.2)\nREADY = True\n",
+    )
+    .expect("write slow");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import builtins\nimport threading\n\nresults = []\n\n\ndef worker():\n    import slow\n\n    results.append(getattr(slow, \"READY\", False))\n\n\nthreads = [threading.Thread(target=worker) for _ in range(8)]\nfor thread in threads:\n    thread.start()\nfor thread in threads:\n    thread.join()\nprint(builtins.cribo_test_loads, len(results), all(results))\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(bundled.contains("with __cribo_init_lock:"), "{bundled}");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Every thread sees the fully initialized module, and its body runs once
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "1 8 True",
+        "{bundled}"
+    );
+}
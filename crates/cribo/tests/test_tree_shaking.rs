@@ -0,0 +1,80 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn write_project(dir: &std::path::Path) -> std::path::PathBuf {
+    fs::write(
+        dir.join("textutils.py"),
+        "SEPARATOR = \"-\"\nUNUSED_WIDTH = 80\n\n\ndef _join(words):\n    return SEPARATOR.join(words)\n\n\ndef slugify(text):\n    return _join(text.lower().split())\n\n\ndef unused_shout(text):\n    return text.upper()\n\n\nclass UnusedFormatter:\n    def format(self, text):\n        return unused_shout(text)\n",
+    )
+    .expect("write textutils");
+    fs::write(
+        dir.join("plugins.py"),
+        "REGISTRY = []\n\n\ndef register(func):\n    REGISTRY.append(func.__name__)\n    return func\n\n\n@register\ndef never_called():\n    pass\n",
+    )
+    .expect("write plugins");
+    let entry = dir.join("main.py");
+    fs::write(
+        &entry,
+        "from plugins import REGISTRY\nfrom textutils import slugify\n\nprint(slugify(\"Hello Tree Shaking\"), REGISTRY)\n",
+    )
+    .expect("write entry");
+    entry
+}
+
+#[test]
+fn test_tree_shaking_drops_unused_definitions() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(project_dir.path());
+
+    let config = Config {
+        tree_shake: true,
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    for unused in ["UNUSED_WIDTH", "unused_shout", "UnusedFormatter"] {
+        assert!(!bundled.contains(unused), "{unused} was kept:\n{bundled}");
+    }
+    // Used transitively, or registered by a decorator as a side effect
+    for kept in ["SEPARATOR", "_join", "def slugify", "never_called"] {
+        assert!(bundled.contains(kept), "{kept} was dropped:\n{bundled}");
+    }
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "hello-tree-shaking ['never_called']"
+    );
+}
+
+#[test]
+fn test_definitions_are_kept_without_tree_shaking() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(project_dir.path());
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    for name in ["UNUSED_WIDTH", "unused_shout", "UnusedFormatter"] {
+        assert!(bundled.contains(name), "{name} was dropped:\n{bundled}");
+    }
+}
@@ -0,0 +1,57 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::api::{self, BundleOptions, BundleRequest};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_type_checker_findings_name_the_module() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("loud.py"),
+        "def shout(text: str) -> str:\n    return text.upper()\n",
+    )
+    .expect("write loud");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "from loud import shout\n\nprint(shout(\"hi\"))\n").expect("write entry");
+
+    // Stands in for mypy: reports every line that defines `shout`
+    let checker = project_dir.path().join("checker.py");
+    fs::write(
+        &checker,
+        "import sys\n\nwith open(sys.argv[1]) as bundle:\n    for number, line in enumerate(bundle, 1):\n        if line.startswith(\"def shout\"):\n            print(\"bundle.py:%d: error: Missing stub  [fake]\" % number)\nprint(\"Found 1 error in 1 file\")\nsys.exit(1)\n",
+    )
+    .expect("write checker");
+
+    let options = BundleOptions::new().with_type_checker(format!("python3 {}", checker.display()));
+    let result = api::bundle(&BundleRequest::new(&entry).with_options(options))
+        .expect("type checker findings should not fail the bundle");
+
+    let findings = &result.diagnostics.type_check_findings;
+    assert_eq!(findings.len(), 1, "{findings:?}");
+    assert_eq!(findings[0].module.as_deref(), Some("loud"));
+    assert_eq!(findings[0].severity, "error");
+    assert_eq!(findings[0].message, "Missing stub  [fake]");
+    let line = result
+        .code
+        .lines()
+        .nth(findings[0].line - 1)
+        .expect("finding points into the bundle");
+    assert!(line.starts_with("def shout"), "{line}");
+}
+
+#[test]
+fn test_missing_type_checker_fails_the_bundle() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = project_dir.path().join("main.py");
+    fs::write(&entry, "print(\"hi\")\n").expect("write entry");
+
+    let options = BundleOptions::new().with_type_checker("cribo-no-such-type-checker --strict");
+    let err = api::bundle(&BundleRequest::new(&entry).with_options(options))
+        .expect_err("an unrunnable type checker should fail the bundle");
+    assert!(
+        err.to_string()
+            .contains("Failed to run type checker `cribo-no-such-type-checker --strict`"),
+        "{err}"
+    );
+}
@@ -0,0 +1,54 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_aliased_type_checking_imports_are_not_bundled() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("models.py"),
+        "print(\"loading models\")\n\n\nclass User:\n    pass\n",
+    )
+    .expect("write models");
+    fs::write(
+        project_dir.path().join("schemas.py"),
+        "print(\"loading schemas\")\n",
+    )
+    .expect("write schemas");
+    fs::write(
+        project_dir.path().join("service.py"),
+        "NAME = \"service\"\n",
+    )
+    .expect("write service");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "import typing as t\nfrom typing import TYPE_CHECKING as TC\n\nif TC:\n    from models import User\nif t.TYPE_CHECKING:\n    import schemas\nelse:\n    import service\n\n\ndef describe(user: \"User\") -> str:\n    return service.NAME\n\n\nprint(describe(None))\n",
+    )
+    .expect("write entry");
+
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+    assert!(!bundled.contains("loading models"), "{bundled}");
+    assert!(!bundled.contains("loading schemas"), "{bundled}");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "service");
+}
@@ -0,0 +1,88 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Bundle the project's `main.py`, run the bundle away from the sources and return
+/// its stdout
+fn bundle_and_run(project_dir: &TempDir) -> String {
+    let entry = project_dir.path().join("main.py");
+    let bundled = BundleOrchestrator::new(Config::default())
+        .bundle_to_string(&entry, false)
+        .expect("bundling should succeed");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_owned()
+}
+
+#[test]
+fn test_unpacked_names_of_inlined_modules_are_renamed() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("left.py"),
+        "x, *others = (1, 2, 3)\n\n\ndef total():\n    return x + sum(others)\n",
+    )
+    .expect("write left");
+    fs::write(
+        project_dir.path().join("right.py"),
+        "[x, y] = \"r\", \"s\"\n",
+    )
+    .expect("write right");
+    fs::write(
+        project_dir.path().join("main.py"),
+        "from left import x as lx, others, total\nfrom right import x as rx, y\n\nprint(lx, others, total(), rx, y)\n",
+    )
+    .expect("write entry");
+
+    assert_eq!(bundle_and_run(&project_dir), "1 [2, 3] 6 r s");
+}
+
+#[test]
+fn test_unpacked_names_of_wrapper_modules_are_exported() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("bounds.py"),
+        "print(\"loading bounds\")\n\nlow, (mid, *high) = 0, [5, 10, 20]\n",
+    )
+    .expect("write bounds");
+    fs::write(
+        project_dir.path().join("main.py"),
+        "import bounds\n\nprint(bounds.low, bounds.mid, bounds.high)\n",
+    )
+    .expect("write entry");
+
+    assert_eq!(bundle_and_run(&project_dir), "loading bounds\n0 5 [10, 20]");
+}
+
+#[test]
+fn test_storing_into_attributes_makes_a_module_a_wrapper() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("settings.py"),
+        "class Config:\n    debug = False\n\n\nConfig.debug, level = True, 3\n",
+    )
+    .expect("write settings");
+    fs::write(
+        project_dir.path().join("main.py"),
+        "from settings import Config, level\n\nprint(Config.debug, level)\n",
+    )
+    .expect("write entry");
+
+    assert_eq!(bundle_and_run(&project_dir), "True 3");
+}
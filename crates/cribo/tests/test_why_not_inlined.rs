@@ -0,0 +1,53 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_why_not_inlined_lists_rules_and_statements() {
+    let project_dir = TempDir::new().expect("create project dir");
+    fs::write(
+        project_dir.path().join("settings.py"),
+        "DEBUG = True\nprint(\"loading settings\")\n",
+    )
+    .expect("write settings");
+    fs::write(
+        project_dir.path().join("helpers.py"),
+        "def load():\n    import json\n    return json\n",
+    )
+    .expect("write helpers");
+    fs::write(project_dir.path().join("plain.py"), "VALUE = 1\n").expect("write plain");
+    fs::write(project_dir.path().join("pure.py"), "NAME = \"pure\"\n").expect("write pure");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from settings import DEBUG\nfrom helpers import load\nimport plain\nfrom pure import NAME\n\nprint(DEBUG, load().__name__, plain.VALUE, NAME)\n",
+    )
+    .expect("write entry");
+
+    let explanation = BundleOrchestrator::new(Config::default())
+        .why_not_inlined(&entry)
+        .expect("analysis should succeed");
+
+    assert!(
+        explanation.contains(
+            "settings (settings.py) is not inlined:\n  settings.py:2: top-level statement with side effects: print(\"loading settings\")\n"
+        ),
+        "{explanation}"
+    );
+    assert!(
+        explanation.contains(
+            "helpers (helpers.py) is not inlined:\n  helpers.py:1: function-scoped import: def load(): ...\n"
+        ),
+        "{explanation}"
+    );
+    assert!(
+        explanation.contains(
+            "plain (plain.py) is not inlined:\n  main.py:3: imported directly: import plain\n"
+        ),
+        "{explanation}"
+    );
+    assert!(!explanation.contains("pure"), "{explanation}");
+}
@@ -0,0 +1,72 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn write_project(project_dir: &TempDir) -> std::path::PathBuf {
+    fs::write(
+        project_dir.path().join("settings.py"),
+        "import contextlib\n\nDEFAULTS = {\"mode\": \"fast\"}\nMODE = \"unset\"\n\nwith contextlib.suppress(KeyError):\n    MODE = DEFAULTS[\"mode\"]\n",
+    )
+    .expect("write settings");
+    fs::write(project_dir.path().join("legacy.py"), "MODE = \"slow\"\n").expect("write legacy");
+    let entry = project_dir.path().join("main.py");
+    fs::write(
+        &entry,
+        "from settings import MODE\nfrom legacy import MODE as LEGACY_MODE\n\nprint(MODE, LEGACY_MODE)\n",
+    )
+    .expect("write entry");
+    entry
+}
+
+fn bundle_and_run(entry: &std::path::Path, inline_with_managers: &[&str]) -> (String, String) {
+    let config = Config {
+        inline_with_managers: inline_with_managers
+            .iter()
+            .map(|name| (*name).to_owned())
+            .collect(),
+        ..Config::default()
+    };
+    let bundled = BundleOrchestrator::new(config)
+        .bundle_to_string(entry, false)
+        .expect("bundling should succeed");
+
+    let run_dir = TempDir::new().expect("create run dir");
+    let bundle_path = run_dir.path().join("bundle.py");
+    fs::write(&bundle_path, &bundled).expect("write bundle");
+    let output = Command::new("python3")
+        .arg(&bundle_path)
+        .current_dir(run_dir.path())
+        .output()
+        .expect("run python3");
+    assert!(
+        output.status.success(),
+        "bundle failed: {}\n{bundled}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    (bundled, stdout)
+}
+
+#[test]
+fn test_with_block_keeps_module_in_wrapper_by_default() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+
+    let (bundled, stdout) = bundle_and_run(&entry, &[]);
+    assert!(bundled.contains("def __cribo_init_"), "{bundled}");
+    assert_eq!(stdout, "fast slow");
+}
+
+#[test]
+fn test_with_block_of_listed_manager_is_inlined() {
+    let project_dir = TempDir::new().expect("create project dir");
+    let entry = write_project(&project_dir);
+
+    let (bundled, stdout) = bundle_and_run(&entry, &["contextlib.suppress"]);
+    assert!(!bundled.contains("def __cribo_init_"), "{bundled}");
+    assert_eq!(stdout, "fast slow");
+}
@@ -0,0 +1,175 @@
+#![allow(clippy::disallowed_methods)]
+
+use cribo::config::{BundleTarget, Config};
+use cribo::workspace::Workspace;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+/// Two apps sharing a `common` module, with a config declaring a bundle for each
+fn write_workspace() -> (TempDir, Config) {
+    let dir = TempDir::new().expect("create workspace dir");
+    let src = dir.path().join("src");
+    fs::create_dir_all(&src).expect("create src");
+    fs::write(
+        src.join("common.py"),
+        "def greet(name):\n    return 'hi ' + name\n",
+    )
+    .expect("write common");
+    fs::write(
+        src.join("api.py"),
+        "from common import greet\n\nprint(greet('api'))\n",
+    )
+    .expect("write api");
+    fs::write(
+        src.join("worker.py"),
+        "from common import greet\n\nprint(greet('worker'))\n",
+    )
+    .expect("write worker");
+
+    let dist = dir.path().join("dist");
+    fs::create_dir_all(&dist).expect("create dist");
+    let config_path = dir.path().join("cribo.toml");
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+src = [{src:?}]
+
+[[bundles]]
+name = "api"
+entry = "api"
+output = {api:?}
+
+[[bundles]]
+name = "worker"
+entry = {worker_entry:?}
+output = {worker:?}
+section_markers = true
+"#,
+            src = src.display().to_string(),
+            api = dist.join("api.py").display().to_string(),
+            worker_entry = src.join("worker.py").display().to_string(),
+            worker = dist.join("worker.py").display().to_string(),
+        ),
+    )
+    .expect("write config");
+
+    let config = Config::load_from_file(&config_path).expect("load config");
+    (dir, config)
+}
+
+#[test]
+fn test_all_bundles_share_parsed_modules() {
+    let (dir, config) = write_workspace();
+
+    let workspace = Workspace::from_config(&config, &[]).expect("select bundles");
+    workspace
+        .bundle_all(false)
+        .expect("bundling should succeed");
+
+    let api = fs::read_to_string(dir.path().join("dist/api.py")).expect("read api bundle");
+    let worker = fs::read_to_string(dir.path().join("dist/worker.py")).expect("read worker bundle");
+    assert!(api.contains("print(greet(\"api\"))"), "{api}");
+    assert!(worker.contains("print(greet(\"worker\"))"), "{worker}");
+
+    // Overrides only apply to their own bundle
+    assert!(!api.contains("# ── module:"), "{api}");
+    assert!(worker.contains("# ── module: common"), "{worker}");
+
+    // common.py is parsed once even though both bundles use it
+    assert_eq!(workspace.module_cache().parse_count(), 3);
+}
+
+#[test]
+fn test_only_selects_named_bundles() {
+    let (dir, config) = write_workspace();
+
+    let workspace =
+        Workspace::from_config(&config, &["worker".to_owned()]).expect("select bundles");
+    workspace
+        .bundle_all(false)
+        .expect("bundling should succeed");
+
+    assert!(dir.path().join("dist/worker.py").exists());
+    assert!(!dir.path().join("dist/api.py").exists());
+}
+
+#[test]
+fn test_unknown_bundle_is_an_error() {
+    let (_dir, config) = write_workspace();
+
+    let error = Workspace::from_config(&config, &["web".to_owned()])
+        .expect_err("unknown bundle should be rejected");
+    assert!(error.to_string().contains("web"), "{error}");
+    assert!(error.to_string().contains("api, worker"), "{error}");
+}
+
+#[test]
+fn test_failing_bundle_does_not_stop_the_others() {
+    let (dir, mut config) = write_workspace();
+    let broken = dir.path().join("src/broken.py");
+    fs::write(&broken, "def broken(:\n").expect("write broken");
+    config.bundles.insert(
+        0,
+        BundleTarget {
+            name: "broken".to_owned(),
+            entry: broken.display().to_string(),
+            output: dir.path().join("dist/broken.py"),
+            overrides: toml::Table::new(),
+        },
+    );
+
+    let workspace = Workspace::from_config(&config, &[])
+        .expect("select bundles")
+        .with_jobs(3);
+    let error = workspace
+        .bundle_all(false)
+        .expect_err("the broken bundle should fail");
+
+    assert!(error.to_string().contains("broken.py"), "{error}");
+    assert!(!dir.path().join("dist/broken.py").exists());
+    assert!(dir.path().join("dist/api.py").exists());
+    assert!(dir.path().join("dist/worker.py").exists());
+}
+
+#[test]
+fn test_several_entries_share_parsed_modules() {
+    let (dir, config) = write_workspace();
+    let src = dir.path().join("src");
+    let out = dir.path().join("out");
+    fs::create_dir_all(&out).expect("create out");
+
+    // Entries are script paths or module names, as with `--entry`
+    let workspace = Workspace::from_entries(
+        &config,
+        &[src.join("api.py"), PathBuf::from("worker")],
+        &out,
+    )
+    .expect("select entries");
+    workspace
+        .bundle_all(false)
+        .expect("bundling should succeed");
+
+    let api = fs::read_to_string(out.join("api.py")).expect("read api bundle");
+    let worker = fs::read_to_string(out.join("worker.py")).expect("read worker bundle");
+    assert!(api.contains("print(greet(\"api\"))"), "{api}");
+    assert!(worker.contains("print(greet(\"worker\"))"), "{worker}");
+    assert_eq!(workspace.module_cache().parse_count(), 3);
+}
+
+#[test]
+fn test_entries_with_the_same_stem_are_an_error() {
+    let (dir, config) = write_workspace();
+    let other = dir.path().join("other");
+    fs::create_dir_all(&other).expect("create other");
+    fs::write(other.join("api.py"), "print('other')\n").expect("write other api");
+
+    let err = Workspace::from_entries(
+        &config,
+        &[dir.path().join("src/api.py"), other.join("api.py")],
+        &dir.path().join("out"),
+    )
+    .expect_err("two api.py entries cannot share an output directory");
+    assert!(err.to_string().contains("would both be bundled"), "{err}");
+}
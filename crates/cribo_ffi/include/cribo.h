@@ -0,0 +1,94 @@
+/* Generated by cbindgen from crates/cribo_ffi. Do not edit by hand. */
+
+#ifndef CRIBO_H
+#define CRIBO_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The call succeeded
+ */
+#define CRIBO_OK 0
+
+/**
+ * A required pointer was null or a string was not valid UTF-8
+ */
+#define CRIBO_ERROR_INVALID_ARGUMENT 1
+
+/**
+ * A module or the entry point could not be resolved or read
+ */
+#define CRIBO_ERROR_RESOLUTION 2
+
+/**
+ * A Python source file could not be parsed
+ */
+#define CRIBO_ERROR_PARSE 3
+
+/**
+ * The project contains circular dependencies that cannot be bundled
+ */
+#define CRIBO_ERROR_CYCLE 4
+
+/**
+ * Generating the bundle failed
+ */
+#define CRIBO_ERROR_EMIT 5
+
+/**
+ * The configuration is invalid or could not be loaded
+ */
+#define CRIBO_ERROR_CONFIG 6
+
+/**
+ * The bundler panicked; this is always a bug
+ */
+#define CRIBO_ERROR_PANIC 7
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Bundle the script at `entry_path` into a newly allocated, NUL-terminated buffer
+ *
+ * `config_path` may be null, in which case configuration is discovered the same
+ * way the CLI does. On success `*out_code` receives the bundle, which must be
+ * released with [`cribo_free_buffer`], and `*out_len` (when not null) its length in
+ * bytes excluding the terminator. On failure `*out_code` is set to null and a
+ * `CRIBO_ERROR_*` status is returned.
+ *
+ * # Safety
+ * `entry_path` and `config_path` must be null or valid NUL-terminated strings, and
+ * `out_code`/`out_len` must be null or valid for writes.
+ */
+int cribo_bundle_to_buffer(const char *entry_path,
+                           const char *config_path,
+                           char **out_code,
+                           size_t *out_len);
+
+/**
+ * Release a buffer returned by [`cribo_bundle_to_buffer`]; null is ignored
+ *
+ * # Safety
+ * `buffer` must be null or a pointer obtained from [`cribo_bundle_to_buffer`] that
+ * has not been freed yet.
+ */
+void cribo_free_buffer(char *buffer);
+
+/**
+ * Message describing the most recent failure on the calling thread, or null
+ *
+ * The pointer stays valid until the next `cribo_*` call on the same thread.
+ */
+const char *cribo_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CRIBO_H */
@@ -0,0 +1,184 @@
+//! C ABI for embedding the bundler in non-Rust hosts
+//!
+//! Hosts call [`cribo_bundle_to_buffer`] to bundle an entry script in-process and
+//! release the returned buffer with [`cribo_free_buffer`]. When a call fails, the
+//! returned status identifies the failure class and [`cribo_last_error`] describes it.
+//! Errors are tracked per thread, so concurrent calls from different threads are safe.
+//!
+//! The C declarations live in `include/cribo.h`, generated with cbindgen.
+#![allow(unsafe_code)]
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use cribo::CriboError;
+use cribo::api::{self, BundleOptions, BundleRequest};
+
+/// The call succeeded
+pub const CRIBO_OK: c_int = 0;
+/// A required pointer was null or a string was not valid UTF-8
+pub const CRIBO_ERROR_INVALID_ARGUMENT: c_int = 1;
+/// A module or the entry point could not be resolved or read
+pub const CRIBO_ERROR_RESOLUTION: c_int = 2;
+/// A Python source file could not be parsed
+pub const CRIBO_ERROR_PARSE: c_int = 3;
+/// The project contains circular dependencies that cannot be bundled
+pub const CRIBO_ERROR_CYCLE: c_int = 4;
+/// Generating the bundle failed
+pub const CRIBO_ERROR_EMIT: c_int = 5;
+/// The configuration is invalid or could not be loaded
+pub const CRIBO_ERROR_CONFIG: c_int = 6;
+/// The bundler panicked; this is always a bug
+pub const CRIBO_ERROR_PANIC: c_int = 7;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: &str) {
+    // The C side would stop reading at an interior NUL anyway
+    let message = CString::new(message).unwrap_or_else(|err| {
+        let nul_position = err.nul_position();
+        CString::new(&message[..nul_position]).unwrap_or_default()
+    });
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+const fn error_code(error: &CriboError) -> c_int {
+    match error {
+        CriboError::Resolution { .. } => CRIBO_ERROR_RESOLUTION,
+        CriboError::Parse { .. } => CRIBO_ERROR_PARSE,
+        CriboError::Cycle { .. } => CRIBO_ERROR_CYCLE,
+        CriboError::Emit { .. } => CRIBO_ERROR_EMIT,
+        CriboError::Config { .. } => CRIBO_ERROR_CONFIG,
+    }
+}
+
+/// Borrow a NUL-terminated UTF-8 string from the host
+///
+/// # Safety
+/// `value` must be null or point to a NUL-terminated string that outlives the call.
+unsafe fn str_arg<'a>(value: *const c_char, name: &str) -> Result<Option<&'a str>, String> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: the caller guarantees a valid NUL-terminated string
+    unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .map(Some)
+        .map_err(|_| format!("`{name}` is not valid UTF-8"))
+}
+
+fn bundle(entry_path: &str, config_path: Option<&str>) -> Result<CString, (c_int, String)> {
+    let to_failure = |error: CriboError| (error_code(&error), error.to_string());
+
+    let options = match config_path {
+        Some(config_path) => BundleOptions::new().with_config_file(config_path),
+        None => BundleOptions::new().with_config_discovery(),
+    };
+    let result =
+        api::bundle(&BundleRequest::new(entry_path).with_options(options)).map_err(to_failure)?;
+    CString::new(result.code).map_err(|_| {
+        (
+            CRIBO_ERROR_EMIT,
+            "The bundled code contains a NUL byte".to_owned(),
+        )
+    })
+}
+
+/// Bundle the script at `entry_path` into a newly allocated, NUL-terminated buffer
+///
+/// `config_path` may be null, in which case configuration is discovered the same
+/// way the CLI does. On success `*out_code` receives the bundle, which must be
+/// released with [`cribo_free_buffer`], and `*out_len` (when not null) its length in
+/// bytes excluding the terminator. On failure `*out_code` is set to null and a
+/// `CRIBO_ERROR_*` status is returned.
+///
+/// # Safety
+/// `entry_path` and `config_path` must be null or valid NUL-terminated strings, and
+/// `out_code`/`out_len` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cribo_bundle_to_buffer(
+    entry_path: *const c_char,
+    config_path: *const c_char,
+    out_code: *mut *mut c_char,
+    out_len: *mut usize,
+) -> c_int {
+    clear_last_error();
+    if out_code.is_null() {
+        set_last_error("`out_code` must not be null");
+        return CRIBO_ERROR_INVALID_ARGUMENT;
+    }
+    // SAFETY: checked for null above; the caller guarantees it is writable
+    unsafe { *out_code = ptr::null_mut() };
+
+    // SAFETY: forwarded caller guarantees
+    let arguments = unsafe { str_arg(entry_path, "entry_path") }.and_then(|entry| {
+        // SAFETY: forwarded caller guarantees
+        let config = unsafe { str_arg(config_path, "config_path") }?;
+        entry
+            .map(|entry| (entry, config))
+            .ok_or_else(|| "`entry_path` must not be null".to_owned())
+    });
+    let (entry, config) = match arguments {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            set_last_error(&message);
+            return CRIBO_ERROR_INVALID_ARGUMENT;
+        }
+    };
+
+    #[allow(clippy::disallowed_methods)]
+    // Panics must not unwind across the C ABI
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| bundle(entry, config)));
+    match outcome {
+        Ok(Ok(code)) => {
+            if !out_len.is_null() {
+                // SAFETY: checked for null; the caller guarantees it is writable
+                unsafe { *out_len = code.as_bytes().len() };
+            }
+            // SAFETY: checked for null above
+            unsafe { *out_code = code.into_raw() };
+            CRIBO_OK
+        }
+        Ok(Err((status, message))) => {
+            set_last_error(&message);
+            status
+        }
+        Err(_) => {
+            set_last_error("The bundler panicked; please report this as a bug");
+            CRIBO_ERROR_PANIC
+        }
+    }
+}
+
+/// Release a buffer returned by [`cribo_bundle_to_buffer`]; null is ignored
+///
+/// # Safety
+/// `buffer` must be null or a pointer obtained from [`cribo_bundle_to_buffer`] that
+/// has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cribo_free_buffer(buffer: *mut c_char) {
+    if !buffer.is_null() {
+        // SAFETY: the caller guarantees the pointer came from `CString::into_raw`
+        drop(unsafe { CString::from_raw(buffer) });
+    }
+}
+
+/// Message describing the most recent failure on the calling thread, or null
+///
+/// The pointer stays valid until the next `cribo_*` call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn cribo_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
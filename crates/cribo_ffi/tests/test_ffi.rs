@@ -0,0 +1,49 @@
+#![allow(clippy::disallowed_methods)]
+#![allow(unsafe_code)]
+
+use cribo_ffi::{
+    CRIBO_ERROR_INVALID_ARGUMENT, CRIBO_ERROR_RESOLUTION, CRIBO_OK, cribo_bundle_to_buffer,
+    cribo_free_buffer, cribo_last_error,
+};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+#[test]
+fn test_bundle_to_buffer_returns_bundled_code() {
+    let entry = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../cribo/tests/fixtures/bundling/simple_math/main.py");
+    let entry = CString::new(entry.to_str().expect("utf-8 path")).expect("no NUL in path");
+    let mut code = ptr::null_mut();
+    let mut len = 0;
+
+    let status =
+        unsafe { cribo_bundle_to_buffer(entry.as_ptr(), ptr::null(), &mut code, &mut len) };
+
+    assert_eq!(status, CRIBO_OK);
+    assert!(cribo_last_error().is_null());
+    let bundled = unsafe { CStr::from_ptr(code) }
+        .to_str()
+        .expect("utf-8 bundle");
+    assert_eq!(bundled.len(), len);
+    assert!(bundled.contains("def add"));
+    unsafe { cribo_free_buffer(code) };
+}
+
+#[test]
+fn test_bundle_to_buffer_reports_errors() {
+    let mut code = ptr::null_mut();
+    let status =
+        unsafe { cribo_bundle_to_buffer(ptr::null(), ptr::null(), &mut code, ptr::null_mut()) };
+    assert_eq!(status, CRIBO_ERROR_INVALID_ARGUMENT);
+    assert!(code.is_null());
+
+    let missing = CString::new("does/not/exist/main.py").expect("no NUL in path");
+    let status = unsafe {
+        cribo_bundle_to_buffer(missing.as_ptr(), ptr::null(), &mut code, ptr::null_mut())
+    };
+    assert_eq!(status, CRIBO_ERROR_RESOLUTION);
+    assert!(code.is_null());
+    let message = unsafe { CStr::from_ptr(cribo_last_error()) };
+    assert!(!message.to_bytes().is_empty());
+}
@@ -0,0 +1,99 @@
+//! Node.js bindings for the bundler
+//!
+//! Exposes `bundle`, `check` and `graph` to JavaScript so monorepo tooling can run the
+//! bundler in-process instead of spawning the CLI. Build the addon with `napi build`;
+//! it wraps the semver-stable `cribo::api` facade.
+use cribo::CriboError;
+use cribo::api::{self, BundleRequest};
+use napi::{Error, Result, Status};
+use napi_derive::napi;
+
+/// Options shared by every entry point
+#[napi(object)]
+#[derive(Debug, Default)]
+pub struct BundleOptions {
+    /// Configuration file; when omitted, configuration is discovered like the CLI does
+    pub config_path: Option<String>,
+    /// Target Python version, e.g. `py311`
+    pub target_version: Option<String>,
+}
+
+/// Result of `bundle()`
+#[napi(object)]
+#[derive(Debug)]
+pub struct BundleOutput {
+    /// The bundled Python source
+    pub code: String,
+    /// Inlined module names in bundle order
+    pub modules: Vec<String>,
+    /// Sorted top-level third-party packages
+    pub requirements: Vec<String>,
+}
+
+/// A module in the result of `graph()`
+#[napi(object)]
+#[derive(Debug)]
+pub struct GraphNode {
+    pub name: String,
+    pub path: Option<String>,
+    /// Names of the first-party modules this module imports
+    pub dependencies: Vec<String>,
+}
+
+fn to_js_error(error: &CriboError) -> Error {
+    let kind = match error {
+        CriboError::Resolution { .. } => "ResolutionError",
+        CriboError::Parse { .. } => "ParseError",
+        CriboError::Cycle { .. } => "CycleError",
+        CriboError::Emit { .. } => "EmitError",
+        CriboError::Config { .. } => "ConfigError",
+    };
+    Error::new(Status::GenericFailure, format!("{}: {}", kind, error))
+}
+
+fn request(entry: &str, options: Option<BundleOptions>) -> BundleRequest {
+    let options = options.unwrap_or_default();
+    let mut settings = match options.config_path {
+        Some(config_path) => api::BundleOptions::new().with_config_file(config_path),
+        None => api::BundleOptions::new().with_config_discovery(),
+    };
+    if let Some(target_version) = options.target_version {
+        settings = settings.with_target_version(target_version);
+    }
+    BundleRequest::new(entry).with_options(settings)
+}
+
+/// Bundle `entry` and everything it imports into a single Python module
+#[napi]
+pub fn bundle(entry: String, options: Option<BundleOptions>) -> Result<BundleOutput> {
+    let result = api::bundle(&request(&entry, options)).map_err(|err| to_js_error(&err))?;
+    Ok(BundleOutput {
+        code: result.code,
+        modules: result
+            .modules
+            .into_iter()
+            .map(|module| module.name)
+            .collect(),
+        requirements: result.requirements,
+    })
+}
+
+/// Throw if `entry` cannot be bundled, without generating code
+#[napi]
+pub fn check(entry: String, options: Option<BundleOptions>) -> Result<()> {
+    api::check(&request(&entry, options)).map_err(|err| to_js_error(&err))
+}
+
+/// First-party modules reachable from `entry` and their dependencies
+#[napi]
+pub fn graph(entry: String, options: Option<BundleOptions>) -> Result<Vec<GraphNode>> {
+    let modules = api::graph(&request(&entry, options)).map_err(|err| to_js_error(&err))?;
+    Ok(modules
+        .into_iter()
+        .map(|module| GraphNode {
+            name: module.name,
+            path: module.path.map(|path| path.to_string_lossy().into_owned()),
+            dependencies: module.dependencies,
+        })
+        .collect())
+}
@@ -0,0 +1,291 @@
+//! Python bindings for the bundler
+//!
+//! The native `cribo._cribo` module of the `cribo` Python package. It exposes a
+//! `Bundler` class so build pipelines written in Python can run the bundler in-process
+//! and customize it with transform callbacks, and `main()`, which runs the command line
+//! for `python -m cribo` and the `cribo` script. Build the package with `maturin build`
+//! from the repository root; `Bundler` wraps the semver-stable `cribo::api` facade.
+//!
+//! ```python
+//! from cribo import Bundler
+//!
+//! def stamp_version(module, path, source):
+//!     return source.replace("@VERSION@", "1.2.3")
+//!
+//! bundler = Bundler(target_version="py311")
+//! bundler.add_transform(stamp_version)
+//! result = bundler.bundle("src/main.py")
+//! print(result.code, result.requirements, result.warnings)
+//! ```
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use cribo::CriboError;
+use cribo::api::{self, BundleOptions, BundlePlugin, BundleRequest, BundleResult, PluginModule};
+use cribo::logging::LogSink;
+use cribo::stats::StatsCollector;
+use log::{Level, LevelFilter};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::prelude::*;
+
+create_exception!(
+    cribo,
+    BundleError,
+    PyException,
+    "Raised when a module cannot be resolved, parsed or bundled"
+);
+
+fn to_py_error(error: &CriboError) -> PyErr {
+    let kind = match error {
+        CriboError::Resolution { .. } => "ResolutionError",
+        CriboError::Parse { .. } => "ParseError",
+        CriboError::Cycle { .. } => "CycleError",
+        CriboError::Emit { .. } => "EmitError",
+        CriboError::Config { .. } => "ConfigError",
+    };
+    let message = match error {
+        // Keep the cause, so an exception raised by a transform is not lost
+        CriboError::Emit { source, .. } => format!("{:#}", source),
+        _ => error.to_string(),
+    };
+    BundleError::new_err(format!("{}: {}", kind, message))
+}
+
+/// A Python callable run on each module's source before it is parsed
+///
+/// The callable receives the dotted module name (or `None` while imports are still
+/// being discovered), the module's path and its source, and returns the source to
+/// bundle instead.
+struct PythonTransform {
+    name: String,
+    callback: Py<PyAny>,
+}
+
+impl BundlePlugin for PythonTransform {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn pre_parse(&self, module: &PluginModule<'_>, source: &mut String) -> anyhow::Result<()> {
+        Python::with_gil(|py| {
+            let transformed = self
+                .callback
+                .call1(py, (module.name, module.path, source.as_str()))
+                .and_then(|result| result.extract::<String>(py))
+                .map_err(|err| anyhow::anyhow!("{}", err))?;
+            *source = transformed;
+            Ok(())
+        })
+    }
+}
+
+/// Keeps the warnings logged while one bundle is built
+#[derive(Default)]
+struct WarningCollector {
+    warnings: Mutex<Vec<String>>,
+}
+
+impl WarningCollector {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl LogSink for WarningCollector {
+    fn enabled(&self, level: Level) -> bool {
+        level <= Level::Warn
+    }
+
+    fn log(&self, _level: Level, _target: &str, message: &str) {
+        self.warnings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(message.to_owned());
+    }
+}
+
+/// A first-party module inlined into the bundle
+#[pyclass(name = "BundledModule", module = "cribo", frozen, get_all)]
+#[derive(Clone)]
+struct PyBundledModule {
+    /// Dotted module name
+    name: String,
+    /// Source file the module was read from
+    path: String,
+}
+
+/// Outcome of `Bundler.bundle()`
+#[pyclass(name = "BundleResult", module = "cribo", frozen, get_all)]
+struct PyBundleResult {
+    /// The bundled Python source
+    code: String,
+    /// File the bundle was written to, or `None` when it was only returned
+    output_path: Option<String>,
+    /// Inlined modules in the order they appear in the bundle
+    modules: Vec<PyBundledModule>,
+    /// Sorted top-level third-party packages the bundle imports
+    requirements: Vec<String>,
+    /// Warnings logged while bundling, including the type checker's findings
+    warnings: Vec<String>,
+    /// Seconds spent in each bundling phase, e.g. `discovery` or `code_generation`
+    timings: BTreeMap<String, f64>,
+}
+
+impl PyBundleResult {
+    fn new(
+        result: BundleResult,
+        output_path: Option<PathBuf>,
+        warnings: Vec<String>,
+        stats: &StatsCollector,
+    ) -> Self {
+        Self {
+            code: result.code,
+            output_path: output_path.map(|path| path.to_string_lossy().into_owned()),
+            modules: result
+                .modules
+                .into_iter()
+                .map(|module| PyBundledModule {
+                    name: module.name,
+                    path: module.path.to_string_lossy().into_owned(),
+                })
+                .collect(),
+            requirements: result.requirements,
+            timings: stats
+                .stats(warnings.len())
+                .phases
+                .into_iter()
+                .map(|(phase, elapsed)| (phase.as_str().to_owned(), elapsed.as_secs_f64()))
+                .collect(),
+            warnings,
+        }
+    }
+}
+
+#[pymethods]
+impl PyBundleResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "BundleResult(modules={}, requirements={:?}, warnings={})",
+            self.modules.len(),
+            self.requirements,
+            self.warnings.len()
+        )
+    }
+}
+
+/// Bundles Python entry scripts in-process
+#[pyclass(name = "Bundler", module = "cribo")]
+struct PyBundler {
+    config_path: Option<PathBuf>,
+    target_version: Option<String>,
+    transforms: Vec<Arc<PythonTransform>>,
+}
+
+impl PyBundler {
+    fn request(&self, entry: PathBuf) -> BundleRequest {
+        let mut options = match &self.config_path {
+            Some(config_path) => BundleOptions::new().with_config_file(config_path),
+            None => BundleOptions::new().with_config_discovery(),
+        };
+        if let Some(target_version) = &self.target_version {
+            options = options.with_target_version(target_version);
+        }
+        let mut request = BundleRequest::new(entry).with_options(options);
+        for transform in &self.transforms {
+            request = request.with_plugin(transform.clone());
+        }
+        request
+    }
+}
+
+#[pymethods]
+impl PyBundler {
+    /// Without `config_path`, configuration is discovered like the CLI does
+    #[new]
+    #[pyo3(signature = (config_path = None, target_version = None))]
+    fn new(config_path: Option<PathBuf>, target_version: Option<String>) -> Self {
+        Self {
+            config_path,
+            target_version,
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Run `callback(module, path, source)` on every first-party module's source and
+    /// bundle the string it returns
+    ///
+    /// Transforms run in the order they were added, each receiving the source
+    /// returned by the previous one.
+    fn add_transform(&mut self, callback: Bound<'_, PyAny>) -> PyResult<()> {
+        if !callback.is_callable() {
+            return Err(PyTypeError::new_err("transform must be callable"));
+        }
+        let name = callback
+            .getattr("__qualname__")
+            .and_then(|name| name.extract::<String>())
+            .unwrap_or_else(|_| "<transform>".to_owned());
+        self.transforms.push(Arc::new(PythonTransform {
+            name,
+            callback: callback.unbind(),
+        }));
+        Ok(())
+    }
+
+    /// Bundle `entry` and everything it imports into a single Python module
+    ///
+    /// With `output`, the bundle is also written there like the CLI's `--output` does,
+    /// with `requirements.txt` next to it when `emit_requirements` is set.
+    #[pyo3(signature = (entry, output = None, emit_requirements = false))]
+    fn bundle(
+        &self,
+        py: Python<'_>,
+        entry: PathBuf,
+        output: Option<PathBuf>,
+        emit_requirements: bool,
+    ) -> PyResult<PyBundleResult> {
+        // Routes warnings to the collector of this bundle; when something else installed
+        // a global logger first, results carry no warnings
+        let _ = cribo::logging::install(None, LevelFilter::Warn);
+        let warnings = Arc::new(WarningCollector::default());
+        let stats = Arc::new(StatsCollector::new());
+        let request = self
+            .request(entry)
+            .with_log_sink(warnings.clone())
+            .with_metrics(stats.clone());
+        // Transforms take the GIL back while they run
+        let result = py
+            .allow_threads(|| match &output {
+                Some(output) => api::bundle_to_file(&request, output, emit_requirements),
+                None => api::bundle(&request),
+            })
+            .map_err(|err| to_py_error(&err))?;
+        Ok(PyBundleResult::new(result, output, warnings.take(), &stats))
+    }
+
+    /// Raise `BundleError` if `entry` cannot be bundled, without generating code
+    fn check(&self, py: Python<'_>, entry: PathBuf) -> PyResult<()> {
+        let request = self.request(entry);
+        py.allow_threads(|| api::check(&request))
+            .map_err(|err| to_py_error(&err))
+    }
+}
+
+/// Run the `cribo` command line with `argv`, program name first, and return its exit
+/// status
+#[pyfunction]
+fn main(py: Python<'_>, argv: Vec<String>) -> u8 {
+    py.allow_threads(|| cribo::cli::run(argv))
+}
+
+#[pymodule]
+fn _cribo(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(main, m)?)?;
+    m.add_class::<PyBundler>()?;
+    m.add_class::<PyBundleResult>()?;
+    m.add_class::<PyBundledModule>()?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("BundleError", m.py().get_type::<BundleError>())?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! WebAssembly bindings for the bundler
+//!
+//! Exposes `bundle` to JavaScript so web playgrounds and serverless build pipelines can
+//! bundle Python sources held in memory, without a filesystem. Build the package with
+//! `wasm-pack build crates/cribo_wasm`; the library is compiled without its `git`
+//! feature, and modules are read through its in-memory file provider.
+use cribo::config::Config;
+use cribo::orchestrator::BundleOrchestrator;
+use indexmap::IndexMap;
+use wasm_bindgen::prelude::*;
+
+/// Bundle `entry` and everything it imports into a single Python module
+///
+/// `sources` is an object mapping paths relative to the source root (`"main.py"`,
+/// `"pkg/util.py"`) to their contents, and `entry` is one of those paths.
+/// `target_version` is the Python version to bundle for, e.g. `py311`.
+#[wasm_bindgen]
+pub fn bundle(
+    sources: JsValue,
+    entry: &str,
+    target_version: Option<String>,
+) -> Result<String, JsError> {
+    let sources: IndexMap<String, String> = serde_wasm_bindgen::from_value(sources)
+        .map_err(|err| JsError::new(&format!("sources must map paths to code: {}", err)))?;
+    let mut config = Config {
+        // There is no environment to take search paths from
+        respect_pythonpath: false,
+        ..Config::default()
+    };
+    if let Some(target_version) = target_version {
+        config
+            .set_target_version(target_version)
+            .map_err(|err| JsError::new(&err.to_string()))?;
+    }
+    BundleOrchestrator::new(config)
+        .bundle_from_sources(&sources, entry)
+        .map_err(|err| JsError::new(&err.to_string()))
+}
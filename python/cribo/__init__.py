@@ -4,8 +4,41 @@ Cribo: Python Source Bundler
 A tool that produces a single .py file from a multi-module Python project
 by inlining all first-party source files.
 
-This package provides the `cribo` command-line tool.
-The main interface is the CLI binary, not Python imports.
+This package provides the `cribo` command-line tool. Build scripts can also bundle
+in-process: `cribo.bundle()` returns a `BundleResult` with the code, the bundled
+modules, the third-party requirements, the warnings logged while bundling and the
+time spent in each phase. `cribo.Bundler` additionally takes transform callbacks.
 """
 
-__version__ = "0.1.0"
+import os
+from typing import Optional, Union
+
+from cribo._cribo import BundledModule, BundleError, BundleResult, Bundler, __version__
+
+__all__ = ["BundleError", "BundleResult", "BundledModule", "Bundler", "__version__", "bundle"]
+
+PathLike = Union[str, "os.PathLike[str]"]
+
+
+def bundle(
+    entry: PathLike,
+    output: Optional[PathLike] = None,
+    *,
+    emit_requirements: bool = False,
+    config: Optional[PathLike] = None,
+    target_version: Optional[str] = None,
+) -> BundleResult:
+    """Bundle `entry`, also writing the bundle to `output` when one is given.
+
+    Without `config`, configuration is discovered like the CLI does. Raises
+    `BundleError` when the project cannot be bundled.
+    """
+    bundler = Bundler(
+        config_path=os.fspath(config) if config is not None else None,
+        target_version=target_version,
+    )
+    return bundler.bundle(
+        os.fspath(entry),
+        output=os.fspath(output) if output is not None else None,
+        emit_requirements=emit_requirements,
+    )
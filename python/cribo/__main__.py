@@ -1,26 +1,25 @@
 """
 Command-line interface for cribo.
 
-This module provides access to the cribo CLI when called as `python -m cribo`.
-The main interface is the binary `cribo` command.
+`python -m cribo` and the `cribo` script run the Rust command line in-process through
+the package's native extension, so argument parsing, help output and exit codes are
+identical to the `cribo` binary.
 """
 
-import subprocess
+import signal
 import sys
+from typing import List, Optional
 
+from cribo._cribo import main as _run_cli
 
-def main() -> None:
-    """Main entry point that delegates to the cribo binary."""
-    try:
-        # Call the cribo binary with the same arguments
-        result = subprocess.run(["cribo"] + sys.argv[1:], check=False)
-        sys.exit(result.returncode)
-    except FileNotFoundError:
-        print(
-            "cribo binary not found. Please ensure cribo is properly installed.",
-            file=sys.stderr,
-        )
-        sys.exit(1)
+
+def main(argv: Optional[List[str]] = None) -> None:
+    """Run the cribo command line with `argv` (default: `sys.argv[1:]`) and exit with its status."""
+    args = sys.argv[1:] if argv is None else argv
+    # The bundler runs without checking for Python signal handlers; let Ctrl-C stop
+    # the process the way it stops the binary
+    signal.signal(signal.SIGINT, signal.SIG_DFL)
+    sys.exit(_run_cli(["cribo", *args]))
 
 
 if __name__ == "__main__":
@@ -0,0 +1,50 @@
+from typing import Callable, Dict, List, Optional, Union
+import os
+
+__version__: str
+
+class BundleError(Exception):
+    """Raised when a module cannot be resolved, parsed or bundled."""
+
+class BundledModule:
+    """A first-party module inlined into the bundle."""
+
+    @property
+    def name(self) -> str: ...
+    @property
+    def path(self) -> str: ...
+
+class BundleResult:
+    """Outcome of `Bundler.bundle()`."""
+
+    @property
+    def code(self) -> str: ...
+    @property
+    def output_path(self) -> Optional[str]: ...
+    @property
+    def modules(self) -> List[BundledModule]: ...
+    @property
+    def requirements(self) -> List[str]: ...
+    @property
+    def warnings(self) -> List[str]: ...
+    @property
+    def timings(self) -> Dict[str, float]: ...
+
+class Bundler:
+    """Bundles Python entry scripts in-process."""
+
+    def __init__(
+        self,
+        config_path: Optional[Union[str, "os.PathLike[str]"]] = None,
+        target_version: Optional[str] = None,
+    ) -> None: ...
+    def add_transform(self, callback: Callable[[Optional[str], str, str], str]) -> None: ...
+    def bundle(
+        self,
+        entry: Union[str, "os.PathLike[str]"],
+        output: Optional[Union[str, "os.PathLike[str]"]] = None,
+        emit_requirements: bool = False,
+    ) -> BundleResult: ...
+    def check(self, entry: Union[str, "os.PathLike[str]"]) -> None: ...
+
+def main(argv: List[str]) -> int: ...
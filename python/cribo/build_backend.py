@@ -0,0 +1,87 @@
+"""
+PEP 517 build backend that bundles the project with cribo before building it.
+
+Point `build-backend` at this module and keep the real backend in `[tool.cribo]`:
+
+    [build-system]
+    requires = ["cribo", "setuptools", "tomli; python_version < '3.11'"]
+    build-backend = "cribo.build_backend"
+
+    [tool.cribo]
+    entry = "src/app/main.py"
+    output = "src/app/_bundle.py"
+    build_backend = "setuptools.build_meta"  # the default
+
+`build_wheel`, `build_sdist` and `build_editable` run cribo in the project
+directory first, so the bundle written to `output` ends up in the distribution
+like any other source file. cribo reads the rest of `[tool.cribo]` itself; every
+other hook is answered by the wrapped backend unchanged.
+"""
+
+import importlib
+from typing import Any, Dict, Optional
+
+DEFAULT_BACKEND = "setuptools.build_meta"
+
+_BUNDLING_HOOKS = ("build_wheel", "build_sdist", "build_editable")
+
+
+def _load_pyproject() -> Dict[str, Any]:
+    try:
+        import tomllib
+    except ImportError:  # Python < 3.11
+        import tomli as tomllib
+
+    with open("pyproject.toml", "rb") as pyproject:
+        return tomllib.load(pyproject)
+
+
+def _settings() -> Dict[str, Any]:
+    return _load_pyproject().get("tool", {}).get("cribo", {})
+
+
+def _backend() -> Any:
+    return importlib.import_module(_settings().get("build_backend", DEFAULT_BACKEND))
+
+
+def _run_cribo() -> None:
+    from cribo import BundleError
+    from cribo._cribo import main as run_cli
+
+    settings = _settings()
+    if "entry" not in settings or "output" not in settings:
+        raise BundleError("[tool.cribo] in pyproject.toml must set `entry` and `output`")
+    # Without --entry/--output the CLI takes both from [tool.cribo]; it reports
+    # failures on stderr itself
+    status = run_cli(["cribo"])
+    if status != 0:
+        raise BundleError("cribo exited with status {}".format(status))
+
+
+def build_wheel(
+    wheel_directory: str,
+    config_settings: Optional[Dict[str, Any]] = None,
+    metadata_directory: Optional[str] = None,
+) -> str:
+    _run_cribo()
+    return _backend().build_wheel(wheel_directory, config_settings, metadata_directory)
+
+
+def build_sdist(sdist_directory: str, config_settings: Optional[Dict[str, Any]] = None) -> str:
+    _run_cribo()
+    return _backend().build_sdist(sdist_directory, config_settings)
+
+
+def __getattr__(name: str) -> Any:
+    # Optional hooks exist only when the wrapped backend provides them
+    if name.startswith("_"):
+        raise AttributeError(name)
+    hook = getattr(_backend(), name)
+    if name not in _BUNDLING_HOOKS:
+        return hook
+
+    def bundling_hook(*args: Any, **kwargs: Any) -> Any:
+        _run_cribo()
+        return hook(*args, **kwargs)
+
+    return bundling_hook